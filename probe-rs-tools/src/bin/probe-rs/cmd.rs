@@ -10,6 +10,7 @@ pub mod download;
 pub mod erase;
 pub mod gdb;
 pub mod info;
+pub mod instruction_trace;
 pub mod itm;
 pub mod list;
 pub mod mi;
@@ -27,6 +27,7 @@ pub struct Config {
     pub reset: Reset,
     pub probe: Probe,
     pub rtt: Rtt,
+    pub itm: Itm,
     pub gdb: Gdb,
 }
 
@@ -133,6 +134,37 @@ impl Rtt {
     }
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+// Note: default values are defined in `ItmPortConfig`.
+pub struct StimulusPortConfig {
+    pub port: u8,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub format: Option<DataFormat>,
+    #[serde(default)]
+    pub show_location: Option<bool>,
+    #[serde(default)]
+    /// Controls the inclusion of timestamps for [`DataFormat::String`] and [`DataFormat::Defmt`].
+    pub show_timestamps: Option<bool>,
+    #[serde(default)]
+    /// Controls the output format for DataFormat::Defmt.
+    pub log_format: Option<String>,
+}
+
+/// The itm config struct holding all the possible itm options.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Itm {
+    pub enabled: bool,
+    /// The frequency of the core clock in Hz, which drives the SWO output and the timestamps.
+    pub clock: Option<u32>,
+    /// The baud rate of the SWO output.
+    pub baud: u32,
+    /// Stimulus ports to be displayed, and options for them
+    pub ports: Vec<StimulusPortConfig>,
+}
+
 mod duration_ms {
     use std::time::Duration;
 
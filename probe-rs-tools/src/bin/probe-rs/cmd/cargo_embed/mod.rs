@@ -6,6 +6,7 @@ use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 use colored::Colorize;
 use parking_lot::FairMutex;
+use probe_rs::architecture::arm::component::TraceSink;
 use probe_rs::flashing::FormatKind;
 use probe_rs::gdb_server::GdbInstanceConfiguration;
 use probe_rs::probe::list::Lister;
@@ -28,6 +29,7 @@ use time::{OffsetDateTime, UtcOffset};
 use crate::util::cargo::target_instruction_set;
 use crate::util::common_options::{BinaryDownloadOptions, OperationError, ProbeOptions};
 use crate::util::flash::{build_loader, run_flash_download};
+use crate::util::itm::{ItmConfig, ItmPortConfig};
 use crate::util::logging::setup_logging;
 use crate::util::rtt::client::RttClient;
 use crate::util::rtt::{RttChannelConfig, RttConfig};
@@ -298,6 +300,11 @@ fn main_try(args: &[OsString], offset: UtcOffset) -> Result<()> {
         core.reset_and_halt(Duration::from_millis(500))?;
     }
 
+    let itm_config = create_itm_config(&config)?;
+    if let Some(itm_config) = &itm_config {
+        session.setup_tracing(core_id, TraceSink::Swo(itm_config.swo_config()))?;
+    }
+
     let session = Arc::new(FairMutex::new(session));
 
     let mut gdb_thread_handle = None;
@@ -328,7 +335,7 @@ fn main_try(args: &[OsString], offset: UtcOffset) -> Result<()> {
         }));
     }
 
-    if config.rtt.enabled {
+    if config.rtt.enabled || config.itm.enabled {
         // GDB is also using the session, so we do not lock on the outside.
         run_rttui_app(
             name,
@@ -337,6 +344,7 @@ fn main_try(args: &[OsString], offset: UtcOffset) -> Result<()> {
             offset,
             should_clear_rtt_header,
             rtt_client,
+            itm_config,
         )?;
     } else if should_resume_core(&config) {
         // If we don't run the app, we have to resume the core somewhere else.
@@ -376,6 +384,7 @@ fn run_rttui_app(
     timezone_offset: UtcOffset,
     should_clear_rtt_header: bool,
     mut client: RttClient,
+    itm_config: Option<ItmConfig>,
 ) -> anyhow::Result<()> {
     let core_id = client.core_id();
 
@@ -395,21 +404,23 @@ fn run_rttui_app(
         }
     }
 
-    let start = Instant::now();
-    let rtt = loop {
-        let mut session_handle = session.lock();
-        let mut core = session_handle.core(core_id)?;
+    if config.rtt.enabled {
+        let start = Instant::now();
+        loop {
+            let mut session_handle = session.lock();
+            let mut core = session_handle.core(core_id)?;
 
-        if client.try_attach(&mut core)? {
-            break client;
-        }
+            if client.try_attach(&mut core)? {
+                break;
+            }
 
-        if start.elapsed() > config.rtt.timeout {
-            return Err(anyhow!("Failed to attach to RTT: Timeout"));
+            if start.elapsed() > config.rtt.timeout {
+                return Err(anyhow!("Failed to attach to RTT: Timeout"));
+            }
         }
-    };
 
-    tracing::info!("RTT initialized.");
+        tracing::info!("RTT initialized.");
+    }
 
     // Check if the terminal supports x
 
@@ -437,7 +448,7 @@ fn run_rttui_app(
         / 1_000_000;
 
     let logname = format!("{name}_{chip_name}_{timestamp_millis}");
-    let mut app = rttui::app::App::new(rtt, config, logname)?;
+    let mut app = rttui::app::App::new(client, itm_config, config, logname)?;
     loop {
         app.render();
 
@@ -451,6 +462,7 @@ fn run_rttui_app(
             }
 
             app.poll_rtt(&mut core)?;
+            app.poll_itm(&mut core)?;
         }
 
         thread::sleep(Duration::from_millis(10));
@@ -510,3 +522,49 @@ fn create_rtt_config(config: &config::Config) -> RttConfig {
 
     rtt_config
 }
+
+fn create_itm_config(config: &config::Config) -> Result<Option<ItmConfig>> {
+    if !config.itm.enabled {
+        return Ok(None);
+    }
+
+    let Some(clock) = config.itm.clock else {
+        return Err(anyhow!(
+            "The core clock frequency `itm.clock` is required to receive the ITM output"
+        ));
+    };
+
+    // Make sure our defaults are the same as the ones intended in the config struct.
+    let default_port_config = ItmPortConfig::new(0, Default::default());
+
+    let mut ports = Vec::new();
+    for port_config in config.itm.ports.iter() {
+        // Where `port_config` is unspecified, apply default from `default_port_config`.
+        ports.push(ItmPortConfig {
+            port: port_config.port,
+            name: port_config.name.clone(),
+            data_format: port_config
+                .format
+                .unwrap_or(default_port_config.data_format),
+            show_timestamps: port_config
+                .show_timestamps
+                .unwrap_or(default_port_config.show_timestamps),
+            show_location: port_config
+                .show_location
+                .unwrap_or(default_port_config.show_location),
+            log_format: port_config
+                .log_format
+                .clone()
+                .or_else(|| default_port_config.log_format.clone()),
+        });
+    }
+    if ports.is_empty() {
+        ports.push(default_port_config);
+    }
+
+    Ok(Some(ItmConfig {
+        clock,
+        baud: config.itm.baud,
+        ports,
+    }))
+}
@@ -17,11 +17,14 @@ use std::{path::PathBuf, sync::mpsc::TryRecvError};
 
 use crate::{
     cmd::cargo_embed::rttui::{channel::ChannelData, tab::TabConfig},
-    util::rtt::client::RttClient,
+    util::{
+        itm::{ItmConfig, ItmDemultiplexer},
+        rtt::client::RttClient,
+    },
 };
 
 use super::super::config;
-use super::channel::UpChannel;
+use super::channel::{StimulusPorts, UpChannel};
 use super::{event::Events, tab::Tab};
 
 use event::KeyModifiers;
@@ -41,10 +44,18 @@ pub struct App {
     up_channels: Vec<Rc<RefCell<UpChannel>>>,
 
     client: RttClient,
+
+    itm: Option<ItmDemultiplexer>,
+    itm_channels: Vec<Rc<RefCell<UpChannel>>>,
 }
 
 impl App {
-    pub fn new(client: RttClient, config: config::Config, logname: String) -> Result<Self> {
+    pub fn new(
+        client: RttClient,
+        itm_config: Option<ItmConfig>,
+        config: config::Config,
+        logname: String,
+    ) -> Result<Self> {
         let mut tab_config = config.rtt.tabs;
 
         // Create channel states
@@ -114,11 +125,22 @@ impl App {
             tabs.push(Tab::new(up_channel.clone(), tab.down_channel, tab.name));
         }
 
+        // Each ITM stimulus port is displayed in its own tab, after the RTT channels.
+        let mut itm_channels = Vec::new();
+        let itm = itm_config.map(|itm_config| {
+            for port in itm_config.ports.iter() {
+                let channel = Rc::new(RefCell::new(UpChannel::itm(port)));
+                tabs.push(Tab::new(channel.clone(), None, None));
+                itm_channels.push(channel);
+            }
+            ItmDemultiplexer::new(&itm_config, client.defmt_data.clone())
+        });
+
         // Code farther down relies on tabs being configured and might panic
         // otherwise.
         if tabs.is_empty() {
             return Err(anyhow!(
-                "Failed to initialize RTT UI: No RTT channels configured"
+                "Failed to initialize RTT UI: No RTT channels or ITM ports configured"
             ));
         }
 
@@ -156,6 +178,9 @@ impl App {
 
             up_channels,
             client,
+
+            itm,
+            itm_channels,
         })
     }
 
@@ -247,6 +272,18 @@ impl App {
         Ok(())
     }
 
+    /// Reads the ITM data received over SWO, and passes it to the stimulus port channels.
+    pub fn poll_itm(&mut self, core: &mut Core) -> Result<()> {
+        let Some(itm) = self.itm.as_mut() else {
+            return Ok(());
+        };
+
+        let data = core.read_swo()?;
+        itm.process(&data, &mut StimulusPorts(&self.itm_channels))?;
+
+        Ok(())
+    }
+
     pub fn push_rtt(&mut self, core: &mut Core) {
         if let Err(error) = self.tabs[self.current_tab].send_input(core, &mut self.client) {
             tracing::warn!("Failed to send input to RTT channel: {error:?}");
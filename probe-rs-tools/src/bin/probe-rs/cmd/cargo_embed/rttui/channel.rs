@@ -1,10 +1,13 @@
-use std::net::SocketAddr;
+use std::{cell::RefCell, net::SocketAddr, rc::Rc};
 
 use probe_rs::{rtt::Error, Core};
 
 use crate::{
     cmd::cargo_embed::rttui::tcp::TcpPublisher,
-    util::rtt::{client::RttClient, ChannelDataCallbacks, RttActiveUpChannel},
+    util::{
+        itm::ItmPortConfig,
+        rtt::{client::RttClient, ChannelDataCallbacks, DataFormat, RttActiveUpChannel},
+    },
 };
 
 pub enum ChannelData {
@@ -63,6 +66,21 @@ impl UpChannel {
         }
     }
 
+    pub fn itm(port: &ItmPortConfig) -> Self {
+        Self {
+            data: if port.data_format == DataFormat::BinaryLE {
+                ChannelData::Binary { data: Vec::new() }
+            } else {
+                ChannelData::Strings {
+                    messages: Vec::new(),
+                }
+            },
+            tcp_stream: None,
+            channel_number: usize::from(port.port),
+            channel_name: port.name(),
+        }
+    }
+
     pub fn poll_rtt(&mut self, core: &mut Core<'_>, client: &mut RttClient) -> Result<(), Error> {
         client.poll_channel(
             core,
@@ -75,3 +93,32 @@ impl UpChannel {
         &self.channel_name
     }
 }
+
+/// Passes the data of the ITM stimulus ports to the channels of the ports.
+pub struct StimulusPorts<'a>(pub &'a [Rc<RefCell<UpChannel>>]);
+
+impl StimulusPorts<'_> {
+    fn channel(&self, port: usize) -> Option<&Rc<RefCell<UpChannel>>> {
+        self.0
+            .iter()
+            .find(|channel| channel.borrow().channel_number == port)
+    }
+}
+
+impl ChannelDataCallbacks for StimulusPorts<'_> {
+    fn on_string_data(&mut self, port: usize, data: String) -> Result<(), Error> {
+        let Some(channel) = self.channel(port) else {
+            return Ok(());
+        };
+        let channel = &mut *channel.borrow_mut();
+        (&mut channel.tcp_stream, &mut channel.data).on_string_data(port, data)
+    }
+
+    fn on_binary_data(&mut self, port: usize, data: &[u8]) -> Result<(), Error> {
+        let Some(channel) = self.channel(port) else {
+            return Ok(());
+        };
+        let channel = &mut *channel.borrow_mut();
+        (&mut channel.tcp_stream, &mut channel.data).on_binary_data(port, data)
+    }
+}
@@ -85,6 +85,16 @@ pub(crate) fn render_diagnostics(error: OperationError) {
             error.to_string(),
             vec![],
         ),
+        OperationError::FailedToLoadCoreDump { .. } => (
+            error.to_string(),
+            vec![],
+        ),
+        OperationError::CoreDumpSessionFailed(_e) => (
+            error.to_string(),
+            vec![
+                "If you specified a chip with `--chip`, make sure it matches the core type of the core dump.".into()
+            ],
+        ),
         OperationError::FailedToChangeWorkingDirectory { .. } => (
             error.to_string(),
             vec![],
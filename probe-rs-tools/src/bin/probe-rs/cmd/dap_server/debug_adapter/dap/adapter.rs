@@ -3,8 +3,9 @@ use super::{
     dap_types,
     repl_commands_helpers::{build_expanded_commands, command_completions},
     request_helpers::{
-        disassemble_target_memory, get_dap_source, get_svd_variable_reference,
-        get_variable_reference, set_instruction_breakpoint,
+        data_breakpoint_info, disassemble_target_memory, exception_description,
+        fault_report_details, get_dap_source, get_svd_variable_reference, get_variable_reference,
+        parse_data_breakpoint_id, set_exception_breakpoints, set_instruction_breakpoint,
     },
 };
 use crate::cmd::dap_server::{
@@ -12,7 +13,7 @@ use crate::cmd::dap_server::{
     server::{
         configuration::ConsoleLog,
         core_data::CoreHandle,
-        session_data::{BreakpointType, SourceLocationScope},
+        session_data::{BreakpointConditions, BreakpointType, SourceLocationScope},
     },
     DebuggerError,
 };
@@ -27,8 +28,8 @@ use probe_rs::{
         xtensa::communication_interface::XtensaError,
     },
     debug::{
-        stack_frame::StackFrameInfo, ColumnType, ObjectRef, SourceLocation, SteppingMode,
-        VariableName, VerifiedBreakpoint,
+        stack_frame::StackFrameInfo, ColumnType, DebugError, ObjectRef, SourceLocation,
+        SteppingMode, VariableName, VerifiedBreakpoint,
     },
     Architecture::Riscv,
     CoreStatus, Error, HaltReason, MemoryInterface, RegisterValue,
@@ -440,86 +441,111 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         response_body.type_ = Some(format!("{}", VariableName::RegistersRoot));
                         response_body.result = format!("{register_value}");
                     } else {
-                        // If the expression wasn't pointing to a register, then check if is a local or static variable in our stack_frame
-                        let mut variable: Option<probe_rs::debug::Variable> = None;
-                        let mut variable_cache: Option<&mut probe_rs::debug::VariableCache> = None;
-                        // Search through available caches and stop as soon as the variable is found
-                        if let Some(search_cache) = stack_frame.local_variables.as_mut() {
-                            if search_cache.len() == 1 {
-                                let mut root_variable = search_cache.root_variable().clone();
-
-                                // This is a special case where we have a single variable in the cache, and it is the root of a scope.
-                                // These variables don't have cached children by default, so we need to resolve them before we proceed.
-                                // We check for len() == 1, so unwrap() on first_mut() is safe.
-                                target_core.core_data.debug_info.cache_deferred_variables(
-                                    search_cache,
-                                    &mut target_core.core,
-                                    &mut root_variable,
-                                    StackFrameInfo {
-                                        registers: &stack_frame.registers,
-                                        frame_base: stack_frame.frame_base,
-                                        canonical_frame_address: stack_frame
-                                            .canonical_frame_address,
-                                    },
-                                )?;
-                            }
-
+                        // Variables in the variables view are referenced by their key.
+                        let evaluated =
                             if let Ok(expression_as_key) = expression.parse::<ObjectRef>() {
-                                variable = search_cache.get_variable_by_key(expression_as_key);
+                                let variable = [
+                                    stack_frame.local_variables.as_ref(),
+                                    target_core.core_data.static_variables.as_ref(),
+                                ]
+                                .into_iter()
+                                .flatten()
+                                .find_map(|cache| cache.get_variable_by_key(expression_as_key));
+
+                                match variable {
+                                    Some(variable) => {
+                                        response_body.memory_reference =
+                                            Some(variable.memory_location.to_string());
+                                        response_body.type_ = Some(variable.type_name());
+                                        Ok(Some(variable.variable_key()))
+                                    }
+                                    None => Err(DebugError::Other(format!(
+                                        "No variable with the key {expression}"
+                                    ))),
+                                }
                             } else {
-                                variable = search_cache
-                                    .get_variable_by_name(&VariableName::Named(expression.clone()));
-                            }
-                            if variable.is_some() {
-                                variable_cache = Some(search_cache);
+                                target_core
+                                    .core_data
+                                    .debug_info
+                                    .evaluate_expression(
+                                        &expression,
+                                        &mut target_core.core,
+                                        stack_frame,
+                                        target_core.core_data.static_variables.as_mut(),
+                                    )
+                                    .map(|evaluated| {
+                                        response_body.result = evaluated.value;
+                                        response_body.type_ = Some(evaluated.type_name);
+                                        response_body.memory_reference = evaluated
+                                            .memory_location
+                                            .map(|address| format!("{address:#010X}"));
+                                        evaluated.variable_key
+                                    })
+                            };
+
+                        match evaluated {
+                            Ok(Some(variable_key)) => {
+                                // The children of variables can be expanded in the client.
+                                for variable_cache in [
+                                    stack_frame.local_variables.as_ref(),
+                                    target_core.core_data.static_variables.as_ref(),
+                                ]
+                                .into_iter()
+                                .flatten()
+                                {
+                                    if let Some(variable) =
+                                        variable_cache.get_variable_by_key(variable_key)
+                                    {
+                                        let (
+                                            variables_reference,
+                                            named_child_variables_cnt,
+                                            indexed_child_variables_cnt,
+                                        ) = get_variable_reference(&variable, variable_cache);
+                                        response_body.indexed_variables =
+                                            Some(indexed_child_variables_cnt);
+                                        response_body.named_variables =
+                                            Some(named_child_variables_cnt);
+                                        response_body.result = variable.to_string(variable_cache);
+                                        response_body.variables_reference =
+                                            variables_reference.into();
+                                        break;
+                                    }
+                                }
                             }
-                        }
-                        // Check if we found a variable.
-                        if let (Some(variable), Some(variable_cache)) = (variable, variable_cache) {
-                            let (
-                                variables_reference,
-                                named_child_variables_cnt,
-                                indexed_child_variables_cnt,
-                            ) = get_variable_reference(&variable, variable_cache);
-                            response_body.indexed_variables = Some(indexed_child_variables_cnt);
-                            response_body.memory_reference =
-                                Some(variable.memory_location.to_string());
-                            response_body.named_variables = Some(named_child_variables_cnt);
-                            response_body.result = variable.to_string(variable_cache);
-                            response_body.type_ = Some(variable.type_name());
-                            response_body.variables_reference = variables_reference.into();
-                        } else {
-                            // If we made it to here, no register or variable matched the expression.
-                            for variable_cache_entry in [target_core
-                                .core_data
-                                .core_peripherals
-                                .as_ref()
-                                .map(|core_peripherals| &core_peripherals.svd_variable_cache)]
-                            .into_iter()
-                            .flatten()
-                            {
-                                let svd_variable = if let Ok(expression_as_key) =
-                                    expression.parse::<ObjectRef>()
+                            Ok(None) => {}
+                            Err(error) => {
+                                response_body.result = error.to_string();
+
+                                // Peripheral registers from the SVD file are not part of the debug information.
+                                if let Some(core_peripherals) =
+                                    target_core.core_data.core_peripherals.as_ref()
                                 {
-                                    variable_cache_entry.get_variable_by_key(expression_as_key)
-                                } else {
-                                    variable_cache_entry.get_variable_by_name(&expression)
-                                };
-
-                                if let Some(svd_variable) = svd_variable {
-                                    let (variables_reference, named_child_variables_cnt) =
-                                        get_svd_variable_reference(
-                                            svd_variable,
-                                            variable_cache_entry,
-                                        );
-                                    response_body.indexed_variables = None;
-                                    response_body.memory_reference =
-                                        svd_variable.memory_reference();
-                                    response_body.named_variables = Some(named_child_variables_cnt);
-                                    response_body.result =
-                                        svd_variable.get_value(&mut target_core.core);
-                                    response_body.type_ = svd_variable.type_name();
-                                    response_body.variables_reference = variables_reference.into();
+                                    let svd_variable_cache = &core_peripherals.svd_variable_cache;
+                                    let svd_variable = if let Ok(expression_as_key) =
+                                        expression.parse::<ObjectRef>()
+                                    {
+                                        svd_variable_cache.get_variable_by_key(expression_as_key)
+                                    } else {
+                                        svd_variable_cache.get_variable_by_name(&expression)
+                                    };
+
+                                    if let Some(svd_variable) = svd_variable {
+                                        let (variables_reference, named_child_variables_cnt) =
+                                            get_svd_variable_reference(
+                                                svd_variable,
+                                                svd_variable_cache,
+                                            );
+                                        response_body.indexed_variables = None;
+                                        response_body.memory_reference =
+                                            svd_variable.memory_reference();
+                                        response_body.named_variables =
+                                            Some(named_child_variables_cnt);
+                                        response_body.result =
+                                            svd_variable.get_value(&mut target_core.core);
+                                        response_body.type_ = svd_variable.type_name();
+                                        response_body.variables_reference =
+                                            variables_reference.into();
+                                    }
                                 }
                             }
                         }
@@ -699,9 +725,11 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                 let saved_breakpoints = std::mem::take(&mut target_core.core_data.breakpoints);
 
                 for breakpoint in saved_breakpoints {
-                    match target_core
-                        .set_breakpoint(breakpoint.address, breakpoint.breakpoint_type.clone())
-                    {
+                    match target_core.set_breakpoint(
+                        breakpoint.address,
+                        breakpoint.breakpoint_type.clone(),
+                        breakpoint.conditions.clone(),
+                    ) {
                         Ok(_) => {}
                         Err(error) => {
                             //This will cause the debugger to show the user an error, but not stop the debugger.
@@ -880,6 +908,14 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         requested_breakpoint_line,
                         requested_breakpoint_column,
                         &args.source,
+                        BreakpointConditions {
+                            condition: bp.condition.clone().filter(|c| !c.trim().is_empty()),
+                            hit_condition: bp
+                                .hit_condition
+                                .clone()
+                                .filter(|c| !c.trim().is_empty()),
+                            log_message: bp.log_message.clone().filter(|m| !m.is_empty()),
+                        },
                     ) {
                         Ok(VerifiedBreakpoint {
                             address,
@@ -931,6 +967,194 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         }
     }
 
+    pub(crate) fn set_function_breakpoints(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: &Request,
+    ) -> Result<()> {
+        let arguments: SetFunctionBreakpointsArguments = get_arguments(self, request)?;
+
+        // The request always contains all function breakpoints, so the existing ones are replaced.
+        if let Err(error) = target_core.clear_breakpoints(BreakpointType::FunctionBreakpoint {
+            name: String::new(),
+        }) {
+            return self.send_response::<()>(
+                request,
+                Err(&DebuggerError::Other(anyhow!(
+                    "Failed to clear existing function breakpoints before setting new ones : {}",
+                    error
+                ))),
+            );
+        }
+
+        let mut created_breakpoints: Vec<Breakpoint> = Vec::new();
+        for requested_breakpoint in arguments.breakpoints {
+            let conditions = BreakpointConditions {
+                condition: requested_breakpoint
+                    .condition
+                    .filter(|c| !c.trim().is_empty()),
+                hit_condition: requested_breakpoint
+                    .hit_condition
+                    .filter(|c| !c.trim().is_empty()),
+                log_message: None,
+            };
+
+            let mut breakpoint_response = Breakpoint {
+                column: None,
+                end_column: None,
+                end_line: None,
+                id: None,
+                instruction_reference: None,
+                line: None,
+                message: None,
+                offset: None,
+                source: None,
+                verified: false,
+            };
+
+            match target_core
+                .verify_and_set_function_breakpoint(&requested_breakpoint.name, conditions)
+            {
+                Ok(locations) => {
+                    // The response has room for a single location, so we report the first one.
+                    let VerifiedBreakpoint {
+                        address,
+                        source_location,
+                    } = &locations[0];
+                    breakpoint_response.verified = true;
+                    breakpoint_response.source = get_dap_source(source_location);
+                    breakpoint_response.line = source_location.line.map(|line| line as i64);
+                    breakpoint_response.column = source_location.column.map(|col| match col {
+                        ColumnType::LeftEdge => 0_i64,
+                        ColumnType::Column(c) => c as i64,
+                    });
+                    breakpoint_response.instruction_reference = Some(format!("{address:#010X}"));
+                    breakpoint_response.message = Some(format!(
+                        "Function breakpoint on {:?} at {} location(s): {}",
+                        requested_breakpoint.name,
+                        locations.len(),
+                        locations
+                            .iter()
+                            .map(|location| format!("{:#010X}", location.address))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+                Err(error) => {
+                    let message = error.to_string();
+                    self.log_to_console(format!("Warning: {message}"));
+                    self.show_message(MessageSeverity::Warning, message.clone());
+                    breakpoint_response.message = Some(message);
+                }
+            }
+
+            created_breakpoints.push(breakpoint_response);
+        }
+
+        self.send_response(
+            request,
+            Ok(Some(SetFunctionBreakpointsResponseBody {
+                breakpoints: created_breakpoints,
+            })),
+        )
+    }
+
+    pub(crate) fn data_breakpoint_info(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: &Request,
+    ) -> Result<()> {
+        let arguments: DataBreakpointInfoArguments = get_arguments(self, request)?;
+
+        match data_breakpoint_info(target_core, &arguments) {
+            Ok(response_body) => self.send_response(request, Ok(Some(response_body))),
+            Err(error) => self.send_response::<()>(request, Err(&error)),
+        }
+    }
+
+    pub(crate) fn set_data_breakpoints(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: &Request,
+    ) -> Result<()> {
+        let arguments: SetDataBreakpointsArguments = get_arguments(self, request)?;
+
+        // The request always contains all data breakpoints, so the existing ones are replaced.
+        if let Err(error) = target_core.clear_data_breakpoints() {
+            return self.send_response::<()>(
+                request,
+                Err(&DebuggerError::Other(anyhow!(
+                    "Failed to clear existing data breakpoints before setting new ones : {}",
+                    error
+                ))),
+            );
+        }
+
+        let mut created_breakpoints: Vec<Breakpoint> = Vec::new();
+        for requested_breakpoint in arguments.breakpoints {
+            let conditions = BreakpointConditions {
+                condition: requested_breakpoint
+                    .condition
+                    .filter(|c| !c.trim().is_empty()),
+                hit_condition: requested_breakpoint
+                    .hit_condition
+                    .filter(|c| !c.trim().is_empty()),
+                log_message: None,
+            };
+
+            let mut breakpoint_response = Breakpoint {
+                column: None,
+                end_column: None,
+                end_line: None,
+                id: None,
+                instruction_reference: None,
+                line: None,
+                message: None,
+                offset: None,
+                source: None,
+                verified: false,
+            };
+
+            match parse_data_breakpoint_id(
+                &requested_breakpoint.data_id,
+                requested_breakpoint.access_type.as_ref(),
+            )
+            .and_then(|watchpoint| {
+                target_core
+                    .set_data_breakpoint(watchpoint, conditions)
+                    .map(|()| watchpoint)
+            }) {
+                Ok(watchpoint) => {
+                    breakpoint_response.verified = true;
+                    breakpoint_response.instruction_reference =
+                        Some(format!("{:#010X}", watchpoint.address));
+                    breakpoint_response.message = Some(format!(
+                        "Data breakpoint on {} bytes at {:#010X} ({:?})",
+                        watchpoint.length, watchpoint.address, watchpoint.kind
+                    ));
+                }
+                Err(error) => {
+                    let message = match error {
+                        DebuggerError::UserMessage(message) => message,
+                        other => other.to_string(),
+                    };
+                    self.log_to_console(format!("Warning: {message}"));
+                    self.show_message(MessageSeverity::Warning, message.clone());
+                    breakpoint_response.message = Some(message);
+                }
+            }
+
+            created_breakpoints.push(breakpoint_response);
+        }
+
+        self.send_response(
+            request,
+            Ok(Some(SetDataBreakpointsResponseBody {
+                breakpoints: created_breakpoints,
+            })),
+        )
+    }
+
     pub(crate) fn set_instruction_breakpoints(
         &mut self,
         target_core: &mut CoreHandle,
@@ -967,6 +1191,63 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         self.send_response(request, Ok(Some(instruction_breakpoint_body)))
     }
 
+    pub(crate) fn set_exception_breakpoints(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: &Request,
+    ) -> Result<()> {
+        let arguments: SetExceptionBreakpointsArguments = get_arguments(self, request)?;
+
+        let exception_breakpoint_body = SetExceptionBreakpointsResponseBody {
+            breakpoints: Some(set_exception_breakpoints(&arguments.filters, target_core)),
+        };
+
+        // Unsupported filters depend on the core, so let the user know about them.
+        for breakpoint_response in exception_breakpoint_body.breakpoints.iter().flatten() {
+            if !breakpoint_response.verified {
+                if let Some(message) = &breakpoint_response.message {
+                    self.log_to_console(message.clone());
+                    self.show_message(MessageSeverity::Warning, message.clone());
+                }
+            }
+        }
+
+        self.send_response(request, Ok(Some(exception_breakpoint_body)))
+    }
+
+    pub(crate) fn exception_info(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: &Request,
+    ) -> Result<()> {
+        let _arguments: ExceptionInfoArguments = get_arguments(self, request)?;
+
+        if target_core.core_data.last_known_status != CoreStatus::Halted(HaltReason::Exception) {
+            return self.send_response::<()>(
+                request,
+                Err(&DebuggerError::Other(anyhow!(
+                    "The core is not halted on an exception"
+                ))),
+            );
+        }
+
+        match exception_description(target_core) {
+            Ok((exception_id, description)) => {
+                let details = fault_report_details(target_core);
+                self.send_response(
+                    request,
+                    Ok(Some(ExceptionInfoResponseBody {
+                        exception_id,
+                        description: Some(description),
+                        break_mode: ExceptionBreakMode::Always,
+                        details,
+                    })),
+                )
+            }
+            Err(error) => self.send_response::<()>(request, Err(&error)),
+        }
+    }
+
     pub(crate) fn threads(
         &mut self,
         target_core: &mut CoreHandle,
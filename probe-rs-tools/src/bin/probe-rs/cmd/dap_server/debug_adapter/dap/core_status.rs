@@ -1,4 +1,4 @@
-use probe_rs::{CoreStatus, HaltReason};
+use probe_rs::{CoreStatus, HaltReason, WatchpointCause};
 
 pub(crate) trait DapStatus {
     fn short_long_status(&self, program_counter: Option<u64>) -> (&'static str, String);
@@ -30,7 +30,14 @@ impl DapStatus for CoreStatus {
                     "exception",
                     "Core halted due to an exception, e.g. interupt handler".to_string(),
                 ),
-                HaltReason::Watchpoint => (
+                HaltReason::Watchpoint(WatchpointCause::Hit(watchpoint)) => (
+                    "data breakpoint",
+                    format!(
+                        "Core halted due to a {:?} watchpoint on {:#010x} ({} bytes)",
+                        watchpoint.kind, watchpoint.address, watchpoint.length
+                    ),
+                ),
+                HaltReason::Watchpoint(WatchpointCause::Unknown) => (
                     "data breakpoint",
                     "Core halted due to a watchpoint or data breakpoint".to_string(),
                 ),
@@ -286,48 +286,51 @@ pub(crate) static REPL_COMMANDS: &[ReplCommand<ReplHandler>] = &[
     },
     ReplCommand {
         command: "p",
-        // Stricly speaking, gdb refers to this as an expression, but we only support variables.
-        help_text: "Print known information about variable.",
+        help_text: "Print the value of an expression, or of all local variables if no expression is given.",
         sub_commands: None,
         args: Some(&[
             ReplCommandArgs::Optional("/f (f=format[n|v])"),
-            ReplCommandArgs::Required("<local variable name>"),
+            ReplCommandArgs::Required("<expression>"),
         ]),
         handler: |target_core, command_arguments, evaluate_arguments| {
-            let input_arguments = command_arguments.split_whitespace();
             let mut gdb_nuf = GdbNuf {
                 format_specifier: GdbFormat::Native,
                 ..Default::default()
             };
-            // If no variable name is provided, use the root of the local scope, and print all it's children.
-            let mut variable_name = VariableName::LocalScopeRoot;
+            let mut expression = command_arguments.trim();
 
-            for input_argument in input_arguments {
-                if input_argument.starts_with('/') {
-                    if let Some(gdb_nuf_string) = input_argument.strip_prefix('/') {
-                        gdb_nuf = GdbNuf::from_str(gdb_nuf_string)?;
-                        gdb_nuf
-                            .check_supported_formats(&[
-                                GdbFormat::Native,
-                                GdbFormat::DapReference,
-                            ])
-                            .map_err(|error| {
-                                DebuggerError::UserMessage(format!(
-                                    "Format specifier : {}, is not valid here.\nPlease select one of the supported formats:\n{error}", gdb_nuf.format_specifier
-                                ))
-                            })?;
-                    } else {
-                        return Err(DebuggerError::UserMessage(
-                            "The '/' specifier must be followed by a valid gdb 'f' format specifier."
-                                .to_string(),
-                        ));
-                    }
-                } else {
-                    variable_name = VariableName::Named(input_argument.to_string());
+            if let Some(format_argument) = expression.strip_prefix('/') {
+                let (gdb_nuf_string, rest) = format_argument
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((format_argument, ""));
+                if gdb_nuf_string.is_empty() {
+                    return Err(DebuggerError::UserMessage(
+                        "The '/' specifier must be followed by a valid gdb 'f' format specifier."
+                            .to_string(),
+                    ));
                 }
+                gdb_nuf = GdbNuf::from_str(gdb_nuf_string)?;
+                gdb_nuf
+                    .check_supported_formats(&[GdbFormat::Native, GdbFormat::DapReference])
+                    .map_err(|error| {
+                        DebuggerError::UserMessage(format!(
+                            "Format specifier : {}, is not valid here.\nPlease select one of the supported formats:\n{error}", gdb_nuf.format_specifier
+                        ))
+                    })?;
+                expression = rest.trim();
             }
 
-            get_local_variable(evaluate_arguments, target_core, variable_name, gdb_nuf)
+            if expression.is_empty() {
+                // Print all the children of the root of the local scope.
+                get_local_variable(
+                    evaluate_arguments,
+                    target_core,
+                    VariableName::LocalScopeRoot,
+                    gdb_nuf,
+                )
+            } else {
+                evaluate_expression(evaluate_arguments, target_core, expression, gdb_nuf)
+            }
         },
     },
     ReplCommand {
@@ -414,7 +417,7 @@ pub(crate) static REPL_COMMANDS: &[ReplCommand<ReplHandler>] = &[
         args: Some(&[
             ReplCommandArgs::Optional("memory start address"),
             ReplCommandArgs::Optional("memory size in bytes"),
-            ReplCommandArgs::Optional("path (default: ./coredump)"),
+            ReplCommandArgs::Optional("path (default: ./coredump, use the `.elf` extension to create an ELF core file)"),
         ]),
         handler: |target_core, command_arguments, _request_arguments| {
             let mut args = command_arguments.split_whitespace().collect_vec();
@@ -467,7 +470,12 @@ pub(crate) static REPL_COMMANDS: &[ReplCommand<ReplHandler>] = &[
                 range_string = range_string.trim_end_matches(", ").to_string();
                 range_string = format!("(Includes memory ranges: {range_string})");
             }
-            CoreDump::dump_core(&mut target_core.core, ranges)?.store(location)?;
+            let core_dump = CoreDump::dump_core(&mut target_core.core, ranges)?;
+            if location.extension().is_some_and(|extension| extension == "elf") {
+                core_dump.store_elf(location)?;
+            } else {
+                core_dump.store(location)?;
+            }
 
             Ok(Response {
                 command: "dump".to_string(),
@@ -107,6 +107,79 @@ pub(crate) fn get_local_variable(
     Ok(response)
 }
 
+/// Evaluate the `expression` in the selected stack frame, and format the result for display to the user.
+pub(crate) fn evaluate_expression(
+    evaluate_arguments: &EvaluateArguments,
+    target_core: &mut CoreHandle,
+    expression: &str,
+    gdb_nuf: GdbNuf,
+) -> Result<Response, DebuggerError> {
+    let frame_ref = evaluate_arguments.frame_id.map(ObjectRef::from);
+
+    let stack_frame = match frame_ref {
+        Some(frame_id) => target_core
+            .core_data
+            .stack_frames
+            .iter_mut()
+            .find(|stack_frame| stack_frame.id == frame_id),
+        None => target_core.core_data.stack_frames.first_mut(),
+    };
+
+    let Some(stack_frame) = stack_frame else {
+        return Err(DebuggerError::UserMessage("No frame selected.".to_string()));
+    };
+
+    let evaluated = target_core
+        .core_data
+        .debug_info
+        .evaluate_expression(
+            expression,
+            &mut target_core.core,
+            stack_frame,
+            target_core.core_data.static_variables.as_mut(),
+        )
+        .map_err(|error| DebuggerError::UserMessage(error.to_string()))?;
+
+    let memory_reference = evaluated
+        .memory_location
+        .map(|address| format!("{address:#010X}"));
+
+    let result = if gdb_nuf.format_specifier == GdbFormat::DapReference {
+        format!("{expression} : {} ", evaluated.value)
+    } else {
+        match &memory_reference {
+            Some(location) => format!(
+                "{expression} [{} @ {location}]: {} ",
+                evaluated.type_name, evaluated.value
+            ),
+            None => format!(
+                "{expression} [{}]: {} ",
+                evaluated.type_name, evaluated.value
+            ),
+        }
+    };
+
+    let response_body = EvaluateResponseBody {
+        result: result.clone(),
+        variables_reference: evaluated.variable_key.map_or(0, i64::from),
+        named_variables: None,
+        indexed_variables: None,
+        memory_reference,
+        type_: Some(evaluated.type_name),
+        presentation_hint: None,
+    };
+
+    Ok(Response {
+        command: "variables".to_string(),
+        success: true,
+        message: Some(result),
+        type_: "response".to_string(),
+        request_seq: 0,
+        seq: 0,
+        body: serde_json::to_value(response_body).ok(),
+    })
+}
+
 /// Read memory at the specified address (hex), using the [`GdbNuf`] specifiers to determine size and format.
 pub(crate) fn memory_read(
     address: u64,
@@ -1,22 +1,31 @@
 use crate::cmd::dap_server::{
     debug_adapter::dap::dap_types::{DisassembledInstruction, Source},
     peripherals::svd_cache::{SvdVariableCache, Variable},
-    server::{core_data::CoreHandle, session_data::BreakpointType},
+    server::{
+        core_data::CoreHandle,
+        session_data::{BreakpointConditions, BreakpointType},
+    },
     DebuggerError,
 };
+use crate::util::fault_report::{fault_report, InterruptNames};
 use anyhow::{anyhow, Result};
 use capstone::{
     arch::arm::ArchMode as armArchMode, arch::arm64::ArchMode as aarch64ArchMode,
     arch::riscv::ArchMode as riscvArchMode, prelude::*, Endian,
 };
 use probe_rs::{
-    debug::{ColumnType, ObjectRef, SourceLocation},
-    CoreType, InstructionSet, MemoryInterface,
+    debug::{ColumnType, DebugRegisters, ObjectRef, SourceLocation, VariableName},
+    exception_handler_for_core, Architecture, CoreType, InstructionSet, MemoryInterface,
+    RegisterId, VectorCatchCondition, Watchpoint, WatchpointKind,
 };
-use std::{fmt::Write, sync::LazyLock, time::Duration};
+use std::{fmt::Write, ops::Range, sync::LazyLock, time::Duration};
 use typed_path::TypedPathBuf;
 
-use super::dap_types::{Breakpoint, InstructionBreakpoint, MemoryAddress};
+use super::dap_types::{
+    Breakpoint, DataBreakpointAccessType, DataBreakpointInfoArguments,
+    DataBreakpointInfoResponseBody, ExceptionBreakpointsFilter, ExceptionDetails,
+    InstructionBreakpoint, MemoryAddress,
+};
 
 // Source file mapping for rustlib, e.g. Some(("/rustc/<hash>", "<sysroot>/lib/rustlib/src/rust"))
 // This can be None if rustc is not found or gives bad output
@@ -506,7 +515,11 @@ pub(crate) fn set_instruction_breakpoint(
         .as_str()
         .try_into()
     {
-        match target_core.set_breakpoint(memory_reference, BreakpointType::InstructionBreakpoint) {
+        match target_core.set_breakpoint(
+            memory_reference,
+            BreakpointType::InstructionBreakpoint,
+            BreakpointConditions::default(),
+        ) {
             Ok(_) => {
                 breakpoint_response.verified = true;
                 breakpoint_response.instruction_reference =
@@ -550,3 +563,375 @@ pub(crate) fn set_instruction_breakpoint(
     };
     breakpoint_response
 }
+
+/// The exception breakpoint filters offered to the client, as `(filter, label, description, condition)`.
+const EXCEPTION_FILTERS: [(&str, &str, &str, VectorCatchCondition); 7] = [
+    (
+        "hardfault",
+        "HardFault",
+        "Halt when a HardFault occurs",
+        VectorCatchCondition::HardFault,
+    ),
+    (
+        "busfault",
+        "BusFault",
+        "Halt when a BusFault occurs (ARMv7-M and ARMv8-M)",
+        VectorCatchCondition::BusFault,
+    ),
+    (
+        "memmanage",
+        "MemManage fault",
+        "Halt when a MemManage fault occurs (ARMv7-M and ARMv8-M)",
+        VectorCatchCondition::MemoryManagementFault,
+    ),
+    (
+        "usagefault",
+        "UsageFault",
+        "Halt when a UsageFault occurs (ARMv7-M and ARMv8-M)",
+        VectorCatchCondition::UsageFault,
+    ),
+    (
+        "securefault",
+        "SecureFault",
+        "Halt when a SecureFault occurs (ARMv8-M with the Security Extension)",
+        VectorCatchCondition::SecureFault,
+    ),
+    (
+        "exception",
+        "All faults / RISC-V exceptions",
+        "Halt on any fault, or on any synchronous exception on RISC-V",
+        VectorCatchCondition::Exception,
+    ),
+    (
+        "reset",
+        "Core reset",
+        "Halt on the first instruction after a core reset",
+        VectorCatchCondition::CoreReset,
+    ),
+];
+
+/// The exception breakpoint filters, which are reported in the `initialize` response.
+pub(crate) fn exception_breakpoint_filters() -> Vec<ExceptionBreakpointsFilter> {
+    EXCEPTION_FILTERS
+        .iter()
+        .map(
+            |(filter, label, description, _)| ExceptionBreakpointsFilter {
+                filter: filter.to_string(),
+                label: label.to_string(),
+                description: Some(description.to_string()),
+                default: Some(false),
+                supports_condition: None,
+                condition_description: None,
+            },
+        )
+        .collect()
+}
+
+/// Enable vector catch for the requested exception filters, and disable it for all others.
+///
+/// The returned breakpoints are in the same order as the requested filters.
+pub(crate) fn set_exception_breakpoints(
+    filters: &[String],
+    target_core: &mut CoreHandle,
+) -> Vec<Breakpoint> {
+    // The conditions overlap, so all unused ones are disabled before enabling the requested ones.
+    for (filter, _, _, condition) in EXCEPTION_FILTERS {
+        if !filters.iter().any(|requested| requested == filter) {
+            if let Err(error) = target_core.core.disable_vector_catch(condition) {
+                tracing::debug!("Could not disable vector catch for {condition:?}: {error}");
+            }
+        }
+    }
+
+    filters
+        .iter()
+        .map(|requested| {
+            let mut breakpoint_response = Breakpoint {
+                column: None,
+                end_column: None,
+                end_line: None,
+                id: None,
+                instruction_reference: None,
+                line: None,
+                message: None,
+                offset: None,
+                source: None,
+                verified: false,
+            };
+
+            match EXCEPTION_FILTERS
+                .iter()
+                .find(|(filter, _, _, _)| filter == requested)
+            {
+                Some((_, label, _, condition)) => {
+                    match target_core.core.enable_vector_catch(*condition) {
+                        Ok(()) => breakpoint_response.verified = true,
+                        Err(error) => {
+                            breakpoint_response.message = Some(format!(
+                            "Warning: Could not enable the {label} exception breakpoint: {error}"
+                        ))
+                        }
+                    }
+                }
+                None => {
+                    breakpoint_response.message =
+                        Some(format!("Unknown exception filter: {requested:?}"))
+                }
+            }
+
+            breakpoint_response
+        })
+        .collect()
+}
+
+/// Describe the exception which halted the core, as `(exception_id, description)`.
+pub(crate) fn exception_description(
+    target_core: &mut CoreHandle,
+) -> Result<(String, String), DebuggerError> {
+    if target_core.core.architecture() == Architecture::Riscv {
+        let mcause: u32 = target_core.core.read_core_reg(RegisterId(0x342))?;
+        let mtval: u32 = target_core.core.read_core_reg(RegisterId(0x343))?;
+        let name = riscv_exception_name(mcause);
+        return Ok((
+            name.to_string(),
+            format!("{name} <mcause: {mcause:#x}, mtval: {mtval:#010x}>"),
+        ));
+    }
+
+    let registers = DebugRegisters::from_core(&mut target_core.core);
+    let exception_interface = exception_handler_for_core(target_core.core.core_type());
+    let raw_exception = exception_interface.raw_exception(&registers)?;
+    let description =
+        exception_interface.exception_description(raw_exception, &mut target_core.core)?;
+
+    let exception_id = description
+        .split_once(" <")
+        .map_or(description.as_str(), |(name, _)| name)
+        .to_string();
+
+    Ok((exception_id, description))
+}
+
+/// The [`FaultReport`](probe_rs::debug::FaultReport) of the active exception, as the details of an `exceptionInfo` response.
+///
+/// Fault reports are not available on every architecture, so this returns `None` if there is none.
+pub(crate) fn fault_report_details(target_core: &mut CoreHandle) -> Option<ExceptionDetails> {
+    let no_interrupt_names = InterruptNames::new();
+    let interrupt_names = target_core
+        .core_data
+        .core_peripherals
+        .as_ref()
+        .map_or(&no_interrupt_names, |svd_cache| &svd_cache.interrupt_names);
+
+    let report = match fault_report(&mut target_core.core, interrupt_names) {
+        Ok(report) => report?,
+        Err(error) => {
+            tracing::debug!("Unable to create the fault report: {error:?}");
+            return None;
+        }
+    };
+
+    Some(ExceptionDetails {
+        message: Some(report.to_string()),
+        type_name: Some(report.exception_name.clone()),
+        full_type_name: None,
+        evaluate_name: None,
+        stack_trace: None,
+        inner_exception: None,
+    })
+}
+
+/// The name of a synchronous RISC-V exception, see the RISC-V Privileged Specification, 3.1.15.
+fn riscv_exception_name(mcause: u32) -> &'static str {
+    if mcause & 0x8000_0000 != 0 {
+        return "Interrupt";
+    }
+
+    match mcause {
+        0 => "Instruction address misaligned",
+        1 => "Instruction access fault",
+        2 => "Illegal instruction",
+        3 => "Breakpoint",
+        4 => "Load address misaligned",
+        5 => "Load access fault",
+        6 => "Store/AMO address misaligned",
+        7 => "Store/AMO access fault",
+        8 => "Environment call from U-mode",
+        9 => "Environment call from S-mode",
+        11 => "Environment call from M-mode",
+        12 => "Instruction page fault",
+        13 => "Load page fault",
+        15 => "Store/AMO page fault",
+        _ => "Unknown exception",
+    }
+}
+
+/// Describe if, and how, a data breakpoint can be set on a variable.
+///
+/// The `dataId` encodes the memory range of the variable, see [`parse_data_breakpoint_id`].
+pub(crate) fn data_breakpoint_info(
+    target_core: &mut CoreHandle,
+    arguments: &DataBreakpointInfoArguments,
+) -> Result<DataBreakpointInfoResponseBody, DebuggerError> {
+    let variable_name = VariableName::Named(arguments.name.clone());
+
+    let mut variable = None;
+    if let Some(parent_key) = arguments.variables_reference.map(ObjectRef::from) {
+        // The variable is a child of a scope or of another variable.
+        for search_cache in target_core
+            .core_data
+            .stack_frames
+            .iter()
+            .filter_map(|stack_frame| stack_frame.local_variables.as_ref())
+            .chain(target_core.core_data.static_variables.as_ref())
+        {
+            variable = search_cache.get_variable_by_name_and_parent(&variable_name, parent_key);
+            if variable.is_some() {
+                break;
+            }
+        }
+    } else {
+        // The name is an expression, evaluated in the requested stack frame, or the current one.
+        let frame_id = arguments
+            .frame_id
+            .map(ObjectRef::try_from)
+            .transpose()
+            .ok()
+            .flatten();
+        let stack_frame = match frame_id {
+            Some(frame_id) => target_core
+                .core_data
+                .stack_frames
+                .iter()
+                .find(|stack_frame| stack_frame.id == frame_id),
+            None => target_core.core_data.stack_frames.first(),
+        };
+        variable = stack_frame
+            .and_then(|stack_frame| stack_frame.local_variables.as_ref())
+            .and_then(|search_cache| search_cache.get_variable_by_name(&variable_name))
+            .or_else(|| {
+                target_core
+                    .core_data
+                    .static_variables
+                    .as_ref()
+                    .and_then(|search_cache| search_cache.get_variable_by_name(&variable_name))
+            });
+    }
+
+    let unavailable = |description: String| DataBreakpointInfoResponseBody {
+        access_types: None,
+        can_persist: None,
+        data_id: None,
+        description,
+    };
+
+    let Some(variable) = variable else {
+        return Ok(unavailable(format!(
+            "Data breakpoints are not available for {:?}, because the variable was not found",
+            arguments.name
+        )));
+    };
+
+    let Some(memory_range) = variable.memory_range() else {
+        return Ok(unavailable(format!(
+            "Data breakpoints are not available for {}, because it is not stored at a known memory location",
+            variable.name
+        )));
+    };
+
+    let watchpoint = Watchpoint {
+        address: memory_range.start,
+        length: memory_range.end - memory_range.start,
+        kind: WatchpointKind::Write,
+    };
+    let required_units = watchpoint.naturally_aligned_parts().len();
+    let available_units = target_core.core.available_watchpoint_units()? as usize;
+    if required_units > available_units {
+        return Ok(unavailable(format!(
+            "Data breakpoints are not available for {}: watching {} bytes at {:#010x} requires {} hardware watchpoints, but this core has {}",
+            variable.name, watchpoint.length, watchpoint.address, required_units, available_units
+        )));
+    }
+
+    Ok(DataBreakpointInfoResponseBody {
+        access_types: Some(vec![
+            DataBreakpointAccessType::Write,
+            DataBreakpointAccessType::Read,
+            DataBreakpointAccessType::ReadWrite,
+        ]),
+        // Local variables move around, so the breakpoint is only valid for this session.
+        can_persist: Some(false),
+        data_id: Some(data_breakpoint_id(&memory_range)),
+        description: format!(
+            "{} ({} bytes at {:#010x})",
+            variable.name, watchpoint.length, watchpoint.address
+        ),
+    })
+}
+
+/// Encode the memory range of a data breakpoint as `<address>/<length>`.
+fn data_breakpoint_id(memory_range: &Range<u64>) -> String {
+    format!(
+        "{:#010x}/{}",
+        memory_range.start,
+        memory_range.end - memory_range.start
+    )
+}
+
+/// Decode the watchpoint described by the `dataId` and `accessType` of a data breakpoint.
+pub(crate) fn parse_data_breakpoint_id(
+    data_id: &str,
+    access_type: Option<&DataBreakpointAccessType>,
+) -> Result<Watchpoint, DebuggerError> {
+    let invalid = || DebuggerError::UserMessage(format!("Invalid data breakpoint id {data_id:?}"));
+
+    let (address, length) = data_id.split_once('/').ok_or_else(invalid)?;
+    let address = address
+        .strip_prefix("0x")
+        .and_then(|address| u64::from_str_radix(address, 16).ok())
+        .ok_or_else(invalid)?;
+    let length: u64 = length.parse().map_err(|_| invalid())?;
+    if length == 0 {
+        return Err(invalid());
+    }
+
+    let kind = match access_type {
+        Some(DataBreakpointAccessType::Read) => WatchpointKind::Read,
+        Some(DataBreakpointAccessType::ReadWrite) => WatchpointKind::Access,
+        Some(DataBreakpointAccessType::Write) | None => WatchpointKind::Write,
+    };
+
+    Ok(Watchpoint {
+        address,
+        length,
+        kind,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn data_breakpoint_ids() {
+        let data_id = data_breakpoint_id(&(0x2000_0004..0x2000_000c));
+        assert_eq!(data_id, "0x20000004/8");
+
+        assert_eq!(
+            parse_data_breakpoint_id(&data_id, Some(&DataBreakpointAccessType::ReadWrite)).ok(),
+            Some(Watchpoint {
+                address: 0x2000_0004,
+                length: 8,
+                kind: WatchpointKind::Access,
+            })
+        );
+        assert_eq!(
+            parse_data_breakpoint_id("0x20000000/4", None)
+                .ok()
+                .map(|watchpoint| watchpoint.kind),
+            Some(WatchpointKind::Write)
+        );
+        assert!(parse_data_breakpoint_id("0x20000000/0", None).is_err());
+        assert!(parse_data_breakpoint_id("counter", None).is_err());
+    }
+}
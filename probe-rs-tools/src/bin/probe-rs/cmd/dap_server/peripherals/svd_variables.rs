@@ -2,6 +2,7 @@ use crate::cmd::dap_server::{
     debug_adapter::{dap::adapter::DebugAdapter, protocol::ProtocolAdapter},
     DebuggerError,
 };
+use crate::util::fault_report::{interrupt_names, InterruptNames};
 use std::{fmt::Debug, fs::File, io::Read, path::Path};
 use svd_parser::Config;
 
@@ -15,6 +16,8 @@ pub struct SvdCache {
     /// After that, only the SVD fields values change values, and the data for these will be re-read
     /// every time they are queried by the debugger.
     pub(crate) svd_variable_cache: SvdVariableCache,
+    /// The names of the interrupts of the device, which are used to name the active interrupt in fault reports.
+    pub(crate) interrupt_names: InterruptNames,
 }
 
 impl SvdCache {
@@ -49,6 +52,7 @@ impl SvdCache {
                     .ok();
 
                 Ok(SvdCache {
+                    interrupt_names: interrupt_names(&peripheral_device),
                     svd_variable_cache: variable_cache_from_svd(
                         peripheral_device,
                         debug_adapter,
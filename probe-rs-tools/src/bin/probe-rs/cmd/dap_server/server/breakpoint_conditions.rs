@@ -0,0 +1,134 @@
+//! Evaluation of the `hitCondition` and `logMessage` of DAP source breakpoints.
+//!
+//! The `condition` of a breakpoint, and the expressions in a `logMessage`, are evaluated with
+//! [`probe_rs::debug::DebugInfo::evaluate_expression`] by the caller.
+
+/// Comparison operators, ordered so that two character operators are matched first.
+const OPERATORS: [(&str, Comparison); 6] = [
+    ("==", Comparison::Equal),
+    ("!=", Comparison::NotEqual),
+    ("<=", Comparison::LessOrEqual),
+    (">=", Comparison::GreaterOrEqual),
+    ("<", Comparison::Less),
+    (">", Comparison::Greater),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn apply<T: PartialOrd>(self, lhs: T, rhs: T) -> bool {
+        match self {
+            Comparison::Equal => lhs == rhs,
+            Comparison::NotEqual => lhs != rhs,
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+            Comparison::Greater => lhs > rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs,
+        }
+    }
+}
+
+/// Check the `hitCondition` of a breakpoint against the number of hits so far.
+///
+/// A plain number `N`, or `== N`, halts on the N-th hit. The comparison operators halt on all
+/// hits which satisfy the comparison, and `% N` halts on every N-th hit.
+pub(crate) fn hit_condition_met(hit_condition: &str, hit_count: u64) -> Result<bool, String> {
+    let hit_condition = hit_condition.trim();
+    let invalid = || format!("Invalid hit condition: {hit_condition:?}");
+
+    let (comparison, count) = if let Some(modulo) = hit_condition.strip_prefix('%') {
+        let modulo: u64 = modulo.trim().parse().map_err(|_| invalid())?;
+        if modulo == 0 {
+            return Err(invalid());
+        }
+        return Ok(hit_count % modulo == 0);
+    } else if let Some((operator, comparison)) = OPERATORS
+        .iter()
+        .find(|(operator, _)| hit_condition.starts_with(operator))
+    {
+        (*comparison, &hit_condition[operator.len()..])
+    } else {
+        (Comparison::Equal, hit_condition)
+    };
+
+    let count: u64 = count.trim().parse().map_err(|_| invalid())?;
+    Ok(comparison.apply(hit_count, count))
+}
+
+/// Replace the `{expression}` placeholders in the message of a logpoint.
+///
+/// Use `{{` and `}}` for literal braces.
+pub(crate) fn interpolate_log_message(
+    message: &str,
+    resolve: &mut impl FnMut(&str) -> Result<String, String>,
+) -> String {
+    let mut output = String::with_capacity(message.len());
+    let mut rest = message;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        output.push_str(&rest[..start]);
+        let brace = &rest[start..];
+
+        if brace.starts_with("{{") || brace.starts_with("}}") {
+            output.push_str(&brace[..1]);
+            rest = &brace[2..];
+        } else if let (true, Some(end)) = (brace.starts_with('{'), brace.find('}')) {
+            let expression = brace[1..end].trim();
+            match resolve(expression) {
+                Ok(value) => output.push_str(&value),
+                Err(error) => output.push_str(&format!("<{error}>")),
+            }
+            rest = &brace[end + 1..];
+        } else {
+            output.push_str(&brace[..1]);
+            rest = &brace[1..];
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resolve(name: &str) -> Result<String, String> {
+        match name {
+            "count" => Ok("5".to_string()),
+            "flags" => Ok("0x10".to_string()),
+            _ => Err(format!("No variable named {name}")),
+        }
+    }
+
+    #[test]
+    fn hit_conditions() {
+        assert_eq!(hit_condition_met("3", 3), Ok(true));
+        assert_eq!(hit_condition_met("3", 4), Ok(false));
+        assert_eq!(hit_condition_met(">= 3", 4), Ok(true));
+        assert_eq!(hit_condition_met("% 2", 4), Ok(true));
+        assert_eq!(hit_condition_met("% 2", 3), Ok(false));
+        assert!(hit_condition_met("% 0", 3).is_err());
+        assert!(hit_condition_met("often", 3).is_err());
+    }
+
+    #[test]
+    fn log_messages() {
+        assert_eq!(
+            interpolate_log_message("count={count} flags={ flags }", &mut resolve),
+            "count=5 flags=0x10"
+        );
+        assert_eq!(
+            interpolate_log_message("{{literal}} {missing}", &mut resolve),
+            "{literal} <No variable named missing>"
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::util::common_options::ProbeOptions;
 use crate::util::rtt;
 use crate::{cmd::dap_server::DebuggerError, FormatOptions};
 use anyhow::{anyhow, Result};
+use probe_rs::debug::PathSubstitution;
 use probe_rs::probe::{DebugProbeSelector, WireProtocol};
 use serde::{Deserialize, Serialize};
 use std::{env::current_dir, path::PathBuf};
@@ -68,6 +69,16 @@ impl SessionConfig {
             let message = "Please do not use any of the `flashing_enabled`, `reset_after_flashing`, halt_after_reset`, `full_chip_erase`, or `restore_unwritten_bytes` options when using `attach` request type.";
             return Err(DebuggerError::Other(anyhow!(message)));
         }
+        // A core dump can only be inspected, it can not be flashed or reset.
+        if requested_target_session_type == TargetSessionType::LaunchRequest
+            && self
+                .core_configs
+                .iter()
+                .any(|core_config| core_config.core_dump.is_some())
+        {
+            let message = "Please use the `attach` request type to debug a `coreDump`.";
+            return Err(DebuggerError::Other(anyhow!(message)));
+        }
         Ok(())
     }
 
@@ -97,6 +108,32 @@ impl SessionConfig {
                         )));
                 }
             };
+            // Update the paths of the `symbol_files` and validate that the files exist.
+            for symbol_file in &mut target_core_config.symbol_files {
+                symbol_file.path = get_absolute_path(self.cwd.as_ref(), Some(&symbol_file.path))?;
+                if !symbol_file.path.is_file() {
+                    return Err(DebuggerError::Other(anyhow!(
+                        "Invalid symbol file specified '{}'",
+                        symbol_file.path.display()
+                    )));
+                }
+            }
+            // Update the `core_dump` and validate that the file exists.
+            if let Some(core_dump) = &target_core_config.core_dump {
+                let core_dump = get_absolute_path(self.cwd.as_ref(), Some(core_dump))?;
+                if !core_dump.is_file() {
+                    return Err(DebuggerError::Other(anyhow!(
+                        "Invalid core dump file specified '{}'",
+                        core_dump.display()
+                    )));
+                }
+                target_core_config.core_dump = Some(core_dump);
+            }
+            // Update the paths of the `debug_file_directories`. Directories which do not exist are
+            // ignored when searching for debug information.
+            for directory in &mut target_core_config.debug_file_directories {
+                *directory = get_absolute_path(self.cwd.as_ref(), Some(&*directory))?;
+            }
             // Update the `svd_file` and validate that the file exists, or else warn the user and continue.
             target_core_config.svd_file =
                 match get_absolute_path(self.cwd.as_ref(), target_core_config.svd_file.as_ref()) {
@@ -233,10 +270,42 @@ pub struct CoreConfig {
     /// CMSIS-SVD file for the target. Relative to `cwd`, or fully qualified.
     pub(crate) svd_file: Option<PathBuf>,
 
+    /// Rules to map the source paths in the debug information of `program_binary` to local
+    /// paths, e.g. when the binary was built in a container. The first matching rule is applied.
+    #[serde(default)]
+    pub(crate) path_substitutions: Vec<PathSubstitution>,
+
+    /// Further binaries which run on this core, e.g. the application started by a bootloader in
+    /// `program_binary`. Their debug information is used in addition to that of `program_binary`.
+    #[serde(default)]
+    pub(crate) symbol_files: Vec<SymbolFile>,
+
+    /// Directories to search for the debug information of stripped binaries, by build ID or by
+    /// the name in the `.gnu_debuglink` section. Relative to `cwd`, or fully qualified.
+    #[serde(default)]
+    pub(crate) debug_file_directories: Vec<PathBuf>,
+
+    /// A core dump of this core, e.g. created with the `dump` REPL command, which is debugged
+    /// instead of connecting to the target with a probe. Relative to `cwd`, or fully qualified.
+    pub(crate) core_dump: Option<PathBuf>,
+
     #[serde(flatten)]
     pub(crate) rtt_config: rtt::RttConfig,
 }
 
+/// A binary whose debug information is loaded in addition to the `program_binary` of a core.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolFile {
+    /// The binary as a path. Relative to `cwd`, or fully qualified.
+    pub(crate) path: PathBuf,
+
+    /// The offset of the address the binary is loaded at, relative to the address it was linked
+    /// at. Default is 0.
+    #[serde(default)]
+    pub(crate) load_offset: u64,
+}
+
 fn default_console_log() -> Option<ConsoleLog> {
     Some(ConsoleLog::Console)
 }
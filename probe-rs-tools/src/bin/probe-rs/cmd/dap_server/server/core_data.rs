@@ -1,6 +1,9 @@
 use std::{ops::Range, path::Path};
 
-use super::session_data::{self, ActiveBreakpoint, BreakpointType, SourceLocationScope};
+use super::breakpoint_conditions::{hit_condition_met, interpolate_log_message};
+use super::session_data::{
+    self, ActiveBreakpoint, BreakpointConditions, BreakpointType, SourceLocationScope,
+};
 use crate::cmd::dap_server::{
     debug_adapter::{
         dap::{
@@ -20,10 +23,12 @@ use anyhow::{anyhow, Result};
 use probe_rs::debug::VerifiedBreakpoint;
 use probe_rs::{
     debug::{
-        debug_info::DebugInfo, stack_frame::StackFrameInfo, ColumnType, ObjectRef, VariableCache,
+        debug_info::DebugInfo, stack_frame::StackFrameInfo, ColumnType, DebugRegisters,
+        EvaluatedExpression, ObjectRef, VariableCache,
     },
+    exception_handler_for_core,
     rtt::ScanRegion,
-    Core, CoreStatus, HaltReason,
+    Core, CoreStatus, HaltReason, Watchpoint, WatchpointCause,
 };
 use time::UtcOffset;
 use typed_path::TypedPathBuf;
@@ -46,10 +51,18 @@ pub struct CoreData {
     pub core_peripherals: Option<SvdCache>,
     pub stack_frames: Vec<probe_rs::debug::stack_frame::StackFrame>,
     pub breakpoints: Vec<session_data::ActiveBreakpoint>,
+    pub data_breakpoints: Vec<session_data::ActiveDataBreakpoint>,
     pub rtt_connection: Option<debug_rtt::RttConnection>,
     pub rtt_client: Option<RttClient>,
 }
 
+/// The breakpoint which halted the core, as an index into [`CoreData::breakpoints`] or [`CoreData::data_breakpoints`].
+#[derive(Clone, Copy, Debug)]
+enum HitBreakpoint {
+    Instruction(usize),
+    Data(usize),
+}
+
 /// [CoreHandle] provides handles to various data structures required to debug a single instance of a core. The actual state is stored in [session_data::SessionData].
 ///
 /// Usage: To get access to this structure please use the [session_data::SessionData::attach_core] method. Please keep access/locks to this to a minimum duration.
@@ -95,10 +108,21 @@ impl<'p> CoreHandle<'p> {
                                     status
                                 );
                             }
-                            CoreStatus::Halted(_) => {
+                            CoreStatus::Halted(halt_reason) => {
                                 // HaltReason::Step is a special case, where we have to send a custome event to the client that the core halted.
                                 // In this case, we don't re-send the "stopped" event, but further down, we will
                                 // update the `last_known_status` to the actual HaltReason returned by the core.
+                                if matches!(
+                                    halt_reason,
+                                    HaltReason::Breakpoint(_) | HaltReason::Watchpoint(_)
+                                ) && !self.breakpoint_halts_core(debug_adapter, halt_reason)?
+                                {
+                                    // The core resumes without the client noticing that it was halted.
+                                    self.core.run()?;
+                                    self.core_data.last_known_status = CoreStatus::Running;
+                                    return Ok(CoreStatus::Running);
+                                }
+
                                 if self.core_data.last_known_status
                                     != CoreStatus::Halted(HaltReason::Step)
                                 {
@@ -163,6 +187,147 @@ impl<'p> CoreHandle<'p> {
         }
     }
 
+    /// Find the breakpoint or data breakpoint which halted the core, if any.
+    fn find_hit_breakpoint(
+        &mut self,
+        halt_reason: HaltReason,
+    ) -> Result<Option<HitBreakpoint>, DebuggerError> {
+        match halt_reason {
+            HaltReason::Breakpoint(_) => {
+                let program_counter: u64 = self
+                    .core
+                    .read_core_reg(self.core.program_counter())
+                    .map_err(DebuggerError::ProbeRs)?;
+
+                Ok(self
+                    .core_data
+                    .breakpoints
+                    .iter()
+                    .position(|breakpoint| breakpoint.address == program_counter)
+                    .map(HitBreakpoint::Instruction))
+            }
+            HaltReason::Watchpoint(cause) => {
+                // If the core can't tell which watchpoint was hit, a single data breakpoint is still unambiguous.
+                let single_data_breakpoint = self.core_data.data_breakpoints.len() == 1;
+                Ok(self
+                    .core_data
+                    .data_breakpoints
+                    .iter()
+                    .position(|data_breakpoint| match cause {
+                        WatchpointCause::Hit(watchpoint) => {
+                            data_breakpoint.watchpoints.contains(&watchpoint)
+                        }
+                        WatchpointCause::Unknown => single_data_breakpoint,
+                    })
+                    .map(HitBreakpoint::Data))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The conditions and the hit count of a breakpoint or data breakpoint.
+    fn hit_breakpoint_state(&mut self, hit: HitBreakpoint) -> (&BreakpointConditions, &mut u64) {
+        match hit {
+            HitBreakpoint::Instruction(index) => {
+                let breakpoint = &mut self.core_data.breakpoints[index];
+                (&breakpoint.conditions, &mut breakpoint.hit_count)
+            }
+            HitBreakpoint::Data(index) => {
+                let data_breakpoint = &mut self.core_data.data_breakpoints[index];
+                (&data_breakpoint.conditions, &mut data_breakpoint.hit_count)
+            }
+        }
+    }
+
+    /// Check the conditions of the breakpoint or data breakpoint which halted the core, and log the message of logpoints.
+    ///
+    /// Returns `false` if the core should continue running.
+    fn breakpoint_halts_core<P: ProtocolAdapter>(
+        &mut self,
+        debug_adapter: &mut DebugAdapter<P>,
+        halt_reason: HaltReason,
+    ) -> Result<bool, DebuggerError> {
+        let Some(hit) = self.find_hit_breakpoint(halt_reason)? else {
+            return Ok(true);
+        };
+
+        let conditions = self.hit_breakpoint_state(hit).0.clone();
+        if conditions.is_empty() {
+            return Ok(true);
+        }
+
+        // The stack frames are needed to evaluate expressions. If the core halts,
+        // they are recomputed when polling the cores, so we don't keep them.
+        let initial_registers = DebugRegisters::from_core(&mut self.core);
+        let exception_interface = exception_handler_for_core(self.core.core_type());
+        let instruction_set = self.core.instruction_set().ok();
+        self.core_data.stack_frames = self.core_data.debug_info.unwind(
+            &mut self.core,
+            initial_registers,
+            exception_interface.as_ref(),
+            instruction_set,
+        )?;
+
+        if let Some(condition) = &conditions.condition {
+            let condition_met = self
+                .evaluate_in_frame(condition)
+                .and_then(|value| value.is_true().map_err(|error| error.to_string()));
+            match condition_met {
+                Ok(true) => {}
+                Ok(false) => return Ok(false),
+                Err(error) => {
+                    // Halting lets the user fix the condition.
+                    debug_adapter.log_to_console(format!(
+                        "Failed to evaluate breakpoint condition {condition:?}: {error}"
+                    ));
+                    return Ok(true);
+                }
+            }
+        }
+
+        let (_, hit_count) = self.hit_breakpoint_state(hit);
+        *hit_count += 1;
+        let hit_count = *hit_count;
+
+        if let Some(hit_condition) = &conditions.hit_condition {
+            match hit_condition_met(hit_condition, hit_count) {
+                Ok(true) => {}
+                Ok(false) => return Ok(false),
+                Err(error) => {
+                    debug_adapter.log_to_console(error);
+                    return Ok(true);
+                }
+            }
+        }
+
+        if let Some(log_message) = &conditions.log_message {
+            let message = interpolate_log_message(log_message, &mut |expression| {
+                self.evaluate_in_frame(expression).map(|value| value.value)
+            });
+            debug_adapter.log_to_console(message);
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Evaluate an expression in the context of the current stack frame.
+    fn evaluate_in_frame(&mut self, expression: &str) -> Result<EvaluatedExpression, String> {
+        let Some(stack_frame) = self.core_data.stack_frames.first_mut() else {
+            return Err("No stack frame available".to_string());
+        };
+
+        self.core_data
+            .debug_info
+            .evaluate_expression(
+                expression,
+                &mut self.core,
+                stack_frame,
+                self.core_data.static_variables.as_mut(),
+            )
+            .map_err(|error| error.to_string())
+    }
+
     /// Search available [`probe_rs::debug::StackFrame`]'s for the given `id`
     pub(crate) fn get_stackframe(
         &'p self,
@@ -249,6 +414,7 @@ impl<'p> CoreHandle<'p> {
         &mut self,
         address: u64,
         breakpoint_type: session_data::BreakpointType,
+        conditions: BreakpointConditions,
     ) -> Result<(), DebuggerError> {
         // NOTE: After receiving a DAP [`crate::debug_adapter::dap::dap_types::BreakpointEvent`], VSCode will mistakenly
         // identify a `InstructionBreakpoint` as a `SourceBreakpoint`. This results in breakpoints not being cleared correctly from [`CoreHandle::clear_breakpoints()`].
@@ -266,6 +432,8 @@ impl<'p> CoreHandle<'p> {
             .push(session_data::ActiveBreakpoint {
                 breakpoint_type,
                 address,
+                conditions,
+                hit_count: 0,
             });
         Ok(())
     }
@@ -284,6 +452,7 @@ impl<'p> CoreHandle<'p> {
     /// Clear all breakpoints of a specified [`super::session_data::BreakpointType`].
     /// Affects target configuration as well as [`CoreData::breakpoints`].
     /// If `breakpoint_type` is of type [`super::session_data::BreakpointType::SourceBreakpoint`], then all breakpoints for the contained [`Source`] will be cleared.
+    /// If `breakpoint_type` is of type [`super::session_data::BreakpointType::FunctionBreakpoint`], then all function breakpoints will be cleared.
     pub(crate) fn clear_breakpoints(
         &mut self,
         breakpoint_type: session_data::BreakpointType,
@@ -300,6 +469,10 @@ impl<'p> CoreHandle<'p> {
                             if matches!(&breakpoint_type, BreakpointType::SourceBreakpoint{source: clear_breakpoint_source, ..}
                                 if clear_breakpoint_source == breakpoint_source)
                     )
+                 || matches!(
+                        (&target_breakpoint.breakpoint_type, &breakpoint_type),
+                        (BreakpointType::FunctionBreakpoint { .. }, BreakpointType::FunctionBreakpoint { .. })
+                    )
             })
             .map(|breakpoint| breakpoint.address)
             .collect::<Vec<u64>>();
@@ -319,6 +492,7 @@ impl<'p> CoreHandle<'p> {
         requested_breakpoint_line: u64,
         requested_breakpoint_column: Option<u64>,
         requested_source: &Source,
+        conditions: BreakpointConditions,
     ) -> Result<VerifiedBreakpoint, DebuggerError> {
         let VerifiedBreakpoint {
                  address,
@@ -338,6 +512,7 @@ impl<'p> CoreHandle<'p> {
                 source: requested_source.clone(),
                 location: SourceLocationScope::Specific(source_location.clone()),
             },
+            conditions,
         )?;
         Ok(VerifiedBreakpoint {
             address,
@@ -345,11 +520,122 @@ impl<'p> CoreHandle<'p> {
         })
     }
 
+    /// Set a breakpoint on every instance of the functions matching `name`, including inlined copies.
+    /// The Result<> contains the "verified" `address` and `SourceLocation` of all breakpoints that were set.
+    pub(crate) fn verify_and_set_function_breakpoint(
+        &mut self,
+        name: &str,
+        conditions: BreakpointConditions,
+    ) -> Result<Vec<VerifiedBreakpoint>, DebuggerError> {
+        let locations = self
+            .core_data
+            .debug_info
+            .get_function_breakpoint_locations(name)
+            .map_err(|debug_error| {
+                DebuggerError::Other(anyhow!(
+                    "Cannot set a breakpoint on {name:?}: {debug_error}"
+                ))
+            })?;
+
+        for location in &locations {
+            self.set_breakpoint(
+                location.address,
+                BreakpointType::FunctionBreakpoint {
+                    name: name.to_string(),
+                },
+                conditions.clone(),
+            )?;
+        }
+
+        Ok(locations)
+    }
+
+    /// Set a data breakpoint, which halts the core when the memory range of `watchpoint` is accessed.
+    ///
+    /// Ranges which are not naturally aligned are watched with several hardware watchpoints.
+    /// If not all of them can be set, none of them are.
+    pub(crate) fn set_data_breakpoint(
+        &mut self,
+        watchpoint: Watchpoint,
+        conditions: BreakpointConditions,
+    ) -> Result<(), DebuggerError> {
+        let parts = watchpoint.naturally_aligned_parts();
+
+        let mut watchpoints = Vec::with_capacity(parts.len());
+        for part in &parts {
+            if let Err(error) = self.core.set_hw_watchpoint(*part) {
+                for watchpoint in watchpoints {
+                    if let Err(error) = self.core.clear_hw_watchpoint(watchpoint) {
+                        tracing::warn!("Failed to clear watchpoint {watchpoint:?}: {error}");
+                    }
+                }
+
+                let message = if parts.len() > 1 {
+                    format!(
+                        "Cannot watch {} bytes at {:#010x}, which requires {} hardware watchpoints because the range is not naturally aligned: {error}",
+                        watchpoint.length,
+                        watchpoint.address,
+                        parts.len()
+                    )
+                } else {
+                    format!(
+                        "Cannot watch {} bytes at {:#010x}: {error}",
+                        watchpoint.length, watchpoint.address
+                    )
+                };
+                return Err(DebuggerError::UserMessage(message));
+            }
+            watchpoints.push(*part);
+        }
+
+        self.core_data
+            .data_breakpoints
+            .push(session_data::ActiveDataBreakpoint {
+                watchpoints,
+                conditions,
+                hit_count: 0,
+            });
+        Ok(())
+    }
+
+    /// Clear all data breakpoints from target configuration as well as [`CoreData::data_breakpoints`].
+    pub(crate) fn clear_data_breakpoints(&mut self) -> Result<(), DebuggerError> {
+        for data_breakpoint in std::mem::take(&mut self.core_data.data_breakpoints) {
+            for watchpoint in data_breakpoint.watchpoints {
+                self.core
+                    .clear_hw_watchpoint(watchpoint)
+                    .map_err(DebuggerError::ProbeRs)?;
+            }
+        }
+        Ok(())
+    }
+
     /// In the case where a new binary is flashed as part of a restart, we need to recompute the breakpoint address,
-    /// for a specified source location, of any [`super::session_data::BreakpointType::SourceBreakpoint`].
+    /// for a specified source location, of any [`super::session_data::BreakpointType::SourceBreakpoint`],
+    /// and for the function of any [`super::session_data::BreakpointType::FunctionBreakpoint`].
     /// This is because the address of the breakpoint may have changed based on changes in the source file that created the new binary.
     pub(crate) fn recompute_breakpoints(&mut self) -> Result<(), DebuggerError> {
         let target_breakpoints = self.core_data.breakpoints.clone();
+
+        // A function breakpoint can have several addresses, which are all recomputed at once.
+        let mut function_breakpoints: Vec<(String, BreakpointConditions)> = Vec::new();
+        for breakpoint in &target_breakpoints {
+            if let BreakpointType::FunctionBreakpoint { name } = &breakpoint.breakpoint_type {
+                self.clear_breakpoint(breakpoint.address)?;
+                if !function_breakpoints.iter().any(|(known, _)| known == name) {
+                    function_breakpoints.push((name.clone(), breakpoint.conditions.clone()));
+                }
+            }
+        }
+        for (name, conditions) in function_breakpoints {
+            self.verify_and_set_function_breakpoint(&name, conditions)
+                .map_err(|breakpoint_error| {
+                    DebuggerError::Other(anyhow!(
+                        "Failed to recompute breakpoint on function {name:?}. Error: {breakpoint_error:?}"
+                    ))
+                })?;
+        }
+
         for breakpoint in target_breakpoints
             .iter()
             .filter(|&breakpoint| {
@@ -379,6 +665,7 @@ impl<'p> CoreHandle<'p> {
                                 ColumnType::Column(c) => c,
                             }),
                             &source,
+                            breakpoint.conditions.clone(),
                         )
                     });
 
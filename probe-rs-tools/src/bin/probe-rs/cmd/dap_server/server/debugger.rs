@@ -13,7 +13,7 @@ use crate::{
                     Capabilities, Event, ExitedEventBody, InitializeRequestArguments,
                     MessageSeverity, Request, RttWindowOpenedArguments, TerminatedEventBody,
                 },
-                request_helpers::halt_core,
+                request_helpers::{exception_breakpoint_filters, halt_core},
             },
             protocol::ProtocolAdapter,
         },
@@ -182,6 +182,10 @@ impl Debugger {
                     | "setBreakpoint"
                     | "setBreakpoints"
                     | "setInstructionBreakpoints"
+                    | "setFunctionBreakpoints"
+                    | "setExceptionBreakpoints"
+                    | "dataBreakpointInfo"
+                    | "setDataBreakpoints"
                     | "clearBreakpoint"
                     | "stackTrace"
                     | "threads"
@@ -280,6 +284,19 @@ impl Debugger {
                     "setInstructionBreakpoints" => {
                         debug_adapter.set_instruction_breakpoints(&mut target_core, &request)
                     }
+                    "setFunctionBreakpoints" => {
+                        debug_adapter.set_function_breakpoints(&mut target_core, &request)
+                    }
+                    "setExceptionBreakpoints" => {
+                        debug_adapter.set_exception_breakpoints(&mut target_core, &request)
+                    }
+                    "exceptionInfo" => debug_adapter.exception_info(&mut target_core, &request),
+                    "dataBreakpointInfo" => {
+                        debug_adapter.data_breakpoint_info(&mut target_core, &request)
+                    }
+                    "setDataBreakpoints" => {
+                        debug_adapter.set_data_breakpoints(&mut target_core, &request)
+                    }
                     "stackTrace" => debug_adapter.stack_trace(&mut target_core, &request),
                     "scopes" => debug_adapter.scopes(&mut target_core, &request),
                     "disassemble" => debug_adapter.disassemble(&mut target_core, &request),
@@ -457,7 +474,9 @@ impl Debugger {
                 }
             };
 
-        debug_adapter.halt_after_reset = self.config.flashing_config.halt_after_reset;
+        // A core dump can't run, so the client is notified that the core is halted.
+        debug_adapter.halt_after_reset =
+            self.config.flashing_config.halt_after_reset || session_data.session.is_core_dump();
 
         if self.config.flashing_config.flashing_enabled {
             let target_core_config = match self.config.core_configs.first_mut() {
@@ -882,14 +901,17 @@ impl Debugger {
             supports_clipboard_context: Some(true),
             supports_disassemble_request: Some(true),
             supports_instruction_breakpoints: Some(true),
+            supports_conditional_breakpoints: Some(true),
+            supports_hit_conditional_breakpoints: Some(true),
+            supports_log_points: Some(true),
             supports_stepping_granularity: Some(true),
             supports_completions_request: Some(true),
             support_terminate_debuggee: Some(true),
+            exception_breakpoint_filters: Some(exception_breakpoint_filters()),
+            supports_exception_info_request: Some(true),
+            supports_function_breakpoints: Some(true),
+            supports_data_breakpoints: Some(true),
             // supports_value_formatting_options: Some(true),
-            // supports_function_breakpoints: Some(true),
-            // TODO: Use DEMCR register to implement exception breakpoints
-            // supports_exception_options: Some(true),
-            // supports_exception_filter_options: Some (true),
             ..Default::default()
         };
         debug_adapter.send_response(&initialize_request, Ok(Some(capabilities)))?;
@@ -975,6 +997,7 @@ mod test {
                     InitializeRequestArguments, Message, Request, Response, Thread,
                     ThreadsResponseBody,
                 },
+                request_helpers::exception_breakpoint_filters,
             },
             protocol::ProtocolAdapter,
         },
@@ -1027,16 +1050,23 @@ mod test {
             support_suspend_debuggee: Some(true),
             supports_clipboard_context: Some(true),
             supports_completions_request: Some(true),
+            supports_conditional_breakpoints: Some(true),
             supports_configuration_done_request: Some(true),
             supports_delayed_stack_trace_loading: Some(true),
             supports_disassemble_request: Some(true),
+            supports_hit_conditional_breakpoints: Some(true),
             supports_instruction_breakpoints: Some(true),
+            supports_log_points: Some(true),
             supports_read_memory_request: Some(true),
             supports_write_memory_request: Some(true),
             supports_restart_request: Some(true),
             supports_set_variable: Some(true),
             supports_stepping_granularity: Some(true),
             support_terminate_debuggee: Some(true),
+            exception_breakpoint_filters: Some(exception_breakpoint_filters()),
+            supports_exception_info_request: Some(true),
+            supports_function_breakpoints: Some(true),
+            supports_data_breakpoints: Some(true),
 
             ..Default::default()
         }
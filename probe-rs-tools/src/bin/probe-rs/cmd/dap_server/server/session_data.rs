@@ -15,16 +15,20 @@ use crate::{
 use anyhow::{anyhow, Result};
 use probe_rs::{
     config::TargetSelector,
-    debug::{debug_info::DebugInfo, DebugRegisters, SourceLocation},
+    debug::{
+        debug_info::{DebugInfo, DebugInfoOptions},
+        DebugRegisters, SourceLocation,
+    },
     exception_handler_for_core,
     probe::list::Lister,
-    CoreStatus, Session,
+    CoreStatus, Session, Watchpoint,
 };
 use std::env::set_current_dir;
 use time::UtcOffset;
 
 /// The supported breakpoint types
 #[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum BreakpointType {
     /// A breakpoint was requested using an instruction address, and usually a result of a user requesting a
     /// breakpoint while in a 'disassembly' view.
@@ -34,6 +38,8 @@ pub(crate) enum BreakpointType {
         source: Source,
         location: SourceLocationScope,
     },
+    /// A breakpoint on all the instances of a function, requested by the function name.
+    FunctionBreakpoint { name: String },
 }
 
 /// Breakpoint requests will either be refer to a specific `SourceLocation`, or unspecified, in which case it will refer to
@@ -44,11 +50,42 @@ pub(crate) enum SourceLocationScope {
     Specific(SourceLocation),
 }
 
+/// The optional conditions of a breakpoint, which decide if the core stays halted when the breakpoint is hit.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct BreakpointConditions {
+    /// An expression which has to be true for the breakpoint to halt the core.
+    pub(crate) condition: Option<String>,
+    /// Decides on which hits the breakpoint halts the core, e.g. `5`, `>= 5` or `% 5`.
+    pub(crate) hit_condition: Option<String>,
+    /// A message with `{expression}` placeholders, which is logged instead of halting the core.
+    pub(crate) log_message: Option<String>,
+}
+
+impl BreakpointConditions {
+    /// Returns `true` if the breakpoint always halts the core.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.condition.is_none() && self.hit_condition.is_none() && self.log_message.is_none()
+    }
+}
+
 /// Provide the storage and methods to handle various [`BreakpointType`]
 #[derive(Clone, Debug)]
 pub struct ActiveBreakpoint {
     pub(crate) breakpoint_type: BreakpointType,
     pub(crate) address: u64,
+    pub(crate) conditions: BreakpointConditions,
+    /// The number of times the breakpoint was hit while its `condition` was true.
+    pub(crate) hit_count: u64,
+}
+
+/// A data breakpoint, which watches a memory range with one or more hardware watchpoints.
+#[derive(Clone, Debug)]
+pub struct ActiveDataBreakpoint {
+    /// The naturally aligned parts of the watched range, one per watchpoint unit.
+    pub(crate) watchpoints: Vec<Watchpoint>,
+    pub(crate) conditions: BreakpointConditions,
+    /// The number of times the data breakpoint was hit while its `condition` was true.
+    pub(crate) hit_count: u64,
 }
 
 /// SessionData is designed to be similar to [probe_rs::Session], in as much that it provides handles to the [CoreHandle] instances for each of the available [probe_rs::Core] involved in the debug session.
@@ -71,11 +108,24 @@ impl SessionData {
         config: &mut configuration::SessionConfig,
         timestamp_offset: UtcOffset,
     ) -> Result<Self, DebuggerError> {
-        let target_selector = TargetSelector::from(config.chip.as_deref());
-
         let options = config.probe_options().load()?;
-        let target_probe = options.attach_probe(lister)?;
-        let target_session = options
+
+        let core_dump = config.core_configs.iter().find_map(|core_config| {
+            Some((core_config.core_index, core_config.core_dump.as_ref()?))
+        });
+        let target_session = if let Some((core_index, core_dump)) = core_dump {
+            // The state of the core is loaded from the core dump, instead of attaching to a probe.
+            if core_index != 0 {
+                return Err(DebuggerError::UserMessage(
+                    "A `coreDump` can only be debugged for the core with `coreIndex` 0."
+                        .to_string(),
+                ));
+            }
+            options.core_dump_session(core_dump)?
+        } else {
+            let target_selector = TargetSelector::from(config.chip.as_deref());
+            let target_probe = options.attach_probe(lister)?;
+            options
             .attach_session(target_probe, target_selector)
             .map_err(|operation_error| {
                 match operation_error {
@@ -96,7 +146,8 @@ impl SessionData {
                     // Return the orginal error.
                     other => other.into(),
                 }
-            })?;
+            })?
+        };
 
         // Change the current working directory if `config.cwd` is `Some(T)`.
         if let Some(new_cwd) = config.cwd.clone() {
@@ -141,6 +192,7 @@ impl SessionData {
                 core_peripherals: None,
                 stack_frames: vec![],
                 breakpoints: vec![],
+                data_breakpoints: vec![],
                 rtt_connection: None,
                 rtt_client: None,
             })
@@ -327,5 +379,27 @@ fn debug_info_from_binary(core_configuration: &CoreConfig) -> anyhow::Result<Deb
         ));
     };
 
-    DebugInfo::from_file(binary_path).map_err(|error| anyhow!(error))
+    let options = DebugInfoOptions {
+        debug_file_directories: core_configuration.debug_file_directories.clone(),
+        ..Default::default()
+    };
+    let mut debug_info =
+        DebugInfo::from_file_with_options(binary_path, &options).map_err(|error| anyhow!(error))?;
+    for symbol_file in &core_configuration.symbol_files {
+        let options = DebugInfoOptions {
+            load_offset: symbol_file.load_offset,
+            ..options.clone()
+        };
+        let object =
+            DebugInfo::from_file_with_options(&symbol_file.path, &options).map_err(|error| {
+                anyhow!(
+                    "Failed to load symbol file {}: {error}",
+                    symbol_file.path.display()
+                )
+            })?;
+        debug_info.add_object(object);
+    }
+    debug_info.set_path_substitutions(core_configuration.path_substitutions.clone());
+
+    Ok(debug_info)
 }
@@ -19,12 +19,25 @@ use probe_rs::CoreDump;
 use probe_rs::CoreDumpError;
 use probe_rs::CoreInterface;
 use probe_rs::{
-    debug::{debug_info::DebugInfo, registers::DebugRegisters, stack_frame::StackFrame},
+    debug::{
+        debug_info::{DebugInfo, DebugInfoOptions},
+        registers::DebugRegisters,
+        stack_frame::StackFrame,
+        PathSubstitution,
+    },
     Core, CoreType, InstructionSet, MemoryInterface, RegisterValue,
 };
 use rustyline::{error::ReadlineError, DefaultEditor};
+use typed_path::TypedPathBuf;
 
-use crate::{util::common_options::ProbeOptions, CoreOptions};
+use crate::{
+    util::{
+        common_options::ProbeOptions,
+        fault_report::{fault_report, interrupt_names_from_svd, InterruptNames},
+        parse_u64,
+    },
+    CoreOptions,
+};
 
 #[derive(clap::Parser)]
 pub struct Cmd {
@@ -37,22 +50,87 @@ pub struct Cmd {
     #[clap(long, value_parser)]
     /// Binary to debug
     exe: Option<PathBuf>,
+
+    /// Replace the prefix FROM of source paths in the debug information with TO, e.g. when the
+    /// binary was built in a container. Can be given multiple times, the first matching rule is used.
+    #[clap(long = "substitute-path", value_name = "FROM=TO")]
+    substitute_path: Vec<PathSubstitution>,
+
+    /// Load the debug information of another binary which runs on the core, e.g. the application
+    /// started by a bootloader. Append `@OFFSET` if the binary is not loaded at the address it was
+    /// linked at. Can be given multiple times.
+    #[clap(long = "symbol-file", value_name = "PATH[@OFFSET]", value_parser = parse_symbol_file)]
+    symbol_files: Vec<(PathBuf, u64)>,
+
+    /// Search for the debug information of stripped binaries in this directory, by build ID or by
+    /// the name in the `.gnu_debuglink` section. Can be given multiple times.
+    #[clap(long = "debug-file-directory", value_name = "DIR")]
+    debug_file_directories: Vec<PathBuf>,
+
+    /// Debug the state of a core in a core dump, e.g. created with the `dump` command, instead of
+    /// attaching to a probe. The core dump is read-only.
+    #[clap(long, value_name = "PATH")]
+    core_dump: Option<PathBuf>,
+
+    /// CMSIS-SVD file of the target, which is used to name the active interrupt in the `fault` command.
+    #[clap(long, value_name = "PATH")]
+    svd: Option<PathBuf>,
+}
+
+/// Parse a symbol file in the form `PATH[@OFFSET]`.
+fn parse_symbol_file(input: &str) -> Result<(PathBuf, u64), String> {
+    match input.rsplit_once('@') {
+        Some((path, offset)) => {
+            let offset = parse_u64(offset)
+                .map_err(|error| format!("'{offset}' is not a valid load offset: {error}"))?;
+            Ok((PathBuf::from(path), offset))
+        }
+        None => Ok((PathBuf::from(input), 0)),
+    }
 }
 
 impl Cmd {
     pub fn run(self, lister: &Lister) -> anyhow::Result<()> {
-        let (mut session, _probe_options) = self.common.simple_attach(lister)?;
+        let (mut session, _probe_options) = match &self.core_dump {
+            Some(path) => self.common.core_dump_attach(path)?,
+            None => self.common.simple_attach(lister)?,
+        };
 
+        let options = DebugInfoOptions {
+            debug_file_directories: self.debug_file_directories.clone(),
+            ..Default::default()
+        };
         let di = self
             .exe
             .as_ref()
-            .and_then(|path| DebugInfo::from_file(path).ok());
+            .and_then(|path| DebugInfo::from_file_with_options(path, &options).ok())
+            .map(|mut di| {
+                for (path, load_offset) in &self.symbol_files {
+                    let options = DebugInfoOptions {
+                        load_offset: *load_offset,
+                        ..options.clone()
+                    };
+                    match DebugInfo::from_file_with_options(path, &options) {
+                        Ok(object) => di.add_object(object),
+                        Err(error) => {
+                            println!("Failed to load symbol file {}: {error}", path.display())
+                        }
+                    }
+                }
+                di.set_path_substitutions(self.substitute_path.clone());
+                di
+            });
+
+        let interrupt_names = match &self.svd {
+            Some(path) => interrupt_names_from_svd(path)?,
+            None => InterruptNames::new(),
+        };
 
         let cli = DebugCli::new();
 
         let core = session.core(self.shared.core)?;
 
-        let mut cli_data = CliData::new(core, di)?;
+        let mut cli_data = CliData::new(core, di, interrupt_names)?;
 
         let mut rl = DefaultEditor::new()?;
 
@@ -63,7 +141,14 @@ impl Cmd {
                 Ok(line) => {
                     let history_entry: &str = line.as_ref();
                     rl.add_history_entry(history_entry)?;
-                    let cli_state = cli.handle_line(&line, &mut cli_data)?;
+                    let cli_state = match cli.handle_line(&line, &mut cli_data) {
+                        // A core dump can't be changed, but it can still be inspected.
+                        Err(CliError::ProbeRs(error @ probe_rs::Error::CoreDumpReadOnly(_))) => {
+                            println!("{error}");
+                            CliState::Continue
+                        }
+                        other => other?,
+                    };
 
                     if cli_state == CliState::Stop {
                         break;
@@ -128,6 +213,20 @@ where
     })
 }
 
+/// Parse a `<file>:<line>` argument. Relative file paths are resolved against the current directory.
+fn source_line_argument(argument: &str) -> Option<(TypedPathBuf, u64)> {
+    let (file, line) = argument.rsplit_once(':')?;
+    let line = line.parse().ok()?;
+    let path = std::env::current_dir()
+        .map(|directory| directory.join(file))
+        .unwrap_or_else(|_| PathBuf::from(file));
+
+    Some((
+        TypedPathBuf::from(path.to_string_lossy().into_owned()),
+        line,
+    ))
+}
+
 impl DebugCli {
     fn new() -> DebugCli {
         let mut cli = DebugCli {
@@ -319,6 +418,28 @@ impl DebugCli {
             },
         });
 
+        cli.add_command(Command {
+            name: "fault",
+            help_text: "Show a report of the active exception, including the fault status registers and the stacked frame. Use `fault json` to print it as JSON.",
+
+            function: |cli_data, args| {
+                if !cli_data.core.core_halted()? {
+                    println!("The core is not halted.");
+                    return Ok(CliState::Continue);
+                }
+
+                match fault_report(&mut cli_data.core, &cli_data.interrupt_names)? {
+                    Some(report) if args.first() == Some(&"json") => {
+                        println!("{}", serde_json::to_string_pretty(&report).map_err(anyhow::Error::from)?);
+                    }
+                    Some(report) => print!("{report}"),
+                    None => println!("No active exception."),
+                }
+
+                Ok(CliState::Continue)
+            },
+        });
+
         cli.add_command(Command {
             name: "run",
             help_text: "Resume execution of the CPU",
@@ -511,14 +632,44 @@ impl DebugCli {
 
         cli.add_command(Command {
             name: "break",
-            help_text: "Set a breakpoint at a specific address",
+            help_text: "Set a breakpoint at a specific address, a source line, or on all instances of a function: break <address>|<file>:<line>|<function>",
 
             function: |cli_data, args| {
-                let address = get_int_argument(args, 0)?;
+                let argument = args.first().ok_or(CliError::MissingArgument)?;
+                if parse::<u64>(argument).is_ok() {
+                    let address = get_int_argument(args, 0)?;
 
-                cli_data.core.set_hw_breakpoint(address)?;
+                    cli_data.core.set_hw_breakpoint(address)?;
 
-                println!("Set new breakpoint at address {address:#08x}");
+                    println!("Set new breakpoint at address {address:#08x}");
+
+                    return Ok(CliState::Continue);
+                }
+
+                let Some(di) = &cli_data.debug_info else {
+                    println!("No debug information present!");
+                    return Ok(CliState::Continue);
+                };
+
+                let locations = match source_line_argument(argument) {
+                    Some((file, line)) => vec![di
+                        .get_breakpoint_location(&file, line, None)
+                        .map_err(|error| anyhow!("{error}"))?],
+                    None => di
+                        .get_function_breakpoint_locations(argument)
+                        .map_err(|error| anyhow!("{error}"))?,
+                };
+                for location in locations {
+                    cli_data.core.set_hw_breakpoint(location.address)?;
+
+                    print!("Set new breakpoint at address {:#08x}", location.address);
+                    if let (Some(file), Some(line)) =
+                        (&location.source_location.file, location.source_location.line)
+                    {
+                        print!(" ({file}:{line})");
+                    }
+                    println!();
+                }
 
                 Ok(CliState::Continue)
             },
@@ -853,7 +1004,7 @@ impl DebugCli {
 
         cli.add_command(Command {
             name: "dump",
-            help_text: "Dump the core memory & registers",
+            help_text: "Dump the core memory & registers. Use a path with the `.elf` extension to create an ELF core file.",
 
             function: |cli_data, args| {
                 let mut args = args.to_vec();
@@ -903,7 +1054,12 @@ impl DebugCli {
 
                 println!("Dumping core");
 
-                CoreDump::dump_core(&mut cli_data.core, ranges)?.store(location)?;
+                let core_dump = CoreDump::dump_core(&mut cli_data.core, ranges)?;
+                if location.extension().is_some_and(|extension| extension == "elf") {
+                    core_dump.store_elf(location)?;
+                } else {
+                    core_dump.store(location)?;
+                }
 
                 println!("Done.");
 
@@ -982,15 +1138,21 @@ impl DebugCli {
 pub struct CliData<'p> {
     pub core: Core<'p>,
     pub debug_info: Option<DebugInfo>,
+    pub interrupt_names: InterruptNames,
 
     state: DebugState,
 }
 
 impl<'p> CliData<'p> {
-    fn new(core: Core<'p>, debug_info: Option<DebugInfo>) -> Result<CliData<'p>, CliError> {
+    fn new(
+        core: Core<'p>,
+        debug_info: Option<DebugInfo>,
+        interrupt_names: InterruptNames,
+    ) -> Result<CliData<'p>, CliError> {
         let mut cli_data = CliData {
             core,
             debug_info,
+            interrupt_names,
             state: DebugState::default(),
         };
 
@@ -1,6 +1,9 @@
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use anyhow::Context;
 use parking_lot::FairMutex;
+use probe_rs::gdb_server::PeripheralRegister;
 use probe_rs::probe::list::Lister;
 
 use crate::util::common_options::ProbeOptions;
@@ -20,13 +23,25 @@ pub struct Cmd {
     )]
     reset_halt: bool,
 
+    /// CMSIS-SVD file describing the peripheral registers, which can then be accessed with `monitor reg`.
+    #[clap(long)]
+    svd: Option<PathBuf>,
+
+    /// Debug the state of a core in a core dump, e.g. created with the `dump` command of
+    /// `probe-rs debug`, instead of attaching to a probe. The core dump is read-only.
+    #[clap(long, value_name = "PATH", conflicts_with = "reset-halt")]
+    core_dump: Option<PathBuf>,
+
     #[clap(flatten)]
     common: ProbeOptions,
 }
 
 impl Cmd {
     pub fn run(self, lister: &Lister) -> anyhow::Result<()> {
-        let (mut session, _probe_options) = self.common.simple_attach(lister)?;
+        let (mut session, _probe_options) = match &self.core_dump {
+            Some(path) => self.common.core_dump_attach(path)?,
+            None => self.common.simple_attach(lister)?,
+        };
 
         if self.reset_halt {
             session
@@ -38,11 +53,18 @@ impl Cmd {
             .gdb_connection_string
             .unwrap_or_else(|| "localhost:1337".to_string());
 
-        let instances = probe_rs::gdb_server::GdbInstanceConfiguration::from_session(
+        let mut instances = probe_rs::gdb_server::GdbInstanceConfiguration::from_session(
             &session,
             Some(gdb_connection_string),
         );
 
+        if let Some(svd) = &self.svd {
+            let registers = peripheral_registers_from_svd(svd)?;
+            for instance in instances.iter_mut() {
+                instance.peripheral_registers = registers.clone();
+            }
+        }
+
         for instance in instances.iter() {
             println!(
                 "Firing up GDB stub for {:?} cores at {:?}",
@@ -60,3 +82,35 @@ impl Cmd {
         Ok(())
     }
 }
+
+/// Load the peripheral registers from a CMSIS-SVD file.
+fn peripheral_registers_from_svd(path: &Path) -> anyhow::Result<Vec<PeripheralRegister>> {
+    let svd_xml = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read SVD file {}", path.display()))?;
+
+    let device = svd_parser::parse_with_config(
+        &svd_xml,
+        &svd_parser::Config::default()
+            .expand(true)
+            .ignore_enums(true),
+    )
+    .map_err(|e| anyhow::anyhow!("Unable to parse CMSIS-SVD file {}: {e:?}", path.display()))?;
+
+    let mut registers = Vec::new();
+    for peripheral in &device.peripherals {
+        for register in peripheral.all_registers() {
+            registers.push(PeripheralRegister {
+                name: format!("{}.{}", peripheral.name, register.name),
+                address: peripheral.base_address + register.address_offset as u64,
+                size: register
+                    .properties
+                    .size
+                    .or(peripheral.default_register_properties.size)
+                    .or(device.default_register_properties.size)
+                    .unwrap_or(32),
+            });
+        }
+    }
+
+    Ok(registers)
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use goblin::elf::{program_header::PT_LOAD, Elf};
+use probe_rs::architecture::arm::{
+    component::MtbBuffer,
+    instruction_trace::{ProgramImage, TraceEvent},
+};
+use probe_rs::probe::list::Lister;
+
+use crate::cmd::profile::Symbols;
+use crate::util::{common_options::ProbeOptions, parse_u32};
+use crate::CoreOptions;
+
+#[derive(clap::Parser)]
+pub struct Cmd {
+    #[clap(flatten)]
+    shared: CoreOptions,
+
+    #[clap(flatten)]
+    common: ProbeOptions,
+
+    /// The ELF file of the program running on the target.
+    path: PathBuf,
+
+    /// The number of source lines to print.
+    #[clap(long, short = 'n', default_value_t = 20)]
+    lines: usize,
+
+    /// Reset the core before starting the trace.
+    #[clap(long)]
+    reset: bool,
+
+    /// Halt the core after this many seconds, if it did not halt by itself.
+    #[clap(long)]
+    timeout: Option<u64>,
+
+    /// The address of a RAM buffer for the MTB of Cortex-M0+ cores. The firmware must not use
+    /// this memory.
+    #[clap(long, value_parser = parse_u32)]
+    mtb_buffer: Option<u32>,
+
+    /// The size of the MTB buffer in bytes.
+    #[clap(long, value_parser = parse_u32, default_value = "1024")]
+    mtb_size: u32,
+}
+
+impl Cmd {
+    pub fn run(self, lister: &Lister) -> anyhow::Result<()> {
+        let elf = std::fs::read(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))?;
+        let image = program_image(&elf)?;
+        let symbols = Symbols::try_from(&self.path).map_err(|e| {
+            anyhow!(
+                "Failed to read symbol data from {}: {}",
+                self.path.display(),
+                e
+            )
+        })?;
+
+        let (mut session, _probe_options) = self.common.simple_attach(lister)?;
+
+        if self.reset {
+            session
+                .core(self.shared.core)?
+                .reset_and_halt(Duration::from_millis(100))?;
+        }
+
+        let mtb_buffer = self.mtb_buffer.map(|address| MtbBuffer {
+            address,
+            size: self.mtb_size,
+        });
+        session.setup_instruction_trace(self.shared.core, mtb_buffer)?;
+
+        let halt_address = {
+            let mut core = session.core(self.shared.core)?;
+            if core.core_halted()? {
+                core.run()?;
+            }
+
+            eprintln!("Tracing until the core halts...");
+            let start = Instant::now();
+            while !core.core_halted()? {
+                if self
+                    .timeout
+                    .is_some_and(|timeout| start.elapsed() > Duration::from_secs(timeout))
+                {
+                    core.halt(Duration::from_millis(100))?;
+                    break;
+                }
+                sleep(Duration::from_millis(10));
+            }
+
+            let pc = core.program_counter();
+            core.read_core_reg::<u32>(pc)?
+        };
+
+        let trace = session.read_instruction_trace()?;
+        let events = trace.decode(&image, Some(halt_address));
+
+        let lines = source_lines(&events, self.lines, |address| {
+            symbols.get_location(u64::from(address))
+        });
+        if lines.is_empty() {
+            println!("No source lines were traced before the core halted at {halt_address:#010x}.");
+            return Ok(());
+        }
+
+        println!("Source lines executed before the core halted at {halt_address:#010x}:");
+        let mut sources = HashMap::new();
+        for line in lines {
+            match line {
+                SourceLine::Line(file, line) => {
+                    let text = sources
+                        .entry(file.clone())
+                        .or_insert_with(|| std::fs::read_to_string(&file).ok())
+                        .as_deref()
+                        .and_then(|source| source.lines().nth(line.saturating_sub(1) as usize))
+                        .map(str::trim)
+                        .unwrap_or_default();
+                    println!("  {}:{line:<5} {text}", display_path(&file));
+                }
+                SourceLine::Exception => println!("  -- exception --"),
+                SourceLine::Gap => println!("  -- trace data lost --"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An entry of the executed source lines.
+#[derive(Debug, PartialEq, Eq)]
+enum SourceLine {
+    /// A source line, by file and line number.
+    Line(String, u32),
+    /// An exception was taken.
+    Exception,
+    /// The trace is incomplete.
+    Gap,
+}
+
+/// Returns the last `count` source lines of the traced instructions, oldest first.
+///
+/// Consecutive instructions of the same source line are reported as a single line.
+fn source_lines(
+    events: &[TraceEvent],
+    count: usize,
+    location: impl Fn(u32) -> Option<(String, u32)>,
+) -> Vec<SourceLine> {
+    let mut lines = Vec::new();
+    let mut found = 0;
+
+    for event in events.iter().rev() {
+        let line = match event {
+            TraceEvent::Instruction(address) => match location(*address) {
+                Some((file, line)) => SourceLine::Line(file, line),
+                None => continue,
+            },
+            TraceEvent::Exception => SourceLine::Exception,
+            TraceEvent::Gap => SourceLine::Gap,
+        };
+
+        if lines.last() == Some(&line) {
+            continue;
+        }
+        if matches!(line, SourceLine::Line(..)) {
+            if found == count {
+                break;
+            }
+            found += 1;
+        }
+        lines.push(line);
+    }
+
+    lines.reverse();
+    lines
+}
+
+/// Shortens paths within the current directory.
+fn display_path(file: &str) -> String {
+    let path = Path::new(file);
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Collects the loadable segments of the ELF file, to follow the program flow.
+fn program_image(elf: &[u8]) -> anyhow::Result<ProgramImage> {
+    let elf_file = Elf::parse(elf)?;
+
+    let mut image = ProgramImage::new();
+    for segment in elf_file
+        .program_headers
+        .iter()
+        .filter(|segment| segment.p_type == PT_LOAD && segment.p_filesz > 0)
+    {
+        let data = elf
+            .get(segment.file_range())
+            .ok_or_else(|| anyhow!("The ELF file is truncated"))?;
+        image.add_segment(segment.p_vaddr as u32, data.to_vec());
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn last_source_lines() {
+        let events = [
+            TraceEvent::Instruction(0x100),
+            TraceEvent::Instruction(0x102),
+            TraceEvent::Exception,
+            TraceEvent::Instruction(0x200),
+            TraceEvent::Instruction(0x202),
+            TraceEvent::Instruction(0x300),
+            TraceEvent::Instruction(0x104),
+        ];
+        let location = |address: u32| match address {
+            0x100..=0x1FF => Some(("main.rs".to_string(), address / 2)),
+            0x200..=0x2FF => Some(("handler.rs".to_string(), 1)),
+            _ => None,
+        };
+
+        assert_eq!(
+            source_lines(&events, 2, location),
+            vec![
+                SourceLine::Exception,
+                SourceLine::Line("handler.rs".to_string(), 1),
+                SourceLine::Line("main.rs".to_string(), 0x82),
+            ]
+        );
+        assert_eq!(source_lines(&events, 10, location).len(), 5);
+    }
+}
@@ -0,0 +1,424 @@
+//! Aggregation of sampled call stacks, and export to the folded stack, pprof and callgrind formats.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::time::Duration;
+
+use flate2::{write::GzEncoder, Compression};
+use probe_rs::debug::StackFrame;
+
+/// The file format a call stack profile is written in.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallStackFormat {
+    /// Folded stacks, one line per call path, as consumed by `inferno` and `flamegraph.pl`
+    Folded,
+    /// Gzipped pprof protobuf, as consumed by `pprof`
+    Pprof,
+    /// Callgrind, as consumed by `kcachegrind`
+    Callgrind,
+}
+
+/// A function in a sampled call stack.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Frame {
+    /// The name of the function.
+    pub function: String,
+    /// The address of the instruction executed in this frame.
+    pub address: u64,
+    /// The source file of the function.
+    pub file: Option<String>,
+    /// The source line of the instruction executed in this frame.
+    pub line: Option<u64>,
+    /// Whether the function was inlined into the function of the next frame.
+    pub inlined: bool,
+}
+
+impl From<&StackFrame> for Frame {
+    fn from(frame: &StackFrame) -> Self {
+        let location = frame.source_location.as_ref();
+        Self {
+            function: frame.function_name.clone(),
+            address: frame.pc.try_into().unwrap_or_default(),
+            file: location
+                .and_then(|location| location.combined_typed_path())
+                .map(|path| path.to_path().display().to_string()),
+            line: location.and_then(|location| location.line),
+            inlined: frame.is_inlined,
+        }
+    }
+}
+
+/// The call stacks sampled while profiling.
+#[derive(Debug, Default)]
+pub struct CallStackProfile {
+    /// The number of times each call stack was sampled. The innermost frame comes first.
+    stacks: HashMap<Vec<Frame>, u64>,
+    samples: u64,
+    duration: Duration,
+}
+
+impl CallStackProfile {
+    /// Records a sampled call stack, with the innermost frame first.
+    pub fn add_sample(&mut self, stack: Vec<Frame>) {
+        *self.stacks.entry(stack).or_default() += 1;
+        self.samples += 1;
+    }
+
+    /// Sets how long the profile was recorded for.
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+
+    /// Writes the profile in the given format.
+    pub fn write(&self, format: CallStackFormat, writer: &mut dyn Write) -> std::io::Result<()> {
+        match format {
+            CallStackFormat::Folded => self.write_folded(writer),
+            CallStackFormat::Pprof => {
+                let mut encoder = GzEncoder::new(writer, Compression::default());
+                encoder.write_all(&self.encode_pprof())?;
+                encoder.finish()?;
+                Ok(())
+            }
+            CallStackFormat::Callgrind => self.write_callgrind(writer),
+        }
+    }
+
+    /// Writes one `outer;inner count` line per call path.
+    fn write_folded(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+        let mut folded = BTreeMap::<String, u64>::new();
+        for (stack, count) in &self.stacks {
+            let path = stack
+                .iter()
+                .rev()
+                .map(|frame| frame.function.replace(';', ":"))
+                .collect::<Vec<_>>()
+                .join(";");
+            *folded.entry(path).or_default() += count;
+        }
+
+        for (path, count) in folded {
+            writeln!(writer, "{path} {count}")?;
+        }
+        Ok(())
+    }
+
+    fn write_callgrind(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+        #[derive(Default)]
+        struct FunctionCosts<'a> {
+            /// The samples in the function itself, by line.
+            own: BTreeMap<u64, u64>,
+            /// The samples in called functions, by calling line and callee.
+            calls: BTreeMap<(u64, FunctionKey<'a>, u64), u64>,
+        }
+        type FunctionKey<'a> = (Option<&'a str>, &'a str);
+
+        fn key(frame: &Frame) -> FunctionKey<'_> {
+            (frame.file.as_deref(), &frame.function)
+        }
+
+        let mut functions = BTreeMap::<FunctionKey, FunctionCosts>::new();
+        for (stack, &count) in &self.stacks {
+            let Some(innermost) = stack.first() else {
+                continue;
+            };
+            *functions
+                .entry(key(innermost))
+                .or_default()
+                .own
+                .entry(innermost.line.unwrap_or_default())
+                .or_default() += count;
+
+            for (callee, caller) in stack.iter().zip(stack.iter().skip(1)) {
+                *functions
+                    .entry(key(caller))
+                    .or_default()
+                    .calls
+                    .entry((
+                        caller.line.unwrap_or_default(),
+                        key(callee),
+                        callee.line.unwrap_or_default(),
+                    ))
+                    .or_default() += count;
+            }
+        }
+
+        writeln!(writer, "# callgrind format")?;
+        writeln!(writer, "version: 1")?;
+        writeln!(writer, "creator: probe-rs")?;
+        writeln!(writer, "positions: line")?;
+        writeln!(writer, "events: Samples")?;
+        writeln!(writer, "totals: {}", self.samples)?;
+
+        for ((file, function), costs) in functions {
+            writeln!(writer)?;
+            writeln!(writer, "fl={}", file.unwrap_or("???"))?;
+            writeln!(writer, "fn={function}")?;
+            for (line, count) in costs.own {
+                writeln!(writer, "{line} {count}")?;
+            }
+            for ((line, (callee_file, callee), callee_line), count) in costs.calls {
+                writeln!(writer, "cfl={}", callee_file.unwrap_or("???"))?;
+                writeln!(writer, "cfn={callee}")?;
+                writeln!(writer, "calls={count} {callee_line}")?;
+                writeln!(writer, "{line} {count}")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the profile as an uncompressed `perftools.profiles.Profile` protobuf message.
+    fn encode_pprof(&self) -> Vec<u8> {
+        let mut strings = StringTable::default();
+        let mut functions = HashMap::<(&str, Option<&str>), u64>::new();
+        let mut encoded_functions = Vec::new();
+        let mut locations = HashMap::<(u64, Vec<(u64, u64)>), u64>::new();
+        let mut encoded_locations = Vec::new();
+        let mut encoded_samples = Vec::new();
+
+        let samples_string = strings.index("samples");
+        let count_string = strings.index("count");
+        let mut value_type = Vec::new();
+        protobuf::uint(&mut value_type, 1, samples_string);
+        protobuf::uint(&mut value_type, 2, count_string);
+
+        for (stack, &count) in &self.stacks {
+            let mut location_ids = Vec::new();
+            let mut lines = Vec::new();
+            for frame in stack {
+                let function_key = (frame.function.as_str(), frame.file.as_deref());
+                let function_id = match functions.get(&function_key) {
+                    Some(&id) => id,
+                    None => {
+                        let id = functions.len() as u64 + 1;
+                        let mut function = Vec::new();
+                        protobuf::uint(&mut function, 1, id);
+                        protobuf::uint(&mut function, 2, strings.index(&frame.function));
+                        protobuf::uint(&mut function, 3, strings.index(&frame.function));
+                        protobuf::uint(
+                            &mut function,
+                            4,
+                            strings.index(frame.file.as_deref().unwrap_or_default()),
+                        );
+                        protobuf::bytes(&mut encoded_functions, 5, &function);
+                        functions.insert(function_key, id);
+                        id
+                    }
+                };
+                lines.push((function_id, frame.line.unwrap_or_default()));
+
+                // Inlined functions share a location with the function they were inlined into,
+                // which is the last line of the location.
+                if frame.inlined {
+                    continue;
+                }
+
+                let location_key = (frame.address, std::mem::take(&mut lines));
+                let location_id = match locations.get(&location_key) {
+                    Some(&id) => id,
+                    None => {
+                        let id = locations.len() as u64 + 1;
+                        let mut location = Vec::new();
+                        protobuf::uint(&mut location, 1, id);
+                        protobuf::uint(&mut location, 3, location_key.0);
+                        for &(function_id, line) in &location_key.1 {
+                            let mut encoded_line = Vec::new();
+                            protobuf::uint(&mut encoded_line, 1, function_id);
+                            protobuf::uint(&mut encoded_line, 2, line);
+                            protobuf::bytes(&mut location, 4, &encoded_line);
+                        }
+                        protobuf::bytes(&mut encoded_locations, 4, &location);
+                        locations.insert(location_key, id);
+                        id
+                    }
+                };
+                location_ids.push(location_id);
+            }
+
+            let mut sample = Vec::new();
+            protobuf::packed(&mut sample, 1, &location_ids);
+            protobuf::packed(&mut sample, 2, &[count]);
+            protobuf::bytes(&mut encoded_samples, 2, &sample);
+        }
+
+        let mut profile = Vec::new();
+        protobuf::bytes(&mut profile, 1, &value_type);
+        profile.extend_from_slice(&encoded_samples);
+        profile.extend_from_slice(&encoded_locations);
+        profile.extend_from_slice(&encoded_functions);
+        for string in &strings.strings {
+            protobuf::bytes(&mut profile, 6, string.as_bytes());
+        }
+        protobuf::uint(&mut profile, 10, self.duration.as_nanos() as u64);
+        protobuf::bytes(&mut profile, 11, &value_type);
+        protobuf::uint(&mut profile, 12, 1);
+        profile
+    }
+}
+
+/// The string table of a pprof profile, whose first entry has to be the empty string.
+struct StringTable {
+    strings: Vec<String>,
+    indices: HashMap<String, u64>,
+}
+
+impl Default for StringTable {
+    fn default() -> Self {
+        Self {
+            strings: vec![String::new()],
+            indices: HashMap::from([(String::new(), 0)]),
+        }
+    }
+}
+
+impl StringTable {
+    fn index(&mut self, string: &str) -> u64 {
+        if let Some(&index) = self.indices.get(string) {
+            return index;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.push(string.to_string());
+        self.indices.insert(string.to_string(), index);
+        index
+    }
+}
+
+/// The subset of the protobuf wire format needed to encode pprof profiles.
+mod protobuf {
+    const VARINT: u64 = 0;
+    const LENGTH_DELIMITED: u64 = 2;
+
+    pub fn varint(buffer: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            buffer.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        buffer.push(value as u8);
+    }
+
+    /// Encodes an integer field, which is omitted if it has the default value of zero.
+    pub fn uint(buffer: &mut Vec<u8>, field: u64, value: u64) {
+        if value != 0 {
+            varint(buffer, field << 3 | VARINT);
+            varint(buffer, value);
+        }
+    }
+
+    /// Encodes a string, bytes or embedded message field.
+    pub fn bytes(buffer: &mut Vec<u8>, field: u64, value: &[u8]) {
+        varint(buffer, field << 3 | LENGTH_DELIMITED);
+        varint(buffer, value.len() as u64);
+        buffer.extend_from_slice(value);
+    }
+
+    /// Encodes a packed repeated integer field.
+    pub fn packed(buffer: &mut Vec<u8>, field: u64, values: &[u64]) {
+        let mut packed = Vec::new();
+        for &value in values {
+            varint(&mut packed, value);
+        }
+        bytes(buffer, field, &packed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+
+    use super::*;
+
+    fn frame(function: &str, address: u64, line: u64, inlined: bool) -> Frame {
+        Frame {
+            function: function.to_string(),
+            address,
+            file: Some("src/main.rs".to_string()),
+            line: Some(line),
+            inlined,
+        }
+    }
+
+    fn profile() -> CallStackProfile {
+        let mut profile = CallStackProfile::default();
+        for _ in 0..3 {
+            profile.add_sample(vec![
+                frame("work", 0x200, 20, false),
+                frame("main", 0x104, 10, false),
+            ]);
+        }
+        profile.add_sample(vec![
+            frame("helper", 0x110, 30, true),
+            frame("main", 0x110, 12, false),
+        ]);
+        profile.add_sample(vec![frame("main", 0x100, 9, false)]);
+        profile
+    }
+
+    fn write(profile: &CallStackProfile, format: CallStackFormat) -> Vec<u8> {
+        let mut output = Vec::new();
+        profile.write(format, &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn folded_stacks() {
+        let output = String::from_utf8(write(&profile(), CallStackFormat::Folded)).unwrap();
+
+        assert_eq!(output, "main 1\nmain;helper 1\nmain;work 3\n");
+    }
+
+    #[test]
+    fn callgrind() {
+        let output = String::from_utf8(write(&profile(), CallStackFormat::Callgrind)).unwrap();
+
+        assert!(output.starts_with("# callgrind format\n"), "{output}");
+        assert!(output.contains("totals: 5\n"), "{output}");
+        assert!(
+            output.contains(
+                "fl=src/main.rs\nfn=main\n9 1\ncfl=src/main.rs\ncfn=work\ncalls=3 20\n10 3\ncfl=src/main.rs\ncfn=helper\ncalls=1 30\n12 1\n"
+            ),
+            "{output}"
+        );
+        assert!(output.contains("fn=work\n20 3\n"), "{output}");
+    }
+
+    #[test]
+    fn pprof() {
+        let mut profile = CallStackProfile::default();
+        profile.add_sample(vec![frame("main", 0x100, 9, false)]);
+        profile.set_duration(Duration::from_nanos(1000));
+
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(write(&profile, CallStackFormat::Pprof).as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+
+        // The value type is `samples` (string 1) in `count` (string 2).
+        let value_type = [0x08, 0x01, 0x10, 0x02];
+        let expected = [
+            &[0x0a, 0x04][..],
+            &value_type,
+            // Sample at location 1 with a value of 1.
+            &[0x12, 0x06, 0x0a, 0x01, 0x01, 0x12, 0x01, 0x01],
+            // Location 1 at 0x100, in function 1 at line 9.
+            &[
+                0x22, 0x0b, 0x08, 0x01, 0x18, 0x80, 0x02, 0x22, 0x04, 0x08, 0x01, 0x10, 0x09,
+            ],
+            // Function 1, named `main` (string 3) in `src/main.rs` (string 4).
+            &[0x2a, 0x08, 0x08, 0x01, 0x10, 0x03, 0x18, 0x03, 0x20, 0x04],
+            &[0x32, 0x00],
+            &[0x32, 0x07],
+            b"samples",
+            &[0x32, 0x05],
+            b"count",
+            &[0x32, 0x04],
+            b"main",
+            &[0x32, 0x0b],
+            b"src/main.rs",
+            // 1000 ns duration, the period type and a period of 1.
+            &[0x50, 0xe8, 0x07, 0x5a, 0x04],
+            &value_type,
+            &[0x60, 0x01],
+        ]
+        .concat();
+        assert_eq!(decoded, expected);
+    }
+}
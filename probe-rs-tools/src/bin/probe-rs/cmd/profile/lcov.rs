@@ -0,0 +1,111 @@
+//! Export of the sampled program counters as lcov tracefile, which approximates the line coverage
+//! of binaries which are not instrumented for code coverage.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use goblin::elf::{
+    section_header::{SHF_EXECINSTR, SHT_PROGBITS},
+    Elf,
+};
+
+use super::Symbols;
+
+/// Writes the number of samples of each source line as lcov tracefile.
+///
+/// All lines with code in the executable sections of `elf` are listed, so that lines which
+/// were never sampled are reported as not covered.
+pub(super) fn write_lcov(
+    symbols: &Symbols,
+    elf: &[u8],
+    samples: &HashMap<u32, u64>,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let elf = Elf::parse(elf)?;
+
+    let mut files = BTreeMap::<String, BTreeMap<u32, u64>>::new();
+    for section in elf.section_headers.iter().filter(|section| {
+        section.sh_type == SHT_PROGBITS && section.sh_flags & u64::from(SHF_EXECINSTR) != 0
+    }) {
+        let locations = symbols
+            .loader
+            .find_location_range(section.sh_addr, section.sh_addr + section.sh_size)
+            .map_err(|e| anyhow::anyhow!("Failed to read the line information: {e}"))?;
+        for (_, _, location) in locations {
+            if let (Some(file), Some(line)) = (location.file, location.line) {
+                files
+                    .entry(file.to_string())
+                    .or_default()
+                    .entry(line)
+                    .or_default();
+            }
+        }
+    }
+
+    for (&address, &count) in samples {
+        if let Some((file, line)) = symbols.get_location(address as u64) {
+            *files.entry(file).or_default().entry(line).or_default() += count;
+        }
+    }
+
+    for (file, lines) in files {
+        writeln!(writer, "SF:{file}")?;
+        for (line, count) in &lines {
+            writeln!(writer, "DA:{line},{count}")?;
+        }
+        writeln!(writer, "LF:{}", lines.len())?;
+        writeln!(
+            writer,
+            "LH:{}",
+            lines.values().filter(|&&count| count > 0).count()
+        )?;
+        writeln!(writer, "end_of_record")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn sampled_lines() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../probe-rs/tests/debug-unwind-tests/nRF52833_xxAA_full_unwind.elf");
+        let symbols = Symbols::try_from(&path).unwrap();
+        let elf = std::fs::read(&path).unwrap();
+
+        // Sample the first instruction with line information.
+        let elf_file = Elf::parse(&elf).unwrap();
+        let text = elf_file
+            .section_headers
+            .iter()
+            .find(|section| elf_file.shdr_strtab.get_at(section.sh_name) == Some(".text"))
+            .unwrap();
+        let (address, _, location) = symbols
+            .loader
+            .find_location_range(text.sh_addr, text.sh_addr + text.sh_size)
+            .unwrap()
+            .find(|(_, _, location)| location.file.is_some() && location.line.is_some())
+            .unwrap();
+        let samples = HashMap::from([(address as u32, 7)]);
+
+        let mut output = Vec::new();
+        write_lcov(&symbols, &elf, &samples, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let sampled_file = format!("SF:{}\n", location.file.unwrap());
+        let sampled_line = format!("DA:{},7\n", location.line.unwrap());
+        let record = output
+            .split("end_of_record\n")
+            .find(|record| record.starts_with(&sampled_file))
+            .unwrap();
+        assert!(record.contains(&sampled_line), "{record}");
+        assert!(record.contains("LH:1\n"), "{record}");
+        assert_eq!(output.matches("LH:1\n").count(), 1, "{output}");
+        assert!(output.contains(",0\n"), "{output}");
+    }
+}
@@ -1,10 +1,15 @@
+mod call_stacks;
+mod lcov;
+
 use std::collections::HashMap;
-use std::path::Path;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::time::Instant;
 
 use addr2line::Loader;
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use itm::TracePacket;
 use probe_rs::{
     architecture::arm::{
@@ -12,9 +17,13 @@ use probe_rs::{
         memory::PeripheralType,
         DpAddress, SwoConfig,
     },
+    debug::{DebugInfo, DebugRegisters},
+    exception_handler_for_core,
     probe::list::Lister,
 };
 
+use call_stacks::{CallStackFormat, CallStackProfile, Frame};
+
 use crate::util::flash::{build_loader, run_flash_download};
 use tracing::info;
 
@@ -37,17 +46,38 @@ pub struct ProfileCmd {
     /// Limit the number of entries to output
     #[clap(long, default_value_t = 25)]
     limit: usize,
+    /// Write the number of samples of each source line to an lcov tracefile.
+    ///
+    /// This approximates the line coverage of binaries which are not instrumented for code
+    /// coverage. Use `probe-rs run --coverage` for instrumented binaries.
+    #[clap(long, value_name = "PATH")]
+    lcov: Option<PathBuf>,
     /// Profile Method
     #[clap(subcommand)]
     method: ProfileMethod,
 }
 
-#[derive(clap::Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(clap::Subcommand, Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum ProfileMethod {
     /// Naive, Halt -> Read PC -> Resume profiler
     #[clap(name = "naive")]
     Naive,
+    /// Sample the PC without halting the core, using the DWT_PCSR register on Cortex-M cores,
+    /// or the DEBUGPC register on Xtensa cores
+    #[clap(name = "pc-sample")]
+    PcSample,
+    /// Halt -> Unwind the call stack -> Resume profiler, which records the time spent in each
+    /// call path
+    #[clap(name = "call-stack")]
+    CallStack {
+        /// The file the call stack profile is written to.
+        #[clap(long, short)]
+        output: PathBuf,
+        /// The format of the call stack profile.
+        #[clap(long, value_enum, default_value_t = CallStackFormat::Folded)]
+        format: CallStackFormat,
+    },
     /// Use the Itm port to profile the chip (ARM only)
     #[clap(name = "itm")]
     Itm {
@@ -105,6 +135,7 @@ impl ProfileCmd {
 
         let start = Instant::now();
         let mut reads = 0;
+        let mut missed = 0;
         let mut samples: HashMap<u32, u64> = HashMap::with_capacity(256 * (self.duration as usize));
         let duration = Duration::from_secs(self.duration);
         info!("Profiling...");
@@ -119,7 +150,7 @@ impl ProfileCmd {
                 loop {
                     core.halt(Duration::from_millis(10))?;
                     let pc: u32 = core.read_core_reg(pc_reg)?;
-                    *samples.entry(pc).or_insert(1) += 1;
+                    add_sample(&mut samples, pc);
                     reads += 1;
                     core.run()?;
                     if start.elapsed() > duration {
@@ -127,6 +158,66 @@ impl ProfileCmd {
                     }
                 }
             }
+            ProfileMethod::PcSample => {
+                let mut core = session.core(self.core)?;
+                info!("Attached to Core {}", self.core);
+                core.reset()?;
+
+                loop {
+                    // No sample is available while the core is halted, e.g. at a breakpoint.
+                    match core.sample_program_counter()? {
+                        Some(pc) => {
+                            add_sample(&mut samples, pc as u32);
+                            reads += 1;
+                        }
+                        None => missed += 1,
+                    }
+                    if start.elapsed() > duration {
+                        break;
+                    }
+                }
+            }
+            ProfileMethod::CallStack { ref output, format } => {
+                let debug_info = DebugInfo::from_file(file_location)
+                    .map_err(|e| anyhow!("Failed to read debug info: {e}"))?;
+                let mut profile = CallStackProfile::default();
+
+                let mut core = session.core(self.core)?;
+                info!("Attached to Core {}", self.core);
+                core.reset()?;
+                let exception_handler = exception_handler_for_core(core.core_type());
+
+                loop {
+                    core.halt(Duration::from_millis(10))?;
+                    let registers = DebugRegisters::from_core(&mut core);
+                    let instruction_set = core.instruction_set().ok();
+                    let stack_frames = debug_info.unwind(
+                        &mut core,
+                        registers,
+                        exception_handler.as_ref(),
+                        instruction_set,
+                    )?;
+                    core.run()?;
+
+                    let stack = stack_frames.iter().map(Frame::from).collect::<Vec<_>>();
+                    if let Some(innermost) = stack.first() {
+                        add_sample(&mut samples, innermost.address as u32);
+                        reads += 1;
+                        profile.add_sample(stack);
+                    }
+                    if start.elapsed() > duration {
+                        break;
+                    }
+                }
+
+                profile.set_duration(start.elapsed());
+                let mut file = BufWriter::new(File::create(output).with_context(|| {
+                    format!("Failed to create the profile {}", output.display())
+                })?);
+                profile.write(format, &mut file)?;
+                file.flush()?;
+                println!("Call stack profile written to {}", output.display());
+            }
             ProfileMethod::Itm { clk, baud } => {
                 let sink = TraceSink::Swo(SwoConfig::new(clk).set_baud(baud));
                 session.setup_tracing(self.core, sink)?;
@@ -146,7 +237,7 @@ impl ProfileCmd {
 
                 for packet in iter {
                     if let TracePacket::PCSample { pc: Some(pc) } = packet? {
-                        *samples.entry(pc).or_insert(1) += 1;
+                        add_sample(&mut samples, pc);
                         reads += 1;
                     }
                     if start.elapsed() > duration {
@@ -156,11 +247,25 @@ impl ProfileCmd {
             }
         }
 
+        if let Some(path) = &self.lcov {
+            let elf = std::fs::read(file_location)?;
+            let mut file = BufWriter::new(
+                File::create(path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?,
+            );
+            lcov::write_lcov(&symbols, &elf, &samples, &mut file)?;
+            file.flush()?;
+            println!("Sampled lines written to {}", path.display());
+        }
+
         let mut v = Vec::from_iter(samples);
         // sort by frequency
         v.sort_by(|&(_, a), &(_, b)| b.cmp(&a));
 
         println!("Samples {}", reads);
+        if missed > 0 {
+            println!("Missed samples while the core was halted {}", missed);
+        }
 
         for (address, count) in v.into_iter().take(self.limit) {
             let name = symbols
@@ -183,6 +288,11 @@ impl ProfileCmd {
     }
 }
 
+/// Counts one sample of the program counter `pc`.
+fn add_sample(samples: &mut HashMap<u32, u64>, pc: u32) {
+    *samples.entry(pc).or_default() += 1;
+}
+
 // Wrapper around addr2line that allows to look up function names
 pub(crate) struct Symbols {
     loader: Loader,
@@ -231,3 +341,21 @@ impl Symbols {
         })?
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::add_sample;
+
+    #[test]
+    fn samples_are_counted_once() {
+        let mut samples = HashMap::new();
+
+        add_sample(&mut samples, 0x1000);
+        add_sample(&mut samples, 0x2000);
+        add_sample(&mut samples, 0x1000);
+
+        assert_eq!(samples, HashMap::from([(0x1000, 2), (0x2000, 1)]));
+    }
+}
@@ -0,0 +1,435 @@
+//! Collection of LLVM source based code coverage from binaries built with `-C instrument-coverage`.
+//!
+//! The profiler runtime which usually writes the `.profraw` file is not available on the target.
+//! Instead, the coverage counters are read from the target memory, and the `.profraw` file is
+//! assembled from the counters and the profile data and names in the ELF file, just like
+//! `minicov` does on the target.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use goblin::elf::Elf;
+use probe_rs::{Core, MemoryInterface};
+
+/// Options for collecting code coverage from the target
+#[derive(Debug, Clone, clap::Parser)]
+pub struct CoverageOptions {
+    /// Store the code coverage of the run in an LLVM `.profraw` file.
+    ///
+    /// The binary has to be built with `-C instrument-coverage`, and the `__llvm_prf_cnts`
+    /// section has to be placed in RAM. The counters are cleared when the core starts running,
+    /// and read when it stops. In test mode the coverage of all tests is summed up. The profile
+    /// can be processed with `llvm-profdata merge` and `llvm-cov`, e.g. to export it as lcov.
+    #[clap(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "default.profraw"
+    )]
+    pub coverage: Option<PathBuf>,
+}
+
+/// Collects the coverage counters of the target, and stores them as `.profraw` file.
+pub struct CoverageCollector {
+    path: PathBuf,
+    layout: ProfileLayout,
+    counters: Vec<u64>,
+    bitmap: Vec<u8>,
+}
+
+impl CoverageCollector {
+    /// Creates a coverage collector for the binary at `elf_path`, if coverage collection is
+    /// enabled.
+    pub fn new(options: &CoverageOptions, elf_path: &Path) -> anyhow::Result<Option<Self>> {
+        let Some(path) = options.coverage.clone() else {
+            return Ok(None);
+        };
+
+        let elf = std::fs::read(elf_path)
+            .with_context(|| format!("Failed to read {}", elf_path.display()))?;
+        let layout = ProfileLayout::from_elf(&elf).with_context(|| {
+            format!("Unable to collect code coverage for {}", elf_path.display())
+        })?;
+
+        Ok(Some(Self {
+            path,
+            counters: vec![0; layout.counter_count],
+            bitmap: vec![0; layout.bitmap_size],
+            layout,
+        }))
+    }
+
+    /// Clears the coverage counters on the target.
+    pub fn clear(&self, core: &mut Core) -> anyhow::Result<()> {
+        let counters = vec![0; self.layout.counter_count * COUNTER_SIZE];
+        core.write(self.layout.counters_address, &counters)?;
+        if self.layout.bitmap_size > 0 {
+            let bitmap = vec![0; self.layout.bitmap_size];
+            core.write(self.layout.bitmap_address, &bitmap)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the coverage counters of the target to the collected coverage, and stores it.
+    pub fn collect(&mut self, core: &mut Core) -> anyhow::Result<()> {
+        let mut counters = vec![0; self.layout.counter_count * COUNTER_SIZE];
+        core.read(self.layout.counters_address, &mut counters)?;
+        for (sum, counter) in self
+            .counters
+            .iter_mut()
+            .zip(counters.chunks_exact(COUNTER_SIZE))
+        {
+            let counter = counter.try_into().unwrap();
+            let counter = if self.layout.little_endian {
+                u64::from_le_bytes(counter)
+            } else {
+                u64::from_be_bytes(counter)
+            };
+            *sum = sum.wrapping_add(counter);
+        }
+
+        if self.layout.bitmap_size > 0 {
+            let mut bitmap = vec![0; self.layout.bitmap_size];
+            core.read(self.layout.bitmap_address, &mut bitmap)?;
+            for (sum, bits) in self.bitmap.iter_mut().zip(bitmap) {
+                *sum |= bits;
+            }
+        }
+
+        let profile = self.layout.encode(&self.counters, &self.bitmap);
+        std::fs::write(&self.path, profile)
+            .with_context(|| format!("Failed to store the coverage at {}", self.path.display()))?;
+        tracing::info!("Stored the code coverage at {}", self.path.display());
+
+        Ok(())
+    }
+}
+
+/// Derives the profile version from the version of rustc in the `.comment` section, which is used
+/// if the binary has no profiler runtime which defines the version.
+fn version_of_rustc(comment: &[u8]) -> Option<u64> {
+    let comment = String::from_utf8_lossy(comment);
+    let (_, version) = comment.split_once("rustc version 1.")?;
+    let minor = version.split('.').next()?.parse::<u32>().ok()?;
+
+    match minor {
+        ..60 => None,
+        // LLVM 14 to 17
+        60..78 => Some(8),
+        // LLVM 18
+        78..82 => Some(9),
+        // LLVM 19 and later
+        _ => Some(10),
+    }
+}
+
+/// The size of a coverage counter.
+const COUNTER_SIZE: usize = 8;
+
+/// The flags stored in the upper byte of the profile version.
+const VARIANT_MASKS: u64 = 0xff << 56;
+/// The profile uses single byte counters, which are set to zero when they are hit.
+const VARIANT_MASK_BYTE_COVERAGE: u64 = 1 << 60;
+
+/// The magic number of a `.profraw` file written by a 64-bit target.
+const RAW_MAGIC_64: u64 = u64::from_be_bytes([0xff, b'l', b'p', b'r', b'o', b'f', b'r', 0x81]);
+/// The magic number of a `.profraw` file written by a 32-bit target.
+const RAW_MAGIC_32: u64 = u64::from_be_bytes([0xff, b'l', b'p', b'r', b'o', b'f', b'R', 0x81]);
+
+/// The location of the profile data of an instrumented binary.
+#[derive(Debug)]
+struct ProfileLayout {
+    /// The profile version of the instrumentation, including the variant flags.
+    version: u64,
+    is_64_bit: bool,
+    little_endian: bool,
+    /// The per function records, which point to the counters of the function.
+    data: Vec<u8>,
+    data_address: u64,
+    counters_address: u64,
+    counter_count: usize,
+    /// The bytes of the bitmap used for MC/DC coverage.
+    bitmap_address: u64,
+    bitmap_size: usize,
+    /// The (compressed) function names.
+    names: Vec<u8>,
+    names_address: u64,
+}
+
+impl ProfileLayout {
+    /// Finds the profile data in an ELF file.
+    fn from_elf(elf_data: &[u8]) -> anyhow::Result<Self> {
+        let elf = Elf::parse(elf_data)?;
+
+        let section = |name: &str| {
+            elf.section_headers
+                .iter()
+                .find(|header| elf.shdr_strtab.get_at(header.sh_name) == Some(name))
+        };
+        let contents = |name: &str| -> anyhow::Result<(u64, Vec<u8>)> {
+            let header = section(name).ok_or_else(|| anyhow!("The section {name} is missing."))?;
+            let range = header
+                .file_range()
+                .ok_or_else(|| anyhow!("The section {name} has no contents."))?;
+            Ok((header.sh_addr, elf_data[range].to_vec()))
+        };
+
+        let counters = section("__llvm_prf_cnts").ok_or_else(|| {
+            anyhow!(
+                "The binary contains no coverage counters. Build it with `-C instrument-coverage`."
+            )
+        })?;
+        // The bitmap only exists if MC/DC coverage is enabled. Like the profiler runtime, an
+        // address of zero is used if it is missing.
+        let (bitmap_address, bitmap_size) = section("__llvm_prf_bits")
+            .map(|header| (header.sh_addr, header.sh_size as usize))
+            .unwrap_or_default();
+        let (data_address, data) = contents("__llvm_prf_data")?;
+        let (names_address, names) = contents("__llvm_prf_names")?;
+
+        let version = match Self::version_variable(&elf, elf_data) {
+            Some(version) => version,
+            None => elf
+                .section_headers
+                .iter()
+                .find(|header| elf.shdr_strtab.get_at(header.sh_name) == Some(".comment"))
+                .and_then(|header| header.file_range())
+                .and_then(|range| version_of_rustc(&elf_data[range]))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "The profile version is unknown, `__llvm_profile_raw_version` is missing."
+                    )
+                })?,
+        };
+
+        let layout = Self {
+            version,
+            is_64_bit: elf.is_64,
+            little_endian: elf.little_endian,
+            data,
+            data_address,
+            counters_address: counters.sh_addr,
+            counter_count: counters.sh_size as usize / COUNTER_SIZE,
+            bitmap_address,
+            bitmap_size,
+            names,
+            names_address,
+        };
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    /// Reads the profile version from `__llvm_profile_raw_version`, which is defined by the
+    /// profiler runtime, or by the compiler if the version has variant flags.
+    fn version_variable(elf: &Elf, elf_data: &[u8]) -> Option<u64> {
+        let symbol = elf.syms.iter().find(|symbol| {
+            elf.strtab.get_at(symbol.st_name) == Some("__llvm_profile_raw_version")
+        })?;
+        let offset = elf
+            .section_headers
+            .iter()
+            .filter(|header| header.sh_type == goblin::elf::section_header::SHT_PROGBITS)
+            .find(|header| header.vm_range().contains(&(symbol.st_value as usize)))
+            .map(|header| (header.sh_offset + symbol.st_value - header.sh_addr) as usize)?;
+
+        let bytes = elf_data.get(offset..offset + 8)?.try_into().ok()?;
+        Some(if elf.little_endian {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        })
+    }
+
+    /// The version of the `.profraw` format, without the variant flags.
+    fn raw_version(&self) -> u64 {
+        self.version & !VARIANT_MASKS
+    }
+
+    /// The size of a per function record.
+    fn record_size(&self) -> usize {
+        match (self.raw_version(), self.is_64_bit) {
+            (8, false) => 40,
+            (8, true) => 48,
+            (_, false) => 48,
+            (_, true) => 64,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !(8..=10).contains(&self.raw_version()) {
+            bail!(
+                "The profile version {} is not supported. Versions 8 to 10 (LLVM 14 to 20) are supported.",
+                self.raw_version()
+            );
+        }
+        if self.version & VARIANT_MASK_BYTE_COVERAGE != 0 {
+            bail!("Single byte coverage counters are not supported.");
+        }
+        if self.data.len() % self.record_size() != 0 {
+            bail!(
+                "The size of the profile data ({} bytes) is not a multiple of the record size ({} bytes).",
+                self.data.len(),
+                self.record_size()
+            );
+        }
+        Ok(())
+    }
+
+    /// Encodes a `.profraw` file with the given counters and bitmap bytes.
+    fn encode(&self, counters: &[u64], bitmap: &[u8]) -> Vec<u8> {
+        let version = self.raw_version();
+        let mut profile = Vec::new();
+        let write_u64 = |profile: &mut Vec<u8>, value: u64| {
+            if self.little_endian {
+                profile.extend_from_slice(&value.to_le_bytes());
+            } else {
+                profile.extend_from_slice(&value.to_be_bytes());
+            }
+        };
+        let padding = |size: usize| (8 - size % 8) % 8;
+        let counters_size = counters.len() * COUNTER_SIZE;
+
+        // The header.
+        let magic = if self.is_64_bit {
+            RAW_MAGIC_64
+        } else {
+            RAW_MAGIC_32
+        };
+        write_u64(&mut profile, magic);
+        write_u64(&mut profile, self.version);
+        // Binary IDs
+        write_u64(&mut profile, 0);
+        write_u64(&mut profile, (self.data.len() / self.record_size()) as u64);
+        // Padding before the counters
+        write_u64(&mut profile, 0);
+        write_u64(&mut profile, counters.len() as u64);
+        write_u64(&mut profile, padding(counters_size) as u64);
+        if version >= 9 {
+            write_u64(&mut profile, bitmap.len() as u64);
+            write_u64(&mut profile, padding(bitmap.len()) as u64);
+        }
+        write_u64(&mut profile, self.names.len() as u64);
+        write_u64(
+            &mut profile,
+            self.counters_address.wrapping_sub(self.data_address),
+        );
+        if version >= 9 {
+            write_u64(
+                &mut profile,
+                self.bitmap_address.wrapping_sub(self.data_address),
+            );
+        }
+        write_u64(&mut profile, self.names_address);
+        if version >= 10 {
+            // Virtual tables and their names
+            write_u64(&mut profile, 0);
+            write_u64(&mut profile, 0);
+        }
+        // The last value profiling kind
+        write_u64(&mut profile, if version >= 10 { 2 } else { 1 });
+
+        // The sections, each padded to eight bytes.
+        profile.extend_from_slice(&self.data);
+        for &counter in counters {
+            write_u64(&mut profile, counter);
+        }
+        profile.resize(profile.len() + padding(counters_size), 0);
+        if version >= 9 {
+            profile.extend_from_slice(bitmap);
+            profile.resize(profile.len() + padding(bitmap.len()), 0);
+        }
+        profile.extend_from_slice(&self.names);
+        profile.resize(profile.len() + padding(self.names.len()), 0);
+
+        profile
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn instrumented_binary() -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/bin/probe-rs/util/test_data/coverage/coverage.elf");
+        std::fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn layout_of_instrumented_binary() {
+        let layout = ProfileLayout::from_elf(&instrumented_binary()).unwrap();
+
+        // There is no profiler runtime, so the version is derived from rustc 1.83.
+        assert_eq!(layout.version, 10);
+        assert!(!layout.is_64_bit);
+        assert_eq!(layout.data.len(), 3 * layout.record_size());
+        assert_eq!(layout.data_address, 0x0800_0178);
+        assert_eq!(layout.counters_address, 0x2000_0000);
+        assert_eq!(layout.counter_count, 6);
+        assert_eq!(layout.bitmap_size, 0);
+        assert_eq!(layout.names_address, 0x0800_0118);
+        assert_eq!(layout.names.len(), 0x4f);
+    }
+
+    #[test]
+    fn encode_profraw() {
+        let layout = ProfileLayout::from_elf(&instrumented_binary()).unwrap();
+
+        let profile = layout.encode(&[1, 2, 3, 4, 5, 6], &[]);
+
+        let header = profile[..128]
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            header,
+            [
+                RAW_MAGIC_32,
+                10,
+                0,
+                3,
+                0,
+                6,
+                0,
+                0,
+                0,
+                0x4f,
+                0x2000_0000 - 0x0800_0178,
+                0u64.wrapping_sub(0x0800_0178),
+                0x0800_0118,
+                0,
+                0,
+                2
+            ]
+        );
+        assert_eq!(&profile[128..272], layout.data);
+        assert_eq!(profile[272..280], 1u64.to_le_bytes());
+        assert_eq!(profile[312..320], 6u64.to_le_bytes());
+        assert_eq!(&profile[320..399], layout.names);
+        assert_eq!(profile.len(), 400);
+    }
+
+    #[test]
+    fn uninstrumented_binary() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../probe-rs/tests/debug-unwind-tests/nRF52833_xxAA_full_unwind.elf");
+        let error = ProfileLayout::from_elf(&std::fs::read(path).unwrap()).unwrap_err();
+
+        assert!(
+            error.to_string().contains("no coverage counters"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn profile_version_of_rustc() {
+        assert_eq!(
+            version_of_rustc(b"Linker: LLD 19.1.1\0rustc version 1.83.0 (90b35a623 2024-11-26)\0"),
+            Some(10)
+        );
+        assert_eq!(version_of_rustc(b"rustc version 1.80.1"), Some(9));
+        assert_eq!(version_of_rustc(b"rustc version 1.75.0-nightly"), Some(8));
+        assert_eq!(version_of_rustc(b"GCC: (GNU) 13.2.0"), None);
+    }
+}
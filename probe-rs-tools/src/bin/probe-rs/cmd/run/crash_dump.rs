@@ -0,0 +1,194 @@
+use std::io::Write;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use probe_rs::debug::DebugRegisters;
+use probe_rs::{exception_handler_for_core, Core, CoreDump, HaltReason, RegisterRole, Session};
+use time::{macros::format_description, OffsetDateTime, UtcOffset};
+
+use crate::util::fault_report::{fault_report, InterruptNames};
+
+/// Options for capturing the state of the target when it crashes
+#[derive(Debug, Clone, clap::Parser)]
+pub struct CrashDumpOptions {
+    /// Store a core dump when the core halts unexpectedly, e.g. because of a HardFault or a panic.
+    ///
+    /// The core dump contains the registers of the core and all RAM regions of the target,
+    /// and is stored as a timestamped ELF core file in the given directory, or the current
+    /// directory if none is given. It can be inspected later with `probe-rs debug --core-dump`.
+    #[clap(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "."
+    )]
+    pub crash_dump: Option<PathBuf>,
+}
+
+/// Captures a core dump when the core crashes.
+pub struct CrashDumper {
+    directory: PathBuf,
+    ranges: Vec<Range<u64>>,
+    timestamp_offset: UtcOffset,
+}
+
+impl CrashDumper {
+    /// Creates a crash dumper for the given core, if crash dumps are enabled.
+    pub fn new(
+        options: &CrashDumpOptions,
+        session: &Session,
+        core_id: usize,
+        timestamp_offset: UtcOffset,
+    ) -> Option<Self> {
+        let directory = options.crash_dump.clone()?;
+        let target = session.target();
+        let core_name = &target.cores[core_id].name;
+
+        let ranges = target
+            .memory_map
+            .iter()
+            .filter_map(|region| region.as_ram_region())
+            .filter(|region| region.accessible_by(core_name))
+            .map(|region| region.range.clone())
+            .collect();
+
+        Some(Self {
+            directory,
+            ranges,
+            timestamp_offset,
+        })
+    }
+
+    /// Stores a core dump of the halted core.
+    pub fn capture(&self, core: &mut Core, output_stream: &mut dyn Write) -> anyhow::Result<()> {
+        let timestamp = OffsetDateTime::now_utc()
+            .to_offset(self.timestamp_offset)
+            .format(format_description!(
+                "[year]-[month]-[day]_[hour repr:24]-[minute]-[second]"
+            ))
+            .expect("Incorrect format string. This shouldn't happen.");
+        let path = self.directory.join(format!("crash-{timestamp}.core"));
+
+        let core_dump = CoreDump::dump_core(core, self.ranges.clone())
+            .context("Failed to capture the core dump")?;
+        core_dump
+            .store_elf(&path)
+            .with_context(|| format!("Failed to store the core dump at {}", path.display()))?;
+
+        writeln!(
+            output_stream,
+            "Core dump stored at {}, inspect it with `probe-rs debug --core-dump {}`",
+            path.display(),
+            path.display()
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Prints why the core halted, the fault report or a description of the active exception,
+/// and the most important registers.
+pub fn print_fault_summary(
+    core: &mut Core,
+    halt_reason: HaltReason,
+    interrupt_names: &InterruptNames,
+    output_stream: &mut dyn Write,
+) -> anyhow::Result<()> {
+    writeln!(output_stream, "The core crashed: {halt_reason:?}")?;
+
+    // Not every architecture can decode the exception, so this is best effort.
+    match fault_report(core, interrupt_names) {
+        Ok(Some(report)) => {
+            let report = report.to_string();
+            let mut lines = report.lines();
+            if let Some(first_line) = lines.next() {
+                writeln!(output_stream, "  Exception: {first_line}")?;
+            }
+            for line in lines {
+                writeln!(output_stream, "  {line}")?;
+            }
+        }
+        Ok(None) | Err(_) => {
+            let registers = DebugRegisters::from_core(core);
+            let exception_handler = exception_handler_for_core(core.core_type());
+            if let Ok(description) =
+                exception_handler
+                    .raw_exception(&registers)
+                    .and_then(|raw_exception| {
+                        exception_handler.exception_description(raw_exception, core)
+                    })
+            {
+                writeln!(output_stream, "  Exception: {description}")?;
+            }
+        }
+    }
+
+    let registers = DebugRegisters::from_core(core);
+    for (name, role) in [
+        ("PC", RegisterRole::ProgramCounter),
+        ("SP", RegisterRole::StackPointer),
+        ("RA", RegisterRole::ReturnAddress),
+    ] {
+        if let Ok(value) = registers.get_register_value_by_role(&role) {
+            writeln!(output_stream, "  {name}: {value:#010x}")?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn capture_crash_of_core_dump() {
+        let core_dump_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../probe-rs/tests/debug-unwind-tests")
+            .join("nRF52833_xxAA_hardfault_from_usagefault.coredump");
+        let core_dump = CoreDump::load(&core_dump_path).unwrap();
+        let mut session = Session::from_core_dumps(vec![core_dump], None).unwrap();
+
+        let directory = std::env::temp_dir().join(format!("probe-rs-crash-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let options = CrashDumpOptions {
+            crash_dump: Some(directory.clone()),
+        };
+        let crash_dumper = CrashDumper::new(&options, &session, 0, UtcOffset::UTC).unwrap();
+
+        let mut output = Vec::new();
+        let mut core = session.core(0).unwrap();
+        print_fault_summary(
+            &mut core,
+            HaltReason::Exception,
+            &InterruptNames::new(),
+            &mut output,
+        )
+        .unwrap();
+        crash_dumper.capture(&mut core, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(
+            output.starts_with("The core crashed: Exception\n"),
+            "{output}"
+        );
+        assert!(output.contains("  Exception: HardFault"), "{output}");
+        assert!(output.contains("UNDEFINSTR (bit 16)"), "{output}");
+        assert!(output.contains("(udf #0)"), "{output}");
+        assert!(output.contains("  PC: 0x"), "{output}");
+        assert!(output.contains("Core dump stored at "), "{output}");
+
+        let stored = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(stored.len(), 1);
+        let stored_dump = CoreDump::load(&stored[0]).unwrap();
+        assert_eq!(stored_dump.data.len(), crash_dumper.ranges.len());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
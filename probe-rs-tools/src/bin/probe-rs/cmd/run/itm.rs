@@ -0,0 +1,84 @@
+use crate::util::itm::{ItmConfig, ItmPortConfig};
+use crate::util::rtt::DataFormat;
+
+/// Options for printing the ITM stimulus ports
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ItmOptions {
+    /// Receive the output of the ITM stimulus ports over SWO, and print it next to RTT.
+    ///
+    /// The value is the frequency of the core clock in Hz, which drives the SWO output and the
+    /// ITM timestamps.
+    #[clap(long, value_name = "HZ", help_heading = "ITM OPTIONS")]
+    pub itm_clock: Option<u32>,
+
+    /// The baud rate of the SWO output.
+    #[clap(
+        long,
+        value_name = "BAUD",
+        default_value_t = 1_000_000,
+        requires = "itm_clock",
+        help_heading = "ITM OPTIONS"
+    )]
+    pub itm_baud: u32,
+
+    /// A stimulus port to print, where FORMAT is `string` (default), `binary` or `defmt`.
+    ///
+    /// Can be used multiple times. The output of other ports is dropped. Defaults to port 0.
+    #[clap(
+        long = "itm-port",
+        value_name = "PORT[:FORMAT]",
+        value_parser = parse_port,
+        requires = "itm_clock",
+        help_heading = "ITM OPTIONS"
+    )]
+    pub itm_ports: Vec<ItmPortConfig>,
+
+    /// Do not prefix the ITM output with the time of the ITM timestamp packets.
+    #[clap(long, requires = "itm_clock", help_heading = "ITM OPTIONS")]
+    pub itm_no_timestamps: bool,
+}
+
+impl ItmOptions {
+    /// Returns the ITM configuration, if ITM output is enabled.
+    pub fn config(&self, show_location: bool, log_format: Option<&str>) -> Option<ItmConfig> {
+        let clock = self.itm_clock?;
+
+        let mut ports = self.itm_ports.clone();
+        if ports.is_empty() {
+            ports.push(ItmPortConfig::new(0, DataFormat::String));
+        }
+        for port in ports.iter_mut() {
+            port.show_timestamps = !self.itm_no_timestamps;
+            port.show_location = show_location;
+            port.log_format = log_format.map(ToString::to_string);
+        }
+
+        Some(ItmConfig {
+            clock,
+            baud: self.itm_baud,
+            ports,
+        })
+    }
+}
+
+fn parse_port(input: &str) -> Result<ItmPortConfig, String> {
+    let (port, format) = input.split_once(':').unwrap_or((input, "string"));
+
+    let port = port
+        .parse()
+        .ok()
+        .filter(|port| *port < 32)
+        .ok_or_else(|| format!("'{port}' is not a stimulus port between 0 and 31"))?;
+    let data_format = match format {
+        "string" => DataFormat::String,
+        "binary" => DataFormat::BinaryLE,
+        "defmt" => DataFormat::Defmt,
+        _ => {
+            return Err(format!(
+                "'{format}' is not a valid format, expected `string`, `binary` or `defmt`"
+            ))
+        }
+    };
+
+    Ok(ItmPortConfig::new(port, data_format))
+}
@@ -1,12 +1,18 @@
+mod coverage;
+use coverage::{CoverageCollector, CoverageOptions};
+mod crash_dump;
+use crash_dump::{print_fault_summary, CrashDumpOptions, CrashDumper};
+mod itm;
+use itm::ItmOptions;
 mod normal_run_mode;
 use normal_run_mode::*;
+mod semihosting;
+use semihosting::{SemihostingHandler, SemihostingOptions};
 mod test_run_mode;
-use probe_rs::semihosting::SemihostingCommand;
 use test_run_mode::*;
 
 use std::fs::{self, File};
 use std::io::{Read, Write};
-use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -14,19 +20,22 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
+use probe_rs::architecture::arm::component::TraceSink;
 use probe_rs::debug::{DebugInfo, DebugRegisters};
 use probe_rs::flashing::{FileDownloadError, FormatKind};
 use probe_rs::{
     exception_handler_for_core,
     probe::list::Lister,
     rtt::{Error as RttError, ScanRegion},
-    Core, CoreInterface, Error, HaltReason, Session, VectorCatchCondition,
+    BreakpointCause, Core, CoreInterface, Error, HaltReason, Session, VectorCatchCondition,
 };
 use signal_hook::consts::signal;
 use time::UtcOffset;
 
 use crate::util::common_options::{BinaryDownloadOptions, ProbeOptions};
+use crate::util::fault_report::{interrupt_names_from_svd, InterruptNames};
 use crate::util::flash::{build_loader, run_flash_download};
+use crate::util::itm::ItmDemultiplexer;
 use crate::util::rtt::client::RttClient;
 use crate::util::rtt::{ChannelDataCallbacks, RttChannelConfig, RttConfig};
 use crate::FormatOptions;
@@ -87,6 +96,22 @@ pub struct SharedOptions {
     /// Scan the memory to find the RTT control block
     #[clap(long)]
     pub(crate) rtt_scan_memory: bool,
+
+    #[clap(flatten)]
+    pub(crate) semihosting_options: SemihostingOptions,
+
+    #[clap(flatten)]
+    pub(crate) crash_dump_options: CrashDumpOptions,
+
+    #[clap(flatten)]
+    pub(crate) coverage_options: CoverageOptions,
+
+    #[clap(flatten)]
+    pub(crate) itm_options: ItmOptions,
+
+    /// CMSIS-SVD file of the target, which is used to name the active interrupt when the core crashes.
+    #[clap(long, value_name = "PATH")]
+    pub(crate) svd: Option<PathBuf>,
 }
 
 impl Cmd {
@@ -182,6 +207,38 @@ impl Cmd {
             tracing::debug!("Skipped clearing RTT header")
         }
 
+        let itm_config = self.shared_options.itm_options.config(
+            !self.shared_options.no_location,
+            self.shared_options.log_format.as_deref(),
+        );
+        let itm = match itm_config {
+            Some(config) => {
+                session.setup_tracing(core_id, TraceSink::Swo(config.swo_config()))?;
+                Some(ItmDemultiplexer::new(
+                    &config,
+                    rtt_client.defmt_data.clone(),
+                ))
+            }
+            None => None,
+        };
+
+        let crash_dumper = CrashDumper::new(
+            &self.shared_options.crash_dump_options,
+            &session,
+            core_id,
+            timestamp_offset,
+        );
+
+        let coverage_collector = CoverageCollector::new(
+            &self.shared_options.coverage_options,
+            &self.shared_options.path,
+        )?;
+
+        let interrupt_names = match &self.shared_options.svd {
+            Some(path) => interrupt_names_from_svd(path)?,
+            None => InterruptNames::new(),
+        };
+
         run_mode.run(
             session,
             RunLoop {
@@ -189,6 +246,11 @@ impl Cmd {
                 path: self.shared_options.path,
                 always_print_stacktrace: self.shared_options.always_print_stacktrace,
                 rtt_client,
+                itm,
+                semihosting_options: self.shared_options.semihosting_options,
+                crash_dumper,
+                coverage_collector,
+                interrupt_names,
             },
         )?;
 
@@ -250,6 +312,11 @@ struct RunLoop {
     path: PathBuf,
     always_print_stacktrace: bool,
     rtt_client: RttClient,
+    itm: Option<ItmDemultiplexer>,
+    semihosting_options: SemihostingOptions,
+    crash_dumper: Option<CrashDumper>,
+    coverage_collector: Option<CoverageCollector>,
+    interrupt_names: InterruptNames,
 }
 
 #[derive(PartialEq, Debug)]
@@ -309,12 +376,23 @@ impl RunLoop {
         }
 
         if core.core_halted()? {
+            // The coverage of a run starting from a halted core, e.g. after a reset, is
+            // collected from scratch.
+            if let Some(coverage_collector) = &self.coverage_collector {
+                coverage_collector.clear(core)?;
+            }
             core.run()?;
         }
         let start = Instant::now();
 
         let result = self.do_run_until(core, output_stream, timeout, start, &mut predicate);
 
+        if let Some(coverage_collector) = &mut self.coverage_collector {
+            if let Err(error) = coverage_collector.collect(core) {
+                tracing::error!("Failed to collect the code coverage: {error:?}");
+            }
+        }
+
         // Always clean up after RTT but don't overwrite the original result.
         let cleanup_result = self.rtt_client.clean_up(core);
 
@@ -353,6 +431,9 @@ impl RunLoop {
             }
         };
 
+        // The reason of an unexpected halt, e.g. a HardFault or a panic.
+        let mut crash_reason = None;
+
         let return_reason = loop {
             // check for halt first, poll rtt after.
             // this is important so we do one last poll after halt, so we flush all messages
@@ -362,7 +443,17 @@ impl RunLoop {
             match core.status()? {
                 probe_rs::CoreStatus::Halted(reason) => match predicate(reason, core) {
                     Ok(Some(r)) => return_reason = Some(Ok(ReturnReason::Predicate(r))),
-                    Err(e) => return_reason = Some(Err(e)),
+                    Err(e) => {
+                        // Semihosting is used by the target to exit on purpose, every other
+                        // halt that isn't handled by the predicate is a crash.
+                        if !matches!(
+                            reason,
+                            HaltReason::Breakpoint(BreakpointCause::Semihosting(_))
+                        ) {
+                            crash_reason = Some(reason);
+                        }
+                        return_reason = Some(Err(e));
+                    }
                     Ok(None) => {
                         was_halted = true;
                         core.run()?
@@ -380,6 +471,10 @@ impl RunLoop {
             }
 
             let had_rtt_data = poll_rtt(&mut self.rtt_client, core, output_stream)?;
+            let had_itm_data = match &mut self.itm {
+                Some(itm) => poll_itm(itm, core, output_stream)?,
+                None => false,
+            };
 
             if return_reason.is_none() {
                 if exit.load(Ordering::Relaxed) {
@@ -405,13 +500,26 @@ impl RunLoop {
             //
             // If the polling frequency is too high, the USB connection to the probe
             // can become unstable. Hence we only pull as little as necessary.
-            if had_rtt_data || was_halted {
+            if had_rtt_data || had_itm_data || was_halted {
                 thread::sleep(Duration::from_millis(1));
             } else {
                 thread::sleep(Duration::from_millis(100));
             }
         };
 
+        if let Some(crash_reason) = crash_reason {
+            if let Err(error) =
+                print_fault_summary(core, crash_reason, &self.interrupt_names, output_stream)
+            {
+                tracing::error!("Failed to print the fault summary: {error:?}");
+            }
+            if let Some(crash_dumper) = &self.crash_dumper {
+                if let Err(error) = crash_dumper.capture(core, output_stream) {
+                    tracing::error!("Failed to capture the crash: {error:?}");
+                }
+            }
+        }
+
         if self.always_print_stacktrace
             || return_reason.is_err()
             || matches!(return_reason, Ok(ReturnReason::Timeout))
@@ -497,30 +605,30 @@ fn print_stacktrace<S: Write + ?Sized>(
     Ok(())
 }
 
+struct OutCollector<'a, O: Write + ?Sized> {
+    out_stream: &'a mut O,
+    had_data: bool,
+}
+
+impl<O: Write + ?Sized> ChannelDataCallbacks for OutCollector<'_, O> {
+    fn on_string_data(&mut self, _channel: usize, data: String) -> Result<(), RttError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.had_data = true;
+        self.out_stream
+            .write_all(data.as_bytes())
+            .map_err(|err| anyhow!(err))?;
+        Ok(())
+    }
+}
+
 /// Poll RTT and print the received buffer.
 fn poll_rtt<S: Write + ?Sized>(
     rtt_client: &mut RttClient,
     core: &mut Core<'_>,
     out_stream: &mut S,
 ) -> Result<bool, anyhow::Error> {
-    struct OutCollector<'a, O: Write + ?Sized> {
-        out_stream: &'a mut O,
-        had_data: bool,
-    }
-
-    impl<O: Write + ?Sized> ChannelDataCallbacks for OutCollector<'_, O> {
-        fn on_string_data(&mut self, _channel: usize, data: String) -> Result<(), RttError> {
-            if data.is_empty() {
-                return Ok(());
-            }
-            self.had_data = true;
-            self.out_stream
-                .write_all(data.as_bytes())
-                .map_err(|err| anyhow!(err))?;
-            Ok(())
-        }
-    }
-
     let mut out = OutCollector {
         out_stream,
         had_data: false,
@@ -531,102 +639,19 @@ fn poll_rtt<S: Write + ?Sized>(
     Ok(out.had_data)
 }
 
-struct SemihostingPrinter {
-    stdout_open: bool,
-    stderr_open: bool,
-}
-
-impl SemihostingPrinter {
-    const STDOUT: u32 = 1;
-    const STDERR: u32 = 2;
-
-    pub fn new() -> Self {
-        Self {
-            stdout_open: false,
-            stderr_open: false,
-        }
-    }
-
-    pub fn handle(
-        &mut self,
-        command: SemihostingCommand,
-        core: &mut Core<'_>,
-    ) -> Result<(), Error> {
-        match command {
-            SemihostingCommand::Open(request) => {
-                let path = request.path(core)?;
-                if path == ":tt" {
-                    match request.mode().as_bytes()[0] {
-                        b'w' => {
-                            self.stdout_open = true;
-                            let fd = NonZeroU32::new(Self::STDOUT).unwrap();
-                            request.respond_with_handle(core, fd)?;
-                        }
-                        b'a' => {
-                            self.stderr_open = true;
-                            let fd = NonZeroU32::new(Self::STDERR).unwrap();
-                            request.respond_with_handle(core, fd)?;
-                        }
-                        other => {
-                            tracing::warn!(
-                                "Target wanted to open file {path} with mode {mode}, but probe-rs does not support this operation yet. Continuing...",
-                                path = path,
-                                mode = other
-                            );
-                        }
-                    };
-                } else {
-                    tracing::warn!(
-                        "Target wanted to open file {path}, but probe-rs does not support this operation yet. Continuing..."
-                    );
-                }
-            }
-            SemihostingCommand::Close(request) => {
-                let handle = request.file_handle(core)?;
-                if handle == Self::STDOUT {
-                    self.stdout_open = false;
-                    request.success(core)?;
-                } else if handle == Self::STDERR {
-                    self.stderr_open = false;
-                    request.success(core)?;
-                } else {
-                    tracing::warn!(
-                        "Target wanted to close file handle {handle}, but probe-rs does not support this operation yet. Continuing..."
-                    );
-                }
-            }
-            SemihostingCommand::Write(request) => match request.file_handle() {
-                handle if handle == Self::STDOUT => {
-                    if self.stdout_open {
-                        let bytes = request.read(core)?;
-                        let str = String::from_utf8_lossy(&bytes);
-                        std::io::stdout().write_all(str.as_bytes()).unwrap();
-                        request.write_status(core, 0)?;
-                    }
-                }
-                handle if handle == Self::STDERR => {
-                    if self.stderr_open {
-                        let bytes = request.read(core)?;
-                        let str = String::from_utf8_lossy(&bytes);
-                        std::io::stderr().write_all(str.as_bytes()).unwrap();
-                        request.write_status(core, 0)?;
-                    }
-                }
-                other => {
-                    tracing::warn!(
-                        "Target wanted to write to file handle {other}, but probe-rs does not support this operation yet. Continuing...",
-                    );
-                }
-            },
-            SemihostingCommand::WriteConsole(request) => {
-                std::io::stdout()
-                    .write_all(request.read(core)?.as_bytes())
-                    .unwrap();
-            }
+/// Read the ITM data received over SWO and print the configured stimulus ports.
+fn poll_itm<S: Write + ?Sized>(
+    itm: &mut ItmDemultiplexer,
+    core: &mut Core<'_>,
+    out_stream: &mut S,
+) -> Result<bool, anyhow::Error> {
+    let mut out = OutCollector {
+        out_stream,
+        had_data: false,
+    };
 
-            _ => {}
-        };
+    let data = core.read_swo()?;
+    itm.process(&data, &mut out)?;
 
-        Ok(())
-    }
+    Ok(out.had_data)
 }
@@ -1,4 +1,4 @@
-use crate::cmd::run::{OutputStream, RunLoop, RunMode, SemihostingPrinter};
+use crate::cmd::run::{OutputStream, RunLoop, RunMode, SemihostingHandler};
 use anyhow::anyhow;
 use probe_rs::{semihosting::SemihostingCommand, BreakpointCause, Core, HaltReason, Session};
 
@@ -27,7 +27,7 @@ impl RunMode for NormalRunMode {
     fn run(&self, mut session: Session, mut run_loop: RunLoop) -> anyhow::Result<()> {
         let mut core = session.core(run_loop.core_id)?;
 
-        let mut printer = SemihostingPrinter::new();
+        let mut semihosting = SemihostingHandler::new(&run_loop.semihosting_options);
         let halt_handler = |halt_reason: HaltReason, core: &mut Core| {
             let HaltReason::Breakpoint(BreakpointCause::Semihosting(cmd)) = halt_reason else {
                 anyhow::bail!("CPU halted unexpectedly.");
@@ -51,11 +51,8 @@ impl RunMode for NormalRunMode {
                     tracing::warn!("Target wanted to run semihosting operation SYS_GET_CMDLINE, but probe-rs does not support this operation yet. Continuing...");
                     Ok(None) // Continue running
                 }
-                other @ (SemihostingCommand::Open(_)
-                | SemihostingCommand::Close(_)
-                | SemihostingCommand::WriteConsole(_)
-                | SemihostingCommand::Write(_)) => {
-                    printer.handle(other, core)?;
+                other => {
+                    semihosting.handle(other, core)?;
                     Ok(None)
                 }
             }
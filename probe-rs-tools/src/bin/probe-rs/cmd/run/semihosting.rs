@@ -0,0 +1,637 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::num::NonZeroU32;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use probe_rs::semihosting::SemihostingCommand;
+use probe_rs::{Core, Error};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use signal_hook::consts::signal;
+use signal_hook::SigId;
+
+/// Error number reported to the target if the host error has no OS error code (EIO).
+const EIO: i32 = 5;
+/// Error number reported to the target for paths outside of the host root (EACCES).
+const EACCES: i32 = 13;
+/// Error number reported to the target for unknown file handles (EBADF).
+const EBADF: i32 = 9;
+/// Error number reported to the target for file lengths which don't fit the status (EOVERFLOW).
+const EOVERFLOW: i32 = 75;
+
+/// Options for the semihosting requests of the target
+#[derive(Debug, Clone, clap::Parser)]
+pub struct SemihostingOptions {
+    /// Directory on the host which the target can access with semihosting file operations.
+    ///
+    /// Paths opened by the target are relative to this directory and cannot leave it.
+    /// Without this option, the target can only use the semihosting console.
+    #[clap(long, value_name = "DIR")]
+    pub semihosting_root: Option<PathBuf>,
+
+    /// How the input of the terminal is passed to semihosting console reads of the target.
+    ///
+    /// In line mode, the terminal echoes the input and the target receives complete lines.
+    /// In raw mode, every key press is passed to the target immediately, which is expected
+    /// to echo the input itself. Ctrl + C still stops probe-rs.
+    #[clap(long, value_enum, value_name = "MODE", default_value_t = StdinMode::Line)]
+    pub semihosting_stdin: StdinMode,
+}
+
+/// How the terminal input is passed to the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StdinMode {
+    /// Pass complete lines, edited by the terminal
+    Line,
+    /// Pass every key press immediately
+    Raw,
+}
+
+/// Handles the semihosting requests of the target.
+///
+/// The console is mapped to the standard streams of probe-rs. Files are only available
+/// if a host root directory was given, and the target can not access anything outside of it.
+pub struct SemihostingHandler {
+    root: Option<PathBuf>,
+    stdin_mode: StdinMode,
+    stdin: Option<ConsoleInput>,
+    files: HashMap<u32, File>,
+    next_handle: u32,
+    stdout_open: bool,
+    stderr_open: bool,
+    start: Instant,
+    errno: i32,
+}
+
+impl SemihostingHandler {
+    const STDOUT: u32 = 1;
+    const STDERR: u32 = 2;
+    const STDIN: u32 = 3;
+    /// The tick frequency reported to the target, ticks are microseconds.
+    const TICK_FREQUENCY: u32 = 1_000_000;
+    /// The most bytes read from a file for a single request, the target is told about the rest.
+    const MAX_READ_LENGTH: usize = 64 * 1024;
+
+    pub fn new(options: &SemihostingOptions) -> Self {
+        Self {
+            root: options.semihosting_root.clone(),
+            stdin_mode: options.semihosting_stdin,
+            stdin: None,
+            files: HashMap::new(),
+            next_handle: Self::STDIN + 1,
+            stdout_open: false,
+            stderr_open: false,
+            start: Instant::now(),
+            errno: 0,
+        }
+    }
+
+    /// Returns whether the command is handled by [`SemihostingHandler::handle`].
+    pub fn handles(command: &SemihostingCommand) -> bool {
+        !matches!(
+            command,
+            SemihostingCommand::ExitSuccess
+                | SemihostingCommand::ExitError(_)
+                | SemihostingCommand::GetCommandLine(_)
+                | SemihostingCommand::Unknown(_)
+        )
+    }
+
+    pub fn handle(
+        &mut self,
+        command: SemihostingCommand,
+        core: &mut Core<'_>,
+    ) -> Result<(), Error> {
+        match command {
+            SemihostingCommand::Open(request) => {
+                let path = request.path(core)?;
+                if path == ":tt" {
+                    match request.mode().as_bytes()[0] {
+                        b'r' => {
+                            let fd = NonZeroU32::new(Self::STDIN).unwrap();
+                            request.respond_with_handle(core, fd)?;
+                        }
+                        b'w' => {
+                            self.stdout_open = true;
+                            let fd = NonZeroU32::new(Self::STDOUT).unwrap();
+                            request.respond_with_handle(core, fd)?;
+                        }
+                        b'a' => {
+                            self.stderr_open = true;
+                            let fd = NonZeroU32::new(Self::STDERR).unwrap();
+                            request.respond_with_handle(core, fd)?;
+                        }
+                        other => {
+                            tracing::warn!(
+                                "Target wanted to open file {path} with mode {mode}, but probe-rs does not support this operation yet. Continuing...",
+                                path = path,
+                                mode = other
+                            );
+                        }
+                    };
+                } else if let Some(file) = self.open_file(&path, request.mode()) {
+                    let handle = self.next_handle;
+                    self.next_handle += 1;
+                    self.files.insert(handle, file);
+                    request.respond_with_handle(core, NonZeroU32::new(handle).unwrap())?;
+                }
+            }
+            SemihostingCommand::Close(request) => {
+                let handle = request.file_handle(core)?;
+                if handle == Self::STDOUT {
+                    self.stdout_open = false;
+                    request.success(core)?;
+                } else if handle == Self::STDERR {
+                    self.stderr_open = false;
+                    request.success(core)?;
+                } else if handle == Self::STDIN || self.files.remove(&handle).is_some() {
+                    request.success(core)?;
+                } else {
+                    self.errno = EBADF;
+                    tracing::warn!("Target wanted to close unknown file handle {handle}");
+                }
+            }
+            SemihostingCommand::Write(request) => match request.file_handle() {
+                handle if handle == Self::STDOUT => {
+                    if self.stdout_open {
+                        let bytes = request.read(core)?;
+                        let str = String::from_utf8_lossy(&bytes);
+                        std::io::stdout().write_all(str.as_bytes()).unwrap();
+                        request.write_status(core, 0)?;
+                    }
+                }
+                handle if handle == Self::STDERR => {
+                    if self.stderr_open {
+                        let bytes = request.read(core)?;
+                        let str = String::from_utf8_lossy(&bytes);
+                        std::io::stderr().write_all(str.as_bytes()).unwrap();
+                        request.write_status(core, 0)?;
+                    }
+                }
+                handle => {
+                    let bytes = request.read(core)?;
+                    let result = self.file(handle).and_then(|file| file.write_all(&bytes));
+                    if self.check(result).is_some() {
+                        request.write_status(core, 0)?;
+                    }
+                }
+            },
+            SemihostingCommand::WriteConsole(request) => {
+                std::io::stdout()
+                    .write_all(request.read(core)?.as_bytes())
+                    .unwrap();
+            }
+            SemihostingCommand::Read(request) if Self::is_stdin(request.file_handle()) => {
+                let data = self.console_input().read(request.buffer_length() as usize);
+                if let Some(data) = data {
+                    request.respond_with_data(core, &data)?;
+                }
+            }
+            SemihostingCommand::Read(request) => {
+                let length = (request.buffer_length() as usize).min(Self::MAX_READ_LENGTH);
+                let mut buffer = vec![0; length];
+                let result = self
+                    .file(request.file_handle())
+                    .and_then(|file| file.read(&mut buffer));
+                if let Some(count) = self.check(result) {
+                    request.respond_with_data(core, &buffer[..count])?;
+                }
+            }
+            SemihostingCommand::ReadConsole(request) => {
+                // At the end of the input, the failure status is returned.
+                if let Some(&[c]) = self.console_input().read(1).as_deref() {
+                    request.respond_with_char(core, c)?;
+                }
+            }
+            SemihostingCommand::IsTty(request) => match request.file_handle() {
+                Self::STDIN | Self::STDOUT | Self::STDERR => request.write_status(core, 1)?,
+                handle if self.files.contains_key(&handle) => request.write_status(core, 0)?,
+                _ => self.errno = EBADF,
+            },
+            SemihostingCommand::Seek(request) => {
+                let result = self
+                    .file(request.file_handle())
+                    .and_then(|file| file.seek(SeekFrom::Start(request.position() as u64)));
+                if self.check(result).is_some() {
+                    request.success(core)?;
+                }
+            }
+            SemihostingCommand::FileLength(request) => {
+                let result = self
+                    .file(request.file_handle())
+                    .and_then(|file| file.metadata())
+                    .and_then(|metadata| {
+                        i32::try_from(metadata.len())
+                            .map_err(|_| io::Error::from_raw_os_error(EOVERFLOW))
+                    });
+                if let Some(length) = self.check(result) {
+                    request.write_status(core, length)?;
+                }
+            }
+            SemihostingCommand::Remove(request) => {
+                let path = request.path(core)?;
+                let result = self.host_path(&path).and_then(fs::remove_file);
+                if self.check(result).is_some() {
+                    request.success(core)?;
+                }
+            }
+            SemihostingCommand::Rename(request) => {
+                let from = request.from_path(core)?;
+                let to = request.to_path(core)?;
+                let result = self
+                    .host_path(&from)
+                    .and_then(|from| fs::rename(from, self.host_path(&to)?));
+                if self.check(result).is_some() {
+                    request.success(core)?;
+                }
+            }
+            SemihostingCommand::Clock(request) => {
+                let centiseconds = self.start.elapsed().as_millis() / 10;
+                request.respond_with_value(core, centiseconds as i32)?;
+            }
+            SemihostingCommand::Time(request) => {
+                let seconds = SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                request.respond_with_value(core, seconds as i32)?;
+            }
+            SemihostingCommand::Elapsed(request) => {
+                let ticks = self.start.elapsed().as_micros() as u64;
+                request.respond_with_ticks(core, ticks)?;
+            }
+            SemihostingCommand::TickFreq(request) => {
+                request.respond_with_value(core, Self::TICK_FREQUENCY as i32)?;
+            }
+            SemihostingCommand::Errno(request) => {
+                request.respond_with_value(core, self.errno)?;
+            }
+            SemihostingCommand::HeapInfo(_) => {
+                // The location of the heap is not known to the host. The request has already been
+                // answered with zeros, which tells the C library to use its own defaults.
+                tracing::debug!("Target requested heap information, which is not available");
+            }
+
+            SemihostingCommand::ExitSuccess
+            | SemihostingCommand::ExitError(_)
+            | SemihostingCommand::GetCommandLine(_)
+            | SemihostingCommand::Unknown(_) => {}
+        };
+
+        Ok(())
+    }
+
+    /// Some targets read from handle 0 without opening the console first,
+    /// like file descriptor 0 on POSIX systems.
+    fn is_stdin(handle: u32) -> bool {
+        handle == 0 || handle == Self::STDIN
+    }
+
+    /// Returns the terminal input, which is only read once the target asks for it.
+    fn console_input(&mut self) -> &mut ConsoleInput {
+        let mode = self.stdin_mode;
+        self.stdin.get_or_insert_with(|| ConsoleInput::new(mode))
+    }
+
+    fn open_file(&mut self, path: &str, mode: &str) -> Option<File> {
+        let mut options = OpenOptions::new();
+        match mode.replace('b', "").as_str() {
+            "r" => options.read(true),
+            "r+" => options.read(true).write(true),
+            "w" => options.write(true).create(true).truncate(true),
+            "w+" => options.read(true).write(true).create(true).truncate(true),
+            "a" => options.append(true).create(true),
+            "a+" => options.read(true).append(true).create(true),
+            _ => {
+                tracing::warn!(
+                    "Target wanted to open file {path} with an unknown mode. Continuing..."
+                );
+                return None;
+            }
+        };
+
+        let result = self.host_path(path).and_then(|path| options.open(path));
+        self.check(result)
+    }
+
+    fn file(&mut self, handle: u32) -> io::Result<&mut File> {
+        self.files
+            .get_mut(&handle)
+            .ok_or_else(|| io::Error::from_raw_os_error(EBADF))
+    }
+
+    /// Resolves a path of the target to a path on the host.
+    fn host_path(&self, path: &str) -> io::Result<PathBuf> {
+        let Some(root) = &self.root else {
+            tracing::warn!(
+                "Target wanted to access file {path}, but no semihosting root directory was given. Continuing..."
+            );
+            return Err(io::Error::from_raw_os_error(EACCES));
+        };
+
+        resolve_path(root, path).ok_or_else(|| {
+            tracing::warn!("Target wanted to access file {path}, which is outside of the semihosting root directory. Continuing...");
+            io::Error::from_raw_os_error(EACCES)
+        })
+    }
+
+    /// Records the error number of a failed operation, which the target can request with SYS_ERRNO.
+    ///
+    /// The failure status has already been written to the target when decoding the request.
+    fn check<T>(&mut self, result: io::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                tracing::debug!("Semihosting file operation failed: {error}");
+                self.errno = error.raw_os_error().unwrap_or(EIO);
+                None
+            }
+        }
+    }
+}
+
+/// The input of the terminal, read in a background thread.
+struct ConsoleInput {
+    rx: mpsc::Receiver<Vec<u8>>,
+    buffer: VecDeque<u8>,
+    eof: bool,
+    mode: StdinMode,
+    interrupted: Arc<AtomicBool>,
+    sig_id: Option<SigId>,
+}
+
+impl ConsoleInput {
+    fn new(mode: StdinMode) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        if mode == StdinMode::Raw {
+            if let Err(error) = enable_raw_mode() {
+                tracing::warn!("Failed to enable raw mode for the terminal: {error}");
+            }
+        }
+
+        thread::Builder::new()
+            .name("probe-rs-semihosting-stdin".to_owned())
+            .spawn(move || match mode {
+                StdinMode::Line => read_lines(tx),
+                StdinMode::Raw => read_keys(tx),
+            })
+            .unwrap();
+
+        // The run loop only checks for Ctrl + C while the target is running,
+        // so we need our own flag to stop waiting for input.
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let sig_id = signal_hook::flag::register(signal::SIGINT, interrupted.clone()).ok();
+
+        Self {
+            rx,
+            buffer: VecDeque::new(),
+            eof: false,
+            mode,
+            interrupted,
+            sig_id,
+        }
+    }
+
+    /// Waits until input is available and returns up to `max_len` bytes of it.
+    ///
+    /// Returns an empty buffer at the end of the input, and `None` if the user pressed Ctrl + C.
+    fn read(&mut self, max_len: usize) -> Option<Vec<u8>> {
+        self.interrupted.store(false, Ordering::Relaxed);
+
+        while self.buffer.is_empty() && !self.eof {
+            match self.rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(data) if data.is_empty() => self.eof = true,
+                Ok(data) => self.buffer.extend(data),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => self.eof = true,
+            }
+
+            if self.interrupted.load(Ordering::Relaxed) {
+                return None;
+            }
+        }
+
+        let len = max_len.min(self.buffer.len());
+        Some(self.buffer.drain(..len).collect())
+    }
+}
+
+impl Drop for ConsoleInput {
+    fn drop(&mut self) {
+        if let Some(sig_id) = self.sig_id.take() {
+            signal_hook::low_level::unregister(sig_id);
+        }
+
+        if self.mode == StdinMode::Raw {
+            let _ = disable_raw_mode();
+        }
+    }
+}
+
+/// Passes the lines of the terminal input to the target. An empty buffer signals the end of the input.
+fn read_lines(tx: mpsc::Sender<Vec<u8>>) {
+    let mut stdin = io::stdin().lock();
+    loop {
+        let mut line = Vec::new();
+        let done = !matches!(stdin.read_until(b'\n', &mut line), Ok(n) if n > 0);
+        if tx.send(line).is_err() || done {
+            return;
+        }
+    }
+}
+
+/// Passes every key press to the target.
+fn read_keys(tx: mpsc::Sender<Vec<u8>>) {
+    loop {
+        let Ok(event) = event::read() else {
+            let _ = tx.send(vec![]);
+            return;
+        };
+
+        let Event::Key(key) = event else {
+            continue;
+        };
+
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            // Raw mode disables the signal, so we raise it ourselves to stop the run loop.
+            let _ = signal_hook::low_level::raise(signal::SIGINT);
+            continue;
+        }
+
+        if let Some(bytes) = key_bytes(key) {
+            if tx.send(bytes).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Translates a key press to the bytes a serial terminal would send.
+fn key_bytes(key: KeyEvent) -> Option<Vec<u8>> {
+    let bytes = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            let c = c.to_ascii_lowercase();
+            if !c.is_ascii_lowercase() {
+                return None;
+            }
+            // Ctrl + A is 0x01, Ctrl + B is 0x02, ...
+            vec![c as u8 - b'a' + 1]
+        }
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => b"\x1b[A".to_vec(),
+        KeyCode::Down => b"\x1b[B".to_vec(),
+        KeyCode::Right => b"\x1b[C".to_vec(),
+        KeyCode::Left => b"\x1b[D".to_vec(),
+        _ => return None,
+    };
+
+    Some(bytes)
+}
+
+/// Joins a path requested by the target to the root directory.
+///
+/// Returns `None` for absolute paths and paths containing `..`, which could leave the root directory,
+/// and for paths which leave it through a symbolic link.
+fn resolve_path(root: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    let root = root.canonicalize().ok()?;
+
+    let mut resolved = root.clone();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    // Symbolic links are followed when the file is opened, so the path they point to has to be checked.
+    let real_path = match resolved.canonicalize() {
+        Ok(real_path) => real_path,
+        // A file which doesn't exist yet is created in its parent directory.
+        // A dangling symbolic link would create the file it points to, wherever that is.
+        Err(_) if resolved.symlink_metadata().is_err() => resolved
+            .parent()?
+            .canonicalize()
+            .ok()?
+            .join(resolved.file_name()?),
+        Err(_) => return None,
+    };
+
+    real_path.starts_with(&root).then_some(real_path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{key_bytes, resolve_path};
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::path::PathBuf;
+
+    #[test]
+    fn keys_are_translated_like_a_serial_terminal() {
+        let key = |code, modifiers| key_bytes(KeyEvent::new(code, modifiers));
+
+        assert_eq!(
+            key(KeyCode::Char('a'), KeyModifiers::NONE),
+            Some(vec![b'a'])
+        );
+        assert_eq!(
+            key(KeyCode::Char('A'), KeyModifiers::SHIFT),
+            Some(vec![b'A'])
+        );
+        assert_eq!(
+            key(KeyCode::Char('d'), KeyModifiers::CONTROL),
+            Some(vec![0x04])
+        );
+        assert_eq!(key(KeyCode::Enter, KeyModifiers::NONE), Some(vec![b'\r']));
+        assert_eq!(
+            key(KeyCode::Up, KeyModifiers::NONE),
+            Some(b"\x1b[A".to_vec())
+        );
+        assert_eq!(key(KeyCode::F(1), KeyModifiers::NONE), None);
+    }
+
+    /// Creates an empty directory to use as the semihosting root.
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "probe-rs-semihosting-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+
+        root.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn paths_are_resolved_in_root() {
+        let root = temp_root("resolve");
+        std::fs::create_dir(root.join("input")).unwrap();
+        std::fs::write(root.join("input/data.bin"), [0xaa]).unwrap();
+
+        assert_eq!(
+            resolve_path(&root, "input/data.bin"),
+            Some(root.join("input/data.bin"))
+        );
+        assert_eq!(
+            resolve_path(&root, "./result.txt"),
+            Some(root.join("result.txt"))
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn paths_cannot_leave_root() {
+        let root = temp_root("leave");
+
+        assert_eq!(resolve_path(&root, "../secret"), None);
+        assert_eq!(resolve_path(&root, "input/../../secret"), None);
+        assert_eq!(resolve_path(&root, "/etc/passwd"), None);
+        assert_eq!(resolve_path(&root, "missing/result.txt"), None);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_leave_root() {
+        let root = temp_root("symlink");
+        let outside = temp_root("symlink-outside");
+        std::fs::write(outside.join("secret"), "secret").unwrap();
+
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+        std::os::unix::fs::symlink(outside.join("created"), root.join("dangling")).unwrap();
+        std::os::unix::fs::symlink(root.join("escape/secret"), root.join("secret")).unwrap();
+
+        assert_eq!(resolve_path(&root, "escape/secret"), None);
+        assert_eq!(resolve_path(&root, "escape/created"), None);
+        assert_eq!(resolve_path(&root, "dangling"), None);
+        assert_eq!(resolve_path(&root, "secret"), None);
+
+        // Links which stay inside of the root are allowed.
+        std::fs::create_dir(root.join("data")).unwrap();
+        std::os::unix::fs::symlink(root.join("data"), root.join("link")).unwrap();
+        assert_eq!(
+            resolve_path(&root, "link/result.txt"),
+            Some(root.join("data/result.txt"))
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+        std::fs::remove_dir_all(&outside).unwrap();
+    }
+}
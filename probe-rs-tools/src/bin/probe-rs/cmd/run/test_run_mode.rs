@@ -1,5 +1,5 @@
 use crate::cmd::run::{
-    print_stacktrace, OutputStream, ReturnReason, RunLoop, RunMode, SemihostingPrinter,
+    print_stacktrace, OutputStream, ReturnReason, RunLoop, RunMode, SemihostingHandler,
 };
 use anyhow::Result;
 use libtest_mimic::{Arguments, Failed, FormatSetting, Trial};
@@ -127,7 +127,8 @@ impl TestRunMode {
 
         let mut cmdline_requested = false;
 
-        let mut printer = SemihostingPrinter::new();
+        let mut semihosting =
+            SemihostingHandler::new(&session_and_runloop.run_loop.semihosting_options);
         // When the target first invokes SYS_GET_CMDLINE (0x15), we answer "list"
         // Then, we wait until the target invokes SEMIHOSTING_USER_LIST (0x100) with the json containing all tests
         let halt_handler = |halt_reason: HaltReason, core: &mut Core| {
@@ -159,11 +160,8 @@ impl TestRunMode {
 
                     Ok(Some(list))
                 }
-                other @ (SemihostingCommand::Open(_)
-                | SemihostingCommand::Close(_)
-                | SemihostingCommand::WriteConsole(_)
-                | SemihostingCommand::Write(_)) => {
-                    printer.handle(other, core)?;
+                other if SemihostingHandler::handles(&other) => {
+                    semihosting.handle(other, core)?;
                     Ok(None)
                 }
                 other => anyhow::bail!(
@@ -208,7 +206,8 @@ impl TestRunMode {
         let timeout = timeout.unwrap_or(Duration::from_secs(60)); // TODO: make global timeout configurable: https://github.com/probe-rs/embedded-test/issues/3
         let mut cmdline_requested = false;
 
-        let mut printer = SemihostingPrinter::new();
+        let mut semihosting =
+            SemihostingHandler::new(&session_and_runloop.run_loop.semihosting_options);
         // When the target first invokes SYS_GET_CMDLINE (0x15), we answer "run <test_name>
         // Then we wait until the target invokes SYS_EXIT (0x18) or SYS_EXIT_EXTENDED(0x20) with the exit code
         let halt_handler = |halt_reason: HaltReason, core: &mut Core| {
@@ -233,11 +232,8 @@ impl TestRunMode {
                 SemihostingCommand::ExitError(_) if cmdline_requested => {
                     Ok(Some(TestOutcome::Panic))
                 }
-                other @ (SemihostingCommand::Open(_)
-                | SemihostingCommand::Close(_)
-                | SemihostingCommand::WriteConsole(_)
-                | SemihostingCommand::Write(_)) => {
-                    printer.handle(other, core)?;
+                other if SemihostingHandler::handles(&other) => {
+                    semihosting.handle(other, core)?;
                     Ok(None)
                 }
                 other => {
@@ -85,6 +85,9 @@ enum Subcommand {
     /// Trace a memory location on the target
     #[clap(name = "trace")]
     Trace(cmd::trace::Cmd),
+    /// Print the source lines executed before the core halts, recorded by the ETM or MTB
+    #[clap(name = "instruction-trace")]
+    InstructionTrace(cmd::instruction_trace::Cmd),
     /// Configure and monitor ITM trace packets from the target.
     #[clap(name = "itm")]
     Itm(cmd::itm::Cmd),
@@ -336,6 +339,7 @@ fn main() -> Result<()> {
         }
         Subcommand::Erase(cmd) => cmd.run(&lister),
         Subcommand::Trace(cmd) => cmd.run(&lister),
+        Subcommand::InstructionTrace(cmd) => cmd.run(&lister),
         Subcommand::Itm(cmd) => cmd.run(&lister),
         Subcommand::Chip(cmd) => cmd.run(),
         Subcommand::Benchmark(cmd) => cmd.run(&lister),
@@ -13,7 +13,7 @@ use probe_rs::{
     probe::{
         list::Lister, DebugProbeError, DebugProbeInfo, DebugProbeSelector, Probe, WireProtocol,
     },
-    Permissions, Session, Target,
+    CoreDump, CoreDumpError, Permissions, Session, Target,
 };
 use serde::{Deserialize, Serialize};
 
@@ -140,6 +140,19 @@ impl ProbeOptions {
 
         Ok((session, common_options))
     }
+
+    /// Convenience method that opens a session for the core dump at `path`,
+    /// which is debugged offline instead of attaching to a probe.
+    pub fn core_dump_attach(
+        self,
+        path: &Path,
+    ) -> Result<(Session, LoadedProbeOptions), OperationError> {
+        let common_options = self.load()?;
+
+        let session = common_options.core_dump_session(path)?;
+
+        Ok((session, common_options))
+    }
 }
 
 /// Common options and logic when interfacing with a [Probe] which already did all pre operation preparation.
@@ -312,6 +325,22 @@ impl LoadedProbeOptions {
         Ok(session)
     }
 
+    /// Opens a session for the core dump at `path`. If no chip was specified,
+    /// a generic target with the core of the dump is used.
+    pub fn core_dump_session(&self, path: &Path) -> Result<Session, OperationError> {
+        let dump = CoreDump::load(path).map_err(|error| OperationError::FailedToLoadCoreDump {
+            source: error,
+            path: path.to_path_buf(),
+        })?;
+
+        let target = match self.get_target_selector()? {
+            TargetSelector::Specified(target) => Some(target),
+            _ => None,
+        };
+
+        Session::from_core_dumps(vec![dump], target).map_err(OperationError::CoreDumpSessionFailed)
+    }
+
     pub(crate) fn protocol(&self) -> Option<WireProtocol> {
         self.0.protocol
     }
@@ -524,6 +553,15 @@ pub enum OperationError {
         connect_under_reset: bool,
     },
 
+    #[error("Failed to load the core dump '{path}'.")]
+    FailedToLoadCoreDump {
+        source: CoreDumpError,
+        path: PathBuf,
+    },
+
+    #[error("The core dump could not be opened for debugging.")]
+    CoreDumpSessionFailed(#[source] probe_rs::Error),
+
     #[error("Failed to get a handle to the first core.")]
     AttachingToCoreFailed(#[source] probe_rs::Error),
 
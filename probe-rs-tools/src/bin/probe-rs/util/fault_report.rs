@@ -0,0 +1,109 @@
+//! Helpers to create a [`FaultReport`] which is enriched with information the library does
+//! not have: the disassembled faulting instruction, and the name of the active interrupt.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use capstone::{arch::arm::ArchMode, prelude::*, Endian};
+use probe_rs::debug::{DebugRegisters, FaultReport};
+use probe_rs::{exception_handler_for_core, Core, CoreType};
+
+/// The names of the interrupts of a device, by interrupt number.
+pub type InterruptNames = HashMap<u32, String>;
+
+/// Collects the interrupt names of all peripherals in a CMSIS-SVD device description.
+pub fn interrupt_names(device: &svd_parser::svd::Device) -> InterruptNames {
+    device
+        .peripherals
+        .iter()
+        .flat_map(|peripheral| &peripheral.interrupt)
+        .map(|interrupt| (interrupt.value, interrupt.name.clone()))
+        .collect()
+}
+
+/// Loads the interrupt names from a CMSIS-SVD file.
+pub fn interrupt_names_from_svd(path: &Path) -> anyhow::Result<InterruptNames> {
+    let svd_xml = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read SVD file {}", path.display()))?;
+
+    let device = svd_parser::parse_with_config(
+        &svd_xml,
+        &svd_parser::Config::default()
+            .expand(true)
+            .ignore_enums(true),
+    )
+    .map_err(|e| anyhow!("Unable to parse CMSIS-SVD file {}: {e:?}", path.display()))?;
+
+    Ok(interrupt_names(&device))
+}
+
+/// Creates the fault report for the halted `core`, or `Ok(None)` if no exception is active,
+/// or the architecture does not support fault reports.
+///
+/// The faulting instruction is disassembled, and the interrupt is named using `interrupt_names`.
+pub fn fault_report(
+    core: &mut Core,
+    interrupt_names: &InterruptNames,
+) -> anyhow::Result<Option<FaultReport>> {
+    let core_type = core.core_type();
+    let registers = DebugRegisters::from_core(core);
+    let exception_handler = exception_handler_for_core(core_type);
+
+    let Some(mut report) = exception_handler.fault_report(core, &registers)? else {
+        return Ok(None);
+    };
+
+    report.interrupt_name = report
+        .interrupt_number
+        .and_then(|number| interrupt_names.get(&number).cloned());
+
+    if let Some(instruction) = report.instruction.as_mut() {
+        instruction.disassembly =
+            disassemble_thumb(core_type, &instruction.bytes, instruction.address);
+    }
+
+    Ok(Some(report))
+}
+
+/// Disassembles a single Thumb instruction.
+fn disassemble_thumb(core_type: CoreType, bytes: &[u8], address: u64) -> Option<String> {
+    let mut capstone_builder = Capstone::new()
+        .arm()
+        .mode(ArchMode::Thumb)
+        .endian(Endian::Little);
+    if core_type == CoreType::Armv8m {
+        capstone_builder =
+            capstone_builder.extra_mode(std::iter::once(capstone::arch::arm::ArchExtraMode::V8));
+    }
+    let capstone = capstone_builder.build().ok()?;
+
+    let instructions = capstone.disasm_count(bytes, address, 1).ok()?;
+    let instruction = instructions.iter().next()?;
+
+    let disassembly = format!(
+        "{} {}",
+        instruction.mnemonic().unwrap_or_default(),
+        instruction.op_str().unwrap_or_default()
+    );
+    Some(disassembly.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn disassemble_load() {
+        let disassembly = disassemble_thumb(CoreType::Armv7m, &[0x00, 0x68], 0x100);
+
+        assert_eq!(disassembly.as_deref(), Some("ldr r0, [r0]"));
+    }
+
+    #[test]
+    fn disassemble_instruction_without_operands() {
+        let disassembly = disassemble_thumb(CoreType::Armv8m, &[0x00, 0xbf], 0x100);
+
+        assert_eq!(disassembly.as_deref(), Some("nop"));
+    }
+}
@@ -0,0 +1,494 @@
+//! Host side demultiplexing of the ITM stimulus ports.
+//!
+//! Firmware can write to any of the 32 stimulus ports of the ITM, e.g. text with
+//! `cortex_m::iprintln!` or defmt frames with `defmt-itm`. The [`ItmDemultiplexer`] decodes the
+//! ITM packets received over SWO, drops the data of ports which are not configured, and formats
+//! the data of each port like an RTT up channel. Instead of the time of the host, text is
+//! prefixed with the time of the local and global timestamp packets of the target.
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+use probe_rs::architecture::arm::SwoConfig;
+use probe_rs::rtt::Error;
+
+use crate::util::rtt::{ChannelDataCallbacks, ChannelDataFormat, DataFormat, DefmtState};
+
+/// The configuration of the ITM output over SWO.
+#[derive(Debug, Clone)]
+pub struct ItmConfig {
+    /// The frequency of the core clock in Hz, which drives the SWO output and the timestamps.
+    pub clock: u32,
+    /// The baud rate of the SWO output.
+    pub baud: u32,
+    /// The stimulus ports to display. The data written to other ports is dropped.
+    pub ports: Vec<ItmPortConfig>,
+}
+
+impl ItmConfig {
+    /// The SWO configuration to receive the ITM output.
+    pub fn swo_config(&self) -> SwoConfig {
+        SwoConfig::new(self.clock).set_baud(self.baud)
+    }
+}
+
+/// The configuration of a single stimulus port.
+#[derive(Debug, Clone)]
+pub struct ItmPortConfig {
+    /// The number of the stimulus port.
+    pub port: u8,
+    /// The name of the port. Defaults to `ITM port <port>`.
+    pub name: Option<String>,
+    pub data_format: DataFormat,
+    /// Controls the inclusion of timestamps for [`DataFormat::String`] and [`DataFormat::Defmt`].
+    pub show_timestamps: bool,
+    /// Controls the inclusion of source location information for [`DataFormat::Defmt`].
+    pub show_location: bool,
+    /// Controls the output format for [`DataFormat::Defmt`].
+    pub log_format: Option<String>,
+}
+
+impl ItmPortConfig {
+    /// Creates the configuration of `port`, with timestamps and without source locations.
+    pub fn new(port: u8, data_format: DataFormat) -> Self {
+        Self {
+            port,
+            name: None,
+            data_format,
+            show_timestamps: true,
+            show_location: false,
+            log_format: None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("ITM port {}", self.port))
+    }
+}
+
+/// Splits the ITM packet stream into the data of the stimulus ports.
+///
+/// The data of each port is passed to the collector with the port number as channel number.
+pub struct ItmDemultiplexer {
+    ports: Vec<ItmPort>,
+    clock: u32,
+    /// Received bytes which do not form a complete packet yet.
+    buffer: Vec<u8>,
+    /// The time of the most recent timestamp packet, in clock cycles.
+    time: Option<u64>,
+    /// The lower 26 bits and the upper bits of the most recent global timestamp.
+    global_timestamp: (u64, u64),
+    /// The data of stimulus packets, by index of the port, which precede their timestamp.
+    pending: Vec<(usize, Vec<u8>)>,
+}
+
+impl ItmDemultiplexer {
+    pub fn new(config: &ItmConfig, defmt_data: Option<Arc<DefmtState>>) -> Self {
+        Self {
+            ports: config
+                .ports
+                .iter()
+                .map(|port| ItmPort::new(port, defmt_data.clone()))
+                .collect(),
+            clock: config.clock,
+            buffer: Vec::new(),
+            time: None,
+            global_timestamp: (0, 0),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Decodes the received ITM data, and passes the data of the configured ports to the
+    /// `collector`.
+    ///
+    /// A local timestamp packet follows the stimulus packets it applies to. Data without a
+    /// timestamp yet is passed on at the end of each call, with the time of the previous
+    /// timestamp.
+    pub fn process(
+        &mut self,
+        data: &[u8],
+        collector: &mut impl ChannelDataCallbacks,
+    ) -> Result<(), Error> {
+        self.buffer.extend_from_slice(data);
+
+        let mut position = 0;
+        while let Some((packet, length)) = Packet::parse(&self.buffer[position..]) {
+            position += length;
+
+            match packet {
+                Packet::Instrumentation { port, payload } => {
+                    if let Some(index) = self.ports.iter().position(|p| p.port == port) {
+                        self.pending.push((index, payload));
+                    }
+                    continue;
+                }
+                Packet::LocalTimestamp(delta) => {
+                    self.time = Some(self.time.unwrap_or(0) + delta);
+                }
+                Packet::GlobalTimestamp1 { value, mask } => {
+                    let (low, high) = &mut self.global_timestamp;
+                    *low = (*low & !mask) | value;
+                    self.time = Some(*high << 26 | *low);
+                }
+                Packet::GlobalTimestamp2(value) => {
+                    let (low, high) = &mut self.global_timestamp;
+                    *high = value;
+                    self.time = Some(*high << 26 | *low);
+                }
+                Packet::Other => continue,
+            }
+
+            self.flush(collector)?;
+        }
+        self.buffer.drain(..position);
+
+        self.flush(collector)
+    }
+
+    /// Passes the pending data to the ports, with the current time.
+    fn flush(&mut self, collector: &mut impl ChannelDataCallbacks) -> Result<(), Error> {
+        let timestamp = self
+            .time
+            .map(|time| format!("{:.6}", time as f64 / f64::from(self.clock)));
+
+        let mut pending = self.pending.drain(..).peekable();
+        while let Some((index, mut data)) = pending.next() {
+            // Consecutive packets of a port are passed on at once, to not split lines.
+            while let Some((_, next)) = pending.next_if(|(next, _)| *next == index) {
+                data.extend(next);
+            }
+            self.ports[index].process(&data, timestamp.as_deref(), collector)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The state of a configured stimulus port.
+struct ItmPort {
+    port: u8,
+    format: ChannelDataFormat,
+    show_timestamps: bool,
+    /// Whether the output of the port ended with a complete line.
+    last_line_done: bool,
+    /// Whether defmt frames are terminated by a zero byte, as with the rzCOBS encoding.
+    delimited_frames: bool,
+    /// Received defmt data which does not form a complete frame yet.
+    frame: Vec<u8>,
+}
+
+impl ItmPort {
+    fn new(config: &ItmPortConfig, defmt_data: Option<Arc<DefmtState>>) -> Self {
+        let delimited_frames = defmt_data
+            .as_ref()
+            .is_some_and(|defmt| defmt.table.encoding().can_recover());
+
+        let format = match config.data_format {
+            DataFormat::String => ChannelDataFormat::String {
+                timestamp_offset: None,
+                last_line_done: true,
+            },
+            DataFormat::BinaryLE => ChannelDataFormat::BinaryLE,
+            DataFormat::Defmt => ChannelDataFormat::defmt(
+                defmt_data,
+                config.show_timestamps,
+                config.show_location,
+                config.log_format.as_deref(),
+            ),
+        };
+
+        Self {
+            port: config.port,
+            format,
+            show_timestamps: config.show_timestamps,
+            last_line_done: true,
+            delimited_frames,
+            frame: Vec::new(),
+        }
+    }
+
+    fn process(
+        &mut self,
+        data: &[u8],
+        timestamp: Option<&str>,
+        collector: &mut impl ChannelDataCallbacks,
+    ) -> Result<(), Error> {
+        // Defmt frames are spread over many stimulus packets, and can only be decoded once
+        // they are complete.
+        let frames;
+        let data =
+            if matches!(self.format, ChannelDataFormat::Defmt { .. }) && self.delimited_frames {
+                self.frame.extend_from_slice(data);
+                let Some(end) = self.frame.iter().rposition(|byte| *byte == 0) else {
+                    return Ok(());
+                };
+                frames = self.frame.drain(..=end).collect::<Vec<_>>();
+                &frames
+            } else {
+                data
+            };
+
+        let mut collector = Timestamped {
+            collector,
+            timestamp: timestamp.filter(|_| self.show_timestamps),
+            last_line_done: &mut self.last_line_done,
+        };
+        self.format
+            .process(usize::from(self.port), data, &mut collector)
+    }
+}
+
+/// Prefixes each line of text with a timestamp.
+struct Timestamped<'a, C> {
+    collector: &'a mut C,
+    timestamp: Option<&'a str>,
+    last_line_done: &'a mut bool,
+}
+
+impl<C: ChannelDataCallbacks> ChannelDataCallbacks for Timestamped<'_, C> {
+    fn on_binary_data(&mut self, channel: usize, data: &[u8]) -> Result<(), Error> {
+        self.collector.on_binary_data(channel, data)
+    }
+
+    fn on_string_data(&mut self, channel: usize, data: String) -> Result<(), Error> {
+        let Some(timestamp) = self.timestamp else {
+            return self.collector.on_string_data(channel, data);
+        };
+
+        let mut formatted_data = String::new();
+        for line in data.split_inclusive('\n') {
+            if *self.last_line_done {
+                write!(formatted_data, "{timestamp}: ").expect("Writing to String cannot fail");
+            }
+            formatted_data.push_str(line);
+            *self.last_line_done = line.ends_with('\n');
+        }
+        self.collector.on_string_data(channel, formatted_data)
+    }
+}
+
+/// The ITM packets which are relevant for the stimulus ports.
+#[derive(Debug, PartialEq)]
+enum Packet {
+    /// Data written to a stimulus port.
+    Instrumentation { port: u8, payload: Vec<u8> },
+    /// The clock cycles since the previous local timestamp.
+    LocalTimestamp(u64),
+    /// The lower 26 bits of the global timestamp, where only the bits in `mask` changed.
+    GlobalTimestamp1 { value: u64, mask: u64 },
+    /// The upper bits of the global timestamp, starting at bit 26.
+    GlobalTimestamp2(u64),
+    /// Synchronization, overflow, extension and hardware source packets.
+    Other,
+}
+
+impl Packet {
+    /// Parses the packet at the start of `data`. Returns the packet and its length, or `None`
+    /// if the packet is incomplete.
+    fn parse(data: &[u8]) -> Option<(Packet, usize)> {
+        let header = *data.first()?;
+
+        match header {
+            // Synchronization: at least 47 cleared bits, followed by a set bit.
+            0x00 => {
+                let zeros = data.iter().take_while(|byte| **byte == 0).count();
+                match data.get(zeros)? {
+                    0x80 => Some((Packet::Other, zeros + 1)),
+                    _ => Some((Packet::Other, zeros)),
+                }
+            }
+            // Overflow
+            0x70 => Some((Packet::Other, 1)),
+            // Local timestamp format 2, with the timestamp in the header.
+            _ if header & 0x8F == 0x00 => Some((Packet::LocalTimestamp(u64::from(header >> 4)), 1)),
+            // Local timestamp format 1
+            _ if header & 0xCF == 0xC0 => {
+                let (value, length) = continued(&data[1..], 4)?;
+                Some((Packet::LocalTimestamp(value), length + 1))
+            }
+            0x94 => {
+                let (value, length) = continued(&data[1..], 4)?;
+                let mask = if length == 4 {
+                    (1 << 26) - 1
+                } else {
+                    (1 << (7 * length)) - 1
+                };
+                Some((
+                    Packet::GlobalTimestamp1 {
+                        value: value & mask,
+                        mask,
+                    },
+                    length + 1,
+                ))
+            }
+            0xB4 => {
+                let (value, length) = continued(&data[1..], 6)?;
+                Some((Packet::GlobalTimestamp2(value), length + 1))
+            }
+            // Extension
+            _ if header & 0x0B == 0x08 => {
+                if header & 0x80 == 0 {
+                    return Some((Packet::Other, 1));
+                }
+                let (_, length) = continued(&data[1..], 4)?;
+                Some((Packet::Other, length + 1))
+            }
+            // Software and hardware source packets
+            _ if header & 0x03 != 0 => {
+                let size = [0, 1, 2, 4][usize::from(header & 0x03)];
+                let payload = data.get(1..=size)?;
+                if header & 0x04 != 0 {
+                    return Some((Packet::Other, size + 1));
+                }
+                Some((
+                    Packet::Instrumentation {
+                        port: header >> 3,
+                        payload: payload.to_vec(),
+                    },
+                    size + 1,
+                ))
+            }
+            // Reserved
+            _ => Some((Packet::Other, 1)),
+        }
+    }
+}
+
+/// Decodes up to `max` bytes with 7 bits of data each, where a set bit 7 indicates that another
+/// byte follows. Returns the value and the number of bytes, or `None` if the data is incomplete.
+fn continued(data: &[u8], max: usize) -> Option<(u64, usize)> {
+    let mut value = 0;
+    for (index, byte) in data.iter().take(max).enumerate() {
+        value |= u64::from(byte & 0x7F) << (7 * index);
+        if byte & 0x80 == 0 || index + 1 == max {
+            return Some((value, index + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct Output(Vec<(usize, String)>);
+
+    impl ChannelDataCallbacks for Output {
+        fn on_string_data(&mut self, channel: usize, data: String) -> Result<(), Error> {
+            self.0.push((channel, data));
+            Ok(())
+        }
+    }
+
+    fn demultiplexer(ports: &[(u8, DataFormat)], show_timestamps: bool) -> ItmDemultiplexer {
+        let config = ItmConfig {
+            clock: 1_000_000,
+            baud: 1_000_000,
+            ports: ports
+                .iter()
+                .map(|(port, data_format)| ItmPortConfig {
+                    show_timestamps,
+                    ..ItmPortConfig::new(*port, *data_format)
+                })
+                .collect(),
+        };
+        ItmDemultiplexer::new(&config, None)
+    }
+
+    /// A stimulus packet with up to 4 bytes written to `port`.
+    fn stimulus(port: u8, payload: &[u8]) -> Vec<u8> {
+        let size = match payload.len() {
+            1 => 0b01,
+            2 => 0b10,
+            4 => 0b11,
+            _ => unreachable!(),
+        };
+        let mut packet = vec![port << 3 | size];
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    #[test]
+    fn ports_are_filtered() {
+        let mut itm = demultiplexer(&[(0, DataFormat::String), (2, DataFormat::BinaryLE)], true);
+
+        let mut stream = vec![0, 0, 0, 0, 0, 0x80];
+        stream.extend(stimulus(0, b"H"));
+        stream.extend(stimulus(0, b"i"));
+        stream.extend(stimulus(1, b"drop"));
+        // A hardware source packet, e.g. an exception trace
+        stream.extend([0x0E, 0x10, 0x20]);
+        stream.extend(stimulus(2, &[0xAB, 0xCD]));
+        stream.extend(stimulus(0, b"\n"));
+
+        let mut output = Output::default();
+        itm.process(&stream, &mut output).unwrap();
+
+        assert_eq!(
+            output.0,
+            [
+                (0, "Hi".to_string()),
+                (2, "0xab0xcd".to_string()),
+                (0, "\n".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn packets_split_across_reads() {
+        let mut itm = demultiplexer(&[(31, DataFormat::String)], true);
+        let packet = stimulus(31, b"abcd");
+
+        let mut output = Output::default();
+        itm.process(&packet[..2], &mut output).unwrap();
+        assert!(output.0.is_empty());
+
+        itm.process(&packet[2..], &mut output).unwrap();
+        assert_eq!(output.0, [(31, "abcd".to_string())]);
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut itm = demultiplexer(&[(0, DataFormat::String)], true);
+
+        let mut stream = stimulus(0, b"a\n");
+        stream.extend(stimulus(0, b"b"));
+        // Local timestamp format 2, 3 cycles
+        stream.push(0x30);
+        stream.extend(stimulus(0, b"c\n"));
+        // Local timestamp format 1, 0x87 cycles
+        stream.extend([0xC0, 0x87, 0x01]);
+        // Global timestamp with bit 26 and the lower bits 0x90
+        stream.extend([0x94, 0x90, 0x01]);
+        stream.extend([0xB4, 0x01]);
+        stream.extend(stimulus(0, b"d\n"));
+
+        let mut output = Output::default();
+        itm.process(&stream, &mut output).unwrap();
+
+        assert_eq!(
+            output.0,
+            [
+                (0, "0.000003: a\n0.000003: b".to_string()),
+                (0, "c\n".to_string()),
+                (0, "67.109008: d\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn timestamps_can_be_disabled() {
+        let mut itm = demultiplexer(&[(0, DataFormat::String)], false);
+
+        let mut stream = stimulus(0, b"a\n");
+        stream.push(0x30);
+
+        let mut output = Output::default();
+        itm.process(&stream, &mut output).unwrap();
+
+        assert_eq!(output.0, [(0, "a\n".to_string())]);
+    }
+}
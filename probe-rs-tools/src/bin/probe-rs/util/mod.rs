@@ -1,6 +1,8 @@
 pub mod cargo;
 pub mod common_options;
+pub mod fault_report;
 pub mod flash;
+pub mod itm;
 pub mod logging;
 pub mod meta;
 pub mod rtt;
@@ -132,13 +132,51 @@ impl fmt::Debug for ChannelDataFormat {
 }
 
 impl ChannelDataFormat {
+    /// Creates the format for defmt encoded data.
+    pub(crate) fn defmt(
+        defmt_data: Option<Arc<DefmtState>>,
+        show_timestamps: bool,
+        show_location: bool,
+        log_format: Option<&str>,
+    ) -> Self {
+        let has_timestamp = if let Some(ref defmt) = defmt_data {
+            defmt.table.has_timestamp()
+        } else {
+            tracing::warn!("No `Table` definition in DWARF info; compile your program with `debug = 2` to enable location info.");
+            false
+        };
+
+        // Format options:
+        // 1. Oneline format with optional location
+        // 2. Custom format for the channel
+        // 3. Default with optional location
+        let format = match log_format {
+            Some("oneline") => FormatterFormat::OneLine {
+                with_location: show_location,
+            },
+            Some(format) => FormatterFormat::Custom(format),
+            None => FormatterFormat::Default {
+                with_location: show_location,
+            },
+        };
+
+        ChannelDataFormat::Defmt {
+            formatter: Formatter::new(FormatterConfig {
+                format,
+                is_timestamp_available: has_timestamp && show_timestamps,
+            }),
+            cwd: std::env::current_dir().unwrap(),
+            defmt_data,
+        }
+    }
+
     /// Returns whether the channel is expected to output binary data (`true`)
     /// or human-readable strings (`false`).
     pub fn is_binary(&self) -> bool {
         matches!(self, ChannelDataFormat::BinaryLE)
     }
 
-    fn process(
+    pub(crate) fn process(
         &mut self,
         number: usize,
         buffer: &[u8],
@@ -299,37 +337,12 @@ impl RttActiveUpChannel {
             DataFormat::BinaryLE if !is_defmt_channel => ChannelDataFormat::BinaryLE,
 
             // either DataFormat::Defmt is configured, or defmt_enabled is true
-            _ => {
-                let has_timestamp = if let Some(ref defmt) = defmt_data {
-                    defmt.table.has_timestamp()
-                } else {
-                    tracing::warn!("No `Table` definition in DWARF info; compile your program with `debug = 2` to enable location info.");
-                    false
-                };
-
-                // Format options:
-                // 1. Oneline format with optional location
-                // 2. Custom format for the channel
-                // 3. Default with optional location
-                let format = match channel_config.log_format.as_deref() {
-                    Some("oneline") => FormatterFormat::OneLine {
-                        with_location: channel_config.show_location,
-                    },
-                    Some(format) => FormatterFormat::Custom(format),
-                    None => FormatterFormat::Default {
-                        with_location: channel_config.show_location,
-                    },
-                };
-
-                ChannelDataFormat::Defmt {
-                    formatter: Formatter::new(FormatterConfig {
-                        format,
-                        is_timestamp_available: has_timestamp && channel_config.show_timestamps,
-                    }),
-                    cwd: std::env::current_dir().unwrap(),
-                    defmt_data,
-                }
-            }
+            _ => ChannelDataFormat::defmt(
+                defmt_data,
+                channel_config.show_timestamps,
+                channel_config.show_location,
+                channel_config.log_format.as_deref(),
+            ),
         };
 
         let mut original_mode = None;
@@ -0,0 +1,39 @@
+//! A minimal binary instrumented for code coverage, built as `coverage.elf` with
+//!
+//! RUSTC_BOOTSTRAP=1 rustc +1.83.0 --target thumbv7m-none-eabi -Zno-profiler-runtime \
+//!     -C instrument-coverage -C panic=abort -C opt-level=1 \
+//!     -C link-arg=-Tlink.x -C link-arg=--nmagic coverage.rs -o coverage.elf
+
+#![no_std]
+#![no_main]
+
+#[no_mangle]
+#[used]
+static __llvm_profile_runtime: i32 = 0;
+
+#[inline(never)]
+fn f(x: u32) -> u32 {
+    if x > 3 { x * 2 } else { x + 1 }
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    let mut s = 0u32;
+    for i in 0..10 {
+        s = s.wrapping_add(f(i));
+    }
+    unsafe { core::ptr::write_volatile(0x2000_0000 as *mut u32, s) };
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+unsafe extern "C" fn __atomic_fetch_add_8(ptr: *mut u64, value: u64, _ordering: i32) -> u64 {
+    let old = *ptr;
+    *ptr = old.wrapping_add(value);
+    old
+}
@@ -0,0 +1,11 @@
+MEMORY { FLASH : ORIGIN = 0x08000000, LENGTH = 64K  RAM : ORIGIN = 0x20000000, LENGTH = 16K }
+ENTRY(_start)
+SECTIONS {
+  .text : { *(.text .text.*) } > FLASH
+  .rodata : { *(.rodata .rodata.*) } > FLASH
+  __llvm_prf_names : { *(__llvm_prf_names) } > FLASH
+  __llvm_prf_data : { *(__llvm_prf_data) } > FLASH
+  __llvm_prf_cnts (NOLOAD) : { *(__llvm_prf_cnts) } > RAM
+  .data : { *(.data .data.*) } > RAM AT > FLASH
+  .bss (NOLOAD) : { *(.bss .bss.*) } > RAM
+}
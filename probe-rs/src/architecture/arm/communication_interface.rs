@@ -94,6 +94,15 @@ pub trait ArmProbeInterface: DapAccess + SwdSequence + SwoAccess + Send {
         &mut self,
         dp: DpAddress,
     ) -> Result<Option<ArmChipInfo>, ArmError>;
+
+    /// Changes the speed of the underlying probe while attached.
+    ///
+    /// Returns the speed in kHz which was actually configured.
+    fn set_speed(&mut self, _speed_khz: u32) -> Result<u32, DebugProbeError> {
+        Err(DebugProbeError::NotImplemented {
+            function_name: "set_speed",
+        })
+    }
 }
 
 // TODO: Rename trait!
@@ -268,6 +277,10 @@ impl ArmProbeInterface for ArmCommunicationInterface<Initialized> {
         self.state.current_dp
     }
 
+    fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
+        self.probe_mut().set_speed(speed_khz)
+    }
+
     fn close(self: Box<Self>) -> Probe {
         ArmCommunicationInterface::close(*self)
     }
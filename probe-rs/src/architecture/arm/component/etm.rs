@@ -0,0 +1,256 @@
+//! Module for the Embedded Trace Macrocell (ETM) of Cortex-M cores.
+//!
+//! The ETM generates a compressed trace of the executed instructions. Cortex-M3 and Cortex-M4
+//! implement ETMv3.5, the ETM of the Cortex-M7, Cortex-M23 and Cortex-M33 implements ETMv4.
+//! Both are configured for plain instruction trace, without cycle counts, timestamps, context
+//! IDs or data trace, which is the subset the decoders in
+//! [`instruction_trace`](crate::architecture::arm::instruction_trace) understand.
+
+use std::time::{Duration, Instant};
+
+use super::DebugComponentInterface;
+use crate::architecture::arm::memory::romtable::CoresightComponent;
+use crate::architecture::arm::{ArmError, ArmProbeInterface};
+use crate::memory_mapped_bitfield_register;
+
+const REGISTER_OFFSET_ACCESS: u32 = 0xFB0;
+
+/// ETMIDR on ETMv3, TRCIDR1 on ETMv4. Both contain the major architecture version.
+const REGISTER_OFFSET_ID: u32 = 0x1E4;
+
+const ETMV3_TRIGGER: u32 = 0x008;
+const ETMV3_TRACE_ENABLE_EVENT: u32 = 0x020;
+const ETMV3_TRACE_ENABLE_CONTROL1: u32 = 0x024;
+const ETMV3_TRACE_ID: u32 = 0x200;
+
+const ETMV4_PROGRAMMING_CONTROL: u32 = 0x004;
+const ETMV4_CONFIG: u32 = 0x010;
+const ETMV4_EVENT_CONTROL0: u32 = 0x020;
+const ETMV4_EVENT_CONTROL1: u32 = 0x024;
+const ETMV4_STALL_CONTROL: u32 = 0x02C;
+const ETMV4_TIMESTAMP_CONTROL: u32 = 0x030;
+const ETMV4_SYNC_PERIOD: u32 = 0x034;
+const ETMV4_TRACE_ID: u32 = 0x040;
+const ETMV4_VIEW_INST_MAIN_CONTROL: u32 = 0x080;
+const ETMV4_VIEW_INST_INCLUDE_EXCLUDE: u32 = 0x084;
+const ETMV4_VIEW_INST_START_STOP: u32 = 0x088;
+const ETMV4_OS_LOCK: u32 = 0x300;
+
+/// The event resource which is always active on ETMv3 ("hard-wired").
+const ETMV3_EVENT_ALWAYS: u32 = 0x6F;
+
+/// How long to wait for the ETM to enter or leave the programming state.
+const PROGRAMMING_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The architecture version of an ETM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EtmVersion {
+    /// ETMv3, implemented by Cortex-M3 and Cortex-M4.
+    V3,
+    /// ETMv4, implemented by Cortex-M7, Cortex-M23 and Cortex-M33.
+    V4,
+}
+
+/// The Embedded Trace Macrocell of a Cortex-M core.
+pub struct Etm<'a> {
+    component: &'a CoresightComponent,
+    interface: &'a mut dyn ArmProbeInterface,
+}
+
+impl<'a> Etm<'a> {
+    /// Construct a new ETM component.
+    pub fn new(
+        interface: &'a mut dyn ArmProbeInterface,
+        component: &'a CoresightComponent,
+    ) -> Self {
+        Etm {
+            component,
+            interface,
+        }
+    }
+
+    /// Unlock the ETM registers for writing.
+    pub fn unlock(&mut self) -> Result<(), ArmError> {
+        self.component
+            .write_reg(self.interface, REGISTER_OFFSET_ACCESS, 0xC5AC_CE55)
+    }
+
+    /// Read the architecture version of the ETM.
+    pub fn version(&mut self) -> Result<EtmVersion, ArmError> {
+        let id = self
+            .component
+            .read_reg(self.interface, REGISTER_OFFSET_ID)?;
+
+        match (id >> 8) & 0xF {
+            0b0010 => Ok(EtmVersion::V3),
+            0b0100 => Ok(EtmVersion::V4),
+            version => Err(ArmError::Other(format!(
+                "Unsupported ETM architecture version {version} (ID register {id:#010x})"
+            ))),
+        }
+    }
+
+    /// Configure the ETM to trace all executed instructions, and enable it.
+    ///
+    /// # Args
+    /// * `trace_id` - The ATB trace source ID the ETM uses for its trace stream.
+    pub fn enable_instruction_trace(&mut self, trace_id: u8) -> Result<EtmVersion, ArmError> {
+        self.unlock()?;
+
+        let version = self.version()?;
+        match version {
+            EtmVersion::V3 => self.enable_etmv3(trace_id)?,
+            EtmVersion::V4 => self.enable_etmv4(trace_id)?,
+        }
+
+        Ok(version)
+    }
+
+    /// Stop the trace generation of the ETM.
+    pub fn disable(&mut self) -> Result<(), ArmError> {
+        self.unlock()?;
+
+        match self.version()? {
+            EtmVersion::V3 => {
+                let mut control = Etmv3Control::load(self.component, self.interface)?;
+                control.set_programming(true);
+                control.store(self.component, self.interface)?;
+                self.wait_for_etmv3_programming(true)?;
+
+                control.set_power_down(true);
+                control.store(self.component, self.interface)
+            }
+            EtmVersion::V4 => {
+                self.component
+                    .write_reg(self.interface, ETMV4_PROGRAMMING_CONTROL, 0)?;
+                self.wait_for_etmv4_idle(true)
+            }
+        }
+    }
+
+    fn enable_etmv3(&mut self, trace_id: u8) -> Result<(), ArmError> {
+        // Power up the ETM and enter the programming state.
+        let mut control = Etmv3Control::load(self.component, self.interface)?;
+        control.set_power_down(false);
+        control.set_programming(true);
+        control.store(self.component, self.interface)?;
+        self.wait_for_etmv3_programming(true)?;
+
+        // Trace always, exclude no address ranges, and never trigger.
+        self.component
+            .write_reg(self.interface, ETMV3_TRIGGER, 0x4000 | ETMV3_EVENT_ALWAYS)?;
+        self.component
+            .write_reg(self.interface, ETMV3_TRACE_ENABLE_EVENT, ETMV3_EVENT_ALWAYS)?;
+        self.component
+            .write_reg(self.interface, ETMV3_TRACE_ENABLE_CONTROL1, 1 << 24)?;
+        self.component
+            .write_reg(self.interface, ETMV3_TRACE_ID, u32::from(trace_id))?;
+
+        let mut control = Etmv3Control(0);
+        control.set_programming(true);
+        control.set_port_enable(true);
+        control.store(self.component, self.interface)?;
+
+        control.set_programming(false);
+        control.store(self.component, self.interface)?;
+        self.wait_for_etmv3_programming(false)
+    }
+
+    fn enable_etmv4(&mut self, trace_id: u8) -> Result<(), ArmError> {
+        self.component.write_reg(self.interface, ETMV4_OS_LOCK, 0)?;
+        self.component
+            .write_reg(self.interface, ETMV4_PROGRAMMING_CONTROL, 0)?;
+        self.wait_for_etmv4_idle(true)?;
+
+        // Bit 0 is RES1. Everything else, including branch broadcasting, stays disabled.
+        self.component.write_reg(self.interface, ETMV4_CONFIG, 1)?;
+        self.component
+            .write_reg(self.interface, ETMV4_EVENT_CONTROL0, 0)?;
+        self.component
+            .write_reg(self.interface, ETMV4_EVENT_CONTROL1, 0)?;
+        self.component
+            .write_reg(self.interface, ETMV4_STALL_CONTROL, 0)?;
+        self.component
+            .write_reg(self.interface, ETMV4_TIMESTAMP_CONTROL, 0)?;
+        // Synchronize every 2^8 bytes, so that a wrapped trace buffer can be decoded.
+        self.component
+            .write_reg(self.interface, ETMV4_SYNC_PERIOD, 8)?;
+        self.component
+            .write_reg(self.interface, ETMV4_TRACE_ID, u32::from(trace_id))?;
+
+        // Select the always-true resource as ViewInst event, with the start/stop logic started
+        // and all exception levels traced.
+        self.component
+            .write_reg(self.interface, ETMV4_VIEW_INST_MAIN_CONTROL, 0x201)?;
+        self.component
+            .write_reg(self.interface, ETMV4_VIEW_INST_INCLUDE_EXCLUDE, 0)?;
+        self.component
+            .write_reg(self.interface, ETMV4_VIEW_INST_START_STOP, 0)?;
+
+        self.component
+            .write_reg(self.interface, ETMV4_PROGRAMMING_CONTROL, 1)?;
+        self.wait_for_etmv4_idle(false)
+    }
+
+    fn wait_for_etmv3_programming(&mut self, programming: bool) -> Result<(), ArmError> {
+        let start = Instant::now();
+        while Etmv3Status::load(self.component, self.interface)?.programming() != programming {
+            if start.elapsed() > PROGRAMMING_TIMEOUT {
+                return Err(ArmError::Timeout);
+            }
+        }
+        Ok(())
+    }
+
+    fn wait_for_etmv4_idle(&mut self, idle: bool) -> Result<(), ArmError> {
+        let start = Instant::now();
+        while Etmv4Status::load(self.component, self.interface)?.idle() != idle {
+            if start.elapsed() > PROGRAMMING_TIMEOUT {
+                return Err(ArmError::Timeout);
+            }
+        }
+        Ok(())
+    }
+}
+
+memory_mapped_bitfield_register! {
+    /// ETMCR, the main control register of an ETMv3.
+    pub struct Etmv3Control(u32);
+    0x000, "ETMCR",
+    impl From;
+
+    /// Enables the ETM trace port.
+    pub port_enable, set_port_enable: 11;
+    /// When set, the ETM can be programmed and does not generate trace.
+    pub programming, set_programming: 10;
+    /// Powers down the ETM.
+    pub power_down, set_power_down: 0;
+}
+
+impl DebugComponentInterface for Etmv3Control {}
+
+memory_mapped_bitfield_register! {
+    /// ETMSR, the status register of an ETMv3.
+    pub struct Etmv3Status(u32);
+    0x010, "ETMSR",
+    impl From;
+
+    /// The effective value of the programming bit in ETMCR.
+    pub programming, _: 1;
+}
+
+impl DebugComponentInterface for Etmv3Status {}
+
+memory_mapped_bitfield_register! {
+    /// TRCSTATR, the status register of an ETMv4.
+    pub struct Etmv4Status(u32);
+    0x00C, "TRCSTATR",
+    impl From;
+
+    /// The programmers' model is stable.
+    pub pm_stable, _: 1;
+    /// The trace unit is idle.
+    pub idle, _: 0;
+}
+
+impl DebugComponentInterface for Etmv4Status {}
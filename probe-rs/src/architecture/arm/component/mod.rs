@@ -1,7 +1,9 @@
 //! Types and functions for interacting with CoreSight Components
 
 mod dwt;
+mod etm;
 mod itm;
+mod mtb;
 mod scs;
 mod swo;
 mod tmc;
@@ -11,6 +13,7 @@ mod trace_funnel;
 use crate::{
     architecture::arm::{
         core::armv6m::Demcr,
+        instruction_trace::InstructionTrace,
         memory::romtable::{CoresightComponent, PeripheralType, RomTableError},
         ArmError, ArmProbeInterface, DpAddress, SwoConfig, SwoMode,
     },
@@ -19,6 +22,8 @@ use crate::{
 
 pub use self::itm::Itm;
 pub use dwt::Dwt;
+pub use etm::{Etm, EtmVersion};
+pub use mtb::{Mtb, MtbBuffer};
 pub use scs::Scs;
 pub use swo::Swo;
 pub use tmc::TraceMemoryController;
@@ -27,6 +32,9 @@ pub use trace_funnel::TraceFunnel;
 
 use super::memory::Component;
 
+/// The ATB trace source ID of the ETM.
+const ETM_TRACE_ID: u8 = 2;
+
 /// Specifies the data sink (destination) for trace data.
 #[derive(Debug, Copy, Clone)]
 pub enum TraceSink {
@@ -316,16 +324,21 @@ pub(crate) fn read_trace_memory(
     // need to deserialize the frames and pull out only the data source of interest. For now, all
     // we care about is the ITM data.
 
+    // ITM ATID, see Itm::tx_enable()
+    Ok(demultiplex(&etf_trace, 13))
+}
+
+/// Extracts the data of the trace source with ATB ID `source` from formatted frames.
+fn demultiplex(frames: &[u8], source: u8) -> Vec<u8> {
     let mut id = 0.into();
-    let mut itm_trace = Vec::new();
+    let mut trace = Vec::new();
 
     // Process each formatted frame and extract the multiplexed trace data.
-    for frame_buffer in etf_trace.chunks_exact(16) {
+    for frame_buffer in frames.chunks_exact(16) {
         let mut frame = tmc::Frame::new(frame_buffer, id);
         for (id, data) in &mut frame {
             match id.into() {
-                // ITM ATID, see Itm::tx_enable()
-                13 => itm_trace.push(data),
+                id if id == source => trace.push(data),
                 0 => (),
                 id => tracing::warn!("Unexpected trace source ATID {id}: {data}, ignoring"),
             }
@@ -333,7 +346,105 @@ pub(crate) fn read_trace_memory(
         id = frame.id();
     }
 
-    Ok(itm_trace)
+    trace
+}
+
+/// Finds the trace memory, which is either a TMC or an ETB.
+fn find_trace_memory(
+    components: &[CoresightComponent],
+) -> Result<(&CoresightComponent, PeripheralType), ArmError> {
+    find_component(components, PeripheralType::Tmc)
+        .map(|component| (component, PeripheralType::Tmc))
+        .or_else(|_| {
+            find_component(components, PeripheralType::Etb)
+                .map(|component| (component, PeripheralType::Etb))
+        })
+}
+
+/// Sets up the instruction trace.
+///
+/// The ETM traces into the trace memory, which is used as circular buffer. Without ETM, the
+/// MTB records the program flow into `mtb_buffer`.
+pub(crate) fn setup_instruction_trace(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+    mtb_buffer: Option<MtbBuffer>,
+) -> Result<(), Error> {
+    let Ok(etm) = find_component(components, PeripheralType::Etm) else {
+        let mtb = find_component(components, PeripheralType::Mtb)?;
+        let buffer = mtb_buffer.ok_or_else(|| {
+            ArmError::Other(
+                "The MTB records the trace into RAM, which requires a buffer that is not used \
+                by the firmware"
+                    .to_string(),
+            )
+        })?;
+        Mtb::new(interface, mtb).enable(&buffer)?;
+        return Ok(());
+    };
+
+    let (trace_memory, peripheral_type) = find_trace_memory(components)?;
+    let mut tmc = TraceMemoryController::new(interface, trace_memory);
+    tmc.disable_capture()?;
+    while !tmc.ready()? {}
+
+    // The ETB is always a circular buffer, and has no mode register.
+    if peripheral_type == PeripheralType::Tmc {
+        tmc.set_mode(tmc::Mode::Circular)?;
+    }
+    tmc.enable_formatter()?;
+    tmc.enable_capture()?;
+
+    Etm::new(interface, etm).enable_instruction_trace(ETM_TRACE_ID)?;
+
+    Ok(())
+}
+
+/// Stops the instruction trace, and reads the recorded trace.
+pub(crate) fn read_instruction_trace(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+) -> Result<InstructionTrace, Error> {
+    let Ok(etm) = find_component(components, PeripheralType::Etm) else {
+        let mut mtb = Mtb::new(interface, find_component(components, PeripheralType::Mtb)?);
+        mtb.disable()?;
+        return Ok(InstructionTrace::Mtb(mtb.read_buffer()?));
+    };
+
+    let mut etm = Etm::new(interface, etm);
+    let version = etm.version()?;
+    etm.disable()?;
+
+    let (trace_memory, peripheral_type) = find_trace_memory(components)?;
+    let mut tmc = TraceMemoryController::new(interface, trace_memory);
+
+    // Flush the trace pipeline, so that the buffer ends with a complete frame.
+    tmc.stop_on_flush(true)?;
+    tmc.manual_flush()?;
+    while !tmc.ready()? {}
+    tmc.disable_capture()?;
+
+    // The write pointer is a byte address in the TMC, but a word address in the ETB.
+    let write_pointer = tmc.write_pointer()?;
+    let (start, size) = if tmc.full()? {
+        (write_pointer, tmc.fifo_size()?)
+    } else if peripheral_type == PeripheralType::Tmc {
+        (0, write_pointer)
+    } else {
+        (0, write_pointer * 4)
+    };
+
+    tmc.set_read_pointer(start)?;
+    let mut frames = Vec::with_capacity(size as usize);
+    for _ in 0..size / 4 {
+        frames.extend_from_slice(&tmc.read_raw()?.to_le_bytes());
+    }
+
+    let stream = demultiplex(&frames, ETM_TRACE_ID);
+    Ok(match version {
+        EtmVersion::V3 => InstructionTrace::Etmv3(stream),
+        EtmVersion::V4 => InstructionTrace::Etmv4(stream),
+    })
 }
 
 /// Configures DWT trace unit `unit` to begin tracing `address`.
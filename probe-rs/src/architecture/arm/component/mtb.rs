@@ -0,0 +1,147 @@
+//! Module for the Micro Trace Buffer (MTB) of the Cortex-M0+.
+//!
+//! The MTB records every non-sequential change of the program flow as a pair of source and
+//! destination addresses. The packets are written into a region of the system SRAM, which the
+//! firmware must not use while tracing.
+
+use super::DebugComponentInterface;
+use crate::architecture::arm::memory::romtable::CoresightComponent;
+use crate::architecture::arm::{ArmError, ArmProbeInterface};
+use crate::memory_mapped_bitfield_register;
+
+/// The SRAM region which the MTB uses as trace buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MtbBuffer {
+    /// The start address of the buffer. It has to be aligned to the buffer size.
+    pub address: u32,
+    /// The size of the buffer in bytes. It has to be a power of two of at least 16 bytes.
+    pub size: u32,
+}
+
+/// The Micro Trace Buffer of a Cortex-M0+ core.
+pub struct Mtb<'a> {
+    component: &'a CoresightComponent,
+    interface: &'a mut dyn ArmProbeInterface,
+}
+
+impl<'a> Mtb<'a> {
+    /// Construct a new MTB component.
+    pub fn new(
+        interface: &'a mut dyn ArmProbeInterface,
+        component: &'a CoresightComponent,
+    ) -> Self {
+        Mtb {
+            component,
+            interface,
+        }
+    }
+
+    /// The address of the SRAM the trace buffer is located in.
+    pub fn sram_base(&mut self) -> Result<u32, ArmError> {
+        Ok(Base::load(self.component, self.interface)?.0)
+    }
+
+    /// Start recording the program flow into `buffer`.
+    pub fn enable(&mut self, buffer: &MtbBuffer) -> Result<(), ArmError> {
+        if !buffer.size.is_power_of_two() || buffer.size < 16 || buffer.address % buffer.size != 0 {
+            return Err(ArmError::Other(format!(
+                "The MTB buffer at {:#010x} with {} bytes is not a power of two of at least \
+                16 bytes, aligned to its size",
+                buffer.address, buffer.size
+            )));
+        }
+
+        let base = self.sram_base()?;
+        let Some(offset) = buffer.address.checked_sub(base) else {
+            return Err(ArmError::Other(format!(
+                "The MTB buffer at {:#010x} is below the MTB SRAM at {base:#010x}",
+                buffer.address
+            )));
+        };
+
+        Master(0).store(self.component, self.interface)?;
+
+        let mut position = Position(0);
+        position.set_pointer(offset >> 3);
+        position.store(self.component, self.interface)?;
+
+        let mut master = Master(0);
+        master.set_mask(buffer.size.trailing_zeros() - 4);
+        master.set_enable(true);
+        master.store(self.component, self.interface)
+    }
+
+    /// Stop recording the program flow.
+    pub fn disable(&mut self) -> Result<(), ArmError> {
+        let mut master = Master::load(self.component, self.interface)?;
+        master.set_enable(false);
+        master.store(self.component, self.interface)
+    }
+
+    /// Read the recorded packets, oldest first.
+    ///
+    /// The recording should be disabled before reading the buffer.
+    pub fn read_buffer(&mut self) -> Result<Vec<u8>, ArmError> {
+        let base = self.sram_base()?;
+        let master = Master::load(self.component, self.interface)?;
+        let position = Position::load(self.component, self.interface)?;
+
+        let size = 1u32 << (master.mask() + 4);
+        let offset = position.pointer() << 3;
+        let start = base + (offset & !(size - 1));
+        let next = offset & (size - 1);
+
+        let mut words = vec![0; (size / 4) as usize];
+        let mut memory = self
+            .interface
+            .memory_interface(&self.component.ap_address)?;
+        memory.read_32(start as u64, &mut words)?;
+        let buffer: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+        // Until the pointer wrapped for the first time, only the beginning of the buffer is
+        // valid. Afterwards, the oldest packet is the one the pointer points to.
+        let (older, newer) = buffer.split_at(next as usize);
+        if position.wrap() {
+            Ok([newer, older].concat())
+        } else {
+            Ok(older.to_vec())
+        }
+    }
+}
+
+memory_mapped_bitfield_register! {
+    /// The position of the next packet within the trace buffer.
+    pub struct Position(u32);
+    0x000, "MTB_POSITION",
+    impl From;
+
+    /// The offset of the next packet into the SRAM, in units of packets.
+    pub pointer, set_pointer: 31, 3;
+    /// Set when the pointer wrapped around the end of the trace buffer.
+    pub wrap, set_wrap: 2;
+}
+
+impl DebugComponentInterface for Position {}
+
+memory_mapped_bitfield_register! {
+    /// The main control register of the MTB.
+    pub struct Master(u32);
+    0x004, "MTB_MASTER",
+    impl From;
+
+    /// Enables the recording of trace packets.
+    pub enable, set_enable: 31;
+    /// The size of the trace buffer is 2^(MASK + 4) bytes.
+    pub mask, set_mask: 4, 0;
+}
+
+impl DebugComponentInterface for Master {}
+
+memory_mapped_bitfield_register! {
+    /// The address of the SRAM the MTB writes to.
+    pub struct Base(u32);
+    0x00C, "MTB_BASE",
+    impl From;
+}
+
+impl DebugComponentInterface for Base {}
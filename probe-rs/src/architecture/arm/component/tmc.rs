@@ -12,6 +12,8 @@ use crate::{
 
 const REGISTER_OFFSET_RSZ: u32 = 0x04;
 const REGISTER_OFFSET_RRD: u32 = 0x10;
+const REGISTER_OFFSET_RRP: u32 = 0x14;
+const REGISTER_OFFSET_RWP: u32 = 0x18;
 const REGISTER_OFFSET_CTL: u32 = 0x20;
 const REGISTER_OFFSET_CBUFLVL: u32 = 0x30;
 
@@ -88,6 +90,42 @@ impl<'a> TraceMemoryController<'a> {
         }
     }
 
+    /// Read a word from the trace memory at the read pointer, and advance the read pointer.
+    ///
+    /// Unlike [`Self::read`], this does not interpret the content, which is required to read
+    /// the buffer in circular mode.
+    pub fn read_raw(&mut self) -> Result<u32, ArmError> {
+        self.component.read_reg(self.interface, REGISTER_OFFSET_RRD)
+    }
+
+    /// Get the pointer into trace memory from which [`Self::read_raw`] reads.
+    pub fn read_pointer(&mut self) -> Result<u32, ArmError> {
+        self.component.read_reg(self.interface, REGISTER_OFFSET_RRP)
+    }
+
+    /// Set the pointer into trace memory from which [`Self::read_raw`] reads.
+    pub fn set_read_pointer(&mut self, pointer: u32) -> Result<(), ArmError> {
+        self.component
+            .write_reg(self.interface, REGISTER_OFFSET_RRP, pointer)
+    }
+
+    /// Get the pointer into trace memory at which the next trace data is written.
+    ///
+    /// # Note
+    /// The pointer is a byte address for the TMC, but a word address for the ETB.
+    pub fn write_pointer(&mut self) -> Result<u32, ArmError> {
+        self.component.read_reg(self.interface, REGISTER_OFFSET_RWP)
+    }
+
+    /// Enable the formatter, which multiplexes the trace sources into frames.
+    pub fn enable_formatter(&mut self) -> Result<(), Error> {
+        let mut ffcr = FormatFlushControl::load(self.component, self.interface)?;
+        ffcr.set_enft(true);
+        ffcr.set_enti(true);
+        ffcr.store(self.component, self.interface)?;
+        Ok(())
+    }
+
     /// Check if the FIFO is full.
     pub fn full(&mut self) -> Result<bool, Error> {
         let status = Status::load(self.component, self.interface)?;
@@ -1,9 +1,11 @@
 //! Register types and the core interface for armv6-M
 
-use super::{registers::cortex_m::*, CortexMState, Dfsr};
+use super::{cortex_m::DwtVariant, registers::cortex_m::*, CortexMState, Dfsr};
 use crate::{
     architecture::arm::{memory::ArmMemoryInterface, sequences::ArmDebugSequence, ArmError},
-    core::{CoreRegisters, RegisterId, RegisterValue, VectorCatchCondition},
+    core::{
+        CoreRegisters, RegisterId, RegisterValue, VectorCatchCondition, Watchpoint, WatchpointCause,
+    },
     error::Error,
     memory::{valid_32bit_address, CoreMemoryInterface},
     Architecture, BreakpointCause, CoreInformation, CoreInterface, CoreRegister, CoreStatus,
@@ -550,6 +552,17 @@ impl CoreInterface for Armv6m<'_> {
                 self.set_core_status(CoreStatus::Halted(reason));
             }
 
+            if reason == HaltReason::Watchpoint(WatchpointCause::Unknown) {
+                let cause = super::cortex_m::dwt_watchpoint_cause(
+                    &mut *self.memory,
+                    DwtVariant::Armv7m,
+                    std::mem::take(&mut self.state.dwt_matched_units),
+                )?;
+                reason = HaltReason::Watchpoint(cause);
+
+                self.set_core_status(CoreStatus::Halted(reason));
+            }
+
             return Ok(CoreStatus::Halted(reason));
         }
 
@@ -792,6 +805,38 @@ impl CoreInterface for Armv6m<'_> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(super::cortex_m::dwt_comparator_count(&mut *self.memory)?)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        Ok(super::cortex_m::read_dwt_watchpoints(
+            &mut *self.memory,
+            DwtVariant::Armv7m,
+            &mut self.state.dwt_matched_units,
+        )?)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        super::cortex_m::set_dwt_watchpoint(
+            &mut *self.memory,
+            DwtVariant::Armv7m,
+            unit_index,
+            watchpoint,
+        )
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        Ok(super::cortex_m::clear_dwt_watchpoint(
+            &mut *self.memory,
+            unit_index,
+        )?)
+    }
+
     fn registers(&self) -> &'static CoreRegisters {
         &CORTEX_M_CORE_REGISTERS
     }
@@ -875,6 +920,15 @@ impl CoreInterface for Armv6m<'_> {
             VectorCatchCondition::SecureFault => {
                 return Err(Error::Arm(ArmError::ArchitectureRequired(&["ARMv8"])));
             }
+            VectorCatchCondition::BusFault
+            | VectorCatchCondition::MemoryManagementFault
+            | VectorCatchCondition::UsageFault => {
+                return Err(Error::Arm(ArmError::ArchitectureRequired(&[
+                    "ARMv7", "ARMv8",
+                ])));
+            }
+            // All faults escalate to a HardFault on ARMv6-M.
+            VectorCatchCondition::Exception => demcr.set_vc_harderr(true),
             VectorCatchCondition::All => {
                 demcr.set_vc_harderr(true);
                 demcr.set_vc_corereset(true);
@@ -894,6 +948,15 @@ impl CoreInterface for Armv6m<'_> {
             VectorCatchCondition::SecureFault => {
                 return Err(Error::Arm(ArmError::ArchitectureRequired(&["ARMv8"])));
             }
+            VectorCatchCondition::BusFault
+            | VectorCatchCondition::MemoryManagementFault
+            | VectorCatchCondition::UsageFault => {
+                return Err(Error::Arm(ArmError::ArchitectureRequired(&[
+                    "ARMv7", "ARMv8",
+                ])));
+            }
+            // All faults escalate to a HardFault on ARMv6-M.
+            VectorCatchCondition::Exception => demcr.set_vc_harderr(false),
             VectorCatchCondition::All => {
                 demcr.set_vc_harderr(false);
                 demcr.set_vc_corereset(false);
@@ -904,6 +967,13 @@ impl CoreInterface for Armv6m<'_> {
             .write_word_32(Demcr::get_mmio_address(), demcr.into())?;
         Ok(())
     }
+
+    fn sample_program_counter(&mut self) -> Result<Option<u64>, Error> {
+        super::cortex_m::sample_pc_with_dwt(
+            &mut *self.memory,
+            &mut self.state.dwt_pc_sampling_enabled,
+        )
+    }
 }
 
 impl CoreMemoryInterface for Armv6m<'_> {
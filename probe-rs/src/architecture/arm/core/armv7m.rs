@@ -1,7 +1,7 @@
 //! Register types and the core interface for armv7-M
 
 use super::{
-    cortex_m::Mvfr0,
+    cortex_m::{DwtVariant, Mvfr0},
     registers::cortex_m::{
         CORTEX_M_CORE_REGISTERS, CORTEX_M_WITH_FP_CORE_REGISTERS, FP, PC, RA, SP,
     },
@@ -10,11 +10,12 @@ use super::{
 use crate::{
     architecture::arm::{
         core::registers::cortex_m::XPSR, memory::ArmMemoryInterface, sequences::ArmDebugSequence,
-        ArmError,
+        ArmError, SwoAccess,
     },
     core::{
         Architecture, CoreInformation, CoreInterface, CoreRegisters, CoreStatus, HaltReason,
-        MemoryMappedRegister, RegisterId, RegisterValue, VectorCatchCondition,
+        MemoryMappedRegister, RegisterId, RegisterValue, VectorCatchCondition, Watchpoint,
+        WatchpointCause,
     },
     error::Error,
     memory::{valid_32bit_address, CoreMemoryInterface},
@@ -353,6 +354,24 @@ bitfield! {
     pub vc_corereset, set_vc_corereset: 0;
 }
 
+impl Demcr {
+    /// Enable or disable the halting debug traps for all UsageFault causes.
+    pub fn set_vc_usage_faults(&mut self, value: bool) {
+        self.set_vc_staterr(value);
+        self.set_vc_chkerr(value);
+        self.set_vc_nocperr(value);
+    }
+
+    /// Enable or disable the halting debug traps for all fault exceptions, except SecureFault.
+    pub fn set_vc_faults(&mut self, value: bool) {
+        self.set_vc_harderr(value);
+        self.set_vc_interr(value);
+        self.set_vc_buserr(value);
+        self.set_vc_mmerr(value);
+        self.set_vc_usage_faults(value);
+    }
+}
+
 impl From<u32> for Demcr {
     fn from(value: u32) -> Self {
         Self(value)
@@ -729,6 +748,17 @@ impl CoreInterface for Armv7m<'_> {
                 self.set_core_status(CoreStatus::Halted(reason));
             }
 
+            if reason == HaltReason::Watchpoint(WatchpointCause::Unknown) {
+                let cause = super::cortex_m::dwt_watchpoint_cause(
+                    &mut *self.memory,
+                    DwtVariant::Armv7m,
+                    std::mem::take(&mut self.state.dwt_matched_units),
+                )?;
+                reason = HaltReason::Watchpoint(cause);
+
+                self.set_core_status(CoreStatus::Halted(reason));
+            }
+
             return Ok(CoreStatus::Halted(reason));
         }
 
@@ -1018,6 +1048,38 @@ impl CoreInterface for Armv7m<'_> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(super::cortex_m::dwt_comparator_count(&mut *self.memory)?)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        Ok(super::cortex_m::read_dwt_watchpoints(
+            &mut *self.memory,
+            DwtVariant::Armv7m,
+            &mut self.state.dwt_matched_units,
+        )?)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        super::cortex_m::set_dwt_watchpoint(
+            &mut *self.memory,
+            DwtVariant::Armv7m,
+            unit_index,
+            watchpoint,
+        )
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        Ok(super::cortex_m::clear_dwt_watchpoint(
+            &mut *self.memory,
+            unit_index,
+        )?)
+    }
+
     fn registers(&self) -> &'static CoreRegisters {
         if self.state.fp_present {
             &CORTEX_M_WITH_FP_CORE_REGISTERS
@@ -1103,6 +1165,10 @@ impl CoreInterface for Armv7m<'_> {
             VectorCatchCondition::SecureFault => {
                 return Err(Error::Arm(ArmError::ArchitectureRequired(&["ARMv8"])));
             }
+            VectorCatchCondition::BusFault => demcr.set_vc_buserr(true),
+            VectorCatchCondition::MemoryManagementFault => demcr.set_vc_mmerr(true),
+            VectorCatchCondition::UsageFault => demcr.set_vc_usage_faults(true),
+            VectorCatchCondition::Exception => demcr.set_vc_faults(true),
             VectorCatchCondition::All => {
                 demcr.set_vc_harderr(true);
                 demcr.set_vc_corereset(true);
@@ -1122,6 +1188,10 @@ impl CoreInterface for Armv7m<'_> {
             VectorCatchCondition::SecureFault => {
                 return Err(Error::Arm(ArmError::ArchitectureRequired(&["ARMv8"])));
             }
+            VectorCatchCondition::BusFault => demcr.set_vc_buserr(false),
+            VectorCatchCondition::MemoryManagementFault => demcr.set_vc_mmerr(false),
+            VectorCatchCondition::UsageFault => demcr.set_vc_usage_faults(false),
+            VectorCatchCondition::Exception => demcr.set_vc_faults(false),
             VectorCatchCondition::All => {
                 demcr.set_vc_harderr(false);
                 demcr.set_vc_corereset(false);
@@ -1132,6 +1202,18 @@ impl CoreInterface for Armv7m<'_> {
             .write_word_32(Demcr::get_mmio_address(), demcr.into())?;
         Ok(())
     }
+
+    fn sample_program_counter(&mut self) -> Result<Option<u64>, Error> {
+        super::cortex_m::sample_pc_with_dwt(
+            &mut *self.memory,
+            &mut self.state.dwt_pc_sampling_enabled,
+        )
+    }
+
+    fn read_swo(&mut self) -> Result<Vec<u8>, Error> {
+        let interface = self.memory.get_arm_communication_interface()?;
+        Ok(interface.read_swo()?)
+    }
 }
 
 impl CoreMemoryInterface for Armv7m<'_> {
@@ -1,6 +1,9 @@
 //! Debug register definitions for ARMv8-A
 
-use crate::{core::BreakpointCause, memory_mapped_bitfield_register, HaltReason};
+use crate::{
+    core::{BreakpointCause, WatchpointCause},
+    memory_mapped_bitfield_register, HaltReason,
+};
 
 memory_mapped_bitfield_register! {
     /// EDSCR - Debug Status and Control Register
@@ -132,7 +135,7 @@ impl Edscr {
             // Reset catch.
             0b100111 => HaltReason::Exception,
             // Watchpoint
-            0b101011 => HaltReason::Watchpoint,
+            0b101011 => HaltReason::Watchpoint(WatchpointCause::Unknown),
             // HLT instruction - causes entry into Debug state.
             0b101111 => HaltReason::Breakpoint(BreakpointCause::Software),
             // Software access to debug register.
@@ -1,8 +1,10 @@
 //! Common functions and data types for Cortex-M core variants
 
+use super::armv7m::Demcr;
 use crate::{
     architecture::arm::{memory::ArmMemoryInterface, ArmError},
-    core::RegisterId,
+    core::{RegisterId, Watchpoint, WatchpointCause, WatchpointKind},
+    memory::valid_32bit_address,
     memory_mapped_bitfield_register,
     semihosting::decode_semihosting_syscall,
     semihosting::SemihostingCommand,
@@ -152,6 +154,308 @@ impl IdPfr1 {
     }
 }
 
+memory_mapped_bitfield_register! {
+    /// DWT Control Register
+    pub struct DwtCtrl(u32);
+    0xE000_1000, "DWT_CTRL",
+    impl From;
+    /// Number of comparators implemented.
+    pub u8, numcomp, _: 31, 28;
+}
+
+memory_mapped_bitfield_register! {
+    /// DWT Comparator Register. The registers of unit `n` are located at a `16 * n` byte offset.
+    pub struct DwtComp(u32);
+    0xE000_1020, "DWT_COMP0",
+    impl From;
+    pub u32, comp, set_comp: 31, 0;
+}
+
+memory_mapped_bitfield_register! {
+    /// DWT Comparator Mask Register (ARMv6-M and ARMv7-M only).
+    pub struct DwtMask(u32);
+    0xE000_1024, "DWT_MASK0",
+    impl From;
+    /// The number of least significant address bits ignored by the comparator.
+    pub u8, mask, set_mask: 4, 0;
+}
+
+memory_mapped_bitfield_register! {
+    /// DWT Comparator Function Register.
+    ///
+    /// The `action` and `datavsize` fields are only interpreted like this on ARMv8-M.
+    pub struct DwtFunction(u32);
+    0xE000_1028, "DWT_FUNCTION0",
+    impl From;
+    /// Set when the comparator matched since the last read of the register.
+    pub matched, _: 24;
+    /// ARMv8-M: log2 of the size of the matched data access.
+    pub u8, datavsize, set_datavsize: 11, 10;
+    /// ARMv8-M: the action taken on a match, `0b01` generates a debug event.
+    pub u8, action, set_action: 5, 4;
+    /// ARMv6-M and ARMv7-M: FUNCTION, ARMv8-M: MATCH.
+    pub u8, function, set_function: 3, 0;
+}
+
+memory_mapped_bitfield_register! {
+    /// DWT Program Counter Sample Register.
+    ///
+    /// Reads as `0xFFFF_FFFF` while the core is halted, and as zero if PC sampling is not
+    /// implemented.
+    pub struct DwtPcsr(u32);
+    0xE000_101C, "DWT_PCSR",
+    impl From;
+}
+
+/// The register layout of the DWT comparators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DwtVariant {
+    /// The comparator matches an address with a mask. Used by ARMv6-M and ARMv7-M.
+    Armv7m,
+    /// The comparator matches an address with an access size. Used by ARMv8-M.
+    Armv8m,
+}
+
+impl DwtVariant {
+    fn function_value(self, kind: WatchpointKind) -> u8 {
+        match (self, kind) {
+            (DwtVariant::Armv7m, WatchpointKind::Read) => 0b0101,
+            (DwtVariant::Armv7m, WatchpointKind::Write) => 0b0110,
+            (DwtVariant::Armv7m, WatchpointKind::Access) => 0b0111,
+            (DwtVariant::Armv8m, WatchpointKind::Access) => 0b0100,
+            (DwtVariant::Armv8m, WatchpointKind::Write) => 0b0101,
+            (DwtVariant::Armv8m, WatchpointKind::Read) => 0b0110,
+        }
+    }
+
+    fn watchpoint_kind(self, function: DwtFunction) -> Option<WatchpointKind> {
+        match self {
+            DwtVariant::Armv7m => match function.function() {
+                0b0101 => Some(WatchpointKind::Read),
+                0b0110 => Some(WatchpointKind::Write),
+                0b0111 => Some(WatchpointKind::Access),
+                _ => None,
+            },
+            DwtVariant::Armv8m if function.action() == 0b01 => match function.function() {
+                0b0100 => Some(WatchpointKind::Access),
+                0b0101 => Some(WatchpointKind::Write),
+                0b0110 => Some(WatchpointKind::Read),
+                _ => None,
+            },
+            DwtVariant::Armv8m => None,
+        }
+    }
+}
+
+/// The longest range an ARMv8-M DWT comparator can watch, which is the largest data access size.
+pub(crate) const ARMV8M_MAX_WATCHPOINT_LENGTH: u64 = 4;
+
+fn dwt_unit_address(register_address: u64, unit_index: usize) -> u64 {
+    register_address + 16 * unit_index as u64
+}
+
+/// Samples the program counter of the running core through the DWT Program Counter Sample Register.
+///
+/// The DWT is enabled on the first call, which is recorded in `dwt_enabled`.
+pub(crate) fn sample_pc_with_dwt(
+    memory: &mut dyn ArmMemoryInterface,
+    dwt_enabled: &mut bool,
+) -> Result<Option<u64>, Error> {
+    if !*dwt_enabled {
+        enable_dwt(memory)?;
+        *dwt_enabled = true;
+    }
+
+    match memory.read_word_32(DwtPcsr::get_mmio_address())? {
+        0 => Err(Error::NotImplemented("DWT PC sampling")),
+        0xFFFF_FFFF => Ok(None),
+        pc => Ok(Some(pc as u64)),
+    }
+}
+
+/// Globally enables the DWT, which is only accessible while DEMCR.TRCENA is set.
+///
+/// The bit is called DEMCR.DWTENA on ARMv6-M.
+fn enable_dwt(memory: &mut dyn ArmMemoryInterface) -> Result<(), ArmError> {
+    let mut demcr = Demcr(memory.read_word_32(Demcr::get_mmio_address())?);
+    if !demcr.trcena() {
+        demcr.set_trcena(true);
+        memory.write_word_32(Demcr::get_mmio_address(), demcr.into())?;
+    }
+
+    Ok(())
+}
+
+/// Returns the number of DWT comparators which can be used as watchpoints.
+pub(crate) fn dwt_comparator_count(memory: &mut dyn ArmMemoryInterface) -> Result<u32, ArmError> {
+    // DWT_CTRL reads as zero while the DWT is disabled.
+    enable_dwt(memory)?;
+
+    let ctrl = DwtCtrl(memory.read_word_32(DwtCtrl::get_mmio_address())?);
+    Ok(ctrl.numcomp() as u32)
+}
+
+fn read_dwt_watchpoint(
+    memory: &mut dyn ArmMemoryInterface,
+    variant: DwtVariant,
+    unit_index: usize,
+) -> Result<(Option<Watchpoint>, bool), ArmError> {
+    let function = DwtFunction(memory.read_word_32(dwt_unit_address(
+        DwtFunction::get_mmio_address(),
+        unit_index,
+    ))?);
+
+    let Some(kind) = variant.watchpoint_kind(function) else {
+        return Ok((None, function.matched()));
+    };
+
+    let address = memory.read_word_32(dwt_unit_address(DwtComp::get_mmio_address(), unit_index))?;
+    let length = match variant {
+        DwtVariant::Armv7m => {
+            let mask = DwtMask(
+                memory.read_word_32(dwt_unit_address(DwtMask::get_mmio_address(), unit_index))?,
+            );
+            1 << mask.mask()
+        }
+        DwtVariant::Armv8m => 1 << function.datavsize(),
+    };
+
+    let watchpoint = Watchpoint {
+        address: address as u64,
+        length,
+        kind,
+    };
+
+    Ok((Some(watchpoint), function.matched()))
+}
+
+/// Reads the watchpoints configured in the DWT comparators.
+///
+/// Comparators which are disabled or used for something else than halting on a data access are reported as `None`.
+///
+/// Reading a comparator clears its `MATCHED` bit, so the comparators which matched are added to
+/// `matched_units`, to be passed on to [`dwt_watchpoint_cause`].
+pub(crate) fn read_dwt_watchpoints(
+    memory: &mut dyn ArmMemoryInterface,
+    variant: DwtVariant,
+    matched_units: &mut u32,
+) -> Result<Vec<Option<Watchpoint>>, ArmError> {
+    (0..dwt_comparator_count(memory)? as usize)
+        .map(|unit_index| {
+            let (watchpoint, matched) = read_dwt_watchpoint(memory, variant, unit_index)?;
+            if matched {
+                *matched_units |= 1 << unit_index;
+            }
+
+            Ok(watchpoint)
+        })
+        .collect()
+}
+
+/// Configures DWT comparator `unit_index` to halt the core on the given data access.
+pub(crate) fn set_dwt_watchpoint(
+    memory: &mut dyn ArmMemoryInterface,
+    variant: DwtVariant,
+    unit_index: usize,
+    watchpoint: Watchpoint,
+) -> Result<(), Error> {
+    let address = valid_32bit_address(watchpoint.address)?;
+
+    if !watchpoint.is_naturally_aligned() {
+        return Err(Error::Other(format!(
+            "The watchpoint at {:#010x} with a length of {} bytes is not supported. The length must be a power of two, and the address must be aligned to the length.",
+            address, watchpoint.length
+        )));
+    }
+
+    if variant == DwtVariant::Armv8m && watchpoint.length > ARMV8M_MAX_WATCHPOINT_LENGTH {
+        return Err(Error::Other(format!(
+            "The watchpoint at {:#010x} with a length of {} bytes is not supported. ARMv8-M comparators can watch at most 4 bytes.",
+            address, watchpoint.length
+        )));
+    }
+
+    enable_dwt(memory)?;
+
+    // Disable the comparator while it is reconfigured.
+    let function_address = dwt_unit_address(DwtFunction::get_mmio_address(), unit_index);
+    memory.write_word_32(function_address, 0)?;
+
+    memory.write_word_32(
+        dwt_unit_address(DwtComp::get_mmio_address(), unit_index),
+        address,
+    )?;
+
+    let mut function = DwtFunction(0);
+    function.set_function(variant.function_value(watchpoint.kind));
+
+    match variant {
+        DwtVariant::Armv7m => {
+            let mask_address = dwt_unit_address(DwtMask::get_mmio_address(), unit_index);
+            let mut mask = DwtMask(0);
+            mask.set_mask(watchpoint.length.trailing_zeros() as u8);
+            memory.write_word_32(mask_address, mask.into())?;
+
+            // The maximum mask size is implementation defined, unsupported bits read as zero.
+            let readback = DwtMask(memory.read_word_32(mask_address)?);
+            if readback.mask() != mask.mask() {
+                return Err(Error::Other(format!(
+                    "The watchpoint at {:#010x} with a length of {} bytes is not supported. This core can watch at most {} bytes.",
+                    address,
+                    watchpoint.length,
+                    1u64 << readback.mask()
+                )));
+            }
+        }
+        DwtVariant::Armv8m => {
+            function.set_action(0b01);
+            function.set_datavsize(watchpoint.length.trailing_zeros() as u8);
+        }
+    }
+
+    memory.write_word_32(function_address, function.into())?;
+
+    Ok(())
+}
+
+/// Disables DWT comparator `unit_index`.
+pub(crate) fn clear_dwt_watchpoint(
+    memory: &mut dyn ArmMemoryInterface,
+    unit_index: usize,
+) -> Result<(), ArmError> {
+    memory.write_word_32(
+        dwt_unit_address(DwtFunction::get_mmio_address(), unit_index),
+        0,
+    )
+}
+
+/// Determines which watchpoint caused the core to halt.
+///
+/// This reads the `MATCHED` bit of the DWT comparators, which is cleared by the read.
+/// The comparators in `matched_units` are treated as matched as well, because their bit
+/// was already cleared by [`read_dwt_watchpoints`].
+pub(crate) fn dwt_watchpoint_cause(
+    memory: &mut dyn ArmMemoryInterface,
+    variant: DwtVariant,
+    matched_units: u32,
+) -> Result<WatchpointCause, ArmError> {
+    let mut cause = WatchpointCause::Unknown;
+
+    // Read all comparators, so all `MATCHED` bits are cleared.
+    for unit_index in 0..dwt_comparator_count(memory)? as usize {
+        let (watchpoint, matched) = read_dwt_watchpoint(memory, variant, unit_index)?;
+        let matched = matched || matched_units & (1 << unit_index) != 0;
+
+        if let (Some(watchpoint), true) = (watchpoint, matched) {
+            if cause == WatchpointCause::Unknown {
+                cause = WatchpointCause::Hit(watchpoint);
+            }
+        }
+    }
+
+    Ok(cause)
+}
+
 pub(crate) fn read_core_reg(
     memory: &mut dyn ArmMemoryInterface,
     addr: RegisterId,
@@ -247,3 +551,33 @@ fn wait_for_core_register_transfer(
     }
     Err(ArmError::Timeout)
 }
+
+#[test]
+fn dwt_watchpoint_function_round_trip() {
+    for variant in [DwtVariant::Armv7m, DwtVariant::Armv8m] {
+        for kind in [
+            WatchpointKind::Read,
+            WatchpointKind::Write,
+            WatchpointKind::Access,
+        ] {
+            let mut function = DwtFunction(0);
+            function.set_function(variant.function_value(kind));
+            if variant == DwtVariant::Armv8m {
+                function.set_action(0b01);
+            }
+
+            assert_eq!(variant.watchpoint_kind(function), Some(kind));
+        }
+    }
+
+    // A disabled comparator, or one used for data tracing, is not a watchpoint.
+    assert_eq!(DwtVariant::Armv7m.watchpoint_kind(DwtFunction(0)), None);
+    assert_eq!(
+        DwtVariant::Armv7m.watchpoint_kind(DwtFunction(0b0011)),
+        None
+    );
+    assert_eq!(
+        DwtVariant::Armv8m.watchpoint_kind(DwtFunction(0b0100)),
+        None
+    );
+}
@@ -3,7 +3,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    core::{BreakpointCause, RegisterValue},
+    core::{BreakpointCause, RegisterValue, WatchpointCause},
     memory_mapped_bitfield_register,
     semihosting::SemihostingCommand,
     CoreStatus, HaltReason,
@@ -101,7 +101,7 @@ impl Dfsr {
         } else if self.external() {
             HaltReason::External
         } else if self.dwttrap() {
-            HaltReason::Watchpoint
+            HaltReason::Watchpoint(WatchpointCause::Unknown)
         } else if self.halted() {
             HaltReason::Request
         } else if self.vcatch() {
@@ -143,6 +143,13 @@ pub struct CortexMState {
 
     /// The semihosting command that was decoded at the current program counter
     semihosting_command: Option<SemihostingCommand>,
+
+    /// Whether the DWT was enabled for PC sampling
+    dwt_pc_sampling_enabled: bool,
+
+    /// The DWT comparators which matched, but whose `MATCHED` bit was cleared by reading
+    /// the watchpoints before the halt reason was determined. Bit `n` is set for comparator `n`.
+    dwt_matched_units: u32,
 }
 
 impl CortexMState {
@@ -153,6 +160,8 @@ impl CortexMState {
             current_state: CoreStatus::Unknown,
             fp_present: false,
             semihosting_command: None,
+            dwt_pc_sampling_enabled: false,
+            dwt_matched_units: 0,
         }
     }
 
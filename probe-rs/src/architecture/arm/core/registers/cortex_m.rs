@@ -42,6 +42,20 @@ pub(crate) const XPSR: CoreRegister = CoreRegister {
     unwind_rule: UnwindRule::Preserve,
 };
 
+pub(crate) const MSP: CoreRegister = CoreRegister {
+    roles: &[RegisterRole::Core("MSP"), RegisterRole::MainStackPointer],
+    id: RegisterId(0b10001),
+    data_type: RegisterDataType::UnsignedInteger(32),
+    unwind_rule: UnwindRule::SpecialRule,
+};
+
+pub(crate) const PSP: CoreRegister = CoreRegister {
+    roles: &[RegisterRole::Core("PSP"), RegisterRole::ProcessStackPointer],
+    id: RegisterId(0b10010),
+    data_type: RegisterDataType::UnsignedInteger(32),
+    unwind_rule: UnwindRule::SpecialRule,
+};
+
 /// All off the Cortex-M core registers.
 pub(crate) static CORTEX_M_CORE_REGISTERS: LazyLock<CoreRegisters> = LazyLock::new(|| {
     CoreRegisters::new(
@@ -150,18 +164,8 @@ pub(super) static ARM32_COMMON_REGS_SET: &[CoreRegister] = &[
 ];
 
 static CORTEX_M_COMMON_REGS_SET: &[CoreRegister] = &[
-    CoreRegister {
-        roles: &[RegisterRole::Core("MSP"), RegisterRole::MainStackPointer],
-        id: RegisterId(0b10001),
-        data_type: RegisterDataType::UnsignedInteger(32),
-        unwind_rule: UnwindRule::SpecialRule,
-    },
-    CoreRegister {
-        roles: &[RegisterRole::Core("PSP"), RegisterRole::ProcessStackPointer],
-        id: RegisterId(0b10010),
-        data_type: RegisterDataType::UnsignedInteger(32),
-        unwind_rule: UnwindRule::SpecialRule,
-    },
+    MSP,
+    PSP,
     XPSR,
     // CONTROL bits [31:24], FAULTMASK bits [23:16],
     // BASEPRI bits [15:8], and PRIMASK bits [7:0]
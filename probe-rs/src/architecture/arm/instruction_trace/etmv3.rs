@@ -0,0 +1,256 @@
+//! Decoder for the ETMv3 instruction trace stream of Cortex-M3 and Cortex-M4.
+//!
+//! Only the packets of a plain instruction trace are supported: no cycle accurate trace, data
+//! trace or context IDs. This is how [`Etm`](crate::architecture::arm::component::Etm)
+//! configures the trace unit. On any other packet, the decoder resynchronizes at the next
+//! alignment synchronization packet.
+//!
+//! Unlike ETMv4, each atom in a P-header stands for a single instruction, and branch address
+//! packets use the alternative encoding, which the Cortex-M ETMs always use.
+
+use super::{thumb::Flow, ProgramFlow, Reader};
+
+/// The alignment synchronization packet.
+const A_SYNC: [u8; 6] = [0, 0, 0, 0, 0, 0x80];
+
+pub(super) fn decode(data: &[u8], flow: &mut ProgramFlow) {
+    let mut reader = Reader::new(data);
+    let mut decoder = Decoder {
+        previous: 0,
+        pending: None,
+    };
+
+    while reader.skip_past(&A_SYNC) {
+        decoder.packets(&mut reader, flow);
+        if !reader.at_end() {
+            flow.lose_track();
+        }
+    }
+}
+
+struct Decoder {
+    /// The previous address, which compressed branch addresses are relative to.
+    previous: u32,
+    /// A branch target which was traced before the atom of its indirect branch.
+    pending: Option<u32>,
+}
+
+impl Decoder {
+    /// Decodes packets until the end of the stream, or an unsupported packet.
+    fn packets(&mut self, reader: &mut Reader, flow: &mut ProgramFlow) -> Option<()> {
+        loop {
+            let header = reader.byte()?;
+            match header {
+                // Branch address
+                _ if header & 0x01 != 0 => {
+                    let (address, exception) = self.branch_address(header, reader)?;
+                    if exception {
+                        flow.exception();
+                        flow.jump(address);
+                    } else if flow.address.is_none() {
+                        flow.jump(address);
+                    } else {
+                        self.pending = Some(address);
+                    }
+                }
+                // P-header format 1: up to 15 E atoms, followed by up to one N atom.
+                _ if header & 0x83 == 0x80 => {
+                    for _ in 0..(header >> 2) & 0xF {
+                        self.atom(flow, true);
+                    }
+                    if header & 0x40 != 0 {
+                        self.atom(flow, false);
+                    }
+                }
+                // P-header format 2: two atoms, where set bits are N atoms.
+                _ if header & 0xF3 == 0x82 => {
+                    self.atom(flow, header & 0x08 == 0);
+                    self.atom(flow, header & 0x04 == 0);
+                }
+                // The rest of an alignment synchronization.
+                0x00 => {
+                    reader.skip_past(&[0x80]);
+                }
+                // Instruction synchronization
+                0x08 => {
+                    let info = reader.byte()?;
+                    let address = u32::from_le_bytes(reader.bytes()?);
+                    self.previous = address & !1;
+                    self.pending = None;
+                    flow.jump(address);
+                    if info & 0x80 != 0 {
+                        // A load or store was in progress, its address follows.
+                        let header = reader.byte()?;
+                        self.branch_address(header, reader)?;
+                    }
+                }
+                // Trigger, ignore, exception exit and exception entry
+                0x0C | 0x66 | 0x76 | 0x7E => {}
+                // VMID
+                0x3C => {
+                    reader.byte()?;
+                }
+                // Timestamp
+                0x42 | 0x46 => reader.skip_continued(9)?,
+                // Context ID, which is configured to have no payload.
+                0x6E => {}
+                _ => {
+                    tracing::warn!(
+                        "Unsupported ETMv3 packet {header:#04x} at offset {}",
+                        reader.position - 1
+                    );
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Passes the next instruction, which is `executed` or failed its condition.
+    fn atom(&mut self, flow: &mut ProgramFlow, executed: bool) {
+        let indirect = flow
+            .address
+            .and_then(|address| flow.image.instruction(address))
+            .is_some_and(|instruction| instruction.flow == Flow::Indirect);
+
+        flow.step(executed);
+        if executed && indirect {
+            if let Some(target) = self.pending.take() {
+                flow.jump(target);
+            }
+        }
+    }
+
+    /// Decodes the branch address packet with `header`. Returns the address and whether the
+    /// branch is an exception.
+    fn branch_address(&mut self, header: u8, reader: &mut Reader) -> Option<(u32, bool)> {
+        // The first byte contains the address bits 6 to 1, as the Cortex-M executes Thumb code.
+        let mut address = u32::from(header & 0x7E);
+        let mut mask = 0x7E;
+        let mut exception = false;
+
+        let mut byte = header;
+        for index in 1..5 {
+            if byte & 0x80 == 0 {
+                break;
+            }
+            byte = reader.byte()?;
+            let shift = 7 * index;
+            let bits = if index == 4 {
+                // The fifth byte contains the address bits 31 to 28 in Thumb state.
+                exception = byte & 0x40 != 0;
+                0x0F
+            } else if byte & 0x80 == 0 {
+                // In the last byte of a shorter packet, bit 6 indicates an exception.
+                exception = byte & 0x40 != 0;
+                0x3F
+            } else {
+                0x7F
+            };
+            address |= (u32::from(byte) & bits) << shift;
+            mask |= bits << shift;
+        }
+
+        if exception {
+            reader.skip_continued(3)?;
+        }
+
+        let address = (self.previous & !mask) | address;
+        self.previous = address;
+        Some((address, exception))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{test::*, InstructionTrace, TraceEvent};
+    use super::*;
+
+    /// An alignment and instruction synchronization at 0x100.
+    fn sync() -> Vec<u8> {
+        let mut stream = A_SYNC.to_vec();
+        stream.extend([0x08, 0x20, 0x01, 0x01, 0x00, 0x00]);
+        stream
+    }
+
+    #[test]
+    fn loop_and_call() {
+        let mut stream = vec![0x42, 0x13];
+        stream.extend(sync());
+        stream.extend([
+            // 4 E
+            0x90, //
+            // 2 E, N
+            0xC8, //
+            // 3 E
+            0x8C, //
+            // Branch to 0x10c
+            0x0D,
+        ]);
+
+        assert_eq!(
+            InstructionTrace::Etmv3(stream).decode(&image(), Some(0x10C)),
+            instructions(&[0x100, 0x102, 0x104, 0x106, 0x102, 0x104, 0x106, 0x108, 0x110, 0x112])
+        );
+    }
+
+    #[test]
+    fn branch_address_before_atom() {
+        let mut stream = sync();
+        stream.extend([
+            // 4 E, then 2 E and N, then 2 E
+            0x90, 0xC8, 0x88, //
+            // Branch to 0x10e, traced before the atom of the indirect branch at 0x112
+            0x0F, 0x84,
+        ]);
+
+        assert_eq!(
+            InstructionTrace::Etmv3(stream).decode(&image(), Some(0x110)),
+            instructions(&[
+                0x100, 0x102, 0x104, 0x106, 0x102, 0x104, 0x106, 0x108, 0x110, 0x112, 0x10E
+            ])
+        );
+    }
+
+    #[test]
+    fn exception() {
+        let mut stream = sync();
+        stream.extend([
+            // E
+            0x84, //
+            // Exception with the handler at 0x110
+            0x91, 0x42, 0x06, //
+            // 2 E
+            0x88,
+        ]);
+
+        let mut expected = instructions(&[0x100]);
+        expected.push(TraceEvent::Exception);
+        expected.extend(instructions(&[0x110, 0x112]));
+        assert_eq!(
+            InstructionTrace::Etmv3(stream).decode(&image(), None),
+            expected
+        );
+    }
+
+    #[test]
+    fn branch_address_compression() {
+        let mut decoder = Decoder {
+            previous: 0x0800_1234,
+            pending: None,
+        };
+
+        // Only the lowest bits change.
+        let mut reader = Reader::new(&[]);
+        assert_eq!(
+            decoder.branch_address(0x0D, &mut reader),
+            Some((0x0800_120C, false))
+        );
+
+        // A full address in Thumb state.
+        let mut reader = Reader::new(&[0x80 | 0x24, 0x80, 0x80, 0x10 | 0x02]);
+        assert_eq!(
+            decoder.branch_address(0x80 | 0x0D, &mut reader),
+            Some((0x2000_120C, false))
+        );
+    }
+}
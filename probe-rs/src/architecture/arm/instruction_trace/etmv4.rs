@@ -0,0 +1,279 @@
+//! Decoder for the ETMv4 instruction trace stream.
+//!
+//! Only the packets of a plain instruction trace are supported: no cycle counts, Q elements,
+//! speculation or conditional instruction trace. This is how
+//! [`Etm`](crate::architecture::arm::component::Etm) configures the trace unit. On any other
+//! packet, the decoder resynchronizes at the next alignment synchronization packet.
+
+use super::{ProgramFlow, Reader};
+
+/// The alignment synchronization packet.
+const A_SYNC: [u8; 12] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80];
+
+pub(super) fn decode(data: &[u8], flow: &mut ProgramFlow) {
+    let mut reader = Reader::new(data);
+    let mut decoder = Decoder { history: [0; 3] };
+
+    while reader.skip_past(&A_SYNC) {
+        decoder.packets(&mut reader, flow);
+        if !reader.at_end() {
+            flow.lose_track();
+        }
+    }
+}
+
+struct Decoder {
+    /// The address history, most recent address first.
+    history: [u32; 3],
+}
+
+impl Decoder {
+    /// Decodes packets until the end of the stream, or an unsupported packet.
+    fn packets(&mut self, reader: &mut Reader, flow: &mut ProgramFlow) -> Option<()> {
+        loop {
+            let header = reader.byte()?;
+            match header {
+                // Extension packets
+                0x00 => match reader.byte()? {
+                    // The rest of an alignment synchronization.
+                    0x00 => {
+                        reader.skip_past(&[0x80]);
+                    }
+                    // Discard and overflow
+                    0x03 | 0x05 => flow.lose_track(),
+                    extension => {
+                        tracing::warn!("Unsupported ETMv4 extension packet {extension:#04x}");
+                        return None;
+                    }
+                },
+                // Trace info
+                0x01 => {
+                    let control = reader.byte()?;
+                    for field in 0..4 {
+                        if control & (1 << field) != 0 {
+                            reader.skip_continued(5)?;
+                        }
+                    }
+                    self.history = [0; 3];
+                }
+                // Timestamp, with and without cycle count
+                0x02 => reader.skip_continued(9)?,
+                0x03 => {
+                    reader.skip_continued(9)?;
+                    reader.skip_continued(3)?;
+                }
+                // Trace on
+                0x04 => flow.lose_track(),
+                // Function return, exception return, events and unchanged context
+                0x05 | 0x07 | 0x70..=0x7F | 0x80 => {}
+                // Exception, followed by the preferred return address.
+                0x06 => {
+                    if reader.byte()? & 0x80 != 0 {
+                        reader.byte()?;
+                    }
+                    let return_address = self.address(reader.byte()?, reader)?;
+                    flow.run_to(return_address);
+                    flow.exception();
+                    flow.address = None;
+                }
+                0x81 => skip_context(reader)?,
+                0x82
+                | 0x83
+                | 0x85
+                | 0x86
+                | 0x90..=0x92
+                | 0x95
+                | 0x96
+                | 0x9A
+                | 0x9B
+                | 0x9D
+                | 0x9E => {
+                    let address = self.address(header, reader)?;
+                    flow.jump(address);
+                }
+                0xC0..=0xFF => {
+                    let (pattern, count) = atoms(header);
+                    for atom in 0..count {
+                        flow.waypoint(pattern & (1 << atom) != 0);
+                    }
+                }
+                _ => {
+                    tracing::warn!(
+                        "Unsupported ETMv4 packet {header:#04x} at offset {}",
+                        reader.position - 1
+                    );
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Decodes the address packet with `header`, and adds the address to the history.
+    fn address(&mut self, header: u8, reader: &mut Reader) -> Option<u32> {
+        let previous = self.history[0];
+
+        let address = match header {
+            // Exact match
+            0x90..=0x92 => self.history[usize::from(header - 0x90)],
+            // Short address, instruction set 0 and 1
+            0x95 | 0x96 => {
+                let shift = if header == 0x95 { 2 } else { 1 };
+                let first = reader.byte()?;
+                let mut address = (previous & !(0x7F << shift)) | u32::from(first & 0x7F) << shift;
+                if first & 0x80 != 0 {
+                    let second = reader.byte()?;
+                    address = (address & !(0xFF << (shift + 7))) | u32::from(second) << (shift + 7);
+                }
+                address
+            }
+            // Long address, instruction set 0 and 1, 32 and 64 bit, and with context.
+            0x82 | 0x83 | 0x85 | 0x86 | 0x9A | 0x9B | 0x9D | 0x9E => {
+                let bytes: [u8; 4] = reader.bytes()?;
+                let low = if matches!(header, 0x82 | 0x85 | 0x9A | 0x9D) {
+                    u32::from(bytes[0] & 0x7F) << 2 | u32::from(bytes[1] & 0x7F) << 9
+                } else {
+                    u32::from(bytes[0] & 0x7F) << 1 | u32::from(bytes[1]) << 8
+                };
+                let address = low | u32::from(bytes[2]) << 16 | u32::from(bytes[3]) << 24;
+
+                if matches!(header, 0x85 | 0x86 | 0x9D | 0x9E) {
+                    // The upper half of 64-bit addresses is irrelevant for Cortex-M.
+                    reader.bytes::<4>()?;
+                }
+                if matches!(header, 0x82 | 0x83 | 0x85 | 0x86) {
+                    skip_context(reader)?;
+                }
+                address
+            }
+            _ => unreachable!("{header:#04x} is not an address packet"),
+        };
+
+        self.history = [address, self.history[0], self.history[1]];
+        Some(address)
+    }
+}
+
+/// Skips the context information, which is not used on Cortex-M.
+fn skip_context(reader: &mut Reader) -> Option<()> {
+    let info = reader.byte()?;
+    if info & 0x40 != 0 {
+        // VMID
+        reader.byte()?;
+    }
+    if info & 0x80 != 0 {
+        // Context ID
+        reader.bytes::<4>()?;
+    }
+    Some(())
+}
+
+/// Returns the atoms of an atom packet, as bit pattern with the oldest atom in bit 0, where set
+/// bits are E atoms, and the number of atoms.
+fn atoms(header: u8) -> (u32, u32) {
+    match header {
+        // Format 1
+        0xF6 | 0xF7 => (u32::from(header & 1), 1),
+        // Format 2
+        0xD8..=0xDB => (u32::from(header & 0b11), 2),
+        // Format 3
+        0xF8..=0xFF => (u32::from(header & 0b111), 3),
+        // Format 4
+        0xDC..=0xDF => (
+            [0b1110, 0b0000, 0b1010, 0b0101][usize::from(header & 0b11)],
+            4,
+        ),
+        // Format 5
+        0xD5..=0xD7 | 0xF5 => {
+            let index = if header == 0xF5 { 0 } else { header & 0b11 };
+            ([0b11110, 0b00000, 0b01010, 0b10101][usize::from(index)], 5)
+        }
+        // Format 6: 3 to 23 E atoms, followed by an E atom if bit 5 is clear, an N atom if set.
+        _ => {
+            let count = u32::from(header & 0x1F) + 3;
+            let last = u32::from(header & 0x20 == 0);
+            (((1 << count) - 1) | last << count, count + 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{test::*, InstructionTrace, TraceEvent};
+    use super::*;
+
+    #[test]
+    fn atom_formats() {
+        assert_eq!(atoms(0xF7), (0b1, 1));
+        assert_eq!(atoms(0xD9), (0b01, 2));
+        assert_eq!(atoms(0xFD), (0b101, 3));
+        assert_eq!(atoms(0xDC), (0b1110, 4));
+        assert_eq!(atoms(0xF5), (0b11110, 5));
+        assert_eq!(atoms(0xD7), (0b10101, 5));
+        assert_eq!(atoms(0xC0), (0b1111, 4));
+        assert_eq!(atoms(0xE1), (0b01111, 5));
+    }
+
+    #[test]
+    fn loop_and_call() {
+        let mut stream = vec![0x42, 0x13];
+        stream.extend(A_SYNC);
+        stream.extend([
+            // Trace info without fields
+            0x01, 0x00, //
+            // Long address 0x100
+            0x9B, 0x00, 0x01, 0x00, 0x00, //
+            // E N E
+            0xFD, //
+            // E
+            0xF7, //
+            // Short address 0x10c
+            0x96, 0x06,
+        ]);
+
+        assert_eq!(
+            InstructionTrace::Etmv4(stream).decode(&image(), Some(0x10C)),
+            instructions(&[0x100, 0x102, 0x104, 0x106, 0x102, 0x104, 0x106, 0x108, 0x110, 0x112])
+        );
+    }
+
+    #[test]
+    fn exception() {
+        let mut stream = A_SYNC.to_vec();
+        stream.extend([
+            // Trace info without fields
+            0x01, 0x00, //
+            // Long address 0x100
+            0x9B, 0x00, 0x01, 0x00, 0x00, //
+            // Exception, returning to 0x104
+            0x06, 0x06, 0x96, 0x02, //
+            // Handler at 0x110
+            0x96, 0x08, //
+            // E
+            0xF7,
+        ]);
+
+        let mut expected = instructions(&[0x100, 0x102]);
+        expected.push(TraceEvent::Exception);
+        expected.extend(instructions(&[0x110, 0x112]));
+        assert_eq!(
+            InstructionTrace::Etmv4(stream).decode(&image(), None),
+            expected
+        );
+    }
+
+    #[test]
+    fn unsupported_packet_is_a_gap() {
+        let mut stream = A_SYNC.to_vec();
+        stream.extend([0x01, 0x00, 0x9B, 0x00, 0x01, 0x00, 0x00, 0xF6, 0xA0, 0xF7]);
+        stream.extend(A_SYNC);
+        stream.extend([0x01, 0x00, 0x9B, 0x08, 0x01, 0x00, 0x00, 0xF7]);
+
+        let mut expected = instructions(&[0x100, 0x102, 0x104, 0x106]);
+        expected.push(TraceEvent::Gap);
+        expected.extend(instructions(&[0x110, 0x112]));
+        assert_eq!(
+            InstructionTrace::Etmv4(stream).decode(&image(), None),
+            expected
+        );
+    }
+}
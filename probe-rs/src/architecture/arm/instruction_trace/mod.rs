@@ -0,0 +1,360 @@
+//! Reconstruction of the executed instructions from the instruction trace of Cortex-M cores.
+//!
+//! The trace units only record how the program flow continued at branches. The decoders follow
+//! the program image from branch to branch to find the instructions in between, so the decoded
+//! trace is only as good as the image which is passed to [`InstructionTrace::decode`].
+
+mod etmv3;
+mod etmv4;
+mod mtb;
+pub(crate) mod thumb;
+
+use thumb::Flow;
+
+/// The maximum number of instructions which are followed without encountering a branch before
+/// the decoder assumes that it lost track of the program flow.
+const MAX_SEQUENTIAL_INSTRUCTIONS: usize = 4096;
+
+/// The instruction trace read from the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstructionTrace {
+    /// The trace stream of an ETMv3, as implemented by Cortex-M3 and Cortex-M4.
+    Etmv3(Vec<u8>),
+    /// The trace stream of an ETMv4, as implemented by Cortex-M7, Cortex-M23 and Cortex-M33.
+    Etmv4(Vec<u8>),
+    /// The packets recorded by the Micro Trace Buffer of the Cortex-M0+, oldest first.
+    Mtb(Vec<u8>),
+}
+
+impl InstructionTrace {
+    /// Reconstructs the executed instructions, oldest first.
+    ///
+    /// If the core halted at `halt_address`, the instructions between the last traced branch and
+    /// the halt are added as well.
+    pub fn decode(&self, image: &ProgramImage, halt_address: Option<u32>) -> Vec<TraceEvent> {
+        let mut flow = ProgramFlow::new(image);
+
+        match self {
+            InstructionTrace::Etmv3(data) => etmv3::decode(data, &mut flow),
+            InstructionTrace::Etmv4(data) => etmv4::decode(data, &mut flow),
+            InstructionTrace::Mtb(data) => mtb::decode(data, &mut flow),
+        }
+
+        if let Some(halt_address) = halt_address {
+            flow.continue_to(halt_address);
+        }
+
+        flow.events
+    }
+}
+
+/// An element of the program flow reconstructed from an instruction trace.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// The program flow passed the instruction at the address. It was either executed, or its
+    /// condition failed.
+    Instruction(u32),
+    /// An exception was taken.
+    Exception,
+    /// The program flow is interrupted, because trace data was lost, or the decoder could not
+    /// follow the program flow through the program image.
+    Gap,
+}
+
+/// The code of the traced program.
+#[derive(Debug, Clone, Default)]
+pub struct ProgramImage {
+    segments: Vec<(u32, Vec<u8>)>,
+}
+
+impl ProgramImage {
+    /// Creates an empty program image.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the memory content `data` at `address` to the image.
+    pub fn add_segment(&mut self, address: u32, data: Vec<u8>) {
+        self.segments.push((address, data));
+    }
+
+    fn halfword(&self, address: u32) -> Option<u16> {
+        self.segments.iter().find_map(|(start, data)| {
+            let offset = address.checked_sub(*start)? as usize;
+            let bytes = data.get(offset..offset + 2)?;
+            Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+        })
+    }
+
+    fn instruction(&self, address: u32) -> Option<thumb::Instruction> {
+        let first = self.halfword(address)?;
+        if thumb::is_32bit(first) {
+            let second = self.halfword(address.wrapping_add(2))?;
+            Some(thumb::decode_32bit(address, first, second))
+        } else {
+            Some(thumb::decode_16bit(address, first))
+        }
+    }
+}
+
+/// Follows the program flow through the program image, as directed by a trace decoder.
+struct ProgramFlow<'a> {
+    image: &'a ProgramImage,
+    /// The address of the next instruction, if it is known.
+    address: Option<u32>,
+    events: Vec<TraceEvent>,
+}
+
+impl<'a> ProgramFlow<'a> {
+    fn new(image: &'a ProgramImage) -> Self {
+        Self {
+            image,
+            address: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// Continues the program flow at `address`.
+    fn jump(&mut self, address: u32) {
+        self.address = Some(address & !1);
+    }
+
+    /// Records an exception.
+    fn exception(&mut self) {
+        self.events.push(TraceEvent::Exception);
+    }
+
+    /// Records that the program flow is unknown until the next address in the trace.
+    fn lose_track(&mut self) {
+        self.address = None;
+        if !matches!(self.events.last(), None | Some(TraceEvent::Gap)) {
+            self.events.push(TraceEvent::Gap);
+        }
+    }
+
+    /// Passes the next instruction, which is `executed` or failed its condition.
+    ///
+    /// After an executed indirect branch, the program flow is unknown until the trace contains
+    /// the target address.
+    fn step(&mut self, executed: bool) {
+        let Some(address) = self.address else {
+            return;
+        };
+        let Some(instruction) = self.image.instruction(address) else {
+            tracing::warn!("No code at {address:#010x} in the program image");
+            self.lose_track();
+            return;
+        };
+
+        self.events.push(TraceEvent::Instruction(address));
+        self.address = match (executed, instruction.flow) {
+            (true, Flow::Direct(target) | Flow::Conditional(target)) => Some(target),
+            (true, Flow::Indirect) => None,
+            _ => Some(address.wrapping_add(instruction.size)),
+        };
+    }
+
+    /// Passes the instructions up to the next branch, and the branch, which is `taken` or not.
+    fn waypoint(&mut self, taken: bool) {
+        for _ in 0..MAX_SEQUENTIAL_INSTRUCTIONS {
+            let Some(address) = self.address else {
+                return;
+            };
+            match self.image.instruction(address) {
+                Some(instruction) if !instruction.is_branch() => {
+                    self.events.push(TraceEvent::Instruction(address));
+                    self.address = Some(address.wrapping_add(instruction.size));
+                }
+                _ => return self.step(taken),
+            }
+        }
+
+        self.lose_track();
+    }
+
+    /// Passes the instructions up to, but excluding `end`, regardless of branches.
+    fn run_to(&mut self, end: u32) {
+        for _ in 0..MAX_SEQUENTIAL_INSTRUCTIONS {
+            let Some(address) = self.address else {
+                return;
+            };
+            if address == end & !1 {
+                return;
+            }
+            let Some(instruction) = self.image.instruction(address) else {
+                return self.lose_track();
+            };
+            self.events.push(TraceEvent::Instruction(address));
+            self.address = Some(address.wrapping_add(instruction.size));
+        }
+
+        self.lose_track();
+    }
+
+    /// Passes the instructions up to `end`, if they can be reached without taking a branch.
+    fn continue_to(&mut self, end: u32) {
+        let Some(mut address) = self.address else {
+            return;
+        };
+
+        let mut instructions = Vec::new();
+        while address != end & !1 {
+            match self.image.instruction(address) {
+                Some(instruction)
+                    if matches!(instruction.flow, Flow::Sequential | Flow::Conditional(_))
+                        && instructions.len() < MAX_SEQUENTIAL_INSTRUCTIONS =>
+                {
+                    instructions.push(TraceEvent::Instruction(address));
+                    address = address.wrapping_add(instruction.size);
+                }
+                _ => return,
+            }
+        }
+
+        self.events.extend(instructions);
+        self.address = Some(address);
+    }
+}
+
+/// A cursor over the bytes of a trace stream.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.position)?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    /// Reads `N` bytes.
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let bytes = self.data.get(self.position..self.position + N)?;
+        self.position += N;
+        bytes.try_into().ok()
+    }
+
+    /// Skips a field of at most `max` bytes, where bit 7 is set in all bytes but the last.
+    fn skip_continued(&mut self, max: usize) -> Option<()> {
+        for _ in 0..max {
+            if self.byte()? & 0x80 == 0 {
+                break;
+            }
+        }
+        Some(())
+    }
+
+    /// Skips the bytes up to and including the next occurrence of `pattern`. Returns false if
+    /// the pattern does not occur.
+    fn skip_past(&mut self, pattern: &[u8]) -> bool {
+        match self.data[self.position.min(self.data.len())..]
+            .windows(pattern.len())
+            .position(|window| window == pattern)
+        {
+            Some(offset) => {
+                self.position += offset + pattern.len();
+                true
+            }
+            None => {
+                self.position = self.data.len();
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A program image with the code
+    ///
+    /// ```text
+    /// 0x100: movs r0, #0
+    /// 0x102: adds r0, #1
+    /// 0x104: cmp r0, #3
+    /// 0x106: bne 0x102
+    /// 0x108: bl 0x110
+    /// 0x10c: b 0x10c
+    /// 0x10e: nop
+    /// 0x110: nop
+    /// 0x112: bx lr
+    /// ```
+    pub(super) fn image() -> ProgramImage {
+        let halfwords: [u16; 10] = [
+            0x2000, 0x3001, 0x2803, 0xD1FC, 0xF000, 0xF802, 0xE7FE, 0xBF00, 0xBF00, 0x4770,
+        ];
+        let mut image = ProgramImage::new();
+        image.add_segment(
+            0x100,
+            halfwords.iter().flat_map(|h| h.to_le_bytes()).collect(),
+        );
+        image
+    }
+
+    /// Shorthand for the instruction events at `addresses`.
+    pub(super) fn instructions(addresses: &[u32]) -> Vec<TraceEvent> {
+        addresses
+            .iter()
+            .map(|&address| TraceEvent::Instruction(address))
+            .collect()
+    }
+
+    #[test]
+    fn follow_waypoints() {
+        let image = image();
+        let mut flow = ProgramFlow::new(&image);
+        flow.jump(0x101);
+        flow.waypoint(true);
+        flow.waypoint(false);
+        flow.waypoint(true);
+        flow.waypoint(true);
+
+        assert_eq!(
+            flow.events,
+            instructions(&[0x100, 0x102, 0x104, 0x106, 0x102, 0x104, 0x106, 0x108, 0x110, 0x112])
+        );
+        assert_eq!(flow.address, None);
+    }
+
+    #[test]
+    fn continue_to_halt() {
+        let image = image();
+        let mut flow = ProgramFlow::new(&image);
+        flow.jump(0x100);
+        flow.continue_to(0x104);
+        // Conditional branches are passed as not taken.
+        flow.continue_to(0x108);
+        // The halt is behind a branch, so the flow cannot be followed.
+        flow.continue_to(0x110);
+
+        assert_eq!(flow.events, instructions(&[0x100, 0x102, 0x104, 0x106]));
+        assert_eq!(flow.address, Some(0x108));
+    }
+
+    #[test]
+    fn missing_code_is_a_gap() {
+        let image = image();
+        let mut flow = ProgramFlow::new(&image);
+        flow.jump(0x110);
+        flow.step(true);
+        flow.jump(0x200);
+        flow.step(true);
+        flow.step(true);
+
+        assert_eq!(
+            flow.events,
+            vec![TraceEvent::Instruction(0x110), TraceEvent::Gap]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Decoder for the packets of the Micro Trace Buffer.
+//!
+//! Each packet consists of two words: the source address of a change in program flow, with the
+//! A-bit in bit 0 which marks exceptions, and the destination address, with the S-bit in bit 0
+//! which marks the first packet after the trace started. The instructions between the
+//! destination of one packet and the source of the next were executed sequentially.
+
+use super::ProgramFlow;
+
+pub(super) fn decode(data: &[u8], flow: &mut ProgramFlow) {
+    for packet in data.chunks_exact(8) {
+        let source = u32::from_le_bytes([packet[0], packet[1], packet[2], packet[3]]);
+        let destination = u32::from_le_bytes([packet[4], packet[5], packet[6], packet[7]]);
+
+        if destination & 1 != 0 {
+            // The trace (re)started, so the flow up to the source is unknown.
+            flow.lose_track();
+        } else if source & 1 != 0 {
+            // On exceptions, the source is the return address, which has not been executed.
+            flow.run_to(source);
+            flow.exception();
+        } else if flow.address.is_some() {
+            // The source is the branch which was taken.
+            flow.run_to(source);
+            flow.step(true);
+        }
+
+        flow.jump(destination);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{test::*, InstructionTrace, TraceEvent};
+
+    fn packets(packets: &[(u32, u32)]) -> InstructionTrace {
+        InstructionTrace::Mtb(
+            packets
+                .iter()
+                .flat_map(|(source, destination)| {
+                    [source.to_le_bytes(), destination.to_le_bytes()].concat()
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn loop_and_call() {
+        let trace = packets(&[
+            (0x0000_0200, 0x0000_0101),
+            (0x0000_0106, 0x0000_0102),
+            (0x0000_0106, 0x0000_0102),
+            (0x0000_0108, 0x0000_0110),
+        ]);
+
+        assert_eq!(
+            trace.decode(&image(), Some(0x112)),
+            instructions(&[
+                0x100, 0x102, 0x104, 0x106, 0x102, 0x104, 0x106, 0x102, 0x104, 0x106, 0x108, 0x110
+            ])
+        );
+    }
+
+    #[test]
+    fn exception() {
+        let trace = packets(&[(0x0000_0200, 0x0000_0101), (0x0000_0105, 0x0000_0110)]);
+
+        let mut expected = instructions(&[0x100, 0x102]);
+        expected.push(TraceEvent::Exception);
+        expected.extend(instructions(&[0x110]));
+        assert_eq!(trace.decode(&image(), Some(0x112)), expected);
+    }
+}
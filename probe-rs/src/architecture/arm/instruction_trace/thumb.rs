@@ -0,0 +1,198 @@
+//! Classification of Thumb instructions by their effect on the program flow.
+//!
+//! This only distinguishes what the trace decoders need: the size of an instruction, and whether
+//! it is a branch with a target encoded in the instruction, or a branch to a target which is
+//! only known at run time.
+
+/// The effect of an instruction on the program flow.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Flow {
+    /// The instruction is followed by the next instruction in memory.
+    Sequential,
+    /// The instruction always branches to an address encoded in the instruction, if it is
+    /// executed.
+    Direct(u32),
+    /// The instruction branches to an address encoded in the instruction if its condition
+    /// passes.
+    Conditional(u32),
+    /// The instruction branches to an address which is only known at run time.
+    Indirect,
+}
+
+/// A decoded Thumb instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Instruction {
+    /// The size of the instruction in bytes.
+    pub size: u32,
+    /// The effect of the instruction on the program flow.
+    pub flow: Flow,
+}
+
+impl Instruction {
+    /// Whether the instruction is a branch, which the trace units report as waypoint.
+    pub fn is_branch(&self) -> bool {
+        self.flow != Flow::Sequential
+    }
+}
+
+/// Returns true if `first` is the first halfword of a 32-bit instruction.
+///
+/// The first halfword of a 32-bit instruction starts with 0b11101, 0b11110 or 0b11111.
+pub(crate) fn is_32bit(first: u16) -> bool {
+    first >> 11 >= 0b11101
+}
+
+/// Decodes the 16-bit instruction `first` at `address`.
+pub(crate) fn decode_16bit(address: u32, first: u16) -> Instruction {
+    let pc = address.wrapping_add(4);
+
+    let flow = if first & 0xF000 == 0xD000 && (first >> 9) & 0b111 != 0b111 {
+        // B<c> (encoding T1). Conditions 0b1110 and 0b1111 are UDF and SVC.
+        Flow::Conditional(pc.wrapping_add(sign_extend(u32::from(first & 0xFF) << 1, 9)))
+    } else if first & 0xF800 == 0xE000 {
+        // B (encoding T2)
+        Flow::Direct(pc.wrapping_add(sign_extend(u32::from(first & 0x7FF) << 1, 12)))
+    } else if first & 0xF500 == 0xB100 {
+        // CB{N}Z
+        let offset = (u32::from(first >> 9) & 1) << 6 | (u32::from(first >> 3) & 0x1F) << 1;
+        Flow::Conditional(pc.wrapping_add(offset))
+    } else if first & 0xFF00 == 0x4700 || first & 0xFF00 == 0xBD00 || first & 0xFD87 == 0x4487 {
+        // BX, BLX (register), POP with the PC, and ADD or MOV with the PC as destination.
+        Flow::Indirect
+    } else {
+        Flow::Sequential
+    };
+
+    Instruction { size: 2, flow }
+}
+
+/// Decodes the 32-bit instruction consisting of the halfwords `first` and `second` at
+/// `address`.
+pub(crate) fn decode_32bit(address: u32, first: u16, second: u16) -> Instruction {
+    let pc = address.wrapping_add(4);
+    let first32 = u32::from(first);
+    let second32 = u32::from(second);
+
+    let flow = if first & 0xF800 == 0xF000 && second & 0x8000 == 0x8000 {
+        let s = (first32 >> 10) & 1;
+        let j1 = (second32 >> 13) & 1;
+        let j2 = (second32 >> 11) & 1;
+        let imm11 = second32 & 0x7FF;
+
+        match second & 0xD000 {
+            // B<c>.W (encoding T3). Conditions 0b111x are miscellaneous control instructions.
+            0x8000 if (first >> 7) & 0b111 != 0b111 => {
+                let imm6 = first32 & 0x3F;
+                let offset = s << 20 | j2 << 19 | j1 << 18 | imm6 << 12 | imm11 << 1;
+                Flow::Conditional(pc.wrapping_add(sign_extend(offset, 21)))
+            }
+            // B.W (encoding T4) and BL
+            0x9000 | 0xD000 => {
+                let i1 = !(j1 ^ s) & 1;
+                let i2 = !(j2 ^ s) & 1;
+                let imm10 = first32 & 0x3FF;
+                let offset = s << 24 | i1 << 23 | i2 << 22 | imm10 << 12 | imm11 << 1;
+                Flow::Direct(pc.wrapping_add(sign_extend(offset, 25)))
+            }
+            _ => Flow::Sequential,
+        }
+    } else if (first & 0xFFF0 == 0xE8D0 && second & 0xFFE0 == 0xF000)
+        || (first & 0xFF70 == 0xF850 && second >> 12 == 0xF)
+        || (first & 0xFFD0 == 0xE890 || first & 0xFFD0 == 0xE910) && second & 0x8000 != 0
+    {
+        // TBB and TBH, LDR with the PC as destination, and LDM with the PC in the register list.
+        Flow::Indirect
+    } else {
+        Flow::Sequential
+    };
+
+    Instruction { size: 4, flow }
+}
+
+/// Sign extends the lowest `bits` of `value`.
+fn sign_extend(value: u32, bits: u32) -> u32 {
+    let shift = 32 - bits;
+    (((value << shift) as i32) >> shift) as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(address: u32, halfwords: &[u16]) -> Instruction {
+        if is_32bit(halfwords[0]) {
+            decode_32bit(address, halfwords[0], halfwords[1])
+        } else {
+            decode_16bit(address, halfwords[0])
+        }
+    }
+
+    #[test]
+    fn direct_branches() {
+        // b .
+        assert_eq!(decode(0x100, &[0xE7FE]).flow, Flow::Direct(0x100));
+        // beq 0x106
+        assert_eq!(decode(0x100, &[0xD001]).flow, Flow::Conditional(0x106));
+        // cbz r0, 0x108
+        assert_eq!(decode(0x100, &[0xB110]).flow, Flow::Conditional(0x108));
+        // bl 0x104
+        assert_eq!(
+            decode(0x100, &[0xF000, 0xF800]),
+            Instruction {
+                size: 4,
+                flow: Flow::Direct(0x104)
+            }
+        );
+        // bl .
+        assert_eq!(decode(0x100, &[0xF7FF, 0xFFFE]).flow, Flow::Direct(0x100));
+        // b.w 0x104
+        assert_eq!(decode(0x100, &[0xF000, 0xB800]).flow, Flow::Direct(0x104));
+        // beq.w .
+        assert_eq!(
+            decode(0x100, &[0xF43F, 0xAFFE]).flow,
+            Flow::Conditional(0x100)
+        );
+    }
+
+    #[test]
+    fn indirect_branches() {
+        // bx lr
+        assert_eq!(decode(0x100, &[0x4770]).flow, Flow::Indirect);
+        // blx r3
+        assert_eq!(decode(0x100, &[0x4798]).flow, Flow::Indirect);
+        // pop {r4, pc}
+        assert_eq!(decode(0x100, &[0xBD10]).flow, Flow::Indirect);
+        // mov pc, lr
+        assert_eq!(decode(0x100, &[0x46F7]).flow, Flow::Indirect);
+        // tbb [pc, r0]
+        assert_eq!(decode(0x100, &[0xE8DF, 0xF000]).flow, Flow::Indirect);
+        // ldr pc, [sp], #4
+        assert_eq!(decode(0x100, &[0xF85D, 0xFB04]).flow, Flow::Indirect);
+        // pop.w {r4-r11, pc}
+        assert_eq!(decode(0x100, &[0xE8BD, 0x8FF0]).flow, Flow::Indirect);
+    }
+
+    #[test]
+    fn sequential_instructions() {
+        // svc 0, udf 0, mov r0, r1
+        for instruction in [0xDF00, 0xDE00, 0x4608] {
+            assert_eq!(
+                decode(0x100, &[instruction]),
+                Instruction {
+                    size: 2,
+                    flow: Flow::Sequential
+                }
+            );
+        }
+        // msr msp, r0, ldr.w r0, [r1], ldmia.w r0, {r1, r2}
+        for instruction in [[0xF380, 0x8808], [0xF8D1, 0x0000], [0xE890, 0x0006]] {
+            assert_eq!(
+                decode(0x100, &instruction),
+                Instruction {
+                    size: 4,
+                    flow: Flow::Sequential
+                }
+            );
+        }
+    }
+}
@@ -6,6 +6,7 @@ pub(crate) mod communication_interface;
 pub mod component;
 pub(crate) mod core;
 pub mod dp;
+pub mod instruction_trace;
 pub mod memory;
 pub mod sequences;
 pub mod swo;
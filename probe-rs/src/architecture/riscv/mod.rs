@@ -4,6 +4,7 @@ use crate::{
     architecture::riscv::sequences::RiscvDebugSequence,
     core::{
         Architecture, BreakpointCause, CoreInformation, CoreRegisters, RegisterId, RegisterValue,
+        Watchpoint, WatchpointCause, WatchpointKind,
     },
     memory::{valid_32bit_address, CoreMemoryInterface},
     memory_mapped_bitfield_register,
@@ -11,7 +12,7 @@ use crate::{
     semihosting::decode_semihosting_syscall,
     semihosting::SemihostingCommand,
     CoreInterface, CoreRegister, CoreStatus, CoreType, Error, HaltReason, InstructionSet,
-    MemoryInterface, MemoryMappedRegister,
+    MemoryInterface, MemoryMappedRegister, VectorCatchCondition,
 };
 use bitfield::bitfield;
 use communication_interface::{AbstractCommandErrorKind, RiscvCommunicationInterface, RiscvError};
@@ -115,6 +116,118 @@ impl<'state> Riscv32<'state> {
         Ok(command)
     }
 
+    /// Reads the watchpoint configured in trigger `unit_index`, if the trigger is used as a watchpoint.
+    ///
+    /// Returns the trigger configuration as well, so the caller can inspect the `hit` bit.
+    fn read_watchpoint(
+        &mut self,
+        unit_index: usize,
+    ) -> Result<Option<(Watchpoint, Mcontrol)>, Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+
+        self.write_csr(tselect, unit_index as u32)?;
+        let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+        let Some(kind) = tdata_value.watchpoint_kind() else {
+            return Ok(None);
+        };
+
+        let tdata2_value = self.read_csr(tdata2)? as u64;
+        let (address, length) = match tdata_value.match_() {
+            // Exact address match
+            0 => (tdata2_value, 1),
+            // Naturally aligned power of two range, encoded in the trailing ones of the address.
+            1 => {
+                let length = 2 << tdata2_value.trailing_ones();
+                (tdata2_value & !(length - 1), length)
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some((
+            Watchpoint {
+                address,
+                length,
+                kind,
+            },
+            tdata_value,
+        )))
+    }
+
+    /// Determines which watchpoint caused the core to halt, if any.
+    ///
+    /// Reporting which trigger fired through the `hit` bit is optional, so if no trigger reports a hit,
+    /// we assume a watchpoint caused the halt if there is no instruction breakpoint at the current PC.
+    fn watchpoint_halt_cause(&mut self) -> Result<Option<WatchpointCause>, Error> {
+        let mut configured_watchpoints = false;
+
+        for unit_index in 0..self.available_breakpoint_units()? as usize {
+            if let Some((watchpoint, tdata_value)) = self.read_watchpoint(unit_index)? {
+                if tdata_value.hit() {
+                    return Ok(Some(WatchpointCause::Hit(watchpoint)));
+                }
+                configured_watchpoints = true;
+            }
+        }
+
+        if !configured_watchpoints {
+            return Ok(None);
+        }
+
+        let pc: u64 = self.read_core_reg(RegisterId(0x7b1))?.try_into()?;
+        if self.hw_breakpoints()?.contains(&Some(pc)) {
+            Ok(None)
+        } else {
+            Ok(Some(WatchpointCause::Unknown))
+        }
+    }
+
+    /// Enables or disables the triggers which are used as watchpoints.
+    ///
+    /// Load and store triggers fire before the access is performed, so they need to be disabled
+    /// to step over the instruction which caused the halt. Re-enabling them clears their `hit` bit.
+    fn enable_watchpoints(&mut self, state: bool) -> Result<(), Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+
+        for unit_index in 0..self.available_breakpoint_units()? as usize {
+            self.write_csr(tselect, unit_index as u32)?;
+            let mut tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+            // Only modify the trigger if it is a watchpoint active in all modes (probe-rs enabled it) or no modes (we previously disabled it).
+            if tdata_value.is_watchpoint()
+                && ((tdata_value.m() && tdata_value.u()) || (!tdata_value.m() && !tdata_value.u()))
+            {
+                tdata_value.set_m(state);
+                tdata_value.set_u(state);
+                tdata_value.set_hit(false);
+                self.write_csr(tdata1, tdata_value.0)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the core halted on the trigger which catches exceptions at the trap vector.
+    fn halted_at_vector_catch(&mut self) -> Result<bool, Error> {
+        let Some(address) = self.state.vector_catch_address else {
+            return Ok(false);
+        };
+
+        let pc: u64 = self.read_core_reg(RegisterId(0x7b1))?.try_into()?;
+        Ok(pc == address)
+    }
+
+    /// The address of the machine mode trap handler.
+    ///
+    /// Synchronous exceptions always use the base address. In direct mode, interrupts do as well.
+    fn trap_vector_address(&mut self) -> Result<u64, Error> {
+        let mtvec = self.read_csr(0x305)?;
+        Ok((mtvec & !0b11) as u64)
+    }
+
     fn determine_number_of_hardware_breakpoints(&mut self) -> Result<u32, RiscvError> {
         tracing::debug!("Determining number of HW breakpoints supported");
 
@@ -219,7 +332,16 @@ impl CoreInterface for Riscv32<'_> {
                     // TODO: Add testcase to probe-rs-debugger-test to validate semihosting exit/abort work and unknown semihosting operations are skipped
                 }
                 // Trigger module caused halt
-                2 => HaltReason::Breakpoint(BreakpointCause::Hardware),
+                2 => {
+                    if self.halted_at_vector_catch()? {
+                        HaltReason::Exception
+                    } else {
+                        match self.watchpoint_halt_cause()? {
+                            Some(cause) => HaltReason::Watchpoint(cause),
+                            None => HaltReason::Breakpoint(BreakpointCause::Hardware),
+                        }
+                    }
+                }
                 // Debugger requested a halt
                 3 => HaltReason::Request,
                 // Core halted after single step
@@ -304,10 +426,15 @@ impl CoreInterface for Riscv32<'_> {
             });
         } else if matches!(
             halt_reason,
-            CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Hardware))
+            CoreStatus::Halted(
+                HaltReason::Breakpoint(BreakpointCause::Hardware) | HaltReason::Exception
+            )
         ) {
-            // If we are halted on a hardware breakpoint.
+            // If we are halted on a hardware breakpoint, or on the trigger which catches exceptions.
             self.enable_breakpoints(false)?;
+        } else if matches!(halt_reason, CoreStatus::Halted(HaltReason::Watchpoint(_))) {
+            // If we are halted on a watchpoint, the access has not been performed yet.
+            self.enable_watchpoints(false)?;
         }
 
         let mut dcsr = Dcsr(self.read_core_reg(RegisterId(0x7b0))?.try_into()?);
@@ -335,10 +462,14 @@ impl CoreInterface for Riscv32<'_> {
         // Re-enable breakpoints before we continue.
         if matches!(
             halt_reason,
-            CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Hardware))
+            CoreStatus::Halted(
+                HaltReason::Breakpoint(BreakpointCause::Hardware) | HaltReason::Exception
+            )
         ) {
-            // If we are halted on a hardware breakpoint.
+            // If we are halted on a hardware breakpoint, or on the trigger which catches exceptions.
             self.enable_breakpoints(true)?;
+        } else if matches!(halt_reason, CoreStatus::Halted(HaltReason::Watchpoint(_))) {
+            self.enable_watchpoints(true)?;
         }
 
         self.state.pc_written = false;
@@ -404,15 +535,13 @@ impl CoreInterface for Riscv32<'_> {
             // The trigger must be active in at least a single mode
             let trigger_any_mode_active = tdata_value.m() || tdata_value.s() || tdata_value.u();
 
-            let trigger_any_action_enabled =
-                tdata_value.execute() || tdata_value.store() || tdata_value.load();
-
             // Only return if the trigger if it is for an execution debug action in all modes.
+            // Triggers on loads and stores are reported by `hw_watchpoints`.
             if tdata_value.type_() == 0b10
                 && tdata_value.action() == 1
                 && tdata_value.match_() == 0
                 && trigger_any_mode_active
-                && trigger_any_action_enabled
+                && tdata_value.execute()
             {
                 let breakpoint = self.read_csr(tdata2)?;
                 breakpoints.push(Some(breakpoint as u64));
@@ -539,6 +668,159 @@ impl CoreInterface for Riscv32<'_> {
         Ok(())
     }
 
+    /// Exceptions are caught with an instruction trigger on the trap vector, as RISC-V has no
+    /// dedicated vector catch. Only [`VectorCatchCondition::Exception`] is supported.
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        if condition != VectorCatchCondition::Exception {
+            return Err(Error::NotImplemented("vector catch for this condition"));
+        }
+
+        let address = self.trap_vector_address()?;
+        if self.state.vector_catch_address == Some(address) {
+            return Ok(());
+        }
+        self.disable_vector_catch(condition)?;
+
+        let breakpoints = self.hw_breakpoints()?;
+        let watchpoints = self.hw_watchpoints()?;
+        let Some(unit_index) = (0..breakpoints.len()).find(|&unit| {
+            breakpoints[unit].is_none() && watchpoints.get(unit).map_or(true, Option::is_none)
+        }) else {
+            return Err(Error::Other(
+                "No trigger is available to catch exceptions.".to_string(),
+            ));
+        };
+
+        self.set_hw_breakpoint(unit_index, address)?;
+        self.state.vector_catch_address = Some(address);
+
+        Ok(())
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        if condition != VectorCatchCondition::Exception {
+            return Err(Error::NotImplemented("vector catch for this condition"));
+        }
+
+        let Some(address) = self.state.vector_catch_address.take() else {
+            return Ok(());
+        };
+
+        let breakpoints = self.hw_breakpoints()?;
+        if let Some(unit_index) = breakpoints.iter().position(|bp| *bp == Some(address)) {
+            self.clear_hw_breakpoint(unit_index)?;
+        }
+
+        Ok(())
+    }
+
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        // Watchpoints use the same triggers as breakpoints.
+        self.available_breakpoint_units()
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        // this can be called w/o halting the core via Session::new - temporarily halt if not halted
+        let was_running = !self.core_halted()?;
+        if was_running {
+            self.halt(Duration::from_millis(100))?;
+        }
+
+        let mut watchpoints = vec![];
+        for unit_index in 0..self.available_watchpoint_units()? as usize {
+            let watchpoint = self.read_watchpoint(unit_index)?;
+            tracing::debug!("Watchpoint {}: {:?}", unit_index, watchpoint);
+
+            watchpoints.push(watchpoint.map(|(watchpoint, _)| watchpoint));
+        }
+
+        if was_running {
+            self.resume_core()?;
+        }
+
+        Ok(watchpoints)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        let addr = valid_32bit_address(watchpoint.address)?;
+
+        if !watchpoint.is_naturally_aligned() {
+            return Err(Error::Other(format!(
+                "The watchpoint at {:#010x} with a length of {} bytes is not supported. The length must be a power of two, and the address must be aligned to the length.",
+                addr, watchpoint.length
+            )));
+        }
+
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+
+        tracing::info!("Setting watchpoint {}", unit_index);
+
+        self.write_csr(tselect, unit_index as u32)?;
+
+        // verify the trigger has the correct type
+        let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+        let trigger_type = tdata_value.type_();
+        if trigger_type != 0b10 {
+            return Err(RiscvError::UnexpectedTriggerType(trigger_type).into());
+        }
+
+        let mut data_watchpoint = Mcontrol(0);
+
+        // Enter debug mode
+        data_watchpoint.set_action(1);
+        data_watchpoint.set_type(2);
+        data_watchpoint.set_dmode(true);
+        data_watchpoint.set_m(true);
+        data_watchpoint.set_u(true);
+
+        // Trigger on the requested data accesses
+        data_watchpoint.set_load(watchpoint.kind.on_read());
+        data_watchpoint.set_store(watchpoint.kind.on_write());
+
+        // Match address
+        data_watchpoint.set_select(false);
+
+        let tdata2_value = if watchpoint.length == 1 {
+            // Match exactly the value in tdata2
+            data_watchpoint.set_match(0);
+            addr
+        } else {
+            // Match a naturally aligned power of two range
+            let maskmax = tdata_value.maskmax();
+            if maskmax == 0 || watchpoint.length > 1 << maskmax {
+                return Err(Error::Other(format!(
+                    "The watchpoint at {:#010x} with a length of {} bytes is not supported. This core can watch at most {} bytes.",
+                    addr,
+                    watchpoint.length,
+                    if maskmax == 0 { 1 } else { 1u64 << maskmax }
+                )));
+            }
+            data_watchpoint.set_match(1);
+            addr | (watchpoint.length as u32 / 2 - 1)
+        };
+
+        self.write_csr(tdata1, 0)?;
+        self.write_csr(tdata2, tdata2_value)?;
+        self.write_csr(tdata1, data_watchpoint.0)?;
+
+        Ok(())
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        // Watchpoints use the same triggers as breakpoints.
+        self.clear_hw_breakpoint(unit_index)
+    }
+
+    fn hw_watchpoints_share_breakpoint_units(&self) -> bool {
+        true
+    }
+
     fn registers(&self) -> &'static CoreRegisters {
         &RISCV_CORE_REGSISTERS
     }
@@ -646,6 +928,9 @@ pub struct RiscvCoreState {
 
     /// The semihosting command that was decoded at the current program counter
     semihosting_command: Option<SemihostingCommand>,
+
+    /// The trap vector address which is caught with a trigger, if exceptions are caught.
+    vector_catch_address: Option<u64>,
 }
 
 impl RiscvCoreState {
@@ -655,6 +940,7 @@ impl RiscvCoreState {
             hw_breakpoints: None,
             pc_written: false,
             semihosting_command: None,
+            vector_catch_address: None,
         }
     }
 }
@@ -888,6 +1174,30 @@ bitfield! {
     load, set_load: 0;
 }
 
+impl Mcontrol {
+    /// Returns `true` if the trigger enters debug mode on a load or store.
+    fn is_watchpoint(&self) -> bool {
+        self.type_() == 0b10
+            && self.action() == 1
+            && !self.execute()
+            && (self.load() || self.store())
+    }
+
+    /// Returns the kind of watchpoint this trigger implements, if it is an active watchpoint.
+    fn watchpoint_kind(&self) -> Option<WatchpointKind> {
+        if !self.is_watchpoint() || !(self.m() || self.s() || self.u()) {
+            return None;
+        }
+
+        match (self.load(), self.store()) {
+            (true, true) => Some(WatchpointKind::Access),
+            (true, false) => Some(WatchpointKind::Read),
+            (false, true) => Some(WatchpointKind::Write),
+            (false, false) => None,
+        }
+    }
+}
+
 memory_mapped_bitfield_register! {
     /// Isa and Extensions (see RISC-V Privileged Spec, 3.1.1)
     pub struct Misa(u32);
@@ -899,3 +1209,272 @@ memory_mapped_bitfield_register! {
     /// Standard RISC-V extensions
     extensions, _: 25, 0;
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::architecture::riscv::{
+        communication_interface::RiscvCommunicationInterfaceState, dtm::dtm_access::DtmAccess,
+        sequences::DefaultRiscvSequence,
+    };
+    use crate::probe::{
+        CommandResult, DeferredResultIndex, DeferredResultSet, JtagCommandQueue, JtagWriteCommand,
+    };
+
+    const TRAP_VECTOR: u32 = 0x4000_0100;
+    const TRIGGER_COUNT: usize = 2;
+
+    /// A debug module with a single hart, which runs until a trigger fires or it is halted.
+    #[derive(Debug)]
+    struct MockDebugModule {
+        queue: JtagCommandQueue,
+        results: DeferredResultSet,
+        dmcontrol: u32,
+        halted: bool,
+        resume_ack: bool,
+        cmderr: u32,
+        data0: u32,
+        csrs: HashMap<u16, u32>,
+        tdata1: [u32; TRIGGER_COUNT],
+        tdata2: [u32; TRIGGER_COUNT],
+    }
+
+    impl MockDebugModule {
+        /// Creates a hart which is halted by the trigger on the trap vector.
+        fn halted_at_trap_vector() -> Self {
+            let mut dcsr = Dcsr(0);
+            dcsr.set_cause(2);
+
+            Self {
+                queue: JtagCommandQueue::new(),
+                results: DeferredResultSet::new(),
+                dmcontrol: 0,
+                halted: true,
+                resume_ack: false,
+                cmderr: 0,
+                data0: 0,
+                csrs: HashMap::from([(0x305, TRAP_VECTOR), (0x7b0, dcsr.0), (0x7b1, TRAP_VECTOR)]),
+                // Address match triggers, which are not enabled in any mode
+                tdata1: [2 << 28; TRIGGER_COUNT],
+                tdata2: [0; TRIGGER_COUNT],
+            }
+        }
+
+        fn read(&mut self, address: u64) -> u32 {
+            match address {
+                0x04 => self.data0,
+                // Only hart 0 exists, so `hartsel` has no writable bits.
+                0x10 => self.dmcontrol & !(0x3ff_ffc0),
+                0x11 => {
+                    // Version 0.13, authenticated
+                    let mut status = 2 | 1 << 7;
+                    if self.halted {
+                        status |= 1 << 8 | 1 << 9;
+                    } else {
+                        status |= 1 << 10 | 1 << 11;
+                    }
+                    if self.resume_ack {
+                        status |= 1 << 16 | 1 << 17;
+                    }
+                    status
+                }
+                // A single data register, no program buffer
+                0x16 => self.cmderr << 8 | 1,
+                _ => 0,
+            }
+        }
+
+        fn write(&mut self, address: u64, value: u32) {
+            match address {
+                0x04 => self.data0 = value,
+                0x10 => {
+                    // `haltreq` and `resumereq`
+                    if value & 1 << 31 != 0 && !self.halted {
+                        self.halt(3);
+                    }
+                    if value & 1 << 30 != 0 && self.halted {
+                        self.resume();
+                    }
+                    self.dmcontrol = value;
+                }
+                0x16 => self.cmderr &= !((value >> 8) & 0x7),
+                0x17 => self.execute_command(value),
+                _ => {}
+            }
+        }
+
+        fn execute_command(&mut self, command: u32) {
+            let transfer = command & 1 << 17 != 0;
+            let write = command & 1 << 16 != 0;
+            let regno = command as u16;
+
+            if command >> 24 != 0 || !transfer || regno >= 0x1000 {
+                self.cmderr = 2;
+            } else if !self.halted {
+                self.cmderr = 4;
+            } else if write {
+                self.write_csr(regno, self.data0);
+            } else {
+                self.data0 = self.read_csr(regno);
+            }
+        }
+
+        fn read_csr(&self, address: u16) -> u32 {
+            let tselect = self.csrs.get(&0x7a0).copied().unwrap_or(0) as usize;
+            match address {
+                0x7a1 => self.tdata1[tselect],
+                0x7a2 => self.tdata2[tselect],
+                // Only address match triggers are supported.
+                0x7a4 => 1 << 2,
+                _ => self.csrs.get(&address).copied().unwrap_or(0),
+            }
+        }
+
+        fn write_csr(&mut self, address: u16, value: u32) {
+            let tselect = self.csrs.get(&0x7a0).copied().unwrap_or(0) as usize;
+            match address {
+                0x7a0 if value as usize >= TRIGGER_COUNT => {}
+                0x7a1 => {
+                    let mut tdata1 = Mcontrol(value);
+                    tdata1.set_type(2);
+                    self.tdata1[tselect] = tdata1.0;
+                }
+                0x7a2 => self.tdata2[tselect] = value,
+                _ => {
+                    self.csrs.insert(address, value);
+                }
+            }
+        }
+
+        fn halt(&mut self, cause: u32) {
+            let mut dcsr = Dcsr(self.read_csr(0x7b0));
+            dcsr.set_cause(cause);
+            self.write_csr(0x7b0, dcsr.0);
+            self.halted = true;
+        }
+
+        /// Resumes the hart, which only halts again if it is single stepped.
+        fn resume(&mut self) {
+            self.halted = false;
+            self.resume_ack = true;
+
+            // Execute triggers fire before the instruction is executed.
+            let pc = self.read_csr(0x7b1);
+            let triggered = (0..TRIGGER_COUNT).any(|unit| {
+                let tdata1 = Mcontrol(self.tdata1[unit]);
+                tdata1.action() == 1 && tdata1.execute() && tdata1.m() && self.tdata2[unit] == pc
+            });
+
+            if triggered {
+                self.halt(2);
+            } else if Dcsr(self.read_csr(0x7b0)).step() {
+                self.write_csr(0x7b1, pc + 4);
+                self.halt(4);
+            }
+        }
+
+        fn schedule(&mut self, result: CommandResult) -> DeferredResultIndex {
+            let index = self.queue.schedule(JtagWriteCommand {
+                address: 0,
+                data: vec![],
+                len: 0,
+                transform: |_, _| Ok(CommandResult::None),
+            });
+            self.results.push(&index, result);
+            index
+        }
+    }
+
+    impl DtmAccess for MockDebugModule {
+        fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn clear_error_state(&mut self) -> Result<(), RiscvError> {
+            Ok(())
+        }
+
+        fn read_deferred_result(
+            &mut self,
+            index: DeferredResultIndex,
+        ) -> Result<CommandResult, RiscvError> {
+            Ok(self.results.take(index).unwrap())
+        }
+
+        fn execute(&mut self) -> Result<(), RiscvError> {
+            Ok(())
+        }
+
+        fn schedule_write(
+            &mut self,
+            address: u64,
+            value: u32,
+        ) -> Result<Option<DeferredResultIndex>, RiscvError> {
+            self.write(address, value);
+            Ok(None)
+        }
+
+        fn schedule_read(&mut self, address: u64) -> Result<DeferredResultIndex, RiscvError> {
+            let value = self.read(address);
+            Ok(self.schedule(CommandResult::U32(value)))
+        }
+
+        fn read_with_timeout(
+            &mut self,
+            address: u64,
+            _timeout: Duration,
+        ) -> Result<u32, RiscvError> {
+            Ok(self.read(address))
+        }
+
+        fn write_with_timeout(
+            &mut self,
+            address: u64,
+            value: u32,
+            _timeout: Duration,
+        ) -> Result<Option<u32>, RiscvError> {
+            self.write(address, value);
+            Ok(None)
+        }
+
+        fn read_idcode(&mut self) -> Result<Option<u32>, DebugProbeError> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn run_from_caught_exception() {
+        let mut interface_state = RiscvCommunicationInterfaceState::new();
+        let mut interface = RiscvCommunicationInterface::new(
+            Box::new(MockDebugModule::halted_at_trap_vector()),
+            &mut interface_state,
+        );
+        interface.enter_debug_mode().unwrap();
+
+        let mut state = RiscvCoreState::new();
+        let mut core = Riscv32::new(interface, &mut state, DefaultRiscvSequence::create()).unwrap();
+        core.enable_vector_catch(VectorCatchCondition::Exception)
+            .unwrap();
+        assert_eq!(
+            core.status().unwrap(),
+            CoreStatus::Halted(HaltReason::Exception)
+        );
+
+        core.run().unwrap();
+
+        assert_eq!(core.status().unwrap(), CoreStatus::Running);
+        let info = core.halt(Duration::from_millis(100)).unwrap();
+        assert_eq!(info.pc, TRAP_VECTOR as u64 + 4);
+        // Exceptions are still caught after resuming.
+        assert_eq!(
+            core.hw_breakpoints().unwrap(),
+            vec![Some(TRAP_VECTOR as u64), None]
+        );
+    }
+}
@@ -13,6 +13,7 @@ use crate::{
         arch::{instruction::Instruction, CpuRegister, Register, SpecialRegister},
         xdm::{DebugStatus, XdmState},
     },
+    core::WatchpointCause,
     probe::{DebugProbeError, DeferredResultIndex, JTAGAccess},
     BreakpointCause, Error as ProbeRsError, HaltReason, MemoryInterface,
 };
@@ -204,6 +205,15 @@ impl<'probe> XtensaCommunicationInterface<'probe> {
         Ok(self.state.is_halted)
     }
 
+    /// Samples the program counter of the running core, or returns `None` if the core is halted.
+    pub fn sample_program_counter(&mut self) -> Result<Option<u32>, XtensaError> {
+        if self.core_halted()? {
+            return Ok(None);
+        }
+
+        Ok(Some(self.xdm.debug_pc()?))
+    }
+
     /// Waits until the core is halted.
     ///
     /// This function lowers the interrupt level to allow halting on debug exceptions.
@@ -1033,7 +1043,7 @@ impl DebugCause {
         } else if is_breakpoint {
             HaltReason::Breakpoint(BreakpointCause::Software)
         } else if is_dbreak_exception {
-            HaltReason::Watchpoint
+            HaltReason::Watchpoint(WatchpointCause::Unknown)
         } else if is_debug_interrupt {
             HaltReason::Request
         } else {
@@ -1078,6 +1088,22 @@ u32_register!(ProgramStatus, Register::CurrentPs);
 pub struct IBreakEn(pub u32);
 u32_register!(IBreakEn, SpecialRegister::IBreakEnable);
 
+bitfield::bitfield! {
+    /// The `DBREAKC` (Data Breakpoint Control) registers.
+    #[derive(Copy, Clone)]
+    pub struct DBreakC(u32);
+    impl Debug;
+
+    /// Address bits compared by the data breakpoint. A cleared bit is ignored.
+    pub mask,        set_mask       : 5, 0;
+
+    /// Break on loads
+    pub load_break,  set_load_break : 30;
+
+    /// Break on stores
+    pub store_break, set_store_break: 31;
+}
+
 /// The `ICOUNT` (Instruction Counter) register.
 #[derive(Copy, Clone, Debug)]
 pub struct ICount(pub u32);
@@ -10,15 +10,15 @@ use crate::{
             instruction::{Instruction, InstructionEncoding},
             Register, SpecialRegister,
         },
-        communication_interface::{DebugCause, IBreakEn, XtensaCommunicationInterface},
+        communication_interface::{DBreakC, DebugCause, IBreakEn, XtensaCommunicationInterface},
         registers::{FP, PC, RA, SP, XTENSA_CORE_REGSISTERS},
         sequences::XtensaDebugSequence,
     },
     core::{
         registers::{CoreRegisters, RegisterId, RegisterValue},
-        BreakpointCause,
+        BreakpointCause, Watchpoint, WatchpointCause, WatchpointKind,
     },
-    memory::CoreMemoryInterface,
+    memory::{valid_32bit_address, CoreMemoryInterface},
     semihosting::decode_semihosting_syscall,
     semihosting::SemihostingCommand,
     CoreInformation, CoreInterface, CoreRegister, CoreStatus, Error, HaltReason, MemoryInterface,
@@ -84,6 +84,13 @@ pub struct Xtensa<'probe> {
 impl<'probe> Xtensa<'probe> {
     const IBREAKA_REGS: [SpecialRegister; 2] =
         [SpecialRegister::IBreakA0, SpecialRegister::IBreakA1];
+    const DBREAKA_REGS: [SpecialRegister; 2] =
+        [SpecialRegister::DBreakA0, SpecialRegister::DBreakA1];
+    const DBREAKC_REGS: [SpecialRegister; 2] =
+        [SpecialRegister::DBreakC0, SpecialRegister::DBreakC1];
+
+    /// The largest range a single data breakpoint can watch.
+    const MAX_WATCHPOINT_LENGTH: u64 = 64;
 
     /// Create a new Xtensa interface for a particular core.
     pub fn new(
@@ -175,6 +182,59 @@ impl<'probe> Xtensa<'probe> {
         Ok(())
     }
 
+    /// Steps over the instruction which triggered a data breakpoint, if the core halted on one.
+    ///
+    /// Data breakpoints are taken before the access is performed, so the data breakpoint
+    /// has to be disabled while the instruction executes, otherwise the core halts on it again.
+    ///
+    /// Returns `true` if a step was performed.
+    fn step_over_watchpoint(&mut self) -> Result<bool, Error> {
+        if self.state.pc_written {
+            return Ok(false);
+        }
+
+        let debug_cause = self.interface.read_register::<DebugCause>()?;
+        if !debug_cause.dbreak_exception() {
+            return Ok(false);
+        }
+
+        let Some(&dbreakc) = Self::DBREAKC_REGS.get(debug_cause.dbreak_num() as usize) else {
+            return Ok(false);
+        };
+
+        let config = self.interface.read_register_untyped(dbreakc)?;
+        self.interface.write_register_untyped(dbreakc, 0)?;
+        self.interface.step()?;
+        self.interface.write_register_untyped(dbreakc, config)?;
+
+        Ok(true)
+    }
+
+    /// Reads the watchpoint configured in data breakpoint unit `unit_index`.
+    fn read_watchpoint(&mut self, unit_index: usize) -> Result<Option<Watchpoint>, Error> {
+        let config = DBreakC(
+            self.interface
+                .read_register_untyped(Self::DBREAKC_REGS[unit_index])?,
+        );
+
+        let kind = match (config.load_break(), config.store_break()) {
+            (true, true) => WatchpointKind::Access,
+            (true, false) => WatchpointKind::Read,
+            (false, true) => WatchpointKind::Write,
+            (false, false) => return Ok(None),
+        };
+
+        let address = self
+            .interface
+            .read_register_untyped(Self::DBREAKA_REGS[unit_index])?;
+
+        Ok(Some(Watchpoint {
+            address: address as u64,
+            length: ((!config.mask() & 0x3F) + 1) as u64,
+            kind,
+        }))
+    }
+
     /// Check if the current breakpoint is a semihosting call
     // OpenOCD implementation: https://github.com/espressif/openocd-esp32/blob/93dd01511fd13d4a9fb322cd9b600c337becef9e/src/target/espressif/esp_xtensa_semihosting.c#L42-L103
     fn check_for_semihosting(&mut self) -> Result<Option<SemihostingCommand>, Error> {
@@ -253,7 +313,14 @@ impl CoreInterface for Xtensa<'_> {
             let debug_cause = self.interface.read_register::<DebugCause>()?;
 
             let mut reason = debug_cause.halt_reason();
-            if reason == HaltReason::Breakpoint(BreakpointCause::Software) {
+            if reason == HaltReason::Watchpoint(WatchpointCause::Unknown) {
+                let unit_index = debug_cause.dbreak_num() as usize;
+                if unit_index < Self::DBREAKC_REGS.len() {
+                    if let Some(watchpoint) = self.read_watchpoint(unit_index)? {
+                        reason = HaltReason::Watchpoint(WatchpointCause::Hit(watchpoint));
+                    }
+                }
+            } else if reason == HaltReason::Breakpoint(BreakpointCause::Software) {
                 // The chip initiated this halt, therefore we need to update pc_written state
                 self.state.pc_written = false;
                 // Check if the breakpoint is a semihosting call
@@ -279,6 +346,7 @@ impl CoreInterface for Xtensa<'_> {
 
     fn run(&mut self) -> Result<(), Error> {
         self.skip_breakpoint_instruction()?;
+        self.step_over_watchpoint()?;
         if self.state.pc_written {
             self.interface.clear_register_cache();
         }
@@ -305,7 +373,9 @@ impl CoreInterface for Xtensa<'_> {
 
     fn step(&mut self) -> Result<CoreInformation, Error> {
         self.skip_breakpoint_instruction()?;
-        self.interface.step()?;
+        if !self.step_over_watchpoint()? {
+            self.interface.step()?;
+        }
         self.on_halted()?;
 
         self.core_info()
@@ -395,6 +465,58 @@ impl CoreInterface for Xtensa<'_> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(Self::DBREAKA_REGS.len() as u32)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        (0..Self::DBREAKA_REGS.len())
+            .map(|unit_index| self.read_watchpoint(unit_index))
+            .collect()
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        let address = valid_32bit_address(watchpoint.address)?;
+
+        if !watchpoint.is_naturally_aligned() || watchpoint.length > Self::MAX_WATCHPOINT_LENGTH {
+            return Err(Error::Other(format!(
+                "The watchpoint at {:#010x} with a length of {} bytes is not supported. The length must be a power of two up to {} bytes, and the address must be aligned to the length.",
+                address,
+                watchpoint.length,
+                Self::MAX_WATCHPOINT_LENGTH
+            )));
+        }
+
+        let mut config = DBreakC(0);
+        config.set_mask(0x3F ^ (watchpoint.length as u32 - 1));
+        config.set_load_break(watchpoint.kind.on_read());
+        config.set_store_break(watchpoint.kind.on_write());
+
+        self.interface
+            .write_register_untyped(Self::DBREAKC_REGS[unit_index], 0)?;
+        self.interface
+            .write_register_untyped(Self::DBREAKA_REGS[unit_index], address)?;
+        self.interface
+            .write_register_untyped(Self::DBREAKC_REGS[unit_index], config.0)?;
+
+        Ok(())
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        self.interface
+            .write_register_untyped(Self::DBREAKC_REGS[unit_index], 0)?;
+
+        Ok(())
+    }
+
+    fn max_hw_watchpoint_length(&self) -> u64 {
+        Self::MAX_WATCHPOINT_LENGTH
+    }
+
     fn registers(&self) -> &'static CoreRegisters {
         &XTENSA_CORE_REGSISTERS
     }
@@ -454,4 +576,8 @@ impl CoreInterface for Xtensa<'_> {
         self.interface.leave_debug_mode()?;
         Ok(())
     }
+
+    fn sample_program_counter(&mut self) -> Result<Option<u64>, Error> {
+        Ok(self.interface.sample_program_counter()?.map(u64::from))
+    }
 }
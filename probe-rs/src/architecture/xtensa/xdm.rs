@@ -14,6 +14,8 @@ use crate::{
 
 use super::communication_interface::XtensaError;
 
+// The PC of the running core, mapped to ERI address 0x10203C
+const NARADR_DEBUGPC: u8 = 0x0F;
 const NARADR_OCDID: u8 = 0x40;
 const NARADR_DCRSET: u8 = 0x43;
 const NARADR_DCRCLR: u8 = 0x42;
@@ -491,6 +493,11 @@ impl<'probe> Xdm<'probe> {
         self.execute()
     }
 
+    /// Reads the program counter without halting the core.
+    pub(super) fn debug_pc(&mut self) -> Result<u32, XtensaError> {
+        Ok(self.read_nexus_register::<DebugPc>()?.0)
+    }
+
     pub(super) fn status(&mut self) -> Result<DebugStatus, XtensaError> {
         self.read_nexus_register::<DebugStatus>()
     }
@@ -819,6 +826,23 @@ impl NexusRegister for DebugStatus {
     }
 }
 
+/// The program counter of the running core, sampled without halting it.
+#[derive(Copy, Clone, Debug)]
+struct DebugPc(u32);
+
+impl NexusRegister for DebugPc {
+    const ADDRESS: u8 = NARADR_DEBUGPC;
+    const NAME: &'static str = "DEBUGPC";
+
+    fn from_bits(bits: u32) -> Result<Self, XtensaError> {
+        Ok(Self(bits))
+    }
+
+    fn bits(&self) -> u32 {
+        self.0
+    }
+}
+
 /// Writes and executes DIR.
 #[derive(Copy, Clone, Debug)]
 struct OcdId(u32);
@@ -16,6 +16,15 @@ use probe_rs_target::{
 };
 use std::sync::Arc;
 
+#[cfg(feature = "debug")]
+use super::RamRegion;
+#[cfg(feature = "debug")]
+use crate::CoreDump;
+#[cfg(feature = "debug")]
+use probe_rs_target::{
+    ArmCoreAccessOptions, CoreAccessOptions, RiscvCoreAccessOptions, XtensaCoreAccessOptions,
+};
+
 /// This describes a complete target with a fixed chip model and variant.
 #[derive(Clone)]
 pub struct Target {
@@ -138,6 +147,65 @@ impl Target {
         }
     }
 
+    /// Create a generic target for the cores of core dumps, which are debugged without a chip
+    /// description. The memory map contains a RAM region for each memory range of the dumps.
+    ///
+    /// The cores of the dumps must have the same architecture.
+    #[cfg(feature = "debug")]
+    pub(crate) fn from_core_dumps(dumps: &[CoreDump]) -> Target {
+        let cores = dumps
+            .iter()
+            .enumerate()
+            .map(|(index, dump)| Core {
+                name: format!("core{index}"),
+                core_type: dump.core_type,
+                core_access_options: match dump.core_type.architecture() {
+                    Architecture::Arm => CoreAccessOptions::Arm(ArmCoreAccessOptions::default()),
+                    Architecture::Riscv => CoreAccessOptions::Riscv(RiscvCoreAccessOptions {
+                        hart_id: None,
+                        jtag_tap: None,
+                    }),
+                    Architecture::Xtensa => {
+                        CoreAccessOptions::Xtensa(XtensaCoreAccessOptions { jtag_tap: None })
+                    }
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let memory_map = dumps
+            .iter()
+            .zip(&cores)
+            .flat_map(|(dump, core)| {
+                dump.data.iter().map(|(range, _)| {
+                    MemoryRegion::Ram(RamRegion {
+                        name: Some(format!("{} dump", core.name)),
+                        range: range.clone(),
+                        cores: vec![core.name.clone()],
+                        access: None,
+                    })
+                })
+            })
+            .collect();
+
+        let debug_sequence = match dumps.first().map(|dump| dump.core_type.architecture()) {
+            Some(Architecture::Riscv) => DebugSequence::Riscv(DefaultRiscvSequence::create()),
+            Some(Architecture::Xtensa) => DebugSequence::Xtensa(DefaultXtensaSequence::create()),
+            Some(Architecture::Arm) | None => DebugSequence::Arm(DefaultArmSequence::create()),
+        };
+
+        Target {
+            name: "Core dump".to_string(),
+            cores,
+            flash_algorithms: Vec::new(),
+            memory_map,
+            source: TargetDescriptionSource::Generic,
+            debug_sequence,
+            rtt_scan_regions: ScanRegion::Ram,
+            jtag: None,
+            default_format: None,
+        }
+    }
+
     /// Get the architecture of the target
     pub fn architecture(&self) -> Architecture {
         let target_arch = self.cores[0].core_type.architecture();
@@ -98,6 +98,46 @@ pub trait CoreInterface: MemoryInterface + CoreMemoryInterfaceShim {
     /// Clears the breakpoint configured in unit `unit_index`.
     fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), Error>;
 
+    /// Returns all the available watchpoint units of the core.
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    /// Read the hardware watchpoints from the watchpoint units, and adds them to the Result Vector.
+    /// A value of None in any position of the Vector indicates that the position is unset/available.
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        Ok(vec![])
+    }
+
+    /// Sets a watchpoint described by `watchpoint`. It does so by using unit `unit_index`.
+    fn set_hw_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        Err(Error::NotImplemented("hardware watchpoints"))
+    }
+
+    /// Clears the watchpoint configured in unit `unit_index`.
+    fn clear_hw_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(Error::NotImplemented("hardware watchpoints"))
+    }
+
+    /// Returns the length of the longest naturally aligned range a single watchpoint unit can watch.
+    ///
+    /// [`Core::set_hw_watchpoint`] uses several units for longer watchpoints.
+    fn max_hw_watchpoint_length(&self) -> u64 {
+        u64::MAX
+    }
+
+    /// Returns `true` if hardware breakpoints and watchpoints are allocated from the
+    /// same set of units, e.g. the trigger module on RISC-V.
+    ///
+    /// In that case a unit index refers to the same unit for both breakpoints and watchpoints.
+    fn hw_watchpoints_share_breakpoint_units(&self) -> bool {
+        false
+    }
+
     /// Returns a list of all the registers of this core.
     fn registers(&self) -> &'static registers::CoreRegisters;
 
@@ -173,6 +213,18 @@ pub trait CoreInterface: MemoryInterface + CoreMemoryInterfaceShim {
         Err(Error::NotImplemented("vector catch"))
     }
 
+    /// Samples the program counter without halting the core.
+    ///
+    /// Returns `Ok(None)` if no sample is available, e.g. because the core is halted.
+    fn sample_program_counter(&mut self) -> Result<Option<u64>, Error> {
+        Err(Error::NotImplemented("non-halting PC sampling"))
+    }
+
+    /// Reads the SWO data the probe received since the last read, without waiting for more.
+    fn read_swo(&mut self) -> Result<Vec<u8>, Error> {
+        Err(Error::NotImplemented("SWO"))
+    }
+
     /// Check if the integer size is 64-bit
     fn is_64_bit(&self) -> bool {
         false
@@ -434,9 +486,15 @@ impl<'probe> Core<'probe> {
 
     /// Find the index of the next available HW breakpoint comparator.
     fn find_free_breakpoint_comparator_index(&mut self) -> Result<usize, Error> {
+        let watchpoints = self.shared_watchpoint_units()?;
+
         let mut next_available_hw_breakpoint = 0;
         for breakpoint in self.inner.hw_breakpoints()? {
-            if breakpoint.is_none() {
+            let used_by_watchpoint = watchpoints
+                .get(next_available_hw_breakpoint)
+                .is_some_and(|wp| wp.is_some());
+
+            if breakpoint.is_none() && !used_by_watchpoint {
                 return Ok(next_available_hw_breakpoint);
             } else {
                 next_available_hw_breakpoint += 1;
@@ -447,6 +505,16 @@ impl<'probe> Core<'probe> {
         ))
     }
 
+    /// Returns the configured watchpoints if they are allocated from the same units as breakpoints,
+    /// or an empty list otherwise.
+    fn shared_watchpoint_units(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        if self.inner.hw_watchpoints_share_breakpoint_units() {
+            self.inner.hw_watchpoints()
+        } else {
+            Ok(vec![])
+        }
+    }
+
     /// Set a hardware breakpoint
     ///
     /// This function will try to set a hardware breakpoint att `address`.
@@ -524,6 +592,144 @@ impl<'probe> Core<'probe> {
         Ok(())
     }
 
+    /// Returns all the available watchpoint units of the core.
+    pub fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        self.inner.available_watchpoint_units()
+    }
+
+    /// Returns all the watchpoints configured on the core.
+    ///
+    /// A value of None in any position of the Vector indicates that the unit is unset/available.
+    pub fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        self.inner.hw_watchpoints()
+    }
+
+    /// Find the index of the next available HW watchpoint unit.
+    fn find_free_watchpoint_unit_index(&mut self) -> Result<usize, Error> {
+        let breakpoints = if self.inner.hw_watchpoints_share_breakpoint_units() {
+            self.inner.hw_breakpoints()?
+        } else {
+            vec![]
+        };
+
+        self.inner
+            .hw_watchpoints()?
+            .iter()
+            .enumerate()
+            .position(|(index, watchpoint)| {
+                watchpoint.is_none() && breakpoints.get(index).map_or(true, |bp| bp.is_none())
+            })
+            .ok_or_else(|| Error::Other("No available hardware watchpoints".to_string()))
+    }
+
+    /// Set a hardware watchpoint
+    ///
+    /// This function will try to set a hardware watchpoint on the memory described by `watchpoint`.
+    ///
+    /// The amount of hardware watchpoints which are supported is chip specific,
+    /// and can be queried using the `available_watchpoint_units` function. Most architectures
+    /// can only watch naturally aligned ranges with a power of two length, see
+    /// [`Watchpoint::is_naturally_aligned`]. If such a range is longer than a single
+    /// watchpoint unit can watch, it is split across several units.
+    #[tracing::instrument(skip(self))]
+    pub fn set_hw_watchpoint(&mut self, watchpoint: Watchpoint) -> Result<(), Error> {
+        let parts = self.watchpoint_unit_parts(watchpoint);
+
+        for (index, part) in parts.iter().enumerate() {
+            if let Err(error) = self.set_hw_watchpoint_unit(*part) {
+                // Don't leave a partial watchpoint behind.
+                for part in &parts[..index] {
+                    if let Err(error) = self.clear_hw_watchpoint_unit(*part) {
+                        tracing::warn!("Failed to clear watchpoint {part:?}: {error}");
+                    }
+                }
+
+                return Err(error);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the parts of `watchpoint` which are set on separate watchpoint units.
+    fn watchpoint_unit_parts(&self, watchpoint: Watchpoint) -> Vec<Watchpoint> {
+        let max_length = self.inner.max_hw_watchpoint_length();
+
+        if watchpoint.is_naturally_aligned() && watchpoint.length > max_length {
+            watchpoint.naturally_aligned_parts_of_max_length(max_length)
+        } else {
+            vec![watchpoint]
+        }
+    }
+
+    fn set_hw_watchpoint_unit(&mut self, watchpoint: Watchpoint) -> Result<(), Error> {
+        // If the watchpoint is set already, return its unit index, else find the next free index.
+        let unit_index = match self
+            .inner
+            .hw_watchpoints()?
+            .iter()
+            .position(|&wp| wp == Some(watchpoint))
+        {
+            Some(unit_index) => unit_index,
+            None => self.find_free_watchpoint_unit_index()?,
+        };
+
+        tracing::debug!(
+            "Trying to set HW watchpoint #{} on {:#010x} ({} bytes, {:?})",
+            unit_index,
+            watchpoint.address,
+            watchpoint.length,
+            watchpoint.kind
+        );
+
+        self.inner.set_hw_watchpoint(unit_index, watchpoint)
+    }
+
+    /// Clear a hardware watchpoint
+    ///
+    /// This function will try to clear the hardware watchpoint matching `watchpoint`, if it exists.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_hw_watchpoint(&mut self, watchpoint: Watchpoint) -> Result<(), Error> {
+        for part in self.watchpoint_unit_parts(watchpoint) {
+            self.clear_hw_watchpoint_unit(part)?;
+        }
+
+        Ok(())
+    }
+
+    fn clear_hw_watchpoint_unit(&mut self, watchpoint: Watchpoint) -> Result<(), Error> {
+        let unit_index = self
+            .inner
+            .hw_watchpoints()?
+            .iter()
+            .position(|&wp| wp == Some(watchpoint));
+
+        match unit_index {
+            Some(unit_index) => self.inner.clear_hw_watchpoint(unit_index),
+            None => Err(Error::Other(format!(
+                "No watchpoint found at address {:#010x}",
+                watchpoint.address
+            ))),
+        }
+    }
+
+    /// Clear all hardware watchpoints
+    ///
+    /// This function will clear all HW watchpoints which are configured on the target,
+    /// regardless if they are set by probe-rs.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_all_hw_watchpoints(&mut self) -> Result<(), Error> {
+        let watchpoints = self.inner.hw_watchpoints()?;
+        for (unit_index, _) in watchpoints
+            .iter()
+            .enumerate()
+            .filter(|(_, wp)| wp.is_some())
+        {
+            self.inner.clear_hw_watchpoint(unit_index)?;
+        }
+        Ok(())
+    }
+
     /// Returns the architecture of the core.
     pub fn architecture(&self) -> Architecture {
         self.inner.architecture()
@@ -576,6 +782,25 @@ impl<'probe> Core<'probe> {
         self.inner.disable_vector_catch(condition)
     }
 
+    /// Samples the program counter without halting the core, e.g. with the PC Sample Register of
+    /// the DWT on Cortex-M cores.
+    ///
+    /// This is much less intrusive than halting the core to read the program counter, but it is
+    /// not available on every core. Returns `Ok(None)` if no sample is available, e.g. because the
+    /// core is halted.
+    pub fn sample_program_counter(&mut self) -> Result<Option<u64>, Error> {
+        self.inner.sample_program_counter()
+    }
+
+    /// Reads the SWO data the probe received since the last read, without waiting for more.
+    ///
+    /// Unlike [`Session::read_trace_data`](crate::Session::read_trace_data), this can be used
+    /// while the core is borrowed, e.g. to receive ITM output while polling RTT. The trace must
+    /// have been configured with [`Session::setup_tracing`](crate::Session::setup_tracing).
+    pub fn read_swo(&mut self) -> Result<Vec<u8>, Error> {
+        self.inner.read_swo()
+    }
+
     /// Check if the integer size is 64-bit
     pub fn is_64_bit(&self) -> bool {
         self.inner.is_64_bit()
@@ -650,6 +875,30 @@ impl CoreInterface for Core<'_> {
         self.clear_all_hw_breakpoints()
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        self.available_watchpoint_units()
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        self.hw_watchpoints()
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        watchpoint: Watchpoint,
+    ) -> Result<(), Error> {
+        self.inner.set_hw_watchpoint(unit_index, watchpoint)
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        self.inner.clear_hw_watchpoint(unit_index)
+    }
+
+    fn hw_watchpoints_share_breakpoint_units(&self) -> bool {
+        self.inner.hw_watchpoints_share_breakpoint_units()
+    }
+
     fn registers(&self) -> &'static registers::CoreRegisters {
         self.registers()
     }
@@ -706,6 +955,14 @@ impl CoreInterface for Core<'_> {
         self.debug_core_stop()
     }
 
+    fn sample_program_counter(&mut self) -> Result<Option<u64>, Error> {
+        self.sample_program_counter()
+    }
+
+    fn read_swo(&mut self) -> Result<Vec<u8>, Error> {
+        self.read_swo()
+    }
+
     fn is_64_bit(&self) -> bool {
         self.is_64_bit()
     }
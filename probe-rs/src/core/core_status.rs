@@ -44,6 +44,15 @@ pub enum BreakpointCause {
     Semihosting(SemihostingCommand),
 }
 
+/// When the core halts due to a watchpoint, some architectures will allow us to determine which watchpoint was hit.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum WatchpointCause {
+    /// The given watchpoint was hit.
+    Hit(Watchpoint),
+    /// We were not able to determine which watchpoint was hit.
+    Unknown,
+}
+
 /// The reason why a core was halted.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum HaltReason {
@@ -58,8 +67,8 @@ pub enum HaltReason {
     /// Core halted due to an exception, e.g. an
     /// an interrupt.
     Exception,
-    /// Core halted due to a data watchpoint
-    Watchpoint,
+    /// Core halted due to a data watchpoint. The cause is `Unknown` if we cannot determine which watchpoint was hit.
+    Watchpoint(WatchpointCause),
     /// Core halted after single step
     Step,
     /// Core halted because of a debugger request
@@ -83,6 +92,149 @@ pub enum VectorCatchCondition {
     CoreReset,
     /// We encountered a SecureFault.
     SecureFault,
+    /// We encountered a BusFault.
+    BusFault,
+    /// We encountered a MemManage fault.
+    MemoryManagementFault,
+    /// We encountered a UsageFault, including state, checking and coprocessor errors.
+    UsageFault,
+    /// We encountered any fault, or on RISC-V any synchronous exception.
+    Exception,
     /// We encountered any exception.
     All,
 }
+
+/// The kind of data access that triggers a hardware watchpoint.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum WatchpointKind {
+    /// Halt when the watched memory is read.
+    Read,
+    /// Halt when the watched memory is written.
+    Write,
+    /// Halt when the watched memory is read or written.
+    Access,
+}
+
+impl WatchpointKind {
+    /// Returns `true` if read accesses trigger the watchpoint.
+    pub fn on_read(&self) -> bool {
+        matches!(self, WatchpointKind::Read | WatchpointKind::Access)
+    }
+
+    /// Returns `true` if write accesses trigger the watchpoint.
+    pub fn on_write(&self) -> bool {
+        matches!(self, WatchpointKind::Write | WatchpointKind::Access)
+    }
+}
+
+/// A hardware watchpoint, also known as a data breakpoint.
+///
+/// A watchpoint halts the core when the memory range `address..address + length`
+/// is accessed in the way described by `kind`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct Watchpoint {
+    /// The start address of the watched memory range.
+    pub address: u64,
+    /// The length of the watched memory range, in bytes.
+    pub length: u64,
+    /// The kind of access that triggers the watchpoint.
+    pub kind: WatchpointKind,
+}
+
+impl Watchpoint {
+    /// Returns `true` if the watched range is a naturally aligned power of two,
+    /// which is the only kind of range most watchpoint comparators can match.
+    pub fn is_naturally_aligned(&self) -> bool {
+        self.length.is_power_of_two() && self.address % self.length == 0
+    }
+
+    /// Splits the watched range into the smallest number of naturally aligned watchpoints
+    /// which exactly cover it.
+    ///
+    /// This allows watching ranges which are not naturally aligned, at the cost of using
+    /// multiple watchpoint units.
+    pub fn naturally_aligned_parts(&self) -> Vec<Watchpoint> {
+        self.naturally_aligned_parts_of_max_length(u64::MAX)
+    }
+
+    /// Splits the watched range like [`Watchpoint::naturally_aligned_parts`], into parts
+    /// which are at most `max_length` bytes long.
+    ///
+    /// `max_length` must be a power of two.
+    pub fn naturally_aligned_parts_of_max_length(&self, max_length: u64) -> Vec<Watchpoint> {
+        let mut parts = vec![];
+
+        let mut address = self.address;
+        let end = self.address.saturating_add(self.length);
+        while address < end {
+            // The largest power of two the address is aligned to, limited by the remaining length.
+            let alignment = 1u64
+                .checked_shl(address.trailing_zeros())
+                .unwrap_or(u64::MAX);
+            let remaining = end - address;
+            let length = alignment.min(1 << remaining.ilog2()).min(max_length);
+
+            parts.push(Watchpoint {
+                address,
+                length,
+                kind: self.kind,
+            });
+            address += length;
+        }
+
+        parts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Watchpoint, WatchpointKind};
+
+    fn watchpoint(address: u64, length: u64) -> Watchpoint {
+        Watchpoint {
+            address,
+            length,
+            kind: WatchpointKind::Write,
+        }
+    }
+
+    #[test]
+    fn aligned_watchpoint_is_not_split() {
+        let wp = watchpoint(0x2000_0010, 8);
+        assert!(wp.is_naturally_aligned());
+        assert_eq!(wp.naturally_aligned_parts(), vec![wp]);
+    }
+
+    #[test]
+    fn long_watchpoint_is_split_into_parts_of_max_length() {
+        let wp = watchpoint(0x2000_0010, 8);
+        assert_eq!(
+            wp.naturally_aligned_parts_of_max_length(4),
+            vec![watchpoint(0x2000_0010, 4), watchpoint(0x2000_0014, 4)]
+        );
+
+        let wp = watchpoint(0x2000_0002, 8);
+        assert_eq!(
+            wp.naturally_aligned_parts_of_max_length(4),
+            vec![
+                watchpoint(0x2000_0002, 2),
+                watchpoint(0x2000_0004, 4),
+                watchpoint(0x2000_0008, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn unaligned_watchpoint_is_split() {
+        let wp = watchpoint(0x2000_0003, 6);
+        assert!(!wp.is_naturally_aligned());
+        assert_eq!(
+            wp.naturally_aligned_parts(),
+            vec![
+                watchpoint(0x2000_0003, 1),
+                watchpoint(0x2000_0004, 4),
+                watchpoint(0x2000_0008, 1),
+            ]
+        );
+    }
+}
@@ -11,8 +11,10 @@ use crate::{
         riscv::registers::RISCV_CORE_REGSISTERS,
         xtensa::registers::XTENSA_CORE_REGSISTERS,
     },
+    core::{CoreInformation, CoreInterface, CoreRegister, CoreRegisters, RegisterRole},
     debug::{DebugRegister, DebugRegisters},
-    Core, CoreType, Error, InstructionSet, MemoryInterface,
+    memory::CoreMemoryInterface,
+    Architecture, Core, CoreStatus, CoreType, Error, HaltReason, InstructionSet, MemoryInterface,
 };
 use crate::{RegisterId, RegisterValue};
 use probe_rs_target::MemoryRange;
@@ -23,10 +25,13 @@ use std::{
     fs::OpenOptions,
     ops::Range,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use super::RegisterDataType;
 
+mod elf;
+
 /// A snapshot representation of a core state.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CoreDump {
@@ -97,22 +102,37 @@ impl CoreDump {
         Ok(())
     }
 
+    /// Store the dumped core to an ELF core file, see [`CoreDump::to_elf`].
+    pub fn store_elf(&self, path: &Path) -> Result<(), CoreDumpError> {
+        let elf = self.to_elf()?;
+        std::fs::write(path, elf).map_err(|e| {
+            CoreDumpError::CoreDumpFileWrite(e, dunce::canonicalize(path).unwrap_or_default())
+        })
+    }
+
     /// Load the dumped core from a file.
+    ///
+    /// Both files written by [`CoreDump::store`] and ELF core files are supported.
     pub fn load(path: &Path) -> Result<Self, CoreDumpError> {
-        let file = OpenOptions::new().read(true).open(path).map_err(|e| {
+        let data = std::fs::read(path).map_err(|e| {
             CoreDumpError::CoreDumpFileRead(e, dunce::canonicalize(path).unwrap_or_default())
         })?;
-        rmp_serde::from_read(&file).map_err(CoreDumpError::DecodingCoreDump)
+        Self::load_raw(&data)
     }
 
-    /// Load the dumped core from a file.
+    /// Load the dumped core from the contents of a file.
+    ///
+    /// Both files written by [`CoreDump::store`] and ELF core files are supported.
     pub fn load_raw(data: &[u8]) -> Result<Self, CoreDumpError> {
+        if elf::is_elf(data) {
+            return Self::from_elf(data);
+        }
         rmp_serde::from_slice(data).map_err(CoreDumpError::DecodingCoreDump)
     }
 
-    /// Read all registers defined in [`crate::core::CoreRegisters`] from the given core.
-    pub fn debug_registers(&self) -> DebugRegisters {
-        let reg_list = match self.core_type {
+    /// The register file of the dumped core.
+    pub fn core_registers(&self) -> &'static CoreRegisters {
+        match self.core_type {
             CoreType::Armv6m => &CORTEX_M_CORE_REGISTERS,
             CoreType::Armv7a => match self.floating_point_register_count {
                 Some(16) => &AARCH32_WITH_FP_16_CORE_REGSISTERS,
@@ -145,10 +165,13 @@ impl CoreDump {
             }
             CoreType::Riscv => &RISCV_CORE_REGSISTERS,
             CoreType::Xtensa => &XTENSA_CORE_REGSISTERS,
-        };
+        }
+    }
 
+    /// Read all registers defined in [`crate::core::CoreRegisters`] from the given core.
+    pub fn debug_registers(&self) -> DebugRegisters {
         let mut debug_registers = Vec::<DebugRegister>::new();
-        for (dwarf_id, core_register) in reg_list.core_registers().enumerate() {
+        for (dwarf_id, core_register) in self.core_registers().core_registers().enumerate() {
             // Check to ensure the register type is compatible with u64.
             if matches!(core_register.data_type(), RegisterDataType::UnsignedInteger(size_in_bits) if size_in_bits <= 64)
             {
@@ -225,7 +248,9 @@ impl CoreDump {
 
 impl MemoryInterface for CoreDump {
     fn supports_native_64bit_access(&mut self) -> bool {
-        self.supports_native_64bit_access
+        // The dumped memory can be read with any access size. This also ensures that `read`
+        // doesn't round reads up to 32 bit words, which could exceed the dumped ranges.
+        true
     }
 
     fn read_word_64(&mut self, address: u64) -> Result<u64, crate::Error> {
@@ -273,43 +298,211 @@ impl MemoryInterface for CoreDump {
     }
 
     fn write_word_64(&mut self, _address: u64, _data: u64) -> Result<(), crate::Error> {
-        todo!()
+        Err(crate::Error::CoreDumpReadOnly("Writing memory"))
     }
 
     fn write_word_32(&mut self, _address: u64, _data: u32) -> Result<(), crate::Error> {
-        todo!()
+        Err(crate::Error::CoreDumpReadOnly("Writing memory"))
     }
 
     fn write_word_16(&mut self, _address: u64, _data: u16) -> Result<(), crate::Error> {
-        todo!()
+        Err(crate::Error::CoreDumpReadOnly("Writing memory"))
     }
 
     fn write_word_8(&mut self, _address: u64, _data: u8) -> Result<(), crate::Error> {
-        todo!()
+        Err(crate::Error::CoreDumpReadOnly("Writing memory"))
     }
 
     fn write_64(&mut self, _address: u64, _data: &[u64]) -> Result<(), crate::Error> {
-        todo!()
+        Err(crate::Error::CoreDumpReadOnly("Writing memory"))
     }
 
     fn write_32(&mut self, _address: u64, _data: &[u32]) -> Result<(), crate::Error> {
-        todo!()
+        Err(crate::Error::CoreDumpReadOnly("Writing memory"))
     }
 
     fn write_16(&mut self, _address: u64, _data: &[u16]) -> Result<(), crate::Error> {
-        todo!()
+        Err(crate::Error::CoreDumpReadOnly("Writing memory"))
     }
 
     fn write_8(&mut self, _address: u64, _data: &[u8]) -> Result<(), crate::Error> {
-        todo!()
+        Err(crate::Error::CoreDumpReadOnly("Writing memory"))
     }
 
     fn supports_8bit_transfers(&self) -> Result<bool, crate::Error> {
-        todo!()
+        Ok(true)
     }
 
     fn flush(&mut self) -> Result<(), crate::Error> {
-        todo!()
+        Ok(())
+    }
+}
+
+/// A read-only [`CoreInterface`] for the state of a core in a [`CoreDump`], which allows to debug
+/// the core offline, e.g. to show backtraces, variables and memory without a probe.
+///
+/// The core is always halted. Operations which change the state of the core, like running it,
+/// writing memory or setting breakpoints, return [`Error::CoreDumpReadOnly`].
+pub(crate) struct DumpedCore<'a> {
+    dump: &'a mut CoreDump,
+}
+
+impl<'a> DumpedCore<'a> {
+    pub(crate) fn new(dump: &'a mut CoreDump) -> Self {
+        Self { dump }
+    }
+
+    /// The register of the dumped core with the given role.
+    fn register_with_role(&self, role: RegisterRole) -> &'static CoreRegister {
+        self.dump
+            .core_registers()
+            .all_registers()
+            .find(|register| register.register_has_role(role))
+            .unwrap_or_else(|| {
+                panic!(
+                    "The {role} register is missing for {:?}. This is a bug, please report it.",
+                    self.dump.core_type
+                )
+            })
+    }
+}
+
+impl CoreMemoryInterface for DumpedCore<'_> {
+    type ErrorType = Error;
+
+    fn memory(&self) -> &dyn MemoryInterface<Self::ErrorType> {
+        self.dump
+    }
+
+    fn memory_mut(&mut self) -> &mut dyn MemoryInterface<Self::ErrorType> {
+        self.dump
+    }
+}
+
+impl CoreInterface for DumpedCore<'_> {
+    fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        Ok(CoreStatus::Halted(HaltReason::Unknown))
+    }
+
+    fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        let pc = self.read_core_reg(self.program_counter().id())?;
+        Ok(CoreInformation { pc: pc.try_into()? })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        Err(Error::CoreDumpReadOnly("Running the core"))
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        Err(Error::CoreDumpReadOnly("Resetting the core"))
+    }
+
+    fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        Err(Error::CoreDumpReadOnly("Resetting the core"))
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        Err(Error::CoreDumpReadOnly("Stepping the core"))
+    }
+
+    fn read_core_reg(&mut self, address: RegisterId) -> Result<RegisterValue, Error> {
+        self.dump.registers.get(&address).copied().ok_or_else(|| {
+            Error::Register(format!(
+                "The core dump does not contain the register {address:?}"
+            ))
+        })
+    }
+
+    fn write_core_reg(&mut self, _address: RegisterId, _value: RegisterValue) -> Result<(), Error> {
+        Err(Error::CoreDumpReadOnly("Writing registers"))
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn enable_breakpoints(&mut self, _state: bool) -> Result<(), Error> {
+        Err(Error::CoreDumpReadOnly("Setting breakpoints"))
+    }
+
+    fn set_hw_breakpoint(&mut self, _unit_index: usize, _addr: u64) -> Result<(), Error> {
+        Err(Error::CoreDumpReadOnly("Setting breakpoints"))
+    }
+
+    fn clear_hw_breakpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(Error::CoreDumpReadOnly("Clearing breakpoints"))
+    }
+
+    fn registers(&self) -> &'static CoreRegisters {
+        self.dump.core_registers()
+    }
+
+    fn program_counter(&self) -> &'static CoreRegister {
+        self.register_with_role(RegisterRole::ProgramCounter)
+    }
+
+    fn frame_pointer(&self) -> &'static CoreRegister {
+        self.register_with_role(RegisterRole::FramePointer)
+    }
+
+    fn stack_pointer(&self) -> &'static CoreRegister {
+        self.register_with_role(RegisterRole::StackPointer)
+    }
+
+    fn return_address(&self) -> &'static CoreRegister {
+        self.register_with_role(RegisterRole::ReturnAddress)
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        false
+    }
+
+    fn architecture(&self) -> Architecture {
+        self.dump.core_type.architecture()
+    }
+
+    fn core_type(&self) -> CoreType {
+        self.dump.core_type
+    }
+
+    fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        Ok(self.dump.instruction_set)
+    }
+
+    fn fpu_support(&mut self) -> Result<bool, Error> {
+        Ok(self.dump.fpu_support)
+    }
+
+    fn floating_point_register_count(&mut self) -> Result<usize, Error> {
+        Ok(self.dump.floating_point_register_count.unwrap_or(0))
+    }
+
+    fn reset_catch_set(&mut self) -> Result<(), Error> {
+        Err(Error::CoreDumpReadOnly("Resetting the core"))
+    }
+
+    fn reset_catch_clear(&mut self) -> Result<(), Error> {
+        Err(Error::CoreDumpReadOnly("Resetting the core"))
+    }
+
+    fn debug_core_stop(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn is_64_bit(&self) -> bool {
+        self.dump.instruction_set == InstructionSet::A64
     }
 }
 
@@ -328,4 +521,113 @@ pub enum CoreDumpError {
     /// Decoding the coredump MessagePack failed.
     #[error("Decoding the coredump MessagePack failed.")]
     DecodingCoreDump(rmp_serde::decode::Error),
+    /// Decoding the ELF core file failed.
+    #[error("Decoding the ELF core file failed.")]
+    DecodingElfCoreDump(#[source] object::read::Error),
+    /// The ELF file is not a core file.
+    #[error("The ELF file is not a core file.")]
+    NotAnElfCoreFile,
+    /// The ELF core file contains a segment outside of the file.
+    #[error("The ELF core file contains a segment outside of the file.")]
+    InvalidElfSegment,
+    /// The ELF core file contains a segment which is mostly not stored in the file.
+    #[error("The ELF core file contains a segment of {0} bytes, which are mostly not stored in the file.")]
+    ElfSegmentTooLarge(u64),
+    /// ELF core files of this machine are not supported.
+    #[error("ELF core files of machine {0} are not supported.")]
+    UnsupportedElfMachine(u16),
+    /// The register does not fit the register layout of the ELF core file.
+    #[error("Register {0:?} does not fit the register layout of the ELF core file.")]
+    UnsupportedElfRegister(RegisterId),
+    /// The ELF core file does not contain the registers of the core.
+    #[error("The ELF core file does not contain the registers of the core in a NT_PRSTATUS note.")]
+    MissingElfCoreRegisters,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        debug::{exception_handling::exception_handler_for_core, DebugInfo, DebugRegisters},
+        CoreDump, CoreStatus, Error, MemoryInterface, RegisterValue, Session,
+    };
+    use std::path::PathBuf;
+    use test_case::test_case;
+
+    fn test_file(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/debug-unwind-tests")
+            .join(name)
+    }
+
+    #[test_case("RP2040_full_unwind"; "Armv6-m")]
+    #[test_case("esp32c3_full_unwind"; "RISC-V")]
+    fn debug_core_dump_session(name: &str) {
+        let dump = CoreDump::load(&test_file(&format!("{name}.coredump"))).unwrap();
+        let debug_info = DebugInfo::from_file(test_file(&format!("{name}.elf"))).unwrap();
+
+        let mut expected_dump = dump.clone();
+        let expected_frames = debug_info
+            .unwind(
+                &mut expected_dump,
+                dump.debug_registers(),
+                exception_handler_for_core(dump.core_type()).as_ref(),
+                Some(dump.instruction_set()),
+            )
+            .unwrap();
+
+        let mut session = Session::from_core_dumps(vec![dump.clone()], None).unwrap();
+        let mut core = session.core(0).unwrap();
+
+        assert!(matches!(core.status(), Ok(CoreStatus::Halted(_))));
+        let pc = core.program_counter().id();
+        assert_eq!(
+            core.read_core_reg::<RegisterValue>(pc).unwrap(),
+            dump.registers[&pc]
+        );
+
+        let (range, data) = &dump.data[0];
+        let mut memory = vec![0; data.len()];
+        core.read_8(range.start, &mut memory).unwrap();
+        assert_eq!(&memory, data);
+
+        // The state of the dumped core can't be changed.
+        assert!(matches!(core.run(), Err(Error::CoreDumpReadOnly(_))));
+        assert!(matches!(
+            core.write_word_32(range.start, 0),
+            Err(Error::CoreDumpReadOnly(_))
+        ));
+        assert!(matches!(
+            core.set_hw_breakpoint(range.start),
+            Err(Error::CoreDumpReadOnly(_))
+        ));
+
+        let registers = DebugRegisters::from_core(&mut core);
+        let frames = debug_info
+            .unwind(
+                &mut core,
+                registers,
+                exception_handler_for_core(dump.core_type()).as_ref(),
+                Some(dump.instruction_set()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| (frame.function_name.as_str(), frame.pc))
+                .collect::<Vec<_>>(),
+            expected_frames
+                .iter()
+                .map(|frame| (frame.function_name.as_str(), frame.pc))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn core_dump_target_mismatch() {
+        let dump = CoreDump::load(&test_file("esp32c3_full_unwind.coredump")).unwrap();
+        let target = crate::config::get_target_by_name("nRF52833_xxAA").unwrap();
+
+        assert!(Session::from_core_dumps(vec![dump], Some(target)).is_err());
+    }
 }
@@ -0,0 +1,607 @@
+//! Conversion of [`CoreDump`]s to and from ELF core files (`ET_CORE`).
+//!
+//! The memory of the dump is stored in `PT_LOAD` segments, and the core registers in a
+//! `NT_PRSTATUS` note with the register layout that GDB and LLDB expect for the architecture.
+//!
+//! Because the `NT_PRSTATUS` note only contains the general purpose registers, the full
+//! [`CoreDump`] without the memory is additionally stored in a `probe-rs` note. When an ELF core
+//! file without this note is loaded, e.g. a core file created by another tool, the registers and
+//! the type of the core are derived from the `NT_PRSTATUS` note and the machine of the file.
+
+use std::collections::HashMap;
+
+use object::{
+    elf,
+    read::elf::{FileHeader, ProgramHeader},
+    Endianness, FileKind,
+};
+
+use super::{CoreDump, CoreDumpError};
+use crate::{CoreType, InstructionSet, RegisterId, RegisterValue};
+
+/// The name of the note which contains the serialized [`CoreDump`], without the memory.
+const PROBE_RS_NOTE_NAME: &[u8] = b"probe-rs";
+/// The type of the note which contains the serialized [`CoreDump`].
+///
+/// GDB interprets notes with unknown names like Linux notes, so a type which is not used by Linux
+/// is required.
+const NT_PROBE_RS_CORE_DUMP: u32 = u32::from_be_bytes(*b"PRCD");
+/// The name of the `NT_PRSTATUS` note.
+const CORE_NOTE_NAME: &[u8] = b"CORE";
+/// The signal which is reported as the reason the core stopped, i.e. `SIGTRAP`.
+const SIGTRAP: u16 = 5;
+/// The most memory of a segment which is not stored in the file, and filled with zeros when the
+/// file is loaded.
+const MAX_ZERO_FILLED_SIZE: u64 = 16 * 1024 * 1024;
+
+/// The layout of the registers in the `NT_PRSTATUS` note of an architecture.
+struct PrStatusLayout {
+    /// The ELF machine (`EM_*`).
+    machine: u16,
+    /// Whether the file uses the 64 bit ELF class.
+    is_64_bit: bool,
+    /// The registers in `pr_reg`, in order. Slots without a matching probe-rs register are
+    /// written as zero.
+    registers: Vec<Option<RegisterId>>,
+}
+
+impl PrStatusLayout {
+    /// The layout for ELF core files of the given core.
+    fn for_core(core_type: CoreType, instruction_set: InstructionSet) -> Self {
+        match core_type {
+            CoreType::Armv6m
+            | CoreType::Armv7m
+            | CoreType::Armv7em
+            | CoreType::Armv8m
+            | CoreType::Armv7a => Self::arm(),
+            CoreType::Armv8a if instruction_set == InstructionSet::A64 => Self::aarch64(),
+            CoreType::Armv8a => Self::arm(),
+            CoreType::Riscv => Self::riscv(),
+            CoreType::Xtensa => Self::xtensa(),
+        }
+    }
+
+    /// The layout for ELF core files of the given machine.
+    fn for_machine(machine: u16, is_64_bit: bool) -> Result<Self, CoreDumpError> {
+        let layout = match machine {
+            elf::EM_ARM => Self::arm(),
+            elf::EM_AARCH64 => Self::aarch64(),
+            elf::EM_RISCV => Self::riscv(),
+            elf::EM_XTENSA => Self::xtensa(),
+            _ => return Err(CoreDumpError::UnsupportedElfMachine(machine)),
+        };
+
+        if layout.is_64_bit != is_64_bit {
+            return Err(CoreDumpError::UnsupportedElfMachine(machine));
+        }
+
+        Ok(layout)
+    }
+
+    /// `r0`-`r15`, `cpsr` (or `xpsr` for Cortex-M) and `orig_r0`.
+    fn arm() -> Self {
+        let mut registers = (0..=16).map(|id| Some(RegisterId(id))).collect::<Vec<_>>();
+        registers.push(None);
+
+        Self {
+            machine: elf::EM_ARM,
+            is_64_bit: false,
+            registers,
+        }
+    }
+
+    /// `x0`-`x30`, `sp`, `pc` and `pstate`.
+    fn aarch64() -> Self {
+        Self {
+            machine: elf::EM_AARCH64,
+            is_64_bit: true,
+            registers: (0..=33).map(|id| Some(RegisterId(id))).collect(),
+        }
+    }
+
+    /// `pc` and `x1`-`x31`.
+    fn riscv() -> Self {
+        let mut registers = vec![Some(RegisterId(0x7b1))];
+        registers.extend((0x1001..=0x101F).map(|id| Some(RegisterId(id))));
+
+        Self {
+            machine: elf::EM_RISCV,
+            is_64_bit: false,
+            registers,
+        }
+    }
+
+    /// `pc`, `ps`, `lbeg`, `lend`, `lcount`, `sar`, `windowstart`, `windowbase`, 56 reserved
+    /// slots and `ar0`-`ar63`.
+    ///
+    /// probe-rs only knows the registers of the current window, so they are stored as `ar0`-`ar15`
+    /// with a `windowbase` of 0.
+    fn xtensa() -> Self {
+        let mut registers = vec![None; 128];
+        registers[0] = Some(RegisterId(0xFF00));
+        for (slot, id) in registers[64..80].iter_mut().zip(0..) {
+            *slot = Some(RegisterId(id));
+        }
+
+        Self {
+            machine: elf::EM_XTENSA,
+            is_64_bit: false,
+            registers,
+        }
+    }
+
+    /// The size of a register, and of a `long`, in bytes.
+    fn word_size(&self) -> usize {
+        if self.is_64_bit {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// The offset of `pr_reg` in the `elf_prstatus` structure.
+    fn registers_offset(&self) -> usize {
+        if self.is_64_bit {
+            112
+        } else {
+            72
+        }
+    }
+
+    /// The size of the `elf_prstatus` structure, including `pr_fpvalid` and padding.
+    fn prstatus_size(&self) -> usize {
+        let size = self.registers_offset() + self.registers.len() * self.word_size() + 4;
+        size.next_multiple_of(self.word_size())
+    }
+
+    /// The offset of `pr_pid` in the `elf_prstatus` structure.
+    fn pid_offset(&self) -> usize {
+        if self.is_64_bit {
+            32
+        } else {
+            24
+        }
+    }
+
+    /// The type of core and instruction set, for ELF core files without a `probe-rs` note.
+    fn core_type(&self) -> (CoreType, InstructionSet) {
+        match self.machine {
+            elf::EM_AARCH64 => (CoreType::Armv8a, InstructionSet::A64),
+            elf::EM_RISCV => (CoreType::Riscv, InstructionSet::RV32C),
+            elf::EM_XTENSA => (CoreType::Xtensa, InstructionSet::Xtensa),
+            // The profile of the core is not stored in the file, so assume a microcontroller.
+            _ => (CoreType::Armv7m, InstructionSet::Thumb2),
+        }
+    }
+}
+
+/// Writes the little endian fields of an ELF file of either class.
+struct ElfWriter {
+    is_64_bit: bool,
+    buffer: Vec<u8>,
+}
+
+impl ElfWriter {
+    fn u16(&mut self, value: u16) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes an address, offset or size, which depends on the class of the file.
+    fn word(&mut self, value: u64) {
+        if self.is_64_bit {
+            self.buffer.extend_from_slice(&value.to_le_bytes());
+        } else {
+            self.u32(value as u32);
+        }
+    }
+
+    fn pad_to(&mut self, alignment: usize) {
+        let len = self.buffer.len().next_multiple_of(alignment);
+        self.buffer.resize(len, 0);
+    }
+
+    fn note(&mut self, name: &[u8], note_type: u32, desc: &[u8]) {
+        // The name is NUL terminated.
+        self.u32(name.len() as u32 + 1);
+        self.u32(desc.len() as u32);
+        self.u32(note_type);
+        self.buffer.extend_from_slice(name);
+        self.buffer.push(0);
+        self.pad_to(4);
+        self.buffer.extend_from_slice(desc);
+        self.pad_to(4);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn program_header(
+        &mut self,
+        p_type: u32,
+        flags: u32,
+        offset: u64,
+        address: u64,
+        file_size: u64,
+        memory_size: u64,
+        alignment: u64,
+    ) {
+        self.u32(p_type);
+        if self.is_64_bit {
+            self.u32(flags);
+        }
+        self.word(offset);
+        self.word(address);
+        self.word(address);
+        self.word(file_size);
+        self.word(memory_size);
+        if !self.is_64_bit {
+            self.u32(flags);
+        }
+        self.word(alignment);
+    }
+}
+
+impl CoreDump {
+    /// Convert the dumped core to an ELF core file.
+    ///
+    /// The file can be loaded by probe-rs with [`CoreDump::load`], and by other tools like GDB
+    /// together with the ELF file of the firmware.
+    pub fn to_elf(&self) -> Result<Vec<u8>, CoreDumpError> {
+        let layout = PrStatusLayout::for_core(self.core_type, self.instruction_set);
+        let word_size = layout.word_size();
+
+        // The `elf_prstatus` structure, of which only the signal, the PID and the registers are used.
+        let mut prstatus = vec![0; layout.prstatus_size()];
+        prstatus[12..14].copy_from_slice(&SIGTRAP.to_le_bytes());
+        prstatus[layout.pid_offset()..][..4].copy_from_slice(&1u32.to_le_bytes());
+        for (index, register) in layout.registers.iter().enumerate() {
+            let Some(id) = *register else {
+                continue;
+            };
+            let Some(value) = self.registers.get(&id) else {
+                continue;
+            };
+            let value: u64 = match value {
+                RegisterValue::U32(value) => *value as u64,
+                RegisterValue::U64(value) if layout.is_64_bit => *value,
+                // 64 bit registers don't fit the layouts of the 32 bit ELF class, and there is no
+                // 64 bit layout for RISC-V yet.
+                _ => return Err(CoreDumpError::UnsupportedElfRegister(id)),
+            };
+            let offset = layout.registers_offset() + index * word_size;
+            prstatus[offset..][..word_size].copy_from_slice(&value.to_le_bytes()[..word_size]);
+        }
+
+        let registers_only = CoreDump {
+            data: Vec::new(),
+            ..self.clone()
+        };
+        let probe_rs_note =
+            rmp_serde::to_vec_named(&registers_only).map_err(CoreDumpError::EncodingCoreDump)?;
+
+        let mut notes = ElfWriter {
+            is_64_bit: layout.is_64_bit,
+            buffer: Vec::new(),
+        };
+        notes.note(CORE_NOTE_NAME, elf::NT_PRSTATUS, &prstatus);
+        notes.note(PROBE_RS_NOTE_NAME, NT_PROBE_RS_CORE_DUMP, &probe_rs_note);
+        let notes = notes.buffer;
+
+        let (header_size, program_header_size, section_header_size) = if layout.is_64_bit {
+            (64, 56, 64)
+        } else {
+            (52, 32, 40)
+        };
+        let program_header_count = 1 + self.data.len();
+        let notes_offset = header_size + program_header_count * program_header_size;
+
+        let mut file = ElfWriter {
+            is_64_bit: layout.is_64_bit,
+            buffer: Vec::new(),
+        };
+
+        // The ELF header.
+        file.buffer.extend_from_slice(&elf::ELFMAG);
+        file.buffer.push(if layout.is_64_bit {
+            elf::ELFCLASS64
+        } else {
+            elf::ELFCLASS32
+        });
+        file.buffer.push(elf::ELFDATA2LSB);
+        file.buffer.push(elf::EV_CURRENT);
+        file.buffer.push(elf::ELFOSABI_NONE);
+        file.pad_to(16);
+        file.u16(elf::ET_CORE);
+        file.u16(layout.machine);
+        file.u32(elf::EV_CURRENT as u32);
+        // The entry point and the offset of the section headers.
+        file.word(0);
+        file.word(header_size as u64);
+        file.word(0);
+        // The flags.
+        file.u32(0);
+        file.u16(header_size as u16);
+        file.u16(program_header_size as u16);
+        file.u16(program_header_count as u16);
+        file.u16(section_header_size);
+        file.u16(0);
+        file.u16(0);
+
+        // The program headers, the notes are followed by the memory of the dump.
+        file.program_header(
+            elf::PT_NOTE,
+            0,
+            notes_offset as u64,
+            0,
+            notes.len() as u64,
+            0,
+            4,
+        );
+        let mut offset = (notes_offset + notes.len()) as u64;
+        for (range, memory) in &self.data {
+            file.program_header(
+                elf::PT_LOAD,
+                elf::PF_R | elf::PF_W | elf::PF_X,
+                offset,
+                range.start,
+                memory.len() as u64,
+                memory.len() as u64,
+                1,
+            );
+            offset += memory.len() as u64;
+        }
+
+        file.buffer.extend_from_slice(&notes);
+        for (_, memory) in &self.data {
+            file.buffer.extend_from_slice(memory);
+        }
+
+        Ok(file.buffer)
+    }
+
+    /// Load the dumped core from the data of an ELF core file.
+    pub fn from_elf(data: &[u8]) -> Result<Self, CoreDumpError> {
+        match FileKind::parse(data).map_err(CoreDumpError::DecodingElfCoreDump)? {
+            FileKind::Elf32 => from_elf::<elf::FileHeader32<Endianness>>(data),
+            FileKind::Elf64 => from_elf::<elf::FileHeader64<Endianness>>(data),
+            _ => Err(CoreDumpError::NotAnElfCoreFile),
+        }
+    }
+}
+
+/// Returns true if the data is an ELF file.
+pub(super) fn is_elf(data: &[u8]) -> bool {
+    data.starts_with(&elf::ELFMAG)
+}
+
+fn from_elf<Elf: FileHeader<Endian = Endianness>>(data: &[u8]) -> Result<CoreDump, CoreDumpError> {
+    let header = Elf::parse(data).map_err(CoreDumpError::DecodingElfCoreDump)?;
+    let endian = header
+        .endian()
+        .map_err(CoreDumpError::DecodingElfCoreDump)?;
+    if header.e_type(endian) != elf::ET_CORE {
+        return Err(CoreDumpError::NotAnElfCoreFile);
+    }
+
+    let mut memory = Vec::new();
+    let mut prstatus = None;
+    let mut probe_rs_note = None;
+
+    for segment in header
+        .program_headers(endian, data)
+        .map_err(CoreDumpError::DecodingElfCoreDump)?
+    {
+        match segment.p_type(endian) {
+            elf::PT_LOAD => {
+                let start: u64 = segment.p_vaddr(endian).into();
+                let memory_size: u64 = segment.p_memsz(endian).into();
+                let mut bytes = segment
+                    .data(endian, data)
+                    .map_err(|()| CoreDumpError::InvalidElfSegment)?
+                    .to_vec();
+                let end = start
+                    .checked_add(memory_size)
+                    .ok_or(CoreDumpError::InvalidElfSegment)?;
+                if memory_size.saturating_sub(bytes.len() as u64) > MAX_ZERO_FILLED_SIZE {
+                    return Err(CoreDumpError::ElfSegmentTooLarge(memory_size));
+                }
+                // Memory which is not stored in the file is zero.
+                bytes.resize(memory_size as usize, 0);
+                memory.push((start..end, bytes));
+            }
+            elf::PT_NOTE => {
+                let Some(mut notes) = segment
+                    .notes(endian, data)
+                    .map_err(CoreDumpError::DecodingElfCoreDump)?
+                else {
+                    continue;
+                };
+                while let Some(note) = notes.next().map_err(CoreDumpError::DecodingElfCoreDump)? {
+                    match (note.name(), note.n_type(endian)) {
+                        (CORE_NOTE_NAME, elf::NT_PRSTATUS) if prstatus.is_none() => {
+                            prstatus = Some(note.desc());
+                        }
+                        (PROBE_RS_NOTE_NAME, NT_PROBE_RS_CORE_DUMP) => {
+                            probe_rs_note = Some(note.desc());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(probe_rs_note) = probe_rs_note {
+        let mut dump: CoreDump =
+            rmp_serde::from_slice(probe_rs_note).map_err(CoreDumpError::DecodingCoreDump)?;
+        dump.data = memory;
+        return Ok(dump);
+    }
+
+    let layout = PrStatusLayout::for_machine(header.e_machine(endian), header.is_class_64())?;
+    let prstatus = prstatus.ok_or(CoreDumpError::MissingElfCoreRegisters)?;
+
+    let word_size = layout.word_size();
+    let mut registers = HashMap::new();
+    for (index, register) in layout.registers.iter().enumerate() {
+        let Some(id) = register else {
+            continue;
+        };
+        let offset = layout.registers_offset() + index * word_size;
+        let Some(bytes) = prstatus.get(offset..offset + word_size) else {
+            return Err(CoreDumpError::MissingElfCoreRegisters);
+        };
+        let value = if layout.is_64_bit {
+            RegisterValue::U64(u64::from_le_bytes(bytes.try_into().unwrap()))
+        } else {
+            RegisterValue::U32(u32::from_le_bytes(bytes.try_into().unwrap()))
+        };
+        registers.insert(*id, value);
+    }
+
+    let (core_type, instruction_set) = layout.core_type();
+
+    Ok(CoreDump {
+        registers,
+        data: memory,
+        instruction_set,
+        supports_native_64bit_access: layout.is_64_bit,
+        core_type,
+        fpu_support: false,
+        floating_point_register_count: None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+    use test_case::test_case;
+
+    fn load_test_dump(name: &str) -> CoreDump {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/debug-unwind-tests")
+            .join(format!("{name}.coredump"));
+        CoreDump::load(&path).unwrap()
+    }
+
+    /// Removes the `probe-rs` note, like in an ELF core file created by another tool.
+    fn strip_probe_rs_note(elf: &mut [u8]) {
+        let position = elf
+            .windows(PROBE_RS_NOTE_NAME.len())
+            .position(|window| window == PROBE_RS_NOTE_NAME)
+            .unwrap();
+        // Change the type of the note, so it is ignored.
+        elf[position - 4..position].copy_from_slice(&0xFFu32.to_le_bytes());
+    }
+
+    #[test_case("RP2040_full_unwind"; "armv6m")]
+    #[test_case("nRF52833_xxAA_full_unwind"; "armv7em")]
+    #[test_case("esp32c3_full_unwind"; "riscv")]
+    fn elf_round_trip(name: &str) {
+        let dump = load_test_dump(name);
+
+        let elf = dump.to_elf().unwrap();
+        let loaded = CoreDump::load_raw(&elf).unwrap();
+
+        assert_eq!(loaded.registers, dump.registers);
+        assert_eq!(loaded.data, dump.data);
+        assert_eq!(loaded.core_type, dump.core_type);
+        assert_eq!(loaded.instruction_set, dump.instruction_set);
+        assert_eq!(loaded.fpu_support, dump.fpu_support);
+    }
+
+    #[test_case("RP2040_full_unwind", &[(0, 0), (13, 13), (15, 15), (16, 16)]; "arm")]
+    #[test_case("esp32c3_full_unwind", &[(0x7b1, 0), (0x1001, 1), (0x1002, 2), (0x101F, 31)]; "riscv")]
+    fn elf_prstatus_registers(name: &str, expected_slots: &[(u16, usize)]) {
+        let dump = load_test_dump(name);
+        let elf = dump.to_elf().unwrap();
+
+        let header = elf::FileHeader32::<Endianness>::parse(elf.as_slice()).unwrap();
+        let endian = header.endian().unwrap();
+        assert_eq!(header.e_type(endian), elf::ET_CORE);
+
+        let segments = header.program_headers(endian, elf.as_slice()).unwrap();
+        assert_eq!(segments.len(), 1 + dump.data.len());
+
+        let mut notes = segments[0].notes(endian, elf.as_slice()).unwrap().unwrap();
+        let note = notes.next().unwrap().unwrap();
+        assert_eq!(note.name(), CORE_NOTE_NAME);
+        assert_eq!(note.n_type(endian), elf::NT_PRSTATUS);
+
+        let registers = &note.desc()[72..];
+        for (id, slot) in expected_slots {
+            let value = u32::from_le_bytes(registers[slot * 4..][..4].try_into().unwrap());
+            assert_eq!(
+                RegisterValue::U32(value),
+                dump.registers[&RegisterId(*id)],
+                "register {id:#x}"
+            );
+        }
+
+        for ((range, memory), segment) in dump.data.iter().zip(&segments[1..]) {
+            assert_eq!(segment.p_type(endian), elf::PT_LOAD);
+            assert_eq!(segment.p_vaddr(endian) as u64, range.start);
+            assert_eq!(segment.data(endian, elf.as_slice()).unwrap(), memory);
+        }
+    }
+
+    #[test]
+    fn elf_without_probe_rs_note() {
+        let dump = load_test_dump("esp32c3_full_unwind");
+        let mut elf = dump.to_elf().unwrap();
+        strip_probe_rs_note(&mut elf);
+
+        let loaded = CoreDump::from_elf(&elf).unwrap();
+
+        assert_eq!(loaded.core_type, CoreType::Riscv);
+        assert_eq!(loaded.data, dump.data);
+        for id in std::iter::once(0x7b1).chain(0x1001..=0x101F) {
+            assert_eq!(
+                loaded.registers[&RegisterId(id)],
+                dump.registers[&RegisterId(id)]
+            );
+        }
+    }
+
+    #[test]
+    fn elf_riscv_64_bit_registers() {
+        let mut dump = load_test_dump("esp32c3_full_unwind");
+        dump.registers
+            .insert(RegisterId(0x1001), RegisterValue::U64(0x1_0000_0000));
+
+        assert!(matches!(
+            dump.to_elf(),
+            Err(CoreDumpError::UnsupportedElfRegister(RegisterId(0x1001)))
+        ));
+    }
+
+    #[test]
+    fn elf_segment_mostly_not_in_file() {
+        let dump = load_test_dump("RP2040_full_unwind");
+        let mut elf = dump.to_elf().unwrap();
+        // The `p_memsz` of the first `PT_LOAD` segment, after the `PT_NOTE` segment.
+        let memory_size_offset = 52 + 32 + 20;
+        elf[memory_size_offset..][..4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(matches!(
+            CoreDump::from_elf(&elf),
+            Err(CoreDumpError::ElfSegmentTooLarge(0xFFFF_FFFF))
+        ));
+    }
+
+    #[test]
+    fn elf_not_a_core_file() {
+        let dump = load_test_dump("RP2040_full_unwind");
+        let mut elf = dump.to_elf().unwrap();
+        elf[16..18].copy_from_slice(&elf::ET_EXEC.to_le_bytes());
+
+        assert!(matches!(
+            CoreDump::from_elf(&elf),
+            Err(CoreDumpError::NotAnElfCoreFile)
+        ));
+    }
+}
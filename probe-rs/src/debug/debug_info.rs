@@ -2,6 +2,8 @@ use super::{
     exception_handling::ExceptionInterface,
     function_die::{Die, FunctionDie},
     get_object_reference,
+    separate_debug_info::{find_debug_file, SplitDwarfLoader},
+    source_path::{substitute_path, PathSubstitution},
     unit_info::UnitInfo,
     variable::*,
     DebugError, DebugRegisters, StackFrame, VariableCache,
@@ -15,57 +17,146 @@ use crate::{
     Error, MemoryInterface,
 };
 use gimli::{
-    BaseAddresses, DebugFrame, DebugInfoOffset, UnwindContext, UnwindSection, UnwindTableRow,
+    BaseAddresses, DebugFrame, DebugInfoOffset, Reader, UnwindContext, UnwindSection,
+    UnwindTableRow,
 };
 use object::read::{Object, ObjectSection};
 use probe_rs_target::InstructionSet;
 use std::{
-    borrow, cmp::Ordering, num::NonZeroU64, ops::ControlFlow, path::Path, rc::Rc, str::from_utf8,
+    borrow,
+    cmp::Ordering,
+    num::NonZeroU64,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::from_utf8,
 };
 use typed_path::{TypedPath, TypedPathBuf};
 
-pub(crate) type GimliReader = gimli::EndianReader<gimli::LittleEndian, std::rc::Rc<[u8]>>;
-pub(crate) type GimliReaderOffset =
-    <gimli::EndianReader<gimli::LittleEndian, Rc<[u8]>> as gimli::Reader>::Offset;
+pub(crate) type GimliReader = super::EndianReader;
+pub(crate) type GimliReaderOffset = <GimliReader as gimli::Reader>::Offset;
 
 pub(crate) type GimliAttribute = gimli::Attribute<GimliReader>;
 
-pub(crate) type DwarfReader = gimli::read::EndianRcSlice<gimli::LittleEndian>;
+pub(crate) type DwarfReader = super::EndianReader;
+
+/// The offset at which an object file is loaded into the target memory, relative to the addresses
+/// it was linked at.
+///
+/// All addresses read from the debug information of the object are shifted by this offset, which
+/// allows debugging position independent code, or code which is copied to a different location
+/// before it is executed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoadOffset(pub u64);
+
+impl gimli::read::Relocate for LoadOffset {
+    fn relocate_address(&self, _offset: usize, value: u64) -> gimli::Result<u64> {
+        // Zero is used as the base address of compilation units with non-contiguous ranges (the
+        // range entries are relocated instead), and for code that was removed by the linker.
+        if value == 0 {
+            return Ok(0);
+        }
+
+        Ok(value.wrapping_add(self.0))
+    }
+
+    fn relocate_offset(&self, _offset: usize, value: usize) -> gimli::Result<usize> {
+        Ok(value)
+    }
+}
+
+/// Options to load debug information, see [`DebugInfo::from_file_with_options`].
+#[derive(Clone, Debug, Default)]
+pub struct DebugInfoOptions {
+    /// The offset of the address the binary is loaded at, relative to the address it was linked
+    /// at. See [`LoadOffset`].
+    pub load_offset: u64,
+    /// Directories which are searched for the debug information of stripped binaries, in addition
+    /// to the directory of the binary and `/usr/lib/debug`.
+    pub debug_file_directories: Vec<PathBuf>,
+}
 
 /// Debug information which is parsed from DWARF debugging information.
 pub struct DebugInfo {
     pub(crate) dwarf: gimli::Dwarf<DwarfReader>,
     pub(crate) frame_section: gimli::DebugFrame<DwarfReader>,
-    pub(crate) locations_section: gimli::LocationLists<DwarfReader>,
-    pub(crate) address_section: gimli::DebugAddr<DwarfReader>,
     pub(crate) debug_line_section: gimli::DebugLine<DwarfReader>,
 
     pub(crate) unit_infos: Vec<UnitInfo>,
+
+    /// Rules to map source paths in the debug information to paths on this machine.
+    pub(crate) path_substitutions: Vec<PathSubstitution>,
+
+    /// The index of this object file in the list of objects, see [`DebugInfo::add_object`].
+    pub(crate) object_index: usize,
+    /// Further object files which are loaded into the same core.
+    pub(crate) additional_objects: Vec<DebugInfo>,
 }
 
 impl DebugInfo {
     /// Read debug info directly from a ELF file.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<DebugInfo, DebugError> {
+        DebugInfo::from_file_with_options(path, &DebugInfoOptions::default())
+    }
+
+    /// Read debug info directly from a ELF file, which is loaded at `load_offset` bytes from the
+    /// addresses it was linked at.
+    pub fn from_file_with_load_offset<P: AsRef<Path>>(
+        path: P,
+        load_offset: u64,
+    ) -> Result<DebugInfo, DebugError> {
+        let options = DebugInfoOptions {
+            load_offset,
+            ..Default::default()
+        };
+
+        DebugInfo::from_file_with_options(path, &options)
+    }
+
+    /// Read debug info from a ELF file.
+    ///
+    /// If the file is stripped, the debug information is read from the companion file it refers
+    /// to, see [`DebugInfoOptions::debug_file_directories`]. Split DWARF units are read from the
+    /// `.dwo` files they refer to, or from the `.dwp` package next to the file.
+    pub fn from_file_with_options<P: AsRef<Path>>(
+        path: P,
+        options: &DebugInfoOptions,
+    ) -> Result<DebugInfo, DebugError> {
+        let path = path.as_ref();
         let data = std::fs::read(path)?;
+        let object = object::File::parse(&*data)?;
+
+        if object.section_by_name(".debug_info").is_none() {
+            if let Some(debug_file) =
+                find_debug_file(&object, path, &options.debug_file_directories)
+            {
+                tracing::debug!("Reading debug information from {}", debug_file.display());
+                let data = std::fs::read(&debug_file)?;
+
+                return DebugInfo::parse(&data, Some(&debug_file), options.load_offset);
+            }
+        }
 
-        DebugInfo::from_raw(&data)
+        DebugInfo::parse(&data, Some(path), options.load_offset)
     }
 
     /// Parse debug information directly from a buffer containing an ELF file.
     pub fn from_raw(data: &[u8]) -> Result<Self, DebugError> {
+        DebugInfo::from_raw_with_load_offset(data, 0)
+    }
+
+    /// Parse debug information directly from a buffer containing an ELF file, which is loaded at
+    /// `load_offset` bytes from the addresses it was linked at.
+    pub fn from_raw_with_load_offset(data: &[u8], load_offset: u64) -> Result<Self, DebugError> {
+        DebugInfo::parse(data, None, load_offset)
+    }
+
+    /// Parse the debug information in `data`, which was read from the file at `path`.
+    fn parse(data: &[u8], path: Option<&Path>, load_offset: u64) -> Result<Self, DebugError> {
         let object = object::File::parse(data)?;
 
-        // Load a section and return as `Cow<[u8]>`.
         let load_section = |id: gimli::SectionId| -> Result<DwarfReader, gimli::Error> {
-            let data = object
-                .section_by_name(id.name())
-                .and_then(|section| section.uncompressed_data().ok())
-                .unwrap_or_else(|| borrow::Cow::Borrowed(&[][..]));
-
-            Ok(gimli::read::EndianRcSlice::new(
-                Rc::from(&*data),
-                gimli::LittleEndian,
-            ))
+            Ok(load_section(&object, Some(id.name()), load_offset))
         };
 
         // Load all of the sections.
@@ -73,13 +164,11 @@ impl DebugInfo {
 
         use gimli::Section;
         let mut frame_section = gimli::DebugFrame::load(load_section)?;
-        let address_section = gimli::DebugAddr::load(load_section)?;
-        let debug_loc = gimli::DebugLoc::load(load_section)?;
-        let debug_loc_lists = gimli::DebugLocLists::load(load_section)?;
-        let locations_section = gimli::LocationLists::new(debug_loc, debug_loc_lists);
         let debug_line_section = gimli::DebugLine::load(load_section)?;
 
         let mut unit_infos = Vec::new();
+        let mut split_units = Vec::new();
+        let mut split_dwarf_loader = SplitDwarfLoader::new(path, load_offset);
 
         let mut iter = dwarf_cow.units();
 
@@ -93,26 +182,132 @@ impl DebugInfo {
                 // The frame section address size is only used for CIE versions before 4.
                 frame_section.set_address_size(unit.encoding().address_size);
 
+                // The debug information of a skeleton unit is stored in a split DWARF file.
+                // Without it, only the line program of the skeleton unit is available.
+                if unit.dwo_id.is_some() {
+                    if let Some(split_unit) = split_dwarf_loader.load(&dwarf_cow, &unit) {
+                        split_units.push(split_unit);
+                        continue;
+                    }
+                }
+
                 unit_infos.push(UnitInfo::new(unit));
             };
         }
 
-        Ok(DebugInfo {
+        let mut debug_info = DebugInfo {
             dwarf: dwarf_cow,
             frame_section,
-            locations_section,
-            address_section,
             debug_line_section,
             unit_infos,
-        })
+            path_substitutions: Vec::new(),
+            object_index: 0,
+            additional_objects: Vec::new(),
+        };
+
+        // Each split DWARF file is added as an object, which shares the frame and line sections
+        // of the binary.
+        for (dwarf, unit, ranges) in split_units {
+            let mut unit_info = UnitInfo::new(unit);
+            unit_info.skeleton_ranges = Some(ranges);
+
+            let object = DebugInfo {
+                dwarf,
+                frame_section: debug_info.frame_section.clone(),
+                debug_line_section: debug_info.debug_line_section.clone(),
+                unit_infos: vec![unit_info],
+                path_substitutions: Vec::new(),
+                object_index: 0,
+                additional_objects: Vec::new(),
+            };
+            debug_info.add_object(object);
+        }
+
+        Ok(debug_info)
+    }
+
+    /// Add the debug information of another object file which is loaded into the same core, e.g.
+    /// the application which is started by a bootloader.
+    ///
+    /// Source locations, breakpoints, stack frames and variables are looked up in the object file
+    /// which contains the respective address, so symbols stay available when the program counter
+    /// moves from one object file to another.
+    pub fn add_object(&mut self, mut object: DebugInfo) {
+        let nested_objects = std::mem::take(&mut object.additional_objects);
+
+        for mut object in std::iter::once(object).chain(nested_objects) {
+            object.object_index = self.additional_objects.len() + 1;
+            for unit_info in &mut object.unit_infos {
+                unit_info.object_index = object.object_index;
+            }
+            object.path_substitutions = self.path_substitutions.clone();
+
+            self.additional_objects.push(object);
+        }
+    }
+
+    /// All object files of this debug information, starting with the one it was created from.
+    pub(crate) fn objects(&self) -> impl Iterator<Item = &DebugInfo> {
+        std::iter::once(self).chain(&self.additional_objects)
+    }
+
+    /// The object file with the given index, see [`Variable::object_index`].
+    pub(crate) fn object(&self, object_index: usize) -> &DebugInfo {
+        self.objects()
+            .find(|object| object.object_index == object_index)
+            .unwrap_or(self)
+    }
+
+    /// The object file which contains the code at `address`.
+    ///
+    /// Falls back to the first object file if no object file has debug information for the address.
+    pub(crate) fn object_for_address(&self, address: u64) -> &DebugInfo {
+        self.objects()
+            .find(|object| object.compile_unit_info(address).is_ok())
+            .unwrap_or(self)
+    }
+
+    /// Create the [`UnitInfo`] for the unit with the given header offset.
+    pub(crate) fn unit_info_at(
+        &self,
+        header_offset: DebugInfoOffset,
+    ) -> Result<UnitInfo, DebugError> {
+        let unit_header = self.dwarf.debug_info.header_from_offset(header_offset)?;
+        let mut unit_info = UnitInfo::new(gimli::Unit::new(&self.dwarf, unit_header)?);
+        unit_info.object_index = self.object_index;
+
+        Ok(unit_info)
+    }
+
+    /// Set the rules which are used to map the source paths in the debug information to paths on
+    /// this machine. The first matching rule is applied to each path.
+    ///
+    /// This affects the source locations reported by [`DebugInfo::get_source_location`] and
+    /// stack frames, and the paths that are matched by [`DebugInfo::get_breakpoint_location`].
+    pub fn set_path_substitutions(&mut self, path_substitutions: Vec<PathSubstitution>) {
+        for object in &mut self.additional_objects {
+            object.path_substitutions = path_substitutions.clone();
+        }
+        self.path_substitutions = path_substitutions;
+    }
+
+    /// The rules used to map source paths, see [`DebugInfo::set_path_substitutions`].
+    pub fn path_substitutions(&self) -> &[PathSubstitution] {
+        &self.path_substitutions
     }
 
     /// Try get the [`SourceLocation`] for a given address.
     pub fn get_source_location(&self, address: u64) -> Option<SourceLocation> {
+        self.objects()
+            .find_map(|object| object.object_source_location(address))
+    }
+
+    /// Try get the [`SourceLocation`] for a given address, from this object file only.
+    fn object_source_location(&self, address: u64) -> Option<SourceLocation> {
         for unit_info in &self.unit_infos {
             let unit = &unit_info.unit;
 
-            let mut ranges = match self.dwarf.unit_ranges(unit) {
+            let ranges = match self.unit_ranges(unit_info) {
                 Ok(ranges) => ranges,
                 Err(error) => {
                     tracing::warn!(
@@ -124,7 +319,7 @@ impl DebugInfo {
                 }
             };
 
-            while let Ok(Some(range)) = ranges.next() {
+            for range in ranges {
                 if !(range.begin <= address && address < range.end) {
                     continue;
                 }
@@ -248,10 +443,39 @@ impl DebugInfo {
             return Ok(());
         }
 
+        if parent_variable.variable_node_type == VariableNodeType::UnitsLookup {
+            if self.objects().all(|object| object.unit_infos.is_empty()) {
+                // No unit infos
+                return Err(DebugError::Other("Missing unit infos".to_string()));
+            }
+
+            // Static variables are looked up in all object files.
+            for object in self.objects() {
+                object.cache_deferred_object_variables(
+                    cache,
+                    memory,
+                    parent_variable,
+                    frame_info,
+                )?;
+            }
+            return Ok(());
+        }
+
+        self.object(parent_variable.object_index)
+            .cache_deferred_object_variables(cache, memory, parent_variable, frame_info)
+    }
+
+    /// Resolve the children of `parent_variable` from the debug information of this object file.
+    fn cache_deferred_object_variables(
+        &self,
+        cache: &mut VariableCache,
+        memory: &mut dyn MemoryInterface,
+        parent_variable: &mut Variable,
+        frame_info: StackFrameInfo<'_>,
+    ) -> Result<(), DebugError> {
         match parent_variable.variable_node_type {
             VariableNodeType::TypeOffset(header_offset, type_offset) => {
-                let unit_header = self.dwarf.debug_info.header_from_offset(header_offset)?;
-                let unit_info = UnitInfo::new(gimli::Unit::new(&self.dwarf, unit_header)?);
+                let unit_info = self.unit_info_at(header_offset)?;
 
                 // Find the parent node
                 let mut type_tree = unit_info.unit.entries_tree(Some(type_offset))?;
@@ -267,8 +491,7 @@ impl DebugInfo {
                 )?;
             }
             VariableNodeType::DirectLookup(header_offset, unit_offset) => {
-                let unit_header = self.dwarf.debug_info.header_from_offset(header_offset)?;
-                let unit_info = UnitInfo::new(gimli::Unit::new(&self.dwarf, unit_header)?);
+                let unit_info = self.unit_info_at(header_offset)?;
 
                 // Find the parent node
                 let mut type_tree = unit_info.unit.entries_tree(Some(unit_offset))?;
@@ -286,31 +509,7 @@ impl DebugInfo {
             }
             VariableNodeType::UnitsLookup => {
                 // Look up static variables from all units
-                let mut unit_infos = self.unit_infos.iter();
-
-                let Some(unit_info) = unit_infos.next() else {
-                    // No unit infos
-                    return Err(DebugError::Other("Missing unit infos".to_string()));
-                };
-
-                let mut entries = unit_info.unit.entries();
-
-                // Only process statics for this unit header.
-                // Navigate the current unit from the header down.
-                let (_, unit_node) = entries.next_dfs()?.unwrap();
-
-                let mut tree = unit_info.unit.entries_tree(Some(unit_node.offset()))?;
-
-                unit_info.process_tree(
-                    self,
-                    tree.root()?,
-                    parent_variable,
-                    memory,
-                    cache,
-                    frame_info,
-                )?;
-
-                for unit in unit_infos {
+                for unit in &self.unit_infos {
                     let mut entries = unit.unit.entries();
 
                     // Only process statics for this unit header.
@@ -606,7 +805,9 @@ impl DebugInfo {
             tracing::trace!("UNWIND: Will generate `StackFrame` for function at address (PC) {frame_pc_register_value:#}");
 
             // PART 1-a: Prepare the `StackFrame`'s that holds the current frame information.
-            let mut cached_stack_frames = match self.get_stackframe_info(
+            // The object file which contains the code of this frame.
+            let debug_info = self.object_for_address(frame_pc);
+            let mut cached_stack_frames = match debug_info.get_stackframe_info(
                 memory,
                 frame_pc,
                 &mut unwind_context,
@@ -656,7 +857,7 @@ impl DebugInfo {
                             frame_pc,
                             width = (unwind_registers.get_address_size_bytes() * 2 + 2)
                         ),
-                        source_location: self.get_source_location(frame_pc),
+                        source_location: debug_info.get_source_location(frame_pc),
                         registers: unwind_registers.clone(),
                         pc: frame_pc_register_value,
                         frame_base: None,
@@ -682,7 +883,7 @@ impl DebugInfo {
             // PART 2-a: get the `gimli::FrameDescriptorEntry` for the program counter
             // and then the unwind info associated with this row.
             let unwind_info =
-                match get_unwind_info(&mut unwind_context, &self.frame_section, frame_pc) {
+                match get_unwind_info(&mut unwind_context, &debug_info.frame_section, frame_pc) {
                     Ok(unwind_info) => {
                         tracing::trace!("UNWIND: Found unwind info for address {frame_pc:#010x}");
                         unwind_info
@@ -793,10 +994,26 @@ impl DebugInfo {
                 .map(|c| c.to_string())
                 .unwrap_or_else(|| "-".to_owned())
         );
-        VerifiedBreakpoint::for_source_location(self, path, line, column)
+        let mut first_error = None;
+        for object in self.objects() {
+            match VerifiedBreakpoint::for_source_location(object, path, line, column) {
+                Ok(breakpoint) => return Ok(breakpoint),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        Err(first_error.unwrap_or_else(|| {
+            DebugError::Other(format!(
+                "No breakpoint location found for {}:{line}",
+                path.to_path().display()
+            ))
+        }))
     }
 
     /// Get the path for an entry in a line program header, using the compilation unit's directory and file entries.
+    ///
+    /// The path substitution rules are applied to the result.
     // TODO: Determine if it is necessary to navigate the include directories to find the file absolute path for C files.
     pub(crate) fn get_path(
         &self,
@@ -813,13 +1030,15 @@ impl DebugInfo {
             return None;
         };
         let file_name_attr_string = self.dwarf.attr_string(unit, file_entry.path_name()).ok()?;
-        let name_path = from_utf8(&file_name_attr_string).ok()?;
+        let name_path = file_name_attr_string.to_slice().ok()?;
+        let name_path = from_utf8(&name_path).ok()?;
 
         let dir_name_attr_string = file_entry
             .directory(header)
             .and_then(|dir| self.dwarf.attr_string(unit, dir).ok());
 
         let dir_path = dir_name_attr_string.and_then(|dir_name| {
+            let dir_name = dir_name.to_slice().ok()?;
             from_utf8(&dir_name)
                 .ok()
                 .map(|p| TypedPath::derive(p).to_path_buf())
@@ -834,7 +1053,12 @@ impl DebugInfo {
             let comp_dir = unit
                 .comp_dir
                 .as_ref()
-                .map(|dir| from_utf8(dir))
+                .map(|dir| dir.to_slice())
+                .transpose()
+                .ok()?;
+            let comp_dir = comp_dir
+                .as_deref()
+                .map(from_utf8)
                 .transpose()
                 .ok()?
                 .map(TypedPath::derive);
@@ -843,7 +1067,7 @@ impl DebugInfo {
             }
         }
 
-        Some(combined_path)
+        Some(substitute_path(&self.path_substitutions, combined_path))
     }
 
     pub(crate) fn find_file_and_directory(
@@ -862,18 +1086,35 @@ impl DebugInfo {
         Some((file_name, directory))
     }
 
+    /// The address ranges of a unit. The ranges of split DWARF units are stored in their skeleton
+    /// unit.
+    pub(crate) fn unit_ranges(
+        &self,
+        unit_info: &UnitInfo,
+    ) -> Result<Vec<gimli::Range>, DebugError> {
+        if let Some(ranges) = &unit_info.skeleton_ranges {
+            return Ok(ranges.clone());
+        }
+
+        let mut ranges = Vec::new();
+        let mut iter = self.dwarf.unit_ranges(&unit_info.unit)?;
+        while let Ok(Some(range)) = iter.next() {
+            ranges.push(range);
+        }
+
+        Ok(ranges)
+    }
+
     // Return the compilation unit that contains the given address
     pub(crate) fn compile_unit_info(
         &self,
         address: u64,
     ) -> Result<&super::unit_info::UnitInfo, DebugError> {
         for header in &self.unit_infos {
-            match self.dwarf.unit_ranges(&header.unit) {
-                Ok(mut ranges) => {
-                    while let Ok(Some(range)) = ranges.next() {
-                        if range.contains(address) {
-                            return Ok(header);
-                        }
+            match self.unit_ranges(header) {
+                Ok(ranges) => {
+                    if ranges.iter().any(|range| range.contains(address)) {
+                        return Ok(header);
                     }
                 }
                 Err(_) => continue,
@@ -951,6 +1192,24 @@ impl DebugInfo {
     }
 }
 
+/// Load the section `name` of `object`, decompressing it if necessary. Returns an empty section if
+/// the object has no such section.
+pub(crate) fn load_section(
+    object: &object::File,
+    name: Option<&str>,
+    load_offset: u64,
+) -> DwarfReader {
+    let data = name
+        .and_then(|name| object.section_by_name(name))
+        .and_then(|section| section.uncompressed_data().ok())
+        .unwrap_or_else(|| borrow::Cow::Borrowed(&[][..]));
+
+    gimli::read::RelocateReader::new(
+        gimli::read::EndianRcSlice::new(Rc::from(&*data), gimli::LittleEndian),
+        LoadOffset(load_offset),
+    )
+}
+
 /// Uses the [`TypedPathBuf::normalize`] function to normalize both paths before comparing them
 pub(crate) fn canonical_path_eq(
     primary_path: &TypedPathBuf,
@@ -1407,13 +1666,14 @@ mod test {
             exception_handling::exception_handler_for_core,
             exception_handling::{armv6m::ArmV6MExceptionHandler, armv7m::ArmV7MExceptionHandler},
             stack_frame::{StackFrameInfo, TestFormatter},
-            DebugInfo, DebugRegister, DebugRegisters,
+            DebugInfo, DebugRegister, DebugRegisters, PathSubstitution,
         },
         test::MockMemory,
         CoreDump, RegisterValue,
     };
     use std::path::{Path, PathBuf};
     use test_case::test_case;
+    use typed_path::TypedPath;
 
     /// Get the full path to a file in the `tests` directory.
     fn get_path_for_test_files(relative_file: &str) -> PathBuf {
@@ -2031,4 +2291,150 @@ mod test {
         // and also because they provide better diffs.
         insta::assert_yaml_snapshot!(snapshot_name, static_variables);
     }
+
+    #[test]
+    fn path_substitutions() {
+        let mut debug_info =
+            load_test_elf_as_debug_info("debug-unwind-tests/nRF52833_xxAA_full_unwind.elf");
+
+        let breakpoint = debug_info
+            .get_function_breakpoint_locations("setup_data_types")
+            .unwrap()
+            .remove(0);
+        let original = breakpoint.source_location.combined_typed_path().unwrap();
+        let directory = breakpoint.source_location.directory.clone().unwrap();
+        let line = breakpoint.source_location.line.unwrap();
+
+        debug_info.set_path_substitutions(vec![PathSubstitution::new(
+            directory.to_string_lossy(),
+            "/home/user/firmware",
+        )]);
+
+        let remapped = debug_info
+            .get_source_location(breakpoint.address)
+            .unwrap()
+            .combined_typed_path()
+            .unwrap();
+        assert_eq!(
+            remapped,
+            TypedPath::derive("/home/user/firmware")
+                .join(breakpoint.source_location.file.as_ref().unwrap())
+        );
+
+        // Breakpoints are set using the paths on this machine ...
+        let verified = debug_info
+            .get_breakpoint_location(&remapped, line, None)
+            .unwrap();
+        assert_eq!(verified.address, breakpoint.address);
+
+        // ... and the original paths no longer match.
+        assert!(debug_info
+            .get_breakpoint_location(&original, line, None)
+            .is_err());
+    }
+
+    #[test]
+    fn load_offset() {
+        let path = get_path_for_test_files("debug-unwind-tests/nRF52833_xxAA_full_unwind.elf");
+        let debug_info = DebugInfo::from_file(&path).unwrap();
+        let offset_debug_info = DebugInfo::from_file_with_load_offset(&path, 0x1000_0000).unwrap();
+
+        let breakpoint = debug_info
+            .get_function_breakpoint_locations("setup_data_types")
+            .unwrap()
+            .remove(0);
+        let offset_breakpoint = offset_debug_info
+            .get_function_breakpoint_locations("setup_data_types")
+            .unwrap()
+            .remove(0);
+
+        assert_eq!(offset_breakpoint.address, breakpoint.address + 0x1000_0000);
+        assert_eq!(
+            offset_breakpoint.source_location,
+            breakpoint.source_location
+        );
+        assert_eq!(
+            offset_debug_info.get_source_location(offset_breakpoint.address),
+            debug_info.get_source_location(breakpoint.address)
+        );
+        assert!(offset_debug_info
+            .get_source_location(breakpoint.address)
+            .is_none());
+    }
+
+    #[test]
+    fn multiple_objects() {
+        let rp2040 = load_test_elf_as_debug_info("debug-unwind-tests/RP2040_full_unwind.elf");
+        let nrf52833 =
+            load_test_elf_as_debug_info("debug-unwind-tests/nRF52833_xxAA_full_unwind.elf");
+
+        let mut debug_info =
+            load_test_elf_as_debug_info("debug-unwind-tests/nRF52833_xxAA_full_unwind.elf");
+        debug_info.add_object(load_test_elf_as_debug_info(
+            "debug-unwind-tests/RP2040_full_unwind.elf",
+        ));
+
+        // Function breakpoints are set in all objects.
+        let locations = |debug_info: &DebugInfo| {
+            debug_info
+                .get_function_breakpoint_locations("main")
+                .unwrap()
+                .into_iter()
+                .map(|location| location.address)
+                .collect::<Vec<_>>()
+        };
+        let mut expected = locations(&nrf52833);
+        expected.extend(locations(&rp2040));
+        expected.sort();
+        assert_eq!(locations(&debug_info), expected);
+
+        // Stack frames are unwound with the object which contains their code.
+        let unwind = |debug_info: &DebugInfo| {
+            let mut adapter = CoreDump::load(&get_path_for_test_files(
+                "debug-unwind-tests/RP2040_full_unwind.coredump",
+            ))
+            .unwrap();
+            let initial_registers = adapter.debug_registers();
+            let exception_handler = exception_handler_for_core(adapter.core_type());
+            let instruction_set = adapter.instruction_set();
+
+            debug_info
+                .unwind(
+                    &mut adapter,
+                    initial_registers,
+                    exception_handler.as_ref(),
+                    Some(instruction_set),
+                )
+                .unwrap()
+                .into_iter()
+                .map(|frame| (frame.function_name, frame.source_location, frame.pc))
+                .collect::<Vec<_>>()
+        };
+        let frames = unwind(&debug_info);
+        assert!(frames.len() > 1);
+        assert_eq!(frames, unwind(&rp2040));
+
+        // Variables are resolved in the object which contains them.
+        let mut adapter = CoreDump::load(&get_path_for_test_files(
+            "debug-unwind-tests/RP2040_full_unwind.coredump",
+        ))
+        .unwrap();
+        let initial_registers = adapter.debug_registers();
+        let mut static_variables = debug_info.create_static_scope_cache();
+        static_variables.recurse_deferred_variables(
+            &debug_info,
+            &mut adapter,
+            1,
+            StackFrameInfo {
+                registers: &initial_registers,
+                frame_base: None,
+                canonical_frame_address: None,
+            },
+        );
+        let object_indices = static_variables
+            .get_children(static_variables.root_variable().variable_key())
+            .map(|variable| variable.object_index)
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(object_indices, [0, 1].into());
+    }
 }
@@ -167,11 +167,14 @@ impl SteppingMode {
         program_counter: u64,
         return_address: Option<u64>,
     ) -> Result<VerifiedBreakpoint, DebugError> {
-        let program_unit = debug_info.compile_unit_info(program_counter)?;
+        // The object file which contains the code at the program counter. Stepping may leave it,
+        // so the halt locations after a step are looked up in all object files again.
+        let object = debug_info.object_for_address(program_counter);
+        let program_unit = object.compile_unit_info(program_counter)?;
         match self {
             SteppingMode::BreakPoint => {
                 // Find the first_breakpoint_address
-                return VerifiedBreakpoint::for_address(debug_info, program_counter);
+                return VerifiedBreakpoint::for_address(object, program_counter);
             }
             SteppingMode::OverStatement => {
                 // Find the "step over location"
@@ -183,19 +186,16 @@ impl SteppingMode {
                 //    -- If there is one, it means the step over target is in the current sequence,
                 //       so we get the valid breakpoint location for this next location.
                 //    -- If there is not one, the step over target is the same as the step out target.
-                return VerifiedBreakpoint::for_address(
-                    debug_info,
-                    program_counter.saturating_add(1),
-                )
-                .or_else(|_| {
-                    // If we cannot find a valid breakpoint in the current sequence, we will step out of the current sequence.
-                    SteppingMode::OutOfStatement.get_halt_location(
-                        core,
-                        debug_info,
-                        program_counter,
-                        return_address,
-                    )
-                });
+                return VerifiedBreakpoint::for_address(object, program_counter.saturating_add(1))
+                    .or_else(|_| {
+                        // If we cannot find a valid breakpoint in the current sequence, we will step out of the current sequence.
+                        SteppingMode::OutOfStatement.get_halt_location(
+                            core,
+                            debug_info,
+                            program_counter,
+                            return_address,
+                        )
+                    });
             }
             SteppingMode::IntoStatement => {
                 // This is a tricky case because the current RUST generated DWARF, does not store the DW_TAG_call_site information described in the DWARF 5 standard.
@@ -211,7 +211,7 @@ impl SteppingMode {
                 //   (b) We hit a PC at the address of the identified next instruction location,
                 //       which means there was nothing to step into, so the target is now halted (correctly) at the next statement.
                 let target_pc = match VerifiedBreakpoint::for_address(
-                    debug_info,
+                    object,
                     program_counter.saturating_add(1),
                 ) {
                     Ok(identified_next_breakpoint) => identified_next_breakpoint.address,
@@ -246,26 +246,21 @@ impl SteppingMode {
                 return SteppingMode::BreakPoint.get_halt_location(core, debug_info, new_pc, None);
             }
             SteppingMode::OutOfStatement => {
-                if let Ok(function_dies) =
-                    program_unit.get_function_dies(debug_info, program_counter)
-                {
+                if let Ok(function_dies) = program_unit.get_function_dies(object, program_counter) {
                     // We want the first qualifying (PC is in range) function from the back of this list,
                     // to access the 'innermost' functions first.
                     if let Some(function) = function_dies.iter().next_back() {
                         tracing::trace!(
                             "Step Out target: Evaluating function {:?}, low_pc={:?}, high_pc={:?}",
-                            function.function_name(debug_info),
+                            function.function_name(object),
                             function.low_pc(),
                             function.high_pc()
                         );
 
-                        if function
-                            .attribute(debug_info, gimli::DW_AT_noreturn)
-                            .is_some()
-                        {
+                        if function.attribute(object, gimli::DW_AT_noreturn).is_some() {
                             return Err(DebugError::Other(format!(
                                 "Function {:?} is marked as `noreturn`. Cannot step out of this function.",
-                                function.function_name(debug_info).as_deref().unwrap_or("<unknown>")
+                                function.function_name(object).as_deref().unwrap_or("<unknown>")
                             )));
                         } else if function.range_contains(program_counter) {
                             if function.is_inline() {
@@ -7,6 +7,7 @@ use probe_rs_target::CoreType;
 
 use crate::{debug::unwind_pc_without_debuginfo, MemoryInterface};
 
+use self::fault_report::FaultReport;
 use super::{DebugError, DebugInfo, DebugRegisters, StackFrame};
 
 pub(crate) mod armv6m;
@@ -18,6 +19,8 @@ pub(crate) mod armv7m;
 
 pub(crate) mod armv8m;
 
+pub mod fault_report;
+
 pub(crate) mod xtensa;
 
 /// Creates a new exception interface for the [`CoreType`] at hand.
@@ -126,6 +129,19 @@ pub trait ExceptionInterface {
         memory: &mut dyn MemoryInterface,
     ) -> Result<String, DebugError>;
 
+    /// Create a structured report of the active exception, including the fault status registers,
+    /// the fault addresses, and the stacked exception frame.
+    ///
+    /// A return value of `Ok(None)` indicates that no exception is active,
+    /// or that the architecture does not support fault reports.
+    fn fault_report(
+        &self,
+        _memory: &mut dyn MemoryInterface,
+        _stackframe_registers: &DebugRegisters,
+    ) -> Result<Option<FaultReport>, DebugError> {
+        Ok(None)
+    }
+
     /// Unwind the stack without debug info.
     ///
     /// This method can be implemented to provide a stack trace using frame pointers, for example.
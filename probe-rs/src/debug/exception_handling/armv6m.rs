@@ -1,9 +1,15 @@
+use probe_rs_target::CoreType;
+
 use crate::{
     debug::{DebugError, DebugInfo, DebugRegisters},
     Error, MemoryInterface,
 };
 
-use super::{armv6m_armv7m_shared, ExceptionInfo, ExceptionInterface};
+use super::{
+    armv6m_armv7m_shared,
+    fault_report::{self, FaultReport},
+    ExceptionInfo, ExceptionInterface,
+};
 
 /// Decode the exception number.
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -144,6 +150,19 @@ impl ExceptionInterface for ArmV6MExceptionHandler {
         //       Until then, this will return a generic error message for all hard faults on this architecture.
         Ok(ExceptionReason::from(raw_exception).expanded_description())
     }
+
+    fn fault_report(
+        &self,
+        memory_interface: &mut dyn MemoryInterface,
+        stackframe_registers: &DebugRegisters,
+    ) -> Result<Option<FaultReport>, DebugError> {
+        fault_report::cortex_m_fault_report(
+            CoreType::Armv6m,
+            self,
+            memory_interface,
+            stackframe_registers,
+        )
+    }
 }
 
 #[cfg(test)]
@@ -113,6 +113,7 @@ pub(crate) fn exception_details(
         // A fault that is escalated to the priority of a HardFault retains program counter value of the original fault,
         // So we have to unwind the frame pointer that matches.
         // Determining the frame base may need the CFA (Canonical Frame Address) to be calculated first.
+        let debug_info = debug_info.object_for_address(exception_frame_pc);
         let mut unwind_context = Box::new(gimli::UnwindContext::new());
         // let exception_interface = exception_handler_for_core(memory_interface.core.core_type());
         // let instruction_set = memory_interface.core.instruction_set().ok();
@@ -1,4 +1,10 @@
-use super::{armv6m_armv7m_shared, ExceptionInfo, ExceptionInterface};
+use probe_rs_target::CoreType;
+
+use super::{
+    armv6m_armv7m_shared,
+    fault_report::{self, FaultReport},
+    ExceptionInfo, ExceptionInterface,
+};
 use crate::{
     debug::{DebugError, DebugInfo, DebugRegisters},
     memory_mapped_bitfield_register, Error, MemoryInterface, MemoryMappedRegister,
@@ -250,7 +256,7 @@ impl ExceptionReason {
             }
             ExceptionReason::MemoryManagementFault => {
                 if let Some(source) = Cfsr(memory.read_word_32(Cfsr::get_mmio_address())?)
-                    .usage_fault_description()?
+                    .memory_management_fault_description(memory)?
                 {
                     Ok(source)
                 } else {
@@ -373,4 +379,17 @@ impl ExceptionInterface for ArmV7MExceptionHandler {
             ExceptionReason::from(raw_exception).expanded_description(memory_interface)?;
         Ok(description)
     }
+
+    fn fault_report(
+        &self,
+        memory_interface: &mut dyn MemoryInterface,
+        stackframe_registers: &DebugRegisters,
+    ) -> Result<Option<FaultReport>, DebugError> {
+        fault_report::cortex_m_fault_report(
+            CoreType::Armv7m,
+            self,
+            memory_interface,
+            stackframe_registers,
+        )
+    }
 }
@@ -4,9 +4,11 @@ use crate::{
     memory_mapped_bitfield_register, Error, MemoryMappedRegister, RegisterRole, RegisterValue,
 };
 use bitfield::bitfield;
+use probe_rs_target::CoreType;
 
 use super::{
     armv6m_armv7m_shared::{Xpsr, EXCEPTION_STACK_REGISTERS},
+    fault_report::{self, FaultReport},
     ExceptionInfo, ExceptionInterface,
 };
 
@@ -340,7 +342,7 @@ impl ExceptionReason {
             }
             ExceptionReason::MemoryManagementFault => {
                 if let Some(source) = Cfsr(memory.read_word_32(Cfsr::get_mmio_address())?)
-                    .usage_fault_description()?
+                    .memory_management_fault_description(memory)?
                 {
                     Ok(source)
                 } else {
@@ -503,6 +505,19 @@ impl ExceptionInterface for ArmV8MExceptionHandler {
             Ok(None)
         }
     }
+
+    fn fault_report(
+        &self,
+        memory_interface: &mut dyn MemoryInterface,
+        stackframe_registers: &DebugRegisters,
+    ) -> Result<Option<FaultReport>, DebugError> {
+        fault_report::cortex_m_fault_report(
+            CoreType::Armv8m,
+            self,
+            memory_interface,
+            stackframe_registers,
+        )
+    }
 }
 
 fn get_stack_frame_return_address(stackframe_registers: &DebugRegisters) -> Result<u32, Error> {
@@ -0,0 +1,845 @@
+//! A structured report of the exception which is active on a Cortex-M core.
+//!
+//! While [`ExceptionInterface::exception_description`](super::ExceptionInterface::exception_description)
+//! only provides a short summary of the first cause it finds, a [`FaultReport`] contains everything
+//! which is known about the exception: all set status bits, the fault address registers,
+//! the decoded EXC_RETURN value, the stacked exception frame and the faulting instruction.
+
+use std::fmt;
+
+use probe_rs_target::CoreType;
+use serde::Serialize;
+
+use super::{
+    armv6m_armv7m_shared::{ExcReturn, Xpsr},
+    armv7m::{Bfar, Cfsr, Hfsr, Mmfar},
+    armv8m::{Sfar, Sfsr},
+    ExceptionInterface,
+};
+use crate::{
+    architecture::arm::instruction_trace::thumb,
+    core::RegisterRole,
+    debug::{DebugError, DebugRegisters},
+    MemoryInterface, MemoryMappedRegister,
+};
+
+/// A structured report of the exception which is active on a core.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FaultReport {
+    /// The architecture specific exception number, e.g. `3` for a HardFault.
+    pub exception_number: u32,
+    /// The architectural name of the exception, e.g. `HardFault` or `IRQ5`.
+    pub exception_name: String,
+    /// The number of the external interrupt, if the exception is an external interrupt.
+    pub interrupt_number: Option<u32>,
+    /// The name of the external interrupt.
+    ///
+    /// The core does not know the names of its interrupts, so this is only filled in
+    /// when the caller has a device description, e.g. an SVD file, available.
+    pub interrupt_name: Option<String>,
+    /// A human readable description of the exception, as returned by
+    /// [`ExceptionInterface::exception_description`].
+    pub description: String,
+    /// The fault status registers, and the bits which are set in them.
+    ///
+    /// This is empty for ARMv6-M cores, which do not have any fault status registers.
+    pub status_registers: Vec<FaultStatusRegister>,
+    /// The fault address registers, and whether they hold a valid address.
+    pub fault_addresses: Vec<FaultAddress>,
+    /// The EXC_RETURN value in the link register, if it holds one.
+    pub exc_return: Option<ExcReturnInfo>,
+    /// The registers which the core pushed onto the stack on exception entry.
+    pub stacked_frame: Option<StackedFrame>,
+    /// The instruction at the stacked program counter.
+    ///
+    /// For synchronous faults, this is the instruction which caused the fault.
+    pub instruction: Option<FaultingInstruction>,
+}
+
+/// A fault status register, e.g. the HFSR.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FaultStatusRegister {
+    /// The name of the register.
+    pub name: &'static str,
+    /// The address of the register.
+    pub address: u64,
+    /// The value of the register.
+    pub value: u32,
+    /// All bits which are set in the register.
+    pub set_bits: Vec<FaultStatusBit>,
+}
+
+impl FaultStatusRegister {
+    /// Whether the bit with the given `name` is set in the register.
+    pub fn is_set(&self, name: &str) -> bool {
+        self.set_bits.iter().any(|bit| bit.name == name)
+    }
+}
+
+/// A single bit in a fault status register.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FaultStatusBit {
+    /// The position of the bit in the register.
+    pub bit: u8,
+    /// The architectural name of the bit, e.g. `FORCED`.
+    pub name: &'static str,
+    /// What it means when the bit is set.
+    pub description: &'static str,
+}
+
+/// A fault address register, e.g. the BFAR.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FaultAddress {
+    /// The name of the register.
+    pub register: &'static str,
+    /// The value of the register.
+    pub address: u32,
+    /// Whether the register holds the address of the fault, as indicated by the
+    /// corresponding `*VALID` bit in the status register.
+    pub valid: bool,
+}
+
+/// The decoded EXC_RETURN value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExcReturnInfo {
+    /// The raw EXC_RETURN value.
+    pub value: u32,
+    /// Whether the exception returns to Thread mode, rather than Handler mode.
+    pub thread_mode: bool,
+    /// Whether the exception frame is stored on the process stack, rather than the main stack.
+    pub process_stack: bool,
+    /// Whether the exception frame includes the floating point registers.
+    pub extended_frame: bool,
+    /// Whether the exception frame is stored on the secure stack.
+    ///
+    /// This is only available on ARMv8-M cores.
+    pub secure_stack: Option<bool>,
+}
+
+/// The registers which the core pushed onto the stack on exception entry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StackedFrame {
+    /// The address of the exception frame on the stack.
+    pub address: u64,
+    /// Stacked R0.
+    pub r0: u32,
+    /// Stacked R1.
+    pub r1: u32,
+    /// Stacked R2.
+    pub r2: u32,
+    /// Stacked R3.
+    pub r3: u32,
+    /// Stacked R12.
+    pub r12: u32,
+    /// Stacked link register.
+    pub lr: u32,
+    /// Stacked program counter.
+    pub pc: u32,
+    /// Stacked xPSR.
+    pub xpsr: u32,
+    /// Whether the stack was realigned to 8 bytes on exception entry.
+    pub stack_realigned: bool,
+}
+
+/// The instruction at the stacked program counter.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FaultingInstruction {
+    /// The address of the instruction.
+    pub address: u64,
+    /// The encoding of the instruction, which is two or four bytes long.
+    pub bytes: Vec<u8>,
+    /// The disassembled instruction.
+    ///
+    /// probe-rs does not disassemble instructions itself, so this is only filled in
+    /// when the caller has a disassembler available.
+    pub disassembly: Option<String>,
+}
+
+impl fmt::Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} (exception #{}, {})",
+            self.description, self.exception_number, self.exception_name
+        )?;
+
+        if let Some(interrupt_number) = self.interrupt_number {
+            match &self.interrupt_name {
+                Some(name) => writeln!(f, "  Interrupt: #{interrupt_number} {name}")?,
+                None => writeln!(f, "  Interrupt: #{interrupt_number}")?,
+            }
+        }
+
+        for register in &self.status_registers {
+            writeln!(f, "  {} = {:#010x}", register.name, register.value)?;
+            for bit in &register.set_bits {
+                writeln!(f, "    {} (bit {}): {}", bit.name, bit.bit, bit.description)?;
+            }
+        }
+
+        for address in &self.fault_addresses {
+            let validity = if address.valid { "valid" } else { "not valid" };
+            writeln!(
+                f,
+                "  {} = {:#010x} ({validity})",
+                address.register, address.address
+            )?;
+        }
+
+        if let Some(exc_return) = &self.exc_return {
+            let mode = if exc_return.thread_mode {
+                "Thread"
+            } else {
+                "Handler"
+            };
+            let stack = if exc_return.process_stack {
+                "process"
+            } else {
+                "main"
+            };
+            let security = match exc_return.secure_stack {
+                Some(true) => "secure ",
+                Some(false) => "non-secure ",
+                None => "",
+            };
+            let frame = if exc_return.extended_frame {
+                "extended"
+            } else {
+                "basic"
+            };
+            writeln!(
+                f,
+                "  EXC_RETURN = {:#010x}: return to {mode} mode, {frame} frame on the {security}{stack} stack",
+                exc_return.value
+            )?;
+        }
+
+        if let Some(frame) = &self.stacked_frame {
+            writeln!(f, "  Stacked frame at {:#010x}:", frame.address)?;
+            writeln!(
+                f,
+                "    R0:  {:#010x}  R1: {:#010x}  R2: {:#010x}  R3:   {:#010x}",
+                frame.r0, frame.r1, frame.r2, frame.r3
+            )?;
+            writeln!(
+                f,
+                "    R12: {:#010x}  LR: {:#010x}  PC: {:#010x}  xPSR: {:#010x}",
+                frame.r12, frame.lr, frame.pc, frame.xpsr
+            )?;
+        }
+
+        if let Some(instruction) = &self.instruction {
+            let bytes = instruction
+                .bytes
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            write!(f, "  Instruction at {:#010x}: {bytes}", instruction.address)?;
+            if let Some(disassembly) = &instruction.disassembly {
+                write!(f, " ({disassembly})")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+const HFSR_BITS: &[FaultStatusBit] = &[
+    FaultStatusBit {
+        bit: 1,
+        name: "VECTTBL",
+        description: "BusFault on a vector table read during exception processing",
+    },
+    FaultStatusBit {
+        bit: 30,
+        name: "FORCED",
+        description: "Escalated from a configurable fault which could not be handled",
+    },
+    FaultStatusBit {
+        bit: 31,
+        name: "DEBUGEVT",
+        description: "Debug event while halting debug is disabled",
+    },
+];
+
+const CFSR_BITS: &[FaultStatusBit] = &[
+    FaultStatusBit {
+        bit: 0,
+        name: "IACCVIOL",
+        description: "MemManage fault on an instruction fetch",
+    },
+    FaultStatusBit {
+        bit: 1,
+        name: "DACCVIOL",
+        description: "MemManage fault on a data access",
+    },
+    FaultStatusBit {
+        bit: 3,
+        name: "MUNSTKERR",
+        description: "MemManage fault when unstacking on exception return",
+    },
+    FaultStatusBit {
+        bit: 4,
+        name: "MSTKERR",
+        description: "MemManage fault when stacking on exception entry",
+    },
+    FaultStatusBit {
+        bit: 5,
+        name: "MLSPERR",
+        description: "MemManage fault during lazy floating point state preservation",
+    },
+    FaultStatusBit {
+        bit: 7,
+        name: "MMARVALID",
+        description: "MMFAR holds the address of the MemManage fault",
+    },
+    FaultStatusBit {
+        bit: 8,
+        name: "IBUSERR",
+        description: "BusFault on an instruction prefetch",
+    },
+    FaultStatusBit {
+        bit: 9,
+        name: "PRECISERR",
+        description: "Precise BusFault on a data access",
+    },
+    FaultStatusBit {
+        bit: 10,
+        name: "IMPRECISERR",
+        description: "Imprecise BusFault on a data access",
+    },
+    FaultStatusBit {
+        bit: 11,
+        name: "UNSTKERR",
+        description: "BusFault when unstacking on exception return",
+    },
+    FaultStatusBit {
+        bit: 12,
+        name: "STKERR",
+        description: "BusFault when stacking on exception entry",
+    },
+    FaultStatusBit {
+        bit: 13,
+        name: "LSPERR",
+        description: "BusFault during lazy floating point state preservation",
+    },
+    FaultStatusBit {
+        bit: 15,
+        name: "BFARVALID",
+        description: "BFAR holds the address of the BusFault",
+    },
+    FaultStatusBit {
+        bit: 16,
+        name: "UNDEFINSTR",
+        description: "Execution of an undefined instruction",
+    },
+    FaultStatusBit {
+        bit: 17,
+        name: "INVSTATE",
+        description: "Execution with an invalid EPSR, e.g. with the Thumb bit cleared",
+    },
+    FaultStatusBit {
+        bit: 18,
+        name: "INVPC",
+        description: "Invalid EXC_RETURN value or integrity check failure on exception return",
+    },
+    FaultStatusBit {
+        bit: 19,
+        name: "NOCP",
+        description: "Access to a coprocessor which is disabled or not present",
+    },
+    FaultStatusBit {
+        bit: 20,
+        name: "STKOF",
+        description: "Stack overflow detected by a stack limit register",
+    },
+    FaultStatusBit {
+        bit: 24,
+        name: "UNALIGNED",
+        description: "Unaligned memory access",
+    },
+    FaultStatusBit {
+        bit: 25,
+        name: "DIVBYZERO",
+        description: "Division by zero",
+    },
+];
+
+const SFSR_BITS: &[FaultStatusBit] = &[
+    FaultStatusBit {
+        bit: 0,
+        name: "INVEP",
+        description: "Invalid entry point into the Secure state",
+    },
+    FaultStatusBit {
+        bit: 1,
+        name: "INVIS",
+        description: "Invalid integrity signature in the exception frame on unstacking",
+    },
+    FaultStatusBit {
+        bit: 2,
+        name: "INVER",
+        description: "Invalid exception return",
+    },
+    FaultStatusBit {
+        bit: 3,
+        name: "AUVIOL",
+        description: "Non-secure access to Secure memory",
+    },
+    FaultStatusBit {
+        bit: 4,
+        name: "INVTRAN",
+        description: "Transition from Secure to Non-secure memory without a domain crossing branch",
+    },
+    FaultStatusBit {
+        bit: 5,
+        name: "LSPERR",
+        description: "SecureFault during lazy floating point state preservation",
+    },
+    FaultStatusBit {
+        bit: 6,
+        name: "SFARVALID",
+        description: "SFAR holds the address of the SecureFault",
+    },
+    FaultStatusBit {
+        bit: 7,
+        name: "LSERR",
+        description: "Error during lazy floating point state activation or deactivation",
+    },
+];
+
+/// Creates the [`FaultReport`] for a Cortex-M core of the given `core_type`,
+/// or `Ok(None)` if no exception is active.
+///
+/// Apart from the exception number, everything in the report is read on a best effort basis,
+/// so that a report is still available when e.g. the system control block is not part of a core dump.
+pub(crate) fn cortex_m_fault_report(
+    core_type: CoreType,
+    exception_interface: &dyn ExceptionInterface,
+    memory: &mut dyn MemoryInterface,
+    stackframe_registers: &DebugRegisters,
+) -> Result<Option<FaultReport>, DebugError> {
+    let exception_number = exception_interface.raw_exception(stackframe_registers)?;
+    if exception_number == 0 {
+        return Ok(None);
+    }
+
+    let exception_name = exception_name(core_type, exception_number);
+    let description = exception_interface
+        .exception_description(exception_number, memory)
+        .unwrap_or_else(|_| exception_name.clone());
+
+    let mut status_registers = Vec::new();
+    let mut fault_addresses = Vec::new();
+
+    if core_type != CoreType::Armv6m {
+        let is_armv8m = core_type == CoreType::Armv8m;
+
+        if let Some(hfsr) = status_register::<Hfsr>(memory, HFSR_BITS) {
+            status_registers.push(hfsr);
+        }
+
+        let cfsr_bits = CFSR_BITS
+            .iter()
+            .filter(|bit| is_armv8m || bit.name != "STKOF")
+            .cloned()
+            .collect::<Vec<_>>();
+        if let Some(cfsr) = status_register::<Cfsr>(memory, &cfsr_bits) {
+            if let Some(mmfar) = fault_address::<Mmfar>(memory, cfsr.is_set("MMARVALID")) {
+                fault_addresses.push(mmfar);
+            }
+            if let Some(bfar) = fault_address::<Bfar>(memory, cfsr.is_set("BFARVALID")) {
+                fault_addresses.push(bfar);
+            }
+            status_registers.push(cfsr);
+        }
+
+        if is_armv8m {
+            if let Some(sfsr) = status_register::<Sfsr>(memory, SFSR_BITS) {
+                if let Some(sfar) = fault_address::<Sfar>(memory, sfsr.is_set("SFARVALID")) {
+                    fault_addresses.push(sfar);
+                }
+                status_registers.push(sfsr);
+            }
+        }
+    }
+
+    let exc_return = stackframe_registers
+        .get_register_value_by_role(&RegisterRole::ReturnAddress)
+        .ok()
+        .and_then(|value| decode_exc_return(core_type, value as u32));
+
+    let stacked_frame = exc_return
+        .as_ref()
+        .and_then(|exc_return| read_stacked_frame(exc_return, memory, stackframe_registers));
+
+    let instruction = stacked_frame
+        .as_ref()
+        .and_then(|frame| read_instruction(memory, frame.pc as u64));
+
+    Ok(Some(FaultReport {
+        exception_number,
+        exception_name,
+        interrupt_number: exception_number.checked_sub(16),
+        interrupt_name: None,
+        description,
+        status_registers,
+        fault_addresses,
+        exc_return,
+        stacked_frame,
+        instruction,
+    }))
+}
+
+/// The architectural name of the exception.
+fn exception_name(core_type: CoreType, exception_number: u32) -> String {
+    let name = match (exception_number, core_type) {
+        (1, _) => "Reset",
+        (2, _) => "NMI",
+        (3, _) => "HardFault",
+        (4, CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m) => "MemManage",
+        (5, CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m) => "BusFault",
+        (6, CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m) => "UsageFault",
+        (7, CoreType::Armv8m) => "SecureFault",
+        (11, _) => "SVCall",
+        (12, CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m) => "DebugMonitor",
+        (14, _) => "PendSV",
+        (15, _) => "SysTick",
+        (16.., _) => return format!("IRQ{}", exception_number - 16),
+        _ => "Reserved",
+    };
+
+    name.to_string()
+}
+
+/// Reads a fault status register, and collects the bits from `bits` which are set in it.
+fn status_register<R: MemoryMappedRegister<u32>>(
+    memory: &mut dyn MemoryInterface,
+    bits: &[FaultStatusBit],
+) -> Option<FaultStatusRegister> {
+    let value = memory.read_word_32(R::get_mmio_address()).ok()?;
+
+    Some(FaultStatusRegister {
+        name: R::NAME,
+        address: R::get_mmio_address(),
+        value,
+        set_bits: bits
+            .iter()
+            .filter(|bit| value & (1 << bit.bit) != 0)
+            .cloned()
+            .collect(),
+    })
+}
+
+/// Reads a fault address register.
+fn fault_address<R: MemoryMappedRegister<u32>>(
+    memory: &mut dyn MemoryInterface,
+    valid: bool,
+) -> Option<FaultAddress> {
+    let address = memory.read_word_32(R::get_mmio_address()).ok()?;
+
+    Some(FaultAddress {
+        register: R::NAME,
+        address,
+        valid,
+    })
+}
+
+/// Decodes the value of the link register, if it is an EXC_RETURN value.
+fn decode_exc_return(core_type: CoreType, value: u32) -> Option<ExcReturnInfo> {
+    let exc_return = ExcReturn(value);
+
+    if core_type == CoreType::Armv8m {
+        // ARMv8-M uses an 0xFF prefix, and stores the security state of the stack in bit 6.
+        if value >> 24 != 0xFF {
+            return None;
+        }
+
+        Some(ExcReturnInfo {
+            value,
+            thread_mode: exc_return.return_to_thread(),
+            process_stack: exc_return.use_process_stack(),
+            extended_frame: !exc_return.use_standard_stackframe(),
+            secure_stack: Some(value & (1 << 6) != 0),
+        })
+    } else {
+        if exc_return.is_exception_flag() != 0xF {
+            return None;
+        }
+
+        Some(ExcReturnInfo {
+            value,
+            thread_mode: exc_return.return_to_thread(),
+            process_stack: exc_return.use_process_stack(),
+            extended_frame: !exc_return.use_standard_stackframe(),
+            secure_stack: None,
+        })
+    }
+}
+
+/// How many words above the stack pointer are searched for the exception frame.
+const FRAME_SEARCH_WORDS: usize = 64;
+
+/// Reads the exception frame from the stack selected by EXC_RETURN.
+///
+/// The frame is only found at the stack pointer while the handler has not pushed anything onto
+/// the same stack, e.g. when the core halted on exception entry because of a vector catch.
+/// Otherwise, the frame is searched for in the words above the stack pointer: a valid frame has the
+/// Thumb bit set in its stacked xPSR, and its stacked exception number matches the mode EXC_RETURN
+/// returns to.
+fn read_stacked_frame(
+    exc_return: &ExcReturnInfo,
+    memory: &mut dyn MemoryInterface,
+    stackframe_registers: &DebugRegisters,
+) -> Option<StackedFrame> {
+    const FRAME_WORDS: usize = 8;
+
+    let stack_pointer_role = if exc_return.process_stack {
+        RegisterRole::ProcessStackPointer
+    } else {
+        RegisterRole::MainStackPointer
+    };
+    let stack_pointer = stackframe_registers
+        .get_register_value_by_role(&stack_pointer_role)
+        .or_else(|_| stackframe_registers.get_register_value_by_role(&RegisterRole::StackPointer))
+        .ok()?;
+
+    // The search window may extend beyond the end of the stack, so it is read in chunks
+    // until the first word which can't be read.
+    let mut words = Vec::new();
+    'search_window: for chunk in 0..FRAME_SEARCH_WORDS / FRAME_WORDS + 1 {
+        let chunk_address = stack_pointer + (chunk * FRAME_WORDS * 4) as u64;
+        let mut chunk = [0u32; FRAME_WORDS];
+        if memory.read_32(chunk_address, &mut chunk).is_ok() {
+            words.extend_from_slice(&chunk);
+            continue;
+        }
+
+        for word_address in (0..FRAME_WORDS).map(|word| chunk_address + word as u64 * 4) {
+            match memory.read_word_32(word_address) {
+                Ok(word) => words.push(word),
+                Err(_) => break 'search_window,
+            }
+        }
+    }
+
+    let (offset, frame) = words
+        .windows(FRAME_WORDS)
+        .enumerate()
+        .find(|(_, frame)| is_exception_frame(exc_return, frame[7]))?;
+    let [r0, r1, r2, r3, r12, lr, pc, xpsr] = frame.try_into().ok()?;
+
+    Some(StackedFrame {
+        address: stack_pointer + offset as u64 * 4,
+        r0,
+        r1,
+        r2,
+        r3,
+        r12,
+        lr,
+        pc,
+        xpsr,
+        stack_realigned: Xpsr(xpsr).stack_was_realigned(),
+    })
+}
+
+/// Whether `xpsr` is a plausible stacked xPSR of a frame which returns as described by `exc_return`.
+fn is_exception_frame(exc_return: &ExcReturnInfo, xpsr: u32) -> bool {
+    const THUMB_BIT: u32 = 1 << 24;
+
+    let returns_to_thread = Xpsr(xpsr).exception_number() == 0;
+    xpsr & THUMB_BIT != 0 && returns_to_thread == exc_return.thread_mode
+}
+
+/// Reads the Thumb instruction at `address`.
+fn read_instruction(memory: &mut dyn MemoryInterface, address: u64) -> Option<FaultingInstruction> {
+    let address = address & !1;
+
+    let mut bytes = vec![0u8; 2];
+    memory.read_8(address, &mut bytes).ok()?;
+
+    if thumb::is_32bit(u16::from_le_bytes([bytes[0], bytes[1]])) {
+        bytes.resize(4, 0);
+        memory.read_8(address + 2, &mut bytes[2..]).ok()?;
+    }
+
+    Some(FaultingInstruction {
+        address,
+        bytes,
+        disassembly: None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use probe_rs_target::CoreType;
+
+    use crate::{
+        architecture::arm::core::registers::cortex_m::{MSP, PC, PSP, RA, SP, XPSR},
+        debug::{exception_handling::exception_handler_for_core, DebugRegister, DebugRegisters},
+        test::MockMemory,
+        RegisterValue,
+    };
+
+    const STACK: u64 = 0x2000_1000;
+
+    fn registers(xpsr: u32, lr: u32) -> DebugRegisters {
+        let mut registers = DebugRegisters(vec![]);
+        for (core_register, value) in [
+            (&XPSR, xpsr),
+            (&RA, lr),
+            (&PC, 0x0000_0100),
+            (&SP, STACK as u32),
+            (&MSP, STACK as u32),
+            (&PSP, 0x2000_2000),
+        ] {
+            registers.0.push(DebugRegister {
+                dwarf_id: None,
+                core_register,
+                value: Some(RegisterValue::U32(value)),
+            });
+        }
+        registers
+    }
+
+    /// Creates a stack with an exception frame `offset` words above the stack pointer,
+    /// and the instruction at the stacked program counter.
+    fn memory_with_frame(offset: usize, pc: u32, instruction: &[u8]) -> MockMemory {
+        let mut stack = vec![0u32; 80];
+        stack[offset..offset + 8].copy_from_slice(&[1, 2, 3, 4, 12, 0x0000_0201, pc, 0x0100_0000]);
+
+        let mut memory = MockMemory::new();
+        memory.add_word_range(STACK, &stack);
+        memory.add_range(pc as u64, instruction.to_vec());
+        memory
+    }
+
+    /// Adds the fault status and fault address registers of the system control block.
+    fn add_fault_registers(memory: &mut MockMemory, cfsr: u32, hfsr: u32, mmfar: u32, bfar: u32) {
+        memory.add_word_range(0xE000_ED28, &[cfsr, hfsr]);
+        memory.add_word_range(0xE000_ED34, &[mmfar, bfar]);
+    }
+
+    #[test]
+    fn no_report_without_active_exception() {
+        let handler = exception_handler_for_core(CoreType::Armv7m);
+        let mut memory = MockMemory::new();
+
+        let report = handler
+            .fault_report(&mut memory, &registers(0, 0x0000_0201))
+            .unwrap();
+
+        assert_eq!(report, None);
+    }
+
+    #[test]
+    fn armv6m_hardfault() {
+        let handler = exception_handler_for_core(CoreType::Armv6m);
+        // udf #0xfe
+        let mut memory = memory_with_frame(0, 0x0000_0300, &[0xfe, 0xde]);
+
+        let report = handler
+            .fault_report(&mut memory, &registers(3, 0xFFFF_FFF9))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(report.exception_name, "HardFault");
+        assert_eq!(report.description, "HardFault");
+        assert!(report.status_registers.is_empty());
+        assert!(report.fault_addresses.is_empty());
+
+        let exc_return = report.exc_return.unwrap();
+        assert!(exc_return.thread_mode);
+        assert!(!exc_return.process_stack);
+        assert!(!exc_return.extended_frame);
+        assert_eq!(exc_return.secure_stack, None);
+
+        let frame = report.stacked_frame.unwrap();
+        assert_eq!(frame.address, STACK);
+        assert_eq!(frame.r12, 12);
+        assert_eq!(frame.pc, 0x0000_0300);
+
+        let instruction = report.instruction.unwrap();
+        assert_eq!(instruction.address, 0x0000_0300);
+        assert_eq!(instruction.bytes, vec![0xfe, 0xde]);
+    }
+
+    #[test]
+    fn armv7m_escalated_bus_fault() {
+        let handler = exception_handler_for_core(CoreType::Armv7m);
+        // ldr.w r0, [r1, #4], with the frame above the registers pushed by the handler.
+        let mut memory = memory_with_frame(2, 0x0000_0400, &[0xd1, 0xf8, 0x04, 0x00]);
+        // CFSR.PRECISERR | CFSR.BFARVALID, HFSR.FORCED
+        add_fault_registers(&mut memory, 0x0000_8200, 0x4000_0000, 0, 0x6000_0004);
+
+        let report = handler
+            .fault_report(&mut memory, &registers(3, 0xFFFF_FFF9))
+            .unwrap()
+            .unwrap();
+
+        let set_bits = report
+            .status_registers
+            .iter()
+            .map(|register| {
+                (
+                    register.name,
+                    register
+                        .set_bits
+                        .iter()
+                        .map(|bit| bit.name)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            set_bits,
+            vec![
+                ("HFSR", vec!["FORCED"]),
+                ("CFSR", vec!["PRECISERR", "BFARVALID"])
+            ]
+        );
+
+        let fault_addresses = report
+            .fault_addresses
+            .iter()
+            .map(|address| (address.register, address.address, address.valid))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fault_addresses,
+            vec![("MMFAR", 0, false), ("BFAR", 0x6000_0004, true)]
+        );
+
+        assert_eq!(report.stacked_frame.unwrap().address, STACK + 8);
+        assert_eq!(
+            report.instruction.unwrap().bytes,
+            vec![0xd1, 0xf8, 0x04, 0x00]
+        );
+    }
+
+    #[test]
+    fn armv8m_external_interrupt_as_json() {
+        let handler = exception_handler_for_core(CoreType::Armv8m);
+        // nop
+        let mut memory = memory_with_frame(0, 0x0000_0500, &[0x00, 0xbf]);
+        add_fault_registers(&mut memory, 0, 0, 0, 0);
+        // SFSR, SFAR
+        memory.add_word_range(0xE000_EDE4, &[0, 0]);
+
+        let report = handler
+            .fault_report(&mut memory, &registers(21, 0xFFFF_FFA8))
+            .unwrap()
+            .unwrap();
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["exception_number"], 21);
+        assert_eq!(json["exception_name"], "IRQ5");
+        assert_eq!(json["interrupt_number"], 5);
+        assert_eq!(json["exc_return"]["secure_stack"], false);
+        assert_eq!(json["exc_return"]["extended_frame"], true);
+        assert_eq!(json["stacked_frame"]["pc"], 0x0000_0500);
+        assert_eq!(json["instruction"]["bytes"], serde_json::json!([0, 0xbf]));
+    }
+}
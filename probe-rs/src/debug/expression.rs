@@ -0,0 +1,2658 @@
+//! Evaluation of expressions in the context of a stack frame, e.g. for the watch expressions and
+//! the REPL of a debugger.
+//!
+//! The syntax is the common subset of Rust and C expressions:
+//! - Names of local variables, paths of static variables (`crate::module::STATIC`) and registers
+//!   (`$pc`, or the plain register name if no variable has the same name).
+//! - Literals in Rust or C syntax: integers with an optional type suffix (`42`, `0xff_u8`, `10UL`),
+//!   floats (`1.5`, `2e3f32`), characters (`'a'`, `b'a'`), strings and `true`/`false`.
+//! - Field access with `.` and `->`, including tuple fields (`pair.0`), indexing of arrays, slices
+//!   and pointers with `[]`, dereferencing with `*` and taking the address with `&`.
+//! - Casts to primitive types and to types from the debug information, either with `as`
+//!   (`x as u8`, `address as *const Foo`) or in C syntax (`(uint8_t)x`, `(struct foo *)address`).
+//! - The arithmetic, comparison, bitwise and logical operators.
+//!
+//! The operator precedence is the one of Rust for all languages, so `flags & 0x10 == 0x10` compares
+//! the result of the bitwise operation in C programs, too.
+
+use super::{
+    extract_byte_size,
+    function_lookup::{path_matches, path_segments},
+    language::value::format_float,
+    stack_frame::StackFrameInfo,
+    DebugError, DebugInfo, DebugRegisters, ObjectRef, StackFrame, Variable, VariableCache,
+    VariableLocation, VariableName, VariableType,
+};
+use crate::MemoryInterface;
+use gimli::{DebugInfoOffset, DwLang, UnitOffset};
+
+/// A reference to the DIE of a type, in the object file with the given index.
+type TypeNode = (usize, DebugInfoOffset, UnitOffset);
+
+/// The result of evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvaluatedExpression {
+    /// The value, formatted in the same way as the values of variables.
+    pub value: String,
+    /// The name of the type of the value.
+    pub type_name: String,
+    /// The address of the value, if the value is stored in memory.
+    pub memory_location: Option<u64>,
+    /// The key of the variable holding the value, if the value is a variable of the stack frame or
+    /// of the static scope. It can be used to retrieve the children of the variable.
+    pub variable_key: Option<ObjectRef>,
+    truthiness: Option<bool>,
+}
+
+impl EvaluatedExpression {
+    /// Interpret the value as a condition.
+    ///
+    /// Numbers, characters and pointers are `true` if they are not zero, strings if they are not empty.
+    pub fn is_true(&self) -> Result<bool, DebugError> {
+        self.truthiness.ok_or_else(|| {
+            error(format!(
+                "A value of type {} cannot be used as a condition",
+                self.type_name
+            ))
+        })
+    }
+}
+
+impl DebugInfo {
+    /// Evaluate an `expression` in the context of the `stack_frame`.
+    ///
+    /// Names are looked up in the local variables of the stack frame first, then in the
+    /// `static_variables`, and then in the registers. See the [module documentation](self) for the
+    /// supported syntax.
+    pub fn evaluate_expression(
+        &self,
+        expression: &str,
+        memory: &mut dyn MemoryInterface,
+        stack_frame: &mut StackFrame,
+        static_variables: Option<&mut VariableCache>,
+    ) -> Result<EvaluatedExpression, DebugError> {
+        let expression = parse(expression)?;
+
+        let StackFrame {
+            registers,
+            frame_base,
+            canonical_frame_address,
+            local_variables,
+            ..
+        } = stack_frame;
+
+        let language = local_variables
+            .as_ref()
+            .map_or(gimli::DW_LANG_Rust, |cache| cache.root_variable().language);
+        let address_bits = match registers.get_address_size_bytes() {
+            0 => 32,
+            bytes => bytes as u32 * 8,
+        };
+
+        let mut scratch_root = Variable::new(None);
+        scratch_root.name = VariableName::Named("<expression>".to_string());
+
+        let mut evaluator = Evaluator {
+            debug_info: self,
+            memory,
+            registers,
+            frame_base: *frame_base,
+            canonical_frame_address: *canonical_frame_address,
+            locals: local_variables.as_mut(),
+            statics: static_variables,
+            scratch: VariableCache::new(scratch_root),
+            language,
+            address_bits,
+        };
+
+        let value = evaluator.evaluate(&expression)?;
+        evaluator.result(value)
+    }
+
+    /// Find a type by its name, which can be qualified with the path of its namespaces.
+    fn find_type(&self, name: &str) -> Result<Option<TypeNode>, DebugError> {
+        let query = path_segments(name);
+        if query.is_empty() {
+            return Ok(None);
+        }
+
+        for object in self.objects() {
+            if let Some((header_offset, type_offset)) = object.find_object_type(&query)? {
+                return Ok(Some((object.object_index, header_offset, type_offset)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find a type in this object file.
+    fn find_object_type(
+        &self,
+        query: &[String],
+    ) -> Result<Option<(DebugInfoOffset, UnitOffset)>, DebugError> {
+        for unit_info in &self.unit_infos {
+            // The names of the enclosing namespaces and types, with their depth in the tree.
+            let mut scopes: Vec<(isize, String)> = Vec::new();
+
+            let mut depth = 0;
+            let mut entries = unit_info.unit.entries();
+            while let Some((delta, entry)) = entries.next_dfs()? {
+                depth += delta;
+                scopes.retain(|(scope_depth, _)| *scope_depth < depth);
+
+                let is_type = match entry.tag() {
+                    gimli::DW_TAG_namespace => false,
+                    gimli::DW_TAG_base_type
+                    | gimli::DW_TAG_structure_type
+                    | gimli::DW_TAG_class_type
+                    | gimli::DW_TAG_union_type
+                    | gimli::DW_TAG_enumeration_type
+                    | gimli::DW_TAG_typedef => true,
+                    _ => continue,
+                };
+
+                let Some(entry_name) = self.die_name(unit_info, entry) else {
+                    continue;
+                };
+
+                if is_type && entry.attr(gimli::DW_AT_declaration)?.is_none() {
+                    let mut candidate = scopes
+                        .iter()
+                        .map(|(_, scope)| scope.clone())
+                        .collect::<Vec<_>>();
+                    candidate.push(entry_name.replace(' ', ""));
+
+                    if path_matches(&candidate, query) {
+                        return Ok(Some((unit_info.debug_info_offset()?, entry.offset())));
+                    }
+                }
+
+                scopes.push((depth, entry_name));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The size of a type in bytes, following typedefs and type modifiers.
+    fn type_byte_size(
+        &self,
+        (object_index, header_offset, type_offset): TypeNode,
+    ) -> Result<Option<u64>, DebugError> {
+        let unit = self.object(object_index).unit_info_at(header_offset)?.unit;
+
+        let mut entry = unit.entry(type_offset)?;
+        loop {
+            if let Some(byte_size) = extract_byte_size(&entry) {
+                return Ok(Some(byte_size));
+            }
+
+            match entry.attr_value(gimli::DW_AT_type)? {
+                Some(gimli::AttributeValue::UnitRef(offset))
+                    if entry.tag() != gimli::DW_TAG_array_type =>
+                {
+                    entry = unit.entry(offset)?;
+                }
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    /// Create a variable of the given type, stored at `address`, as a child of the root of the `cache`.
+    fn cache_variable_at_address(
+        &self,
+        cache: &mut VariableCache,
+        memory: &mut dyn MemoryInterface,
+        (object_index, header_offset, type_offset): TypeNode,
+        address: u64,
+        name: String,
+        frame_info: StackFrameInfo<'_>,
+    ) -> Result<Variable, DebugError> {
+        let debug_info = self.object(object_index);
+        let unit_info = debug_info.unit_info_at(header_offset)?;
+        let type_entry = unit_info.unit.entry(type_offset)?;
+
+        let parent_variable = cache.root_variable().clone();
+        let mut variable =
+            cache.create_variable(parent_variable.variable_key(), Some(&unit_info))?;
+        variable.name = VariableName::Named(name);
+        variable.memory_location = VariableLocation::Address(address);
+
+        unit_info.extract_type(
+            debug_info,
+            &type_entry,
+            &parent_variable,
+            &mut variable,
+            memory,
+            cache,
+            frame_info,
+        )?;
+
+        Ok(variable)
+    }
+}
+
+/// The type of a variable, see [`Variable::type_node`].
+fn type_node(variable: &Variable) -> Option<TypeNode> {
+    let (header_offset, type_offset) = variable.type_node?;
+    Some((variable.object_index, header_offset, type_offset))
+}
+
+fn error(message: impl Into<String>) -> DebugError {
+    DebugError::Other(message.into())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Register(String),
+    Int(i128, Option<String>),
+    Float(f64, Option<String>),
+    Char(char),
+    Str(String),
+    Punct(&'static str),
+}
+
+/// Punctuation, ordered so that the longest operators are matched first.
+const PUNCTUATION: [&str; 29] = [
+    "::", "->", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "&", "|",
+    "^", "!", "~", "<", ">", "=", "(", ")", "[", "]", ".", ",",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, DebugError> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let c = chars[index];
+
+        if c.is_whitespace() {
+            index += 1;
+        } else if c == 'b' && chars.get(index + 1) == Some(&'\'') {
+            // Byte literal, `b'a'`
+            let (value, next) = lex_char(&chars, index + 1)?;
+            if !value.is_ascii() {
+                return Err(error(format!("Byte literal {value:?} is not ASCII")));
+            }
+            tokens.push(Token::Int(value as i128, Some("u8".to_string())));
+            index = next;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = index;
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                index += 1;
+            }
+            let ident = chars[start..index].iter().collect::<String>();
+            tokens.push(match ident.as_str() {
+                "NULL" | "nullptr" => Token::Int(0, None),
+                _ => Token::Ident(ident),
+            });
+        } else if c == '$' {
+            let start = index + 1;
+            index = start;
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                index += 1;
+            }
+            if index == start {
+                return Err(error("Expected a register name after `$`"));
+            }
+            tokens.push(Token::Register(chars[start..index].iter().collect()));
+        } else if c.is_ascii_digit() {
+            // Tuple fields, like `a.0.1`, are not floats.
+            let after_dot = tokens.last() == Some(&Token::Punct("."));
+            let (token, next) = lex_number(&chars, index, after_dot)?;
+            tokens.push(token);
+            index = next;
+        } else if c == '\'' {
+            let (value, next) = lex_char(&chars, index)?;
+            tokens.push(Token::Char(value));
+            index = next;
+        } else if c == '"' {
+            let mut value = String::new();
+            index += 1;
+            loop {
+                match chars.get(index) {
+                    None => return Err(error("Unterminated string literal")),
+                    Some('"') => break,
+                    Some('\\') => {
+                        let (escaped, next) = lex_escape(&chars, index)?;
+                        value.push(escaped);
+                        index = next;
+                    }
+                    Some(&c) => {
+                        value.push(c);
+                        index += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Str(value));
+            index += 1;
+        } else if let Some(punct) = PUNCTUATION.iter().find(|punct| {
+            punct
+                .chars()
+                .enumerate()
+                .all(|(offset, p)| chars.get(index + offset) == Some(&p))
+        }) {
+            tokens.push(Token::Punct(punct));
+            index += punct.len();
+        } else {
+            return Err(error(format!("Unexpected character {c:?}")));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Lex an integer or float literal, with an optional type suffix.
+fn lex_number(
+    chars: &[char],
+    start: usize,
+    integer_only: bool,
+) -> Result<(Token, usize), DebugError> {
+    let mut index = start;
+    let digits_while = |index: &mut usize, radix: u32| {
+        let mut digits = String::new();
+        while let Some(&c) = chars.get(*index) {
+            if c == '_' {
+                *index += 1;
+            } else if c.is_digit(radix) {
+                digits.push(c);
+                *index += 1;
+            } else {
+                break;
+            }
+        }
+        digits
+    };
+
+    let prefix = chars.get(index + 1).map(|c| c.to_ascii_lowercase());
+    let radix = match (chars[index], prefix) {
+        (_, _) if integer_only => 10,
+        ('0', Some('x')) => 16,
+        ('0', Some('o')) => 8,
+        ('0', Some('b')) => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        index += 2;
+    }
+
+    let mut digits = digits_while(&mut index, radix);
+    let mut is_float = false;
+
+    if radix == 10 && !integer_only {
+        if chars.get(index) == Some(&'.') && chars.get(index + 1).is_some_and(char::is_ascii_digit)
+        {
+            index += 1;
+            digits.push('.');
+            digits.push_str(&digits_while(&mut index, 10));
+            is_float = true;
+        }
+
+        if matches!(chars.get(index), Some('e' | 'E')) {
+            let sign = matches!(chars.get(index + 1), Some('+' | '-'));
+            let exponent_start = index + 1 + sign as usize;
+            if chars.get(exponent_start).is_some_and(char::is_ascii_digit) {
+                digits.push('e');
+                if sign {
+                    digits.push(chars[index + 1]);
+                }
+                index = exponent_start;
+                digits.push_str(&digits_while(&mut index, 10));
+                is_float = true;
+            }
+        }
+    }
+
+    let suffix_start = index;
+    while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+        index += 1;
+    }
+    let suffix = chars[suffix_start..index]
+        .iter()
+        .filter(|c| **c != '_')
+        .collect::<String>();
+    let suffix = (!suffix.is_empty()).then_some(suffix);
+    let literal = chars[start..index].iter().collect::<String>();
+
+    let is_float_suffix = matches!(suffix.as_deref(), Some("f32" | "f64" | "f" | "F"));
+    if is_float || (is_float_suffix && radix == 10) {
+        let value = digits
+            .parse::<f64>()
+            .map_err(|_| error(format!("Invalid float literal {literal:?}")))?;
+        return Ok((Token::Float(value, suffix), index));
+    }
+
+    if digits.is_empty() {
+        return Err(error(format!("Invalid integer literal {literal:?}")));
+    }
+    let value = u128::from_str_radix(&digits, radix)
+        .map_err(|_| error(format!("Integer literal {literal:?} is too large")))?;
+
+    Ok((Token::Int(value as i128, suffix), index))
+}
+
+/// Lex a character literal, starting at the opening quote.
+fn lex_char(chars: &[char], start: usize) -> Result<(char, usize), DebugError> {
+    let (value, next) = match chars.get(start + 1) {
+        Some('\\') => lex_escape(chars, start + 1)?,
+        Some(&c) if c != '\'' => (c, start + 2),
+        _ => return Err(error("Empty character literal")),
+    };
+
+    if chars.get(next) != Some(&'\'') {
+        return Err(error("Unterminated character literal"));
+    }
+
+    Ok((value, next + 1))
+}
+
+/// Lex an escape sequence, starting at the backslash.
+fn lex_escape(chars: &[char], start: usize) -> Result<(char, usize), DebugError> {
+    let Some(&c) = chars.get(start + 1) else {
+        return Err(error("Unterminated escape sequence"));
+    };
+
+    let simple = match c {
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        '0' => Some('\0'),
+        '\\' => Some('\\'),
+        '\'' => Some('\''),
+        '"' => Some('"'),
+        _ => None,
+    };
+    if let Some(simple) = simple {
+        return Ok((simple, start + 2));
+    }
+
+    let (digits, next) = match c {
+        'x' => {
+            let end = (start + 4).min(chars.len());
+            (chars[start + 2..end].iter().collect::<String>(), end)
+        }
+        'u' if chars.get(start + 2) == Some(&'{') => {
+            let Some(length) = chars[start + 3..].iter().position(|c| *c == '}') else {
+                return Err(error("Unterminated unicode escape"));
+            };
+            (
+                chars[start + 3..start + 3 + length].iter().collect(),
+                start + 4 + length,
+            )
+        }
+        other => return Err(error(format!("Unknown escape sequence \\{other}"))),
+    };
+
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .map(|c| (c, next))
+        .ok_or_else(|| error(format!("Invalid escape sequence \\{c}{digits}")))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Int(i128, Option<String>),
+    Float(f64, Option<String>),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    /// A variable name or the path of a static variable.
+    Name(String),
+    Register(String),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Cast(Box<Expr>, TypeExpr),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnaryOp {
+    Neg,
+    Not,
+    BitNot,
+    Deref,
+    AddressOf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    Shl,
+    Shr,
+    BitAnd,
+    BitXor,
+    BitOr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    /// The operator for a token, with its precedence. Higher values bind more tightly.
+    fn from_token(token: &Token) -> Option<(BinaryOp, u8)> {
+        let Token::Punct(punct) = token else {
+            return None;
+        };
+
+        Some(match *punct {
+            "||" => (BinaryOp::Or, 1),
+            "&&" => (BinaryOp::And, 2),
+            "==" => (BinaryOp::Eq, 3),
+            "!=" => (BinaryOp::Ne, 3),
+            "<" => (BinaryOp::Lt, 3),
+            "<=" => (BinaryOp::Le, 3),
+            ">" => (BinaryOp::Gt, 3),
+            ">=" => (BinaryOp::Ge, 3),
+            "|" => (BinaryOp::BitOr, 4),
+            "^" => (BinaryOp::BitXor, 5),
+            "&" => (BinaryOp::BitAnd, 6),
+            "<<" => (BinaryOp::Shl, 7),
+            ">>" => (BinaryOp::Shr, 7),
+            "+" => (BinaryOp::Add, 8),
+            "-" => (BinaryOp::Sub, 8),
+            "*" => (BinaryOp::Mul, 9),
+            "/" => (BinaryOp::Div, 9),
+            "%" => (BinaryOp::Rem, 9),
+            _ => return None,
+        })
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitXor => "^",
+            BinaryOp::BitOr => "|",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+
+    fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+        )
+    }
+
+    fn compare<T: PartialOrd>(self, lhs: T, rhs: T) -> bool {
+        match self {
+            BinaryOp::Eq => lhs == rhs,
+            BinaryOp::Ne => lhs != rhs,
+            BinaryOp::Lt => lhs < rhs,
+            BinaryOp::Le => lhs <= rhs,
+            BinaryOp::Gt => lhs > rhs,
+            BinaryOp::Ge => lhs >= rhs,
+            _ => unreachable!("{self:?} is not a comparison"),
+        }
+    }
+}
+
+/// The target type of a cast.
+#[derive(Clone, Debug, PartialEq)]
+enum TypeExpr {
+    Named(String),
+    Pointer(Box<TypeExpr>),
+}
+
+/// Words which can be part of the name of a C type.
+const C_TYPE_WORDS: [&str; 6] = ["unsigned", "signed", "short", "long", "int", "char"];
+
+fn parse(expression: &str) -> Result<Expr, DebugError> {
+    let tokens = tokenize(expression)?;
+    if tokens.is_empty() {
+        return Err(error("Empty expression"));
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+    let expr = parser.binary(0)?;
+
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(error(format!("Unexpected {}", describe_token(token)))),
+    }
+}
+
+fn describe_token(token: &Token) -> String {
+    match token {
+        Token::Ident(ident) => format!("`{ident}`"),
+        Token::Register(name) => format!("`${name}`"),
+        Token::Int(value, _) => format!("`{value}`"),
+        Token::Float(value, _) => format!("`{value}`"),
+        Token::Char(value) => format!("{value:?}"),
+        Token::Str(value) => format!("{value:?}"),
+        Token::Punct(punct) => format!("`{punct}`"),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn peek_ident(&self, ident: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(i)) if i == ident)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> Result<(), DebugError> {
+        if self.peek_punct(punct) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{punct}`")))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> DebugError {
+        match self.peek() {
+            Some(token) => error(format!(
+                "Expected {expected}, found {}",
+                describe_token(token)
+            )),
+            None => error(format!("Expected {expected} at the end of the expression")),
+        }
+    }
+
+    /// Parse binary operators with at least the given precedence.
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, DebugError> {
+        let mut lhs = self.cast()?;
+
+        while let Some((op, precedence)) = self.peek().and_then(BinaryOp::from_token) {
+            if precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+            let rhs = self.binary(precedence + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    /// Parse Rust casts, `x as T`.
+    fn cast(&mut self) -> Result<Expr, DebugError> {
+        let mut expr = self.unary()?;
+
+        while self.peek_ident("as") {
+            self.position += 1;
+            let target = self.type_expr(false)?;
+            expr = Expr::Cast(Box::new(expr), target);
+        }
+
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, DebugError> {
+        let op = match self.peek() {
+            Some(Token::Punct("-")) => UnaryOp::Neg,
+            Some(Token::Punct("!")) => UnaryOp::Not,
+            Some(Token::Punct("~")) => UnaryOp::BitNot,
+            Some(Token::Punct("*")) => UnaryOp::Deref,
+            Some(Token::Punct("&")) => UnaryOp::AddressOf,
+            Some(Token::Punct("(")) => {
+                if let Some(target) = self.c_cast_type() {
+                    let operand = self.unary()?;
+                    return Ok(Expr::Cast(Box::new(operand), target));
+                }
+                return self.postfix();
+            }
+            _ => return self.postfix(),
+        };
+
+        self.position += 1;
+        if op == UnaryOp::AddressOf && self.peek_ident("mut") {
+            self.position += 1;
+        }
+
+        let operand = self.unary()?;
+        Ok(Expr::Unary(op, Box::new(operand)))
+    }
+
+    /// Try to parse the `(T)` of a C cast. The position is only advanced if it is a cast.
+    fn c_cast_type(&mut self) -> Option<TypeExpr> {
+        let start = self.position;
+        self.position += 1;
+
+        let target = self
+            .type_expr(true)
+            .ok()
+            .filter(|_| self.expect_punct(")").is_ok());
+
+        // `(name)` can be a cast or a parenthesized variable, depending on what follows.
+        let is_cast = target.as_ref().is_some_and(|target| {
+            let operand_follows = match self.peek() {
+                Some(Token::Ident(ident)) => ident != "as",
+                Some(
+                    Token::Register(_)
+                    | Token::Int(..)
+                    | Token::Float(..)
+                    | Token::Char(_)
+                    | Token::Str(_)
+                    | Token::Punct("("),
+                ) => true,
+                _ => false,
+            };
+            let is_type = match target {
+                TypeExpr::Pointer(_) => true,
+                TypeExpr::Named(name) => name.contains(' ') || is_primitive_type_name(name),
+            };
+
+            operand_follows || (is_type && matches!(self.peek(), Some(Token::Punct(_))))
+        });
+
+        if is_cast {
+            target
+        } else {
+            self.position = start;
+            None
+        }
+    }
+
+    /// Parse a type. C syntax allows pointer types with a trailing `*`.
+    fn type_expr(&mut self, c_syntax: bool) -> Result<TypeExpr, DebugError> {
+        if self.peek_punct("*") {
+            self.position += 1;
+            if self.peek_ident("const") || self.peek_ident("mut") {
+                self.position += 1;
+            } else {
+                return Err(self.unexpected("`const` or `mut`"));
+            }
+            return Ok(TypeExpr::Pointer(Box::new(self.type_expr(c_syntax)?)));
+        }
+
+        if self.peek_punct("&") {
+            self.position += 1;
+            if self.peek_ident("mut") {
+                self.position += 1;
+            }
+            return Ok(TypeExpr::Pointer(Box::new(self.type_expr(c_syntax)?)));
+        }
+
+        let mut words = Vec::new();
+        let mut tagged = false;
+        while let Some(Token::Ident(ident)) = self.peek() {
+            match ident.as_str() {
+                "const" | "volatile" => {}
+                "struct" | "union" | "enum" => tagged = true,
+                word if C_TYPE_WORDS.contains(&word) && !tagged => words.push(word.to_string()),
+                _ => break,
+            }
+            self.position += 1;
+        }
+
+        let mut target = if words.is_empty() {
+            TypeExpr::Named(self.type_path()?)
+        } else {
+            TypeExpr::Named(words.join(" "))
+        };
+
+        if c_syntax {
+            loop {
+                if self.peek_punct("*") {
+                    target = TypeExpr::Pointer(Box::new(target));
+                } else if !self.peek_ident("const") {
+                    break;
+                }
+                self.position += 1;
+            }
+        }
+
+        Ok(target)
+    }
+
+    /// Parse a path, which can have generic arguments, like `heapless::Vec<u8, 4>`.
+    fn type_path(&mut self) -> Result<String, DebugError> {
+        let mut path = String::new();
+
+        loop {
+            match self.next() {
+                Some(Token::Ident(ident)) => path.push_str(&ident),
+                _ => {
+                    self.position -= 1;
+                    return Err(self.unexpected("a type"));
+                }
+            }
+
+            if self.peek_punct("<") {
+                path.push_str(&self.generic_arguments()?);
+            }
+
+            if self.peek_punct("::") {
+                self.position += 1;
+                path.push_str("::");
+            } else {
+                return Ok(path);
+            }
+        }
+    }
+
+    /// Collect the generic arguments of a type as text.
+    fn generic_arguments(&mut self) -> Result<String, DebugError> {
+        let mut text = String::new();
+        let mut depth = 0;
+
+        loop {
+            let Some(token) = self.next() else {
+                return Err(error("Unterminated generic arguments"));
+            };
+
+            match &token {
+                Token::Punct("<") => depth += 1,
+                Token::Punct(">") => depth -= 1,
+                Token::Punct(">>") => depth -= 2,
+                _ => {}
+            }
+
+            match token {
+                Token::Ident(ident) => text.push_str(&ident),
+                Token::Int(value, _) => text.push_str(&value.to_string()),
+                Token::Punct(",") => text.push_str(", "),
+                Token::Punct(punct) => text.push_str(punct),
+                other => {
+                    return Err(error(format!(
+                        "Unexpected {} in type",
+                        describe_token(&other)
+                    )))
+                }
+            }
+
+            if depth <= 0 {
+                return Ok(text);
+            }
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr, DebugError> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.peek_punct(".") || self.peek_punct("->") {
+                if self.peek_punct("->") {
+                    expr = Expr::Unary(UnaryOp::Deref, Box::new(expr));
+                }
+                self.position += 1;
+
+                let field = match self.next() {
+                    Some(Token::Ident(field)) => field,
+                    Some(Token::Int(index, None)) => index.to_string(),
+                    _ => {
+                        self.position -= 1;
+                        return Err(self.unexpected("a field name"));
+                    }
+                };
+                expr = Expr::Field(Box::new(expr), field);
+            } else if self.peek_punct("[") {
+                self.position += 1;
+                let index = self.binary(0)?;
+                self.expect_punct("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, DebugError> {
+        let Some(token) = self.next() else {
+            return Err(error("Unexpected end of the expression"));
+        };
+
+        Ok(match token {
+            Token::Int(value, suffix) => Expr::Int(value, suffix),
+            Token::Float(value, suffix) => Expr::Float(value, suffix),
+            Token::Char(value) => Expr::Char(value),
+            Token::Str(value) => Expr::Str(value),
+            Token::Register(name) => Expr::Register(name),
+            Token::Ident(ident) if ident == "true" => Expr::Bool(true),
+            Token::Ident(ident) if ident == "false" => Expr::Bool(false),
+            Token::Ident(ident) => {
+                let mut path = ident;
+                while self.peek_punct("::") {
+                    self.position += 1;
+                    match self.next() {
+                        Some(Token::Ident(segment)) => {
+                            path.push_str("::");
+                            path.push_str(&segment);
+                        }
+                        _ => {
+                            self.position -= 1;
+                            return Err(self.unexpected("a path segment"));
+                        }
+                    }
+                }
+                Expr::Name(path)
+            }
+            Token::Punct("(") => {
+                let expr = self.binary(0)?;
+                self.expect_punct(")")?;
+                expr
+            }
+            other => {
+                self.position -= 1;
+                return Err(error(format!("Unexpected {}", describe_token(&other))));
+            }
+        })
+    }
+}
+
+/// The names of primitive types which can be used in casts, in any language.
+fn is_primitive_type_name(name: &str) -> bool {
+    primitive_type(name, gimli::DW_LANG_C99, 32).is_some()
+        || primitive_type(name, gimli::DW_LANG_Rust, 32).is_some()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct IntType {
+    bits: u32,
+    signed: bool,
+}
+
+impl IntType {
+    /// Truncate a value to the range of the type.
+    fn wrap(self, value: i128) -> i128 {
+        if self.bits >= 128 {
+            return value;
+        }
+
+        let modulus = 1i128 << self.bits;
+        let value = value.rem_euclid(modulus);
+        if self.signed && value >= modulus / 2 {
+            value - modulus
+        } else {
+            value
+        }
+    }
+
+    /// Convert a float to the type, saturating at the limits of the type like Rust does.
+    fn saturate(self, value: f64) -> i128 {
+        let (min, max) = match (self.signed, self.bits) {
+            (true, 128) => (i128::MIN, i128::MAX),
+            (false, 128) => (0, i128::MAX),
+            (true, bits) => (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1),
+            (false, bits) => (0, (1i128 << bits) - 1),
+        };
+
+        if value.is_nan() {
+            0
+        } else {
+            (value as i128).clamp(min, max)
+        }
+    }
+
+    /// The common type of two operands of a binary operator.
+    fn common(self, other: IntType) -> IntType {
+        match self.bits.cmp(&other.bits) {
+            std::cmp::Ordering::Equal => IntType {
+                bits: self.bits,
+                signed: self.signed && other.signed,
+            },
+            std::cmp::Ordering::Greater => self,
+            std::cmp::Ordering::Less => other,
+        }
+    }
+
+    fn name(self) -> String {
+        format!("{}{}", if self.signed { 'i' } else { 'u' }, self.bits)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScalarType {
+    Int(IntType),
+    Float(u32),
+    Bool,
+    Char,
+}
+
+impl ScalarType {
+    fn byte_size(self) -> u64 {
+        match self {
+            ScalarType::Int(int_type) => int_type.bits as u64 / 8,
+            ScalarType::Float(bits) => bits as u64 / 8,
+            ScalarType::Bool => 1,
+            ScalarType::Char => 4,
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            ScalarType::Int(int_type) => int_type.name(),
+            ScalarType::Float(bits) => format!("f{bits}"),
+            ScalarType::Bool => "bool".to_string(),
+            ScalarType::Char => "char".to_string(),
+        }
+    }
+}
+
+/// The primitive type with the given name, in Rust or C syntax.
+fn primitive_type(name: &str, language: DwLang, address_bits: u32) -> Option<ScalarType> {
+    let int = |bits, signed| Some(ScalarType::Int(IntType { bits, signed }));
+    let sized_int = |bits: &str, signed| {
+        bits.parse::<u32>()
+            .ok()
+            .filter(|bits| [8, 16, 32, 64, 128].contains(bits))
+            .and_then(|bits| int(bits, signed))
+    };
+
+    match name {
+        "bool" | "_Bool" => Some(ScalarType::Bool),
+        "char" if language == gimli::DW_LANG_Rust => Some(ScalarType::Char),
+        "f32" | "float" => Some(ScalarType::Float(32)),
+        "f64" | "double" | "long double" => Some(ScalarType::Float(64)),
+        "isize" | "ssize_t" | "intptr_t" | "ptrdiff_t" => int(address_bits, true),
+        "usize" | "size_t" | "uintptr_t" => int(address_bits, false),
+        _ => {
+            if let Some(bits) = name.strip_prefix('i') {
+                sized_int(bits, true)
+            } else if let Some(bits) = name.strip_prefix('u').filter(|bits| !bits.starts_with('n'))
+            {
+                sized_int(bits, false)
+            } else if let Some(bits) = name
+                .strip_prefix("uint")
+                .and_then(|bits| bits.strip_suffix("_t"))
+            {
+                sized_int(bits, false)
+            } else if let Some(bits) = name
+                .strip_prefix("int")
+                .and_then(|bits| bits.strip_suffix("_t"))
+            {
+                sized_int(bits, true)
+            } else {
+                c_integer_type(name)
+            }
+        }
+    }
+}
+
+/// Integer types with C names like `unsigned char` or `long long int`, for 32-bit targets.
+fn c_integer_type(name: &str) -> Option<ScalarType> {
+    let words = name.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() || !words.iter().all(|word| C_TYPE_WORDS.contains(word)) {
+        return None;
+    }
+
+    let count = |word| words.iter().filter(|w| **w == word).count();
+    let bits = if count("char") > 0 {
+        8
+    } else if count("short") > 0 {
+        16
+    } else if count("long") > 1 {
+        64
+    } else {
+        32
+    };
+
+    Some(ScalarType::Int(IntType {
+        bits,
+        // A plain `char` is unsigned on ARM and RISC-V.
+        signed: count("unsigned") == 0 && (bits != 8 || count("signed") > 0),
+    }))
+}
+
+/// The type of the value a pointer points to.
+#[derive(Clone, Debug, PartialEq)]
+enum Pointee {
+    /// A type from the debug information.
+    Type { node: TypeNode, name: String },
+    /// A primitive type, which can be read without debug information.
+    Primitive(ScalarType),
+    /// Another pointer.
+    Pointer(Box<Pointee>),
+    /// The type is not known, like for `void *`.
+    Unknown,
+}
+
+impl Pointee {
+    fn name(&self) -> String {
+        match self {
+            Pointee::Type { name, .. } => name.clone(),
+            Pointee::Primitive(scalar_type) => scalar_type.name(),
+            Pointee::Pointer(pointee) => format!("*const {}", pointee.name()),
+            Pointee::Unknown => "()".to_string(),
+        }
+    }
+}
+
+/// The caches which contain the variables used in an expression.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Scope {
+    Local,
+    Static,
+    /// Variables which are created during the evaluation, e.g. by casts.
+    Scratch,
+}
+
+#[derive(Clone, Debug)]
+enum Value {
+    /// An integer literal without a suffix, which takes the type of the other operand.
+    Literal(i128),
+    Int(i128, IntType),
+    Float(f64, u32),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    Pointer {
+        address: u64,
+        pointee: Pointee,
+    },
+    Variable(Scope, Variable),
+    /// A name which is not a variable, only used to compare enums with their variants.
+    Symbol(String),
+}
+
+impl Value {
+    fn describe(&self) -> String {
+        match self {
+            Value::Literal(_) | Value::Int(..) => "an integer".to_string(),
+            Value::Float(..) => "a float".to_string(),
+            Value::Bool(_) => "a bool".to_string(),
+            Value::Char(_) => "a char".to_string(),
+            Value::Str(_) => "a string".to_string(),
+            Value::Pointer { .. } => "a pointer".to_string(),
+            Value::Variable(_, variable) => {
+                format!("{} of type {}", variable.name, variable.type_name())
+            }
+            Value::Symbol(name) => format!("{name} (which is not a variable)"),
+        }
+    }
+
+    fn truthiness(&self) -> Option<bool> {
+        match self {
+            Value::Literal(value) | Value::Int(value, _) => Some(*value != 0),
+            Value::Float(value, _) => Some(*value != 0.0),
+            Value::Bool(value) => Some(*value),
+            Value::Char(value) => Some(*value != '\0'),
+            Value::Str(value) => Some(!value.is_empty()),
+            Value::Pointer { address, .. } => Some(*address != 0),
+            Value::Variable(..) | Value::Symbol(_) => None,
+        }
+    }
+
+    fn integer(&self) -> Option<i128> {
+        match self {
+            Value::Literal(value) | Value::Int(value, _) => Some(*value),
+            Value::Char(value) => Some(*value as i128),
+            Value::Bool(value) => Some(*value as i128),
+            _ => None,
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    debug_info: &'a DebugInfo,
+    memory: &'a mut dyn MemoryInterface,
+    registers: &'a DebugRegisters,
+    frame_base: Option<u64>,
+    canonical_frame_address: Option<u64>,
+    locals: Option<&'a mut VariableCache>,
+    statics: Option<&'a mut VariableCache>,
+    scratch: VariableCache,
+    language: DwLang,
+    address_bits: u32,
+}
+
+impl<'a> Evaluator<'a> {
+    fn frame_info(&self) -> StackFrameInfo<'a> {
+        StackFrameInfo {
+            registers: self.registers,
+            frame_base: self.frame_base,
+            canonical_frame_address: self.canonical_frame_address,
+        }
+    }
+
+    fn cache(&self, scope: Scope) -> Result<&VariableCache, DebugError> {
+        match scope {
+            Scope::Local => self.locals.as_deref(),
+            Scope::Static => self.statics.as_deref(),
+            Scope::Scratch => Some(&self.scratch),
+        }
+        .ok_or_else(|| error(format!("No {scope:?} variables available")))
+    }
+
+    /// The children of a variable, which are resolved first if they are deferred.
+    fn children(&mut self, scope: Scope, variable: &Variable) -> Result<Vec<Variable>, DebugError> {
+        let frame_info = self.frame_info();
+        let cache = match scope {
+            Scope::Local => self.locals.as_deref_mut(),
+            Scope::Static => self.statics.as_deref_mut(),
+            Scope::Scratch => Some(&mut self.scratch),
+        }
+        .ok_or_else(|| error(format!("No {scope:?} variables available")))?;
+
+        if variable.variable_node_type.is_deferred() && !cache.has_children(variable) {
+            let mut parent_variable = variable.clone();
+            self.debug_info.cache_deferred_variables(
+                cache,
+                &mut *self.memory,
+                &mut parent_variable,
+                frame_info,
+            )?;
+        }
+
+        Ok(cache
+            .get_children(variable.variable_key())
+            .cloned()
+            .collect())
+    }
+
+    /// Find a child by name. The fields of the active variant of an enum, and the fields of
+    /// anonymous structs and unions in C, are children of the parent, too.
+    fn child(
+        &mut self,
+        scope: Scope,
+        variable: &Variable,
+        name: &str,
+    ) -> Result<Option<Variable>, DebugError> {
+        let children = self.children(scope, variable)?;
+
+        if let Some(child) = children.iter().find(
+            |child| matches!(&child.name, VariableName::Named(child_name) if child_name == name),
+        ) {
+            return Ok(Some(child.clone()));
+        }
+
+        // The active variant of a Rust enum is a struct named after the variant.
+        let is_variant = |child: &Variable| {
+            child.language == gimli::DW_LANG_Rust
+                && matches!((&child.name, child.type_name.inner()), (VariableName::Named(name), VariableType::Struct(type_name)) if name == type_name)
+        };
+        for nested in children
+            .iter()
+            .filter(|child| is_variant(child) || child.name == VariableName::Unknown)
+        {
+            if let Some(child) = self.child(scope, nested, name)? {
+                return Ok(Some(child));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn evaluate(&mut self, expr: &Expr) -> Result<Value, DebugError> {
+        match expr {
+            Expr::Int(value, None) => Ok(Value::Literal(*value)),
+            Expr::Int(value, Some(suffix)) => match self.literal_type(suffix) {
+                Some(ScalarType::Int(int_type)) => Ok(Value::Int(int_type.wrap(*value), int_type)),
+                Some(ScalarType::Float(bits)) => Ok(Value::Float(*value as f64, bits)),
+                _ => Err(error(format!(
+                    "Invalid suffix {suffix:?} on integer literal"
+                ))),
+            },
+            Expr::Float(value, suffix) => match suffix.as_deref() {
+                None | Some("f64") => Ok(Value::Float(*value, 64)),
+                Some("f32" | "f" | "F") => Ok(Value::Float(*value as f32 as f64, 32)),
+                Some(suffix) => Err(error(format!("Invalid suffix {suffix:?} on float literal"))),
+            },
+            Expr::Bool(value) => Ok(Value::Bool(*value)),
+            Expr::Char(value) => Ok(Value::Char(*value)),
+            Expr::Str(value) => Ok(Value::Str(value.clone())),
+            Expr::Name(name) => self.name(name),
+            Expr::Register(name) => self
+                .register(name)
+                .ok_or_else(|| error(format!("No register named {name:?}"))),
+            Expr::Field(base, field) => {
+                let base = self.evaluate(base)?;
+                self.field(base, field)
+            }
+            Expr::Index(base, index) => {
+                let base = self.evaluate(base)?;
+                let index = self.evaluate(index)?;
+                self.index(base, index)
+            }
+            Expr::Unary(op, operand) => {
+                let operand = self.evaluate(operand)?;
+                self.unary(*op, operand)
+            }
+            Expr::Binary(op, lhs, rhs) => self.binary(*op, lhs, rhs),
+            Expr::Cast(operand, target) => {
+                let operand = self.evaluate(operand)?;
+                self.cast(operand, target)
+            }
+        }
+    }
+
+    /// The type of an integer literal with a Rust or C suffix.
+    fn literal_type(&self, suffix: &str) -> Option<ScalarType> {
+        let int = |bits, signed| Some(ScalarType::Int(IntType { bits, signed }));
+        match suffix.to_ascii_lowercase().as_str() {
+            "u" | "ul" | "lu" => int(32, false),
+            "l" => int(32, true),
+            "ll" => int(64, true),
+            "ull" | "llu" => int(64, false),
+            "f32" | "f64" => primitive_type(suffix, gimli::DW_LANG_Rust, self.address_bits),
+            _ => primitive_type(suffix, gimli::DW_LANG_Rust, self.address_bits)
+                .filter(|scalar_type| matches!(scalar_type, ScalarType::Int(_))),
+        }
+    }
+
+    fn name(&mut self, name: &str) -> Result<Value, DebugError> {
+        if !name.contains("::") {
+            if let Some(variable) = self.local_variable(name)? {
+                return Ok(Value::Variable(Scope::Local, variable));
+            }
+        }
+
+        if let Some(variable) = self.static_variable(name)? {
+            return Ok(Value::Variable(Scope::Static, variable));
+        }
+
+        self.register(name)
+            .ok_or_else(|| error(format!("No variable named {name:?} in the current scope")))
+    }
+
+    fn local_variable(&mut self, name: &str) -> Result<Option<Variable>, DebugError> {
+        let Some(locals) = self.locals.as_deref() else {
+            return Ok(None);
+        };
+        let root = locals.root_variable().clone();
+
+        // Variables of nested blocks come last, and shadow the variables of the outer blocks.
+        Ok(self
+            .children(Scope::Local, &root)?
+            .into_iter()
+            .filter(|child| matches!(&child.name, VariableName::Named(child_name) if child_name == name))
+            .last())
+    }
+
+    fn static_variable(&mut self, path: &str) -> Result<Option<Variable>, DebugError> {
+        let Some(statics) = self.statics.as_deref() else {
+            return Ok(None);
+        };
+        let query = path_segments(path);
+        let mut pending = vec![(statics.root_variable().clone(), Vec::<String>::new())];
+
+        while let Some((parent, namespaces)) = pending.pop() {
+            for child in self.children(Scope::Static, &parent)? {
+                match &child.name {
+                    VariableName::Namespace(name) => {
+                        let mut child_namespaces = namespaces.clone();
+                        child_namespaces.push(name.clone());
+                        pending.push((child, child_namespaces));
+                    }
+                    VariableName::AnonymousNamespace => pending.push((child, namespaces.clone())),
+                    VariableName::Named(name) => {
+                        let mut candidate = namespaces.clone();
+                        candidate.push(name.clone());
+                        if path_matches(&candidate, &query) {
+                            return Ok(Some(child));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn register(&self, name: &str) -> Option<Value> {
+        let register = self.registers.get_register_by_name(name)?;
+        let value: u128 = register.value?.try_into().ok()?;
+        let int_type = IntType {
+            bits: register.core_register.size_in_bits() as u32,
+            signed: false,
+        };
+
+        Some(Value::Int(int_type.wrap(value as i128), int_type))
+    }
+
+    fn field(&mut self, base: Value, field: &str) -> Result<Value, DebugError> {
+        let base = match base {
+            pointer @ Value::Pointer { .. } => self.unary(UnaryOp::Deref, pointer)?,
+            other => other,
+        };
+
+        let Value::Variable(scope, mut variable) = base else {
+            return Err(error(format!(
+                "Cannot access the field {field} of {}",
+                base.describe()
+            )));
+        };
+
+        // Fields are accessed through pointers and references, like `->` in C.
+        while matches!(variable.type_name.inner(), VariableType::Pointer(_)) {
+            variable = self.pointee_variable(scope, &variable)?;
+        }
+
+        let name = if field.chars().all(|c| c.is_ascii_digit()) {
+            format!("__{field}")
+        } else {
+            field.to_string()
+        };
+
+        match self.child(scope, &variable, &name)? {
+            Some(child) => Ok(Value::Variable(scope, child)),
+            None => Err(error(format!(
+                "{} of type {} has no field named {field}",
+                variable.name,
+                variable.type_name()
+            ))),
+        }
+    }
+
+    /// The variable a pointer variable points to.
+    fn pointee_variable(
+        &mut self,
+        scope: Scope,
+        pointer: &Variable,
+    ) -> Result<Variable, DebugError> {
+        self.children(scope, pointer)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                error(format!(
+                    "Cannot dereference {}, the type it points to is not known",
+                    pointer.name
+                ))
+            })
+    }
+
+    fn index(&mut self, base: Value, index: Value) -> Result<Value, DebugError> {
+        let index = match self.load(index)? {
+            index @ (Value::Literal(_) | Value::Int(..)) => index.integer().unwrap_or_default(),
+            other => {
+                return Err(error(format!(
+                    "Cannot use {} as an index",
+                    other.describe()
+                )))
+            }
+        };
+
+        match base {
+            Value::Pointer { address, pointee } => self.index_pointer(address, &pointee, index),
+            Value::Variable(scope, variable) => match variable.type_name.inner() {
+                VariableType::Array { count, .. } => {
+                    if index < 0 || index >= *count as i128 {
+                        return Err(error(format!(
+                            "Index {index} is out of bounds for {}, which has {count} elements",
+                            variable.name
+                        )));
+                    }
+                    let element = self.child(scope, &variable, &format!("__{index}"))?;
+                    element
+                        .map(|element| Value::Variable(scope, element))
+                        .ok_or_else(|| {
+                            error(format!(
+                                "Element {index} of {} is not available",
+                                variable.name
+                            ))
+                        })
+                }
+                // Indexing a reference indexes the value it refers to in Rust.
+                VariableType::Pointer(_) if variable.language == gimli::DW_LANG_Rust => {
+                    let pointee = self.pointee_variable(scope, &variable)?;
+                    self.index(Value::Variable(scope, pointee), Value::Literal(index))
+                }
+                VariableType::Pointer(_) => {
+                    let Value::Pointer { address, pointee } =
+                        self.pointer_value(scope, &variable)?
+                    else {
+                        unreachable!("pointer_value always returns a pointer");
+                    };
+                    self.index_pointer(address, &pointee, index)
+                }
+                _ => {
+                    // Pretty printed containers, like `Vec<T>`, have their elements as children.
+                    if index >= 0 {
+                        if let Some(element) =
+                            self.child(scope, &variable, &format!("__{index}"))?
+                        {
+                            return Ok(Value::Variable(scope, element));
+                        }
+                    }
+
+                    // Slices are structs with a pointer to the data, and a length.
+                    let data_ptr = self.child(scope, &variable, "data_ptr")?;
+                    let length = self.child(scope, &variable, "length")?;
+                    let (Some(data_ptr), Some(length)) = (data_ptr, length) else {
+                        return Err(error(format!(
+                            "Cannot index {} of type {}",
+                            variable.name,
+                            variable.type_name()
+                        )));
+                    };
+
+                    let length = self
+                        .load(Value::Variable(scope, length))?
+                        .integer()
+                        .unwrap_or_default();
+                    if index < 0 || index >= length {
+                        return Err(error(format!(
+                            "Index {index} is out of bounds for {}, which has {length} elements",
+                            variable.name
+                        )));
+                    }
+
+                    let Value::Pointer { address, pointee } =
+                        self.pointer_value(scope, &data_ptr)?
+                    else {
+                        unreachable!("pointer_value always returns a pointer");
+                    };
+                    self.index_pointer(address, &pointee, index)
+                }
+            },
+            other => Err(error(format!("Cannot index {}", other.describe()))),
+        }
+    }
+
+    fn index_pointer(
+        &mut self,
+        address: u64,
+        pointee: &Pointee,
+        index: i128,
+    ) -> Result<Value, DebugError> {
+        let address = self.offset_address(address, pointee, index)?;
+        self.read_pointee(address, pointee)
+    }
+
+    fn offset_address(
+        &self,
+        address: u64,
+        pointee: &Pointee,
+        count: i128,
+    ) -> Result<u64, DebugError> {
+        let size = self.pointee_size(pointee)?;
+        let offset = count
+            .checked_mul(size as i128)
+            .and_then(|offset| (address as i128).checked_add(offset))
+            .filter(|address| (0..=u64::MAX as i128).contains(address))
+            .ok_or_else(|| error("Address is out of range"))?;
+
+        Ok(offset as u64)
+    }
+
+    fn pointee_size(&self, pointee: &Pointee) -> Result<u64, DebugError> {
+        match pointee {
+            Pointee::Primitive(scalar_type) => Ok(scalar_type.byte_size()),
+            Pointee::Pointer(_) => Ok(self.address_bits as u64 / 8),
+            Pointee::Type { node, name } => self
+                .debug_info
+                .type_byte_size(*node)?
+                .ok_or_else(|| error(format!("The size of {name} is not known"))),
+            Pointee::Unknown => Err(error(
+                "Pointer arithmetic requires the type of the value the pointer points to",
+            )),
+        }
+    }
+
+    /// Read the value a pointer points to.
+    fn read_pointee(&mut self, address: u64, pointee: &Pointee) -> Result<Value, DebugError> {
+        match pointee {
+            Pointee::Type { node, name } => {
+                let frame_info = self.frame_info();
+                let variable = self.debug_info.cache_variable_at_address(
+                    &mut self.scratch,
+                    &mut *self.memory,
+                    *node,
+                    address,
+                    format!("*({name}*){address:#010x}"),
+                    frame_info,
+                )?;
+                Ok(Value::Variable(Scope::Scratch, variable))
+            }
+            Pointee::Primitive(scalar_type) => self.read_scalar(address, *scalar_type),
+            Pointee::Pointer(inner) => {
+                let int_type = IntType {
+                    bits: self.address_bits,
+                    signed: false,
+                };
+                let target = self.read_scalar(address, ScalarType::Int(int_type))?;
+                Ok(Value::Pointer {
+                    address: target.integer().unwrap_or_default() as u64,
+                    pointee: *inner.clone(),
+                })
+            }
+            Pointee::Unknown => Err(error(
+                "Cannot dereference a pointer to a value of unknown type, cast it to a pointer to a known type first",
+            )),
+        }
+    }
+
+    fn read_scalar(&mut self, address: u64, scalar_type: ScalarType) -> Result<Value, DebugError> {
+        let mut bytes = vec![0; scalar_type.byte_size() as usize];
+        self.memory.read(address, &mut bytes)?;
+        Ok(decode_scalar(&bytes, scalar_type))
+    }
+
+    /// The pointer value of a pointer variable.
+    fn pointer_value(&mut self, scope: Scope, variable: &Variable) -> Result<Value, DebugError> {
+        let pointee_variable = self.children(scope, variable)?.into_iter().next();
+
+        let address = match pointee_variable
+            .as_ref()
+            .map(|pointee| &pointee.memory_location)
+        {
+            Some(VariableLocation::Address(address)) => *address,
+            _ => {
+                let VariableLocation::Address(location) = variable.memory_location else {
+                    return Err(error(format!(
+                        "The value of {} is not available",
+                        variable.name
+                    )));
+                };
+                let int_type = IntType {
+                    bits: variable
+                        .byte_size
+                        .map_or(self.address_bits, |size| size as u32 * 8),
+                    signed: false,
+                };
+                self.read_scalar(location, ScalarType::Int(int_type))?
+                    .integer()
+                    .unwrap_or_default() as u64
+            }
+        };
+
+        let pointee = match pointee_variable.as_ref() {
+            Some(pointee) => match type_node(pointee) {
+                Some(node) => Pointee::Type {
+                    node,
+                    name: pointee.type_name(),
+                },
+                None => Pointee::Unknown,
+            },
+            None => Pointee::Unknown,
+        };
+
+        Ok(Value::Pointer { address, pointee })
+    }
+
+    /// Convert a variable with a scalar type to its value. Other values are returned unchanged.
+    fn load(&mut self, value: Value) -> Result<Value, DebugError> {
+        let Value::Variable(scope, variable) = value else {
+            return Ok(value);
+        };
+
+        if !variable.is_valid() {
+            return Err(error(format!(
+                "{}: {}",
+                variable.name,
+                variable.to_string(self.cache(scope)?)
+            )));
+        }
+
+        match variable.type_name.inner() {
+            VariableType::Base(name) => {
+                let Some(mut scalar_type) =
+                    primitive_type(name, variable.language, self.address_bits)
+                else {
+                    return Ok(Value::Variable(scope, variable));
+                };
+
+                if let (ScalarType::Int(int_type), Some(byte_size @ 1..=16)) =
+                    (&mut scalar_type, variable.byte_size)
+                {
+                    int_type.bits = byte_size as u32 * 8;
+                }
+
+                match variable.memory_location {
+                    VariableLocation::Address(address) => self.read_scalar(address, scalar_type),
+                    _ => self.parse_scalar(scope, &variable, scalar_type),
+                }
+            }
+            VariableType::Bitfield(_, base) => {
+                let signed = !matches!(base.inner(), VariableType::Base(name) if name.contains("unsigned") || name.starts_with('u'));
+                let bits = variable.byte_size.map_or(32, |size| size as u32 * 8);
+                self.parse_scalar(scope, &variable, ScalarType::Int(IntType { bits, signed }))
+            }
+            VariableType::Pointer(_) => self.pointer_value(scope, &variable),
+            VariableType::Struct(name) if name == "&str" => {
+                Ok(Value::Str(variable.to_string(self.cache(scope)?)))
+            }
+            _ => Ok(Value::Variable(scope, variable)),
+        }
+    }
+
+    /// Parse the formatted value of a variable, which is not stored in memory.
+    fn parse_scalar(
+        &self,
+        scope: Scope,
+        variable: &Variable,
+        scalar_type: ScalarType,
+    ) -> Result<Value, DebugError> {
+        let text = variable.to_string(self.cache(scope)?);
+        let invalid = || {
+            error(format!(
+                "Cannot read the value of {}: {text}",
+                variable.name
+            ))
+        };
+
+        Ok(match scalar_type {
+            ScalarType::Int(int_type) => {
+                let value = match text.strip_prefix("0x") {
+                    Some(hex) => i128::from_str_radix(hex, 16),
+                    None => text.parse(),
+                };
+                Value::Int(int_type.wrap(value.map_err(|_| invalid())?), int_type)
+            }
+            ScalarType::Float(bits) => Value::Float(text.parse().map_err(|_| invalid())?, bits),
+            ScalarType::Bool => Value::Bool(text.parse().map_err(|_| invalid())?),
+            ScalarType::Char => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Value::Char(c),
+                    _ => return Err(invalid()),
+                }
+            }
+        })
+    }
+
+    fn unary(&mut self, op: UnaryOp, operand: Value) -> Result<Value, DebugError> {
+        match op {
+            UnaryOp::AddressOf => return self.address_of(operand),
+            UnaryOp::Deref => return self.deref(operand),
+            _ => {}
+        }
+
+        let operand = self.load(operand)?;
+        let value = match (op, &operand) {
+            (UnaryOp::Neg, Value::Literal(value)) => Value::Literal(-value),
+            (UnaryOp::Neg, Value::Int(value, int_type)) => {
+                Value::Int(int_type.wrap(-value), *int_type)
+            }
+            (UnaryOp::Neg, Value::Float(value, bits)) => Value::Float(-value, *bits),
+            (UnaryOp::Not, Value::Bool(value)) => Value::Bool(!value),
+            // `!` is the logical not in C, and the bitwise not in Rust.
+            (UnaryOp::Not, _) if self.language != gimli::DW_LANG_Rust => {
+                match operand.truthiness() {
+                    Some(value) => Value::Bool(!value),
+                    None => return Err(self.unary_error(op, &operand)),
+                }
+            }
+            (UnaryOp::Not | UnaryOp::BitNot, Value::Literal(value)) => Value::Literal(!value),
+            (UnaryOp::Not | UnaryOp::BitNot, Value::Int(value, int_type)) => {
+                Value::Int(int_type.wrap(!value), *int_type)
+            }
+            _ => return Err(self.unary_error(op, &operand)),
+        };
+
+        Ok(value)
+    }
+
+    fn unary_error(&self, op: UnaryOp, operand: &Value) -> DebugError {
+        let symbol = match op {
+            UnaryOp::Neg => "-",
+            UnaryOp::Not => "!",
+            UnaryOp::BitNot => "~",
+            UnaryOp::Deref => "*",
+            UnaryOp::AddressOf => "&",
+        };
+        error(format!("Cannot apply `{symbol}` to {}", operand.describe()))
+    }
+
+    fn address_of(&mut self, operand: Value) -> Result<Value, DebugError> {
+        let Value::Variable(_, variable) = &operand else {
+            return Err(error(format!(
+                "Cannot take the address of {}, which is not stored in memory",
+                operand.describe()
+            )));
+        };
+
+        let VariableLocation::Address(address) = variable.memory_location else {
+            return Err(error(format!(
+                "Cannot take the address of {}, which is not stored in memory",
+                variable.name
+            )));
+        };
+
+        let pointee = match type_node(variable) {
+            Some(node) => Pointee::Type {
+                node,
+                name: variable.type_name(),
+            },
+            None => Pointee::Unknown,
+        };
+
+        Ok(Value::Pointer { address, pointee })
+    }
+
+    fn deref(&mut self, operand: Value) -> Result<Value, DebugError> {
+        match operand {
+            Value::Variable(scope, variable)
+                if matches!(variable.type_name.inner(), VariableType::Pointer(_)) =>
+            {
+                let pointee = self.pointee_variable(scope, &variable)?;
+                Ok(Value::Variable(scope, pointee))
+            }
+            // Arrays decay to a pointer to their first element.
+            Value::Variable(scope, variable)
+                if matches!(variable.type_name.inner(), VariableType::Array { .. }) =>
+            {
+                self.index(Value::Variable(scope, variable), Value::Literal(0))
+            }
+            Value::Pointer { address, pointee } => self.read_pointee(address, &pointee),
+            Value::Literal(_) | Value::Int(..) => Err(error(
+                "Cannot dereference an integer, cast it to a pointer first, e.g. `*(0x20000000 as *const u32)`",
+            )),
+            other => Err(self.unary_error(UnaryOp::Deref, &other)),
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, lhs: &Expr, rhs: &Expr) -> Result<Value, DebugError> {
+        if matches!(op, BinaryOp::And | BinaryOp::Or) {
+            let lhs = self.condition(lhs)?;
+            if (op == BinaryOp::And) != lhs {
+                return Ok(Value::Bool(lhs));
+            }
+            return Ok(Value::Bool(self.condition(rhs)?));
+        }
+
+        let (lhs, rhs) = if matches!(op, BinaryOp::Eq | BinaryOp::Ne) {
+            (self.operand_or_symbol(lhs)?, self.operand_or_symbol(rhs)?)
+        } else {
+            (self.evaluate(lhs)?, self.evaluate(rhs)?)
+        };
+        let lhs = self.load(lhs)?;
+        let rhs = self.load(rhs)?;
+
+        self.apply(op, lhs, rhs)
+    }
+
+    fn condition(&mut self, expr: &Expr) -> Result<bool, DebugError> {
+        let value = self.evaluate(expr)?;
+        let value = self.load(value)?;
+        value.truthiness().ok_or_else(|| {
+            error(format!(
+                "{} cannot be used as a condition",
+                value.describe()
+            ))
+        })
+    }
+
+    /// Names which are not variables can be compared with enums, e.g. `state == Idle`.
+    fn operand_or_symbol(&mut self, expr: &Expr) -> Result<Value, DebugError> {
+        match (expr, self.evaluate(expr)) {
+            (Expr::Name(name), Err(_)) => Ok(Value::Symbol(name.clone())),
+            (_, result) => result,
+        }
+    }
+
+    fn apply(&mut self, op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, DebugError> {
+        use Value::{Bool, Char, Float, Int, Literal, Pointer, Str, Symbol, Variable};
+
+        let mismatch = |lhs: &Value, rhs: &Value| {
+            if let Symbol(name) = lhs {
+                return error(format!("No variable named {name:?} in the current scope"));
+            }
+            if let Symbol(name) = rhs {
+                return error(format!("No variable named {name:?} in the current scope"));
+            }
+            error(format!(
+                "Cannot apply `{}` to {} and {}",
+                op.symbol(),
+                lhs.describe(),
+                rhs.describe()
+            ))
+        };
+
+        match (&lhs, &rhs) {
+            (Literal(a), Literal(b)) => literal_op(op, *a, *b),
+            (Literal(a), Int(b, int_type)) => {
+                if matches!(op, BinaryOp::Shl | BinaryOp::Shr) {
+                    literal_op(op, *a, *b)
+                } else {
+                    int_op(op, int_type.wrap(*a), *b, *int_type)
+                }
+            }
+            (Int(a, int_type), Literal(b)) => {
+                let b = if matches!(op, BinaryOp::Shl | BinaryOp::Shr) {
+                    *b
+                } else {
+                    int_type.wrap(*b)
+                };
+                int_op(op, *a, b, *int_type)
+            }
+            (Int(a, a_type), Int(b, b_type)) => {
+                if matches!(op, BinaryOp::Shl | BinaryOp::Shr) {
+                    int_op(op, *a, *b, *a_type)
+                } else {
+                    let int_type = a_type.common(*b_type);
+                    int_op(op, int_type.wrap(*a), int_type.wrap(*b), int_type)
+                }
+            }
+            (Float(a, a_bits), Float(b, b_bits)) => float_op(op, *a, *b, *a_bits.max(b_bits)),
+            (Float(a, bits), Literal(b) | Int(b, _)) => float_op(op, *a, *b as f64, *bits),
+            (Literal(a) | Int(a, _), Float(b, bits)) => float_op(op, *a as f64, *b, *bits),
+            (Bool(a), Bool(b)) => match op {
+                BinaryOp::BitAnd => Ok(Bool(a & b)),
+                BinaryOp::BitOr => Ok(Bool(a | b)),
+                BinaryOp::BitXor => Ok(Bool(a ^ b)),
+                _ if op.is_comparison() => Ok(Bool(op.compare(a, b))),
+                _ => Err(mismatch(&lhs, &rhs)),
+            },
+            (Char(a), Char(b)) if op.is_comparison() => Ok(Bool(op.compare(a, b))),
+            (Char(_), Literal(_) | Int(..)) | (Literal(_) | Int(..), Char(_))
+                if op.is_comparison() =>
+            {
+                Ok(Bool(op.compare(lhs.integer(), rhs.integer())))
+            }
+            (Str(a), Str(b)) if op.is_comparison() => Ok(Bool(op.compare(a, b))),
+            (Pointer { address, pointee }, Literal(count) | Int(count, _))
+                if matches!(op, BinaryOp::Add | BinaryOp::Sub) =>
+            {
+                let count = if op == BinaryOp::Sub { -count } else { *count };
+                Ok(Pointer {
+                    address: self.offset_address(*address, pointee, count)?,
+                    pointee: pointee.clone(),
+                })
+            }
+            (Literal(count) | Int(count, _), Pointer { address, pointee })
+                if op == BinaryOp::Add =>
+            {
+                Ok(Pointer {
+                    address: self.offset_address(*address, pointee, *count)?,
+                    pointee: pointee.clone(),
+                })
+            }
+            (
+                Pointer {
+                    address: a,
+                    pointee,
+                },
+                Pointer { address: b, .. },
+            ) if op == BinaryOp::Sub => {
+                let size = self.pointee_size(pointee)?.max(1);
+                let int_type = IntType {
+                    bits: self.address_bits,
+                    signed: true,
+                };
+                Ok(Int((*a as i128 - *b as i128) / size as i128, int_type))
+            }
+            (Pointer { address: a, .. }, Pointer { address: b, .. }) if op.is_comparison() => {
+                Ok(Bool(op.compare(a, b)))
+            }
+            (Pointer { address, .. }, Literal(b) | Int(b, _)) if op.is_comparison() => {
+                Ok(Bool(op.compare(*address as i128, *b)))
+            }
+            (Literal(a) | Int(a, _), Pointer { address, .. }) if op.is_comparison() => {
+                Ok(Bool(op.compare(*a, *address as i128)))
+            }
+            (Variable(scope, variable), Str(name) | Symbol(name))
+            | (Str(name) | Symbol(name), Variable(scope, variable))
+                if matches!(op, BinaryOp::Eq | BinaryOp::Ne) =>
+            {
+                let value = variable.to_string(self.cache(*scope)?);
+                let equal = value == *name
+                    || value.ends_with(&format!("::{name}"))
+                    || name.ends_with(&format!("::{value}"));
+                Ok(Bool(equal == (op == BinaryOp::Eq)))
+            }
+            _ => Err(mismatch(&lhs, &rhs)),
+        }
+    }
+
+    fn cast(&mut self, operand: Value, target: &TypeExpr) -> Result<Value, DebugError> {
+        match target {
+            TypeExpr::Pointer(pointee) => {
+                let pointee = self.pointee(pointee)?;
+                let operand = match operand {
+                    // Arrays decay to pointers to their first element in C.
+                    Value::Variable(_, variable)
+                        if matches!(variable.type_name.inner(), VariableType::Array { .. }) =>
+                    {
+                        return match variable.memory_location {
+                            VariableLocation::Address(address) => {
+                                Ok(Value::Pointer { address, pointee })
+                            }
+                            _ => Err(error(format!("{} is not stored in memory", variable.name))),
+                        };
+                    }
+                    other => self.load(other)?,
+                };
+
+                let address = match operand {
+                    Value::Literal(value) | Value::Int(value, _) => value as u64,
+                    Value::Pointer { address, .. } => address,
+                    other => {
+                        return Err(error(format!(
+                            "Cannot cast {} to a pointer",
+                            other.describe()
+                        )))
+                    }
+                };
+
+                Ok(Value::Pointer { address, pointee })
+            }
+            TypeExpr::Named(name) => {
+                if let Some(scalar_type) = primitive_type(name, self.language, self.address_bits) {
+                    return self.convert(operand, scalar_type);
+                }
+
+                // Reinterpret the memory of the value as a different type.
+                let node = self
+                    .debug_info
+                    .find_type(name)?
+                    .ok_or_else(|| error(format!("No type named {name:?} was found")))?;
+
+                let address = match &operand {
+                    Value::Variable(_, variable) => variable.memory_location.memory_address().ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    error(format!(
+                        "Cannot cast {} to {name}, only values stored in memory can be cast to types which are not primitive",
+                        operand.describe()
+                    ))
+                })?;
+
+                let frame_info = self.frame_info();
+                let variable = self.debug_info.cache_variable_at_address(
+                    &mut self.scratch,
+                    &mut *self.memory,
+                    node,
+                    address,
+                    format!("({name}){address:#010x}"),
+                    frame_info,
+                )?;
+                Ok(Value::Variable(Scope::Scratch, variable))
+            }
+        }
+    }
+
+    /// The pointee of a pointer type in a cast.
+    fn pointee(&self, target: &TypeExpr) -> Result<Pointee, DebugError> {
+        match target {
+            TypeExpr::Pointer(inner) => Ok(Pointee::Pointer(Box::new(self.pointee(inner)?))),
+            TypeExpr::Named(name) if matches!(name.as_str(), "void" | "()" | "c_void") => {
+                Ok(Pointee::Unknown)
+            }
+            TypeExpr::Named(name) => {
+                if let Some(scalar_type) = primitive_type(name, self.language, self.address_bits) {
+                    return Ok(Pointee::Primitive(scalar_type));
+                }
+
+                match self.debug_info.find_type(name)? {
+                    Some(node) => Ok(Pointee::Type {
+                        node,
+                        name: name.clone(),
+                    }),
+                    None => Err(error(format!("No type named {name:?} was found"))),
+                }
+            }
+        }
+    }
+
+    /// Convert a value to a primitive type.
+    fn convert(&mut self, operand: Value, target: ScalarType) -> Result<Value, DebugError> {
+        let operand = self.load(operand)?;
+
+        let value = match (&operand, target) {
+            (Value::Literal(value) | Value::Int(value, _), ScalarType::Int(int_type)) => {
+                Value::Int(int_type.wrap(*value), int_type)
+            }
+            (Value::Float(value, _), ScalarType::Int(int_type)) => {
+                Value::Int(int_type.saturate(*value), int_type)
+            }
+            (Value::Bool(_) | Value::Char(_), ScalarType::Int(int_type)) => Value::Int(
+                int_type.wrap(operand.integer().unwrap_or_default()),
+                int_type,
+            ),
+            (Value::Pointer { address, .. }, ScalarType::Int(int_type)) => {
+                Value::Int(int_type.wrap(*address as i128), int_type)
+            }
+            (Value::Literal(value) | Value::Int(value, _), ScalarType::Float(bits)) => {
+                Value::Float(round_float(*value as f64, bits), bits)
+            }
+            (Value::Float(value, _), ScalarType::Float(bits)) => {
+                Value::Float(round_float(*value, bits), bits)
+            }
+            (Value::Bool(value), ScalarType::Bool) => Value::Bool(*value),
+            (Value::Literal(value) | Value::Int(value, _), ScalarType::Bool) => {
+                Value::Bool(*value != 0)
+            }
+            (Value::Float(value, _), ScalarType::Bool) => Value::Bool(*value != 0.0),
+            (Value::Char(value), ScalarType::Char) => Value::Char(*value),
+            (Value::Literal(value) | Value::Int(value, _), ScalarType::Char) => {
+                u32::try_from(*value)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(Value::Char)
+                    .ok_or_else(|| error(format!("{value} is not a valid char")))?
+            }
+            _ => {
+                return Err(error(format!(
+                    "Cannot cast {} to {}",
+                    operand.describe(),
+                    target.name()
+                )))
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Format the final value of the expression.
+    fn result(&mut self, value: Value) -> Result<EvaluatedExpression, DebugError> {
+        let (value, type_name, truthiness) = match value {
+            Value::Variable(scope, variable) => {
+                // Resolve the first level of children, so the value shows the fields of structs.
+                if variable.variable_node_type.is_deferred() {
+                    self.children(scope, &variable)?;
+                }
+                let variable = self
+                    .cache(scope)?
+                    .get_variable_by_key(variable.variable_key())
+                    .unwrap_or(variable);
+
+                let truthiness = self
+                    .load(Value::Variable(scope, variable.clone()))
+                    .ok()
+                    .and_then(|value| value.truthiness());
+
+                return Ok(EvaluatedExpression {
+                    value: variable.to_string(self.cache(scope)?),
+                    type_name: variable.type_name(),
+                    memory_location: variable.memory_location.memory_address().ok(),
+                    variable_key: (scope != Scope::Scratch).then_some(variable.variable_key()),
+                    truthiness,
+                });
+            }
+            Value::Literal(value) => {
+                let type_name = if i32::try_from(value).is_ok() {
+                    "i32"
+                } else if i64::try_from(value).is_ok() {
+                    "i64"
+                } else {
+                    "i128"
+                };
+                (value.to_string(), type_name.to_string(), Some(value != 0))
+            }
+            Value::Int(value, int_type) => (value.to_string(), int_type.name(), Some(value != 0)),
+            Value::Float(value, bits) => {
+                (format_float(value), format!("f{bits}"), Some(value != 0.0))
+            }
+            Value::Bool(value) => (value.to_string(), "bool".to_string(), Some(value)),
+            Value::Char(value) => (value.to_string(), "char".to_string(), Some(value != '\0')),
+            Value::Str(value) => {
+                let truthiness = !value.is_empty();
+                (value, "&str".to_string(), Some(truthiness))
+            }
+            Value::Pointer { address, pointee } => {
+                let type_name = if self.language == gimli::DW_LANG_Rust {
+                    format!("*const {}", pointee.name())
+                } else {
+                    format!("{}*", pointee.name())
+                };
+                (format!("{address:#010x}"), type_name, Some(address != 0))
+            }
+            Value::Symbol(name) => {
+                return Err(error(format!(
+                    "No variable named {name:?} in the current scope"
+                )))
+            }
+        };
+
+        Ok(EvaluatedExpression {
+            value,
+            type_name,
+            memory_location: None,
+            variable_key: None,
+            truthiness,
+        })
+    }
+}
+
+fn round_float(value: f64, bits: u32) -> f64 {
+    if bits == 32 {
+        value as f32 as f64
+    } else {
+        value
+    }
+}
+
+/// Decode a little-endian scalar value.
+fn decode_scalar(bytes: &[u8], scalar_type: ScalarType) -> Value {
+    let mut raw = [0; 16];
+    let length = bytes.len().min(raw.len());
+    raw[..length].copy_from_slice(&bytes[..length]);
+    let raw = u128::from_le_bytes(raw);
+
+    match scalar_type {
+        ScalarType::Int(int_type) => Value::Int(int_type.wrap(raw as i128), int_type),
+        ScalarType::Float(32) => Value::Float(f32::from_bits(raw as u32) as f64, 32),
+        ScalarType::Float(bits) => Value::Float(f64::from_bits(raw as u64), bits),
+        ScalarType::Bool => Value::Bool(raw != 0),
+        ScalarType::Char => {
+            Value::Char(char::from_u32(raw as u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+        }
+    }
+}
+
+/// Apply an operator to two integer literals.
+fn literal_op(op: BinaryOp, lhs: i128, rhs: i128) -> Result<Value, DebugError> {
+    if op.is_comparison() {
+        return Ok(Value::Bool(op.compare(lhs, rhs)));
+    }
+
+    let value = match op {
+        BinaryOp::Add => lhs.checked_add(rhs),
+        BinaryOp::Sub => lhs.checked_sub(rhs),
+        BinaryOp::Mul => lhs.checked_mul(rhs),
+        BinaryOp::Div | BinaryOp::Rem if rhs == 0 => return Err(error("Division by zero")),
+        BinaryOp::Div => lhs.checked_div(rhs),
+        BinaryOp::Rem => lhs.checked_rem(rhs),
+        BinaryOp::Shl => u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shl(rhs)),
+        BinaryOp::Shr => u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shr(rhs)),
+        BinaryOp::BitAnd => Some(lhs & rhs),
+        BinaryOp::BitOr => Some(lhs | rhs),
+        BinaryOp::BitXor => Some(lhs ^ rhs),
+        _ => unreachable!("{op:?} is handled by the caller"),
+    };
+
+    value
+        .map(Value::Literal)
+        .ok_or_else(|| error(format!("Overflow in `{lhs} {} {rhs}`", op.symbol())))
+}
+
+/// Apply an operator to two integers of the same type, wrapping around on overflow.
+fn int_op(op: BinaryOp, lhs: i128, rhs: i128, int_type: IntType) -> Result<Value, DebugError> {
+    if op.is_comparison() {
+        return Ok(Value::Bool(op.compare(lhs, rhs)));
+    }
+
+    let value = match op {
+        BinaryOp::Add => lhs.wrapping_add(rhs),
+        BinaryOp::Sub => lhs.wrapping_sub(rhs),
+        BinaryOp::Mul => lhs.wrapping_mul(rhs),
+        BinaryOp::Div | BinaryOp::Rem if rhs == 0 => return Err(error("Division by zero")),
+        BinaryOp::Div => lhs.wrapping_div(rhs),
+        BinaryOp::Rem => lhs.wrapping_rem(rhs),
+        BinaryOp::Shl | BinaryOp::Shr => {
+            let shift = u32::try_from(rhs)
+                .ok()
+                .filter(|shift| *shift < int_type.bits)
+                .ok_or_else(|| {
+                    error(format!(
+                        "Shift by {rhs} is out of range for {}",
+                        int_type.name()
+                    ))
+                })?;
+            if op == BinaryOp::Shl {
+                lhs << shift
+            } else {
+                lhs >> shift
+            }
+        }
+        BinaryOp::BitAnd => lhs & rhs,
+        BinaryOp::BitOr => lhs | rhs,
+        BinaryOp::BitXor => lhs ^ rhs,
+        _ => unreachable!("{op:?} is handled by the caller"),
+    };
+
+    Ok(Value::Int(int_type.wrap(value), int_type))
+}
+
+fn float_op(op: BinaryOp, lhs: f64, rhs: f64, bits: u32) -> Result<Value, DebugError> {
+    if op.is_comparison() {
+        return Ok(Value::Bool(op.compare(lhs, rhs)));
+    }
+
+    let value = match op {
+        BinaryOp::Add => lhs + rhs,
+        BinaryOp::Sub => lhs - rhs,
+        BinaryOp::Mul => lhs * rhs,
+        BinaryOp::Div => lhs / rhs,
+        BinaryOp::Rem => lhs % rhs,
+        _ => return Err(error(format!("Cannot apply `{}` to floats", op.symbol()))),
+    };
+
+    Ok(Value::Float(round_float(value, bits), bits))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{debug::exception_handling::exception_handler_for_core, CoreDump};
+    use std::path::PathBuf;
+
+    fn load_test_files(name: &str) -> (DebugInfo, CoreDump) {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests/debug-unwind-tests");
+
+        let debug_info = DebugInfo::from_file(path.join(format!("{name}.elf"))).unwrap();
+        let core_dump = CoreDump::load(&path.join(format!("{name}.coredump"))).unwrap();
+
+        (debug_info, core_dump)
+    }
+
+    fn stack_frame(debug_info: &DebugInfo, core_dump: &mut CoreDump, function: &str) -> StackFrame {
+        let initial_registers = core_dump.debug_registers();
+        let exception_handler = exception_handler_for_core(core_dump.core_type());
+        let instruction_set = core_dump.instruction_set();
+
+        debug_info
+            .unwind(
+                core_dump,
+                initial_registers,
+                exception_handler.as_ref(),
+                Some(instruction_set),
+            )
+            .unwrap()
+            .into_iter()
+            .find(|frame| frame.function_name == function)
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_precedence() {
+        let name = |name: &str| Box::new(Expr::Name(name.to_string()));
+        let int = |value| Box::new(Expr::Int(value, None));
+
+        assert_eq!(
+            parse("a + b * 2 == 7").unwrap(),
+            Expr::Binary(
+                BinaryOp::Eq,
+                Box::new(Expr::Binary(
+                    BinaryOp::Add,
+                    name("a"),
+                    Box::new(Expr::Binary(BinaryOp::Mul, name("b"), int(2)))
+                )),
+                int(7)
+            )
+        );
+        assert_eq!(
+            parse("flags & 0x10 == 0x10").unwrap(),
+            Expr::Binary(
+                BinaryOp::Eq,
+                Box::new(Expr::Binary(BinaryOp::BitAnd, name("flags"), int(0x10))),
+                int(0x10)
+            )
+        );
+        assert_eq!(
+            parse("-x as u8").unwrap(),
+            Expr::Cast(
+                Box::new(Expr::Unary(UnaryOp::Neg, name("x"))),
+                TypeExpr::Named("u8".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn parse_postfix() {
+        assert_eq!(
+            parse("self.buf[idx].len").unwrap(),
+            Expr::Field(
+                Box::new(Expr::Index(
+                    Box::new(Expr::Field(
+                        Box::new(Expr::Name("self".to_string())),
+                        "buf".to_string()
+                    )),
+                    Box::new(Expr::Name("idx".to_string()))
+                )),
+                "len".to_string()
+            )
+        );
+        assert_eq!(
+            parse("pair.0.1").unwrap(),
+            Expr::Field(
+                Box::new(Expr::Field(
+                    Box::new(Expr::Name("pair".to_string())),
+                    "0".to_string()
+                )),
+                "1".to_string()
+            )
+        );
+        assert_eq!(
+            parse("node->next").unwrap(),
+            Expr::Field(
+                Box::new(Expr::Unary(
+                    UnaryOp::Deref,
+                    Box::new(Expr::Name("node".to_string()))
+                )),
+                "next".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_casts() {
+        let pointer = |name: &str| TypeExpr::Pointer(Box::new(TypeExpr::Named(name.to_string())));
+
+        assert_eq!(
+            parse("(unsigned char)x").unwrap(),
+            Expr::Cast(
+                Box::new(Expr::Name("x".to_string())),
+                TypeExpr::Named("unsigned char".to_string())
+            )
+        );
+        assert_eq!(
+            parse("(struct foo *)0x20000000").unwrap(),
+            Expr::Cast(Box::new(Expr::Int(0x2000_0000, None)), pointer("foo"))
+        );
+        assert_eq!(
+            parse("0x20000000 as *const heapless::Vec<u8, 4>").unwrap(),
+            Expr::Cast(
+                Box::new(Expr::Int(0x2000_0000, None)),
+                pointer("heapless::Vec<u8, 4>")
+            )
+        );
+        // A parenthesized variable is not a cast.
+        assert_eq!(
+            parse("(x) - 1").unwrap(),
+            Expr::Binary(
+                BinaryOp::Sub,
+                Box::new(Expr::Name("x".to_string())),
+                Box::new(Expr::Int(1, None))
+            )
+        );
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(
+            tokenize("0xff_u8 10UL 0b101 1_000").unwrap(),
+            vec![
+                Token::Int(0xff, Some("u8".to_string())),
+                Token::Int(10, Some("UL".to_string())),
+                Token::Int(0b101, None),
+                Token::Int(1000, None),
+            ]
+        );
+        assert_eq!(
+            tokenize("1.5 2e3f32 'a' b'\\n' '\\u{1F4A9}' \"a\\\"b\"").unwrap(),
+            vec![
+                Token::Float(1.5, None),
+                Token::Float(2000.0, Some("f32".to_string())),
+                Token::Char('a'),
+                Token::Int(b'\n' as i128, Some("u8".to_string())),
+                Token::Char('💩'),
+                Token::Str("a\"b".to_string()),
+            ]
+        );
+        assert!(tokenize("'ab'").is_err());
+        assert!(tokenize("0x").is_err());
+    }
+
+    #[test]
+    fn int_types() {
+        let u8_type = IntType {
+            bits: 8,
+            signed: false,
+        };
+        let i8_type = IntType {
+            bits: 8,
+            signed: true,
+        };
+
+        assert_eq!(u8_type.wrap(256 + 3), 3);
+        assert_eq!(u8_type.wrap(-1), 255);
+        assert_eq!(i8_type.wrap(128), -128);
+        assert_eq!(i8_type.saturate(1000.0), 127);
+        assert_eq!(u8_type.saturate(-3.5), 0);
+        assert_eq!(
+            primitive_type("unsigned long long", gimli::DW_LANG_C11, 32),
+            Some(ScalarType::Int(IntType {
+                bits: 64,
+                signed: false
+            }))
+        );
+        assert_eq!(
+            primitive_type("char", gimli::DW_LANG_C11, 32),
+            Some(ScalarType::Int(u8_type))
+        );
+        assert_eq!(
+            primitive_type("char", gimli::DW_LANG_Rust, 32),
+            Some(ScalarType::Char)
+        );
+    }
+
+    #[test]
+    fn evaluate_rust() {
+        let (debug_info, mut core_dump) = load_test_files("nRF52833_xxAA_full_unwind");
+        let mut frame = stack_frame(&debug_info, &mut core_dump, "setup_data_types");
+        let mut statics = debug_info.create_static_scope_cache();
+
+        let mut evaluate = |expression: &str| {
+            debug_info
+                .evaluate_expression(expression, &mut core_dump, &mut frame, Some(&mut statics))
+                .unwrap_or_else(|error| panic!("Failed to evaluate {expression:?}: {error:?}"))
+        };
+
+        assert_eq!(evaluate("int8_minus_twenty_three + 1").value, "-22");
+        assert_eq!(evaluate("int8_minus_twenty_three as u8").value, "233");
+        assert_eq!(evaluate("int8_twenty_six * 10").value, "4");
+        assert_eq!(evaluate("int8_twenty_six as i32 * 10").value, "260");
+        assert_eq!(evaluate("a2 + a3 * a4").value, "7");
+        assert_eq!(evaluate("(a4 << 4 | 1) & 0xff").value, "49");
+        assert_eq!(evaluate("*float64_ptr").value, "1.7608695652173911");
+        assert_eq!(evaluate("float64 > 1.5 && true_bool").value, "true");
+        assert_eq!(evaluate("*emoji_ptr == '💩'").value, "true");
+        assert_eq!(evaluate("global_types.1").value, "-1");
+        assert_eq!(evaluate("global_types.12 * 2.0").value, "5.0");
+        assert_eq!(evaluate("my_array_ptr[3]").value, "55");
+        assert_eq!(evaluate("heapless_vec[1] + 1").value, "3");
+        assert_eq!(evaluate("any_old_string_slice.length").value, "30");
+        assert_eq!(evaluate("any_old_string_slice[0] as char").value, "H");
+        assert_eq!(
+            evaluate("any_old_string_slice == \"How long is a piece of String.\"").value,
+            "true"
+        );
+        assert_eq!(evaluate("three == SimpleEnum::Two").value, "true");
+        assert_eq!(evaluate("three != Two").value, "false");
+        assert_eq!(
+            evaluate("local_reference_to_global_static_struct.__1.y").value,
+            "25"
+        );
+        assert_eq!(
+            evaluate("(*local_reference_to_global_static_struct).__1.z as u8").value,
+            "26"
+        );
+
+        let evaluated = evaluate("three_d_usize_array.raw[3][1][2]");
+        assert_eq!(evaluated.value, "23");
+        assert_eq!(evaluated.type_name, "i32");
+        assert!(evaluated.variable_key.is_some());
+
+        let address_of = evaluate("&a2");
+        let a2 = evaluate("a2");
+        assert_eq!(
+            address_of.value,
+            format!("{:#010x}", a2.memory_location.unwrap())
+        );
+        assert_eq!(address_of.type_name, "*const i64");
+        assert_eq!(evaluate("*(&a2 + 1)").value, "2");
+        assert_eq!(evaluate("&my_array_ptr[1] - &my_array_ptr[0]").value, "1");
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        let (debug_info, mut core_dump) = load_test_files("nRF52833_xxAA_full_unwind");
+        let mut frame = stack_frame(&debug_info, &mut core_dump, "setup_data_types");
+
+        let mut error = |expression: &str| match debug_info.evaluate_expression(
+            expression,
+            &mut core_dump,
+            &mut frame,
+            None,
+        ) {
+            Err(DebugError::Other(message)) => message,
+            other => panic!("Expected an error for {expression:?}, got {other:?}"),
+        };
+
+        assert_eq!(
+            error("missing + 1"),
+            "No variable named \"missing\" in the current scope"
+        );
+        assert_eq!(
+            error("my_array_ptr[10]"),
+            "Index 10 is out of bounds for *my_array_ptr, which has 10 elements"
+        );
+        assert_eq!(error("a2 / 0"), "Division by zero");
+        assert_eq!(error("a2 +"), "Unexpected end of the expression");
+    }
+
+    #[test]
+    fn evaluate_c() {
+        let (debug_info, mut core_dump) = load_test_files("atsamd51p19a");
+        let initial_registers = core_dump.debug_registers();
+        let exception_handler = exception_handler_for_core(core_dump.core_type());
+        let instruction_set = core_dump.instruction_set();
+        let mut frame = debug_info
+            .unwind(
+                &mut core_dump,
+                initial_registers,
+                exception_handler.as_ref(),
+                Some(instruction_set),
+            )
+            .unwrap()
+            .remove(0);
+        let mut statics = debug_info.create_static_scope_cache();
+
+        let mut value = |expression: &str| {
+            debug_info
+                .evaluate_expression(expression, &mut core_dump, &mut frame, Some(&mut statics))
+                .unwrap_or_else(|error| panic!("Failed to evaluate {expression:?}: {error:?}"))
+                .value
+        };
+
+        assert_eq!(value("_SEGGER_RTT.aUp[0].WrOff"), "113");
+        assert_eq!(value("_SEGGER_RTT.aUp->SizeOfBuffer - 1"), "1023");
+        assert_eq!(value("_SEGGER_RTT.acID[0] == 'S'"), "true");
+        assert_eq!(value("(char)0x53 == *_SEGGER_RTT.acID"), "true");
+        assert_eq!(value("foo.x"), "1200000");
+        assert_eq!(value("!foo.x"), "false");
+        assert_eq!(value("(unsigned short)foo.x"), "20352");
+    }
+}
@@ -1,3 +1,4 @@
+use gimli::Reader;
 use std::ops::Range;
 
 use crate::{debug::stack_frame::StackFrameInfo, MemoryInterface};
@@ -142,7 +143,7 @@ impl<'a> FunctionDie<'a> {
             return None;
         };
         match debug_info.dwarf.string(fn_name_ref) {
-            Ok(fn_name_raw) => Some(String::from_utf8_lossy(&fn_name_raw).to_string()),
+            Ok(fn_name_raw) => Some(fn_name_raw.to_string_lossy().ok()?.to_string()),
             Err(error) => {
                 tracing::debug!("No value for DW_AT_name: {:?}: error", error);
 
@@ -242,7 +243,7 @@ fn collapsed_attribute(
     function_die: &Die,
     specification_die: &Option<Die>,
     attribute_name: gimli::DwAt,
-) -> Option<gimli::Attribute<gimli::EndianReader<gimli::LittleEndian, std::rc::Rc<[u8]>>>> {
+) -> Option<debug_info::GimliAttribute> {
     let attribute = specification_die
         .as_ref()
         .and_then(|specification_die| {
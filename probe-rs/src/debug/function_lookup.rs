@@ -0,0 +1,416 @@
+//! Lookup of functions by name, e.g. to set a breakpoint on a function.
+//!
+//! Functions are matched against their qualified name in the DWARF tree, built from the enclosing
+//! namespaces, and against their demangled linkage name. The requested name can be a suffix of the
+//! full path, so `main`, `module::function` and `Type::method` all find the function they refer to.
+//! Generic arguments are ignored, unless they are part of the requested name.
+
+use std::collections::HashMap;
+
+use gimli::{Reader, UnitOffset};
+
+use super::{function_die::Die, unit_info::UnitInfo, DebugError, DebugInfo, VerifiedBreakpoint};
+
+/// A function instance, which contains machine code.
+struct FunctionInstance {
+    /// The offset of the DIE of the instance.
+    offset: UnitOffset,
+    /// The address where the code of the instance starts.
+    entry_address: u64,
+    /// The address ranges of the instance.
+    ranges: Vec<std::ops::Range<u64>>,
+}
+
+impl DebugInfo {
+    /// Find the breakpoint locations for all instances of the functions matching `name`.
+    ///
+    /// Each out-of-line instance, generic instantiation and inlined copy of a matching function
+    /// results in one location. The location is the first statement after the prologue, if one
+    /// can be found in the function, or the entry address of the function otherwise.
+    pub fn get_function_breakpoint_locations(
+        &self,
+        name: &str,
+    ) -> Result<Vec<VerifiedBreakpoint>, DebugError> {
+        let query = path_segments(name.trim());
+        if query.is_empty() {
+            return Err(DebugError::Other("No function name specified".to_string()));
+        }
+
+        let mut locations: Vec<VerifiedBreakpoint> = Vec::new();
+        for object in self.objects() {
+            for unit_info in &object.unit_infos {
+                for instance in object.matching_functions(unit_info, &query)? {
+                    let address =
+                        match VerifiedBreakpoint::for_address(object, instance.entry_address) {
+                            Ok(breakpoint)
+                                if instance
+                                    .ranges
+                                    .iter()
+                                    .any(|range| range.contains(&breakpoint.address)) =>
+                            {
+                                breakpoint
+                            }
+                            _ => VerifiedBreakpoint {
+                                address: instance.entry_address,
+                                source_location: object
+                                    .get_source_location(instance.entry_address)
+                                    .unwrap_or_default(),
+                            },
+                        };
+
+                    if !locations
+                        .iter()
+                        .any(|location| location.address == address.address)
+                    {
+                        locations.push(address);
+                    }
+                }
+            }
+        }
+
+        if locations.is_empty() {
+            return Err(DebugError::Other(format!(
+                "No function named {name:?} was found in the debug information."
+            )));
+        }
+
+        locations.sort_by_key(|location| location.address);
+        Ok(locations)
+    }
+
+    /// Find the function instances in a compilation unit which match the `query`.
+    fn matching_functions(
+        &self,
+        unit_info: &UnitInfo,
+        query: &[String],
+    ) -> Result<Vec<FunctionInstance>, DebugError> {
+        // The qualified names of all functions, including declarations and abstract instances,
+        // which are referenced by the instances containing code.
+        let mut names: HashMap<UnitOffset, String> = HashMap::new();
+        let mut instances = Vec::new();
+        // The names of the enclosing namespaces and types, with their depth in the tree.
+        let mut scopes: Vec<(isize, String)> = Vec::new();
+
+        let mut depth = 0;
+        let mut entries = unit_info.unit.entries();
+        while let Some((delta, entry)) = entries.next_dfs()? {
+            depth += delta;
+            scopes.retain(|(scope_depth, _)| *scope_depth < depth);
+
+            match entry.tag() {
+                gimli::DW_TAG_namespace
+                | gimli::DW_TAG_structure_type
+                | gimli::DW_TAG_class_type
+                | gimli::DW_TAG_union_type
+                | gimli::DW_TAG_enumeration_type => {
+                    if let Some(name) = self.die_name(unit_info, entry) {
+                        scopes.push((depth, name));
+                    }
+                }
+                gimli::DW_TAG_subprogram | gimli::DW_TAG_inlined_subroutine => {
+                    if let Some(name) = self.die_name(unit_info, entry) {
+                        let mut qualified_name = scopes
+                            .iter()
+                            .map(|(_, scope)| scope.as_str())
+                            .collect::<Vec<_>>()
+                            .join("::");
+                        if !qualified_name.is_empty() {
+                            qualified_name.push_str("::");
+                        }
+                        qualified_name.push_str(&name);
+                        names.insert(entry.offset(), qualified_name);
+                    }
+
+                    if let Some(instance) = self.function_instance(unit_info, entry)? {
+                        instances.push(instance);
+                    }
+
+                    // Nested functions are scoped to their parent function.
+                    if entry.tag() == gimli::DW_TAG_subprogram {
+                        if let Some(name) = self.die_name(unit_info, entry) {
+                            scopes.push((depth, name));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut matches = Vec::new();
+        for instance in instances {
+            let Ok(entry) = unit_info.unit.entry(instance.offset) else {
+                continue;
+            };
+
+            let mut candidates = Vec::new();
+            if let Some(name) = self.resolve_function_name(unit_info, &entry, &names) {
+                candidates.push(name);
+            }
+            if let Some(linkage_name) = self.linkage_name(unit_info, &entry) {
+                // Trait methods can be found by the name of the trait as well.
+                if let Some(trait_method) = trait_method_path(&linkage_name) {
+                    candidates.push(trait_method);
+                }
+                candidates.push(linkage_name);
+            }
+
+            if candidates
+                .iter()
+                .any(|candidate| path_matches(&path_segments(candidate), query))
+            {
+                matches.push(instance);
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// The address range of a function DIE, if it contains code.
+    fn function_instance(
+        &self,
+        unit_info: &UnitInfo,
+        entry: &Die,
+    ) -> Result<Option<FunctionInstance>, DebugError> {
+        let mut ranges = Vec::new();
+        let mut die_ranges = self.dwarf.die_ranges(&unit_info.unit, entry)?;
+        while let Some(range) = die_ranges.next()? {
+            // Ranges at address 0 belong to functions which were removed by the linker.
+            if range.begin == 0 || range.begin >= range.end {
+                return Ok(None);
+            }
+            ranges.push(range.begin..range.end);
+        }
+
+        let Some(lowest_address) = ranges.iter().map(|range| range.start).min() else {
+            return Ok(None);
+        };
+
+        let entry_address = match entry.attr_value(gimli::DW_AT_entry_pc)? {
+            Some(gimli::AttributeValue::Addr(address)) => address,
+            Some(gimli::AttributeValue::DebugAddrIndex(index)) => {
+                self.dwarf.address(&unit_info.unit, index)?
+            }
+            _ => lowest_address,
+        };
+
+        Ok(Some(FunctionInstance {
+            offset: entry.offset(),
+            entry_address,
+            ranges,
+        }))
+    }
+
+    /// The qualified name of a function, following the references from concrete and inlined
+    /// instances to the declaration of the function.
+    fn resolve_function_name(
+        &self,
+        unit_info: &UnitInfo,
+        entry: &Die,
+        names: &HashMap<UnitOffset, String>,
+    ) -> Option<String> {
+        let mut offset = entry.offset();
+        // Declarations don't nest deeply, this only guards against cycles in malformed DWARF.
+        for _ in 0..8 {
+            if let Some(name) = names.get(&offset) {
+                return Some(name.clone());
+            }
+
+            let entry = unit_info.unit.entry(offset).ok()?;
+            offset = [gimli::DW_AT_abstract_origin, gimli::DW_AT_specification]
+                .into_iter()
+                .find_map(|attribute| match entry.attr_value(attribute).ok()? {
+                    Some(gimli::AttributeValue::UnitRef(offset)) => Some(offset),
+                    _ => None,
+                })?;
+        }
+
+        None
+    }
+
+    /// The demangled linkage name of a function, without the hash of Rust symbols.
+    ///
+    /// Abstract instances of inlined functions can be in a different compilation unit,
+    /// so the references are followed across units.
+    fn linkage_name(&self, unit_info: &UnitInfo, entry: &Die) -> Option<String> {
+        let mut unit_info = unit_info;
+        let mut entry = entry.clone();
+        for _ in 0..8 {
+            for attribute in [gimli::DW_AT_linkage_name, gimli::DW_AT_MIPS_linkage_name] {
+                if let Some(value) = entry.attr_value(attribute).ok()? {
+                    let name = self.dwarf.attr_string(&unit_info.unit, value).ok()?;
+                    let name = name.to_string_lossy().ok()?;
+                    return Some(match rustc_demangle::try_demangle(&name) {
+                        Ok(demangled) => format!("{demangled:#}"),
+                        Err(_) => name.into_owned(),
+                    });
+                }
+            }
+
+            (unit_info, entry) = [gimli::DW_AT_abstract_origin, gimli::DW_AT_specification]
+                .into_iter()
+                .find_map(|attribute| match entry.attr_value(attribute).ok()? {
+                    Some(gimli::AttributeValue::UnitRef(offset)) => {
+                        Some((unit_info, unit_info.unit.entry(offset).ok()?))
+                    }
+                    Some(gimli::AttributeValue::DebugInfoRef(offset)) => {
+                        self.unit_infos.iter().find_map(|unit_info| {
+                            let offset = offset.to_unit_offset(&unit_info.unit.header)?;
+                            Some((unit_info, unit_info.unit.entry(offset).ok()?))
+                        })
+                    }
+                    _ => None,
+                })?;
+        }
+
+        None
+    }
+
+    pub(super) fn die_name(&self, unit_info: &UnitInfo, entry: &Die) -> Option<String> {
+        let value = entry.attr_value(gimli::DW_AT_name).ok()??;
+        let name = self.dwarf.attr_string(&unit_info.unit, value).ok()?;
+        Some(name.to_string_lossy().ok()?.into_owned())
+    }
+}
+
+/// Split a path like `<crate::Type as Trait>::method<T>` into its segments, `["crate", "Type", "method<T>"]`.
+///
+/// Qualified self types are replaced by the type, and the `{impl#N}` scopes which Rust uses for
+/// the methods in `impl` blocks are removed, because they are not part of the names users write.
+pub(super) fn path_segments(path: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let bytes = path.as_bytes();
+
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'<' => depth += 1,
+            b'>' => depth -= 1,
+            b':' if depth == 0 && bytes.get(index + 1) == Some(&b':') => {
+                segments.push(&path[start..index]);
+                index += 2;
+                start = index;
+                continue;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    segments.push(&path[start..]);
+
+    let mut result = Vec::new();
+    for segment in segments {
+        let segment = segment.trim();
+        if segment.is_empty() || segment.starts_with("{impl#") {
+            continue;
+        }
+
+        if let Some(qualified_self) = segment
+            .strip_prefix('<')
+            .and_then(|segment| segment.strip_suffix('>'))
+        {
+            let self_type = qualified_self
+                .split_once(" as ")
+                .map_or(qualified_self, |(self_type, _)| self_type);
+            result.extend(path_segments(self_type));
+        } else {
+            result.push(segment.replace(' ', ""));
+        }
+    }
+
+    result
+}
+
+/// Rewrite the path of a trait method, `<Type as Trait>::method`, to `Trait::method`.
+fn trait_method_path(path: &str) -> Option<String> {
+    let rest = path.strip_prefix('<')?;
+
+    let mut depth = 0;
+    let mut trait_start = None;
+    for (index, c) in rest.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' if depth == 0 => {
+                let trait_path = &rest[trait_start?..index];
+                return Some(format!("{trait_path}{}", &rest[index + 1..]));
+            }
+            '>' => depth -= 1,
+            _ if depth == 0 && rest[index..].starts_with(" as ") => {
+                trait_start = Some(index + " as ".len());
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Check if the `query` is a suffix of the `candidate` path.
+///
+/// A query segment without generic arguments matches all instantiations of the segment.
+pub(super) fn path_matches(candidate: &[String], query: &[String]) -> bool {
+    let Some(offset) = candidate.len().checked_sub(query.len()) else {
+        return false;
+    };
+
+    candidate[offset..]
+        .iter()
+        .zip(query)
+        .all(|(candidate, query)| {
+            if query.contains('<') {
+                candidate == query
+            } else {
+                candidate.split('<').next() == Some(query.as_str())
+            }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn matches(candidate: &str, query: &str) -> bool {
+        path_matches(&path_segments(candidate), &path_segments(query))
+    }
+
+    #[test]
+    fn split_paths() {
+        assert_eq!(
+            path_segments("app::{impl#0}::run<u32>"),
+            vec!["app", "run<u32>"]
+        );
+        assert_eq!(
+            path_segments("<app::Driver as core::fmt::Debug>::fmt"),
+            vec!["app", "Driver", "fmt"]
+        );
+        assert_eq!(
+            path_segments("core::ptr::drop_in_place<alloc::vec::Vec<u8>>"),
+            vec!["core", "ptr", "drop_in_place<alloc::vec::Vec<u8>>"]
+        );
+    }
+
+    #[test]
+    fn trait_methods() {
+        assert_eq!(
+            trait_method_path("<app::Timer<T> as embedded_hal::timer::CountDown>::start"),
+            Some("embedded_hal::timer::CountDown::start".to_string())
+        );
+        assert_eq!(trait_method_path("<app::Timer<T>>::start"), None);
+        assert_eq!(trait_method_path("app::start"), None);
+    }
+
+    #[test]
+    fn match_function_names() {
+        assert!(matches("app::main", "main"));
+        assert!(matches("app::main", "app::main"));
+        assert!(!matches("app::main", "other::main"));
+        assert!(!matches("app::domain", "main"));
+        assert!(matches("app::Driver::run", "Driver::run"));
+        assert!(matches("app::{impl#2}::run<u32>", "app::run"));
+        assert!(matches("app::{impl#2}::run<u32>", "run<u32>"));
+        assert!(!matches("app::{impl#2}::run<u32>", "run<u8>"));
+        assert!(matches("<app::Driver>::run", "Driver::run"));
+        assert!(!matches("main", "app::main"));
+    }
+}
@@ -1,4 +1,5 @@
 use gimli::DwLang;
+use pretty_printer::PrettyPrinter;
 
 use crate::{
     debug::{
@@ -14,7 +15,8 @@ pub mod c;
 pub mod rust;
 
 mod parsing;
-mod value;
+pub(crate) mod pretty_printer;
+pub(crate) mod value;
 
 pub fn from_dwarf(language: DwLang) -> Box<dyn ProgrammingLanguage> {
     match language {
@@ -66,6 +68,11 @@ pub trait ProgrammingLanguage {
         false
     }
 
+    /// The pretty printers for container and smart pointer types of this language, tried in order.
+    fn pretty_printers(&self) -> &'static [PrettyPrinter] {
+        &[]
+    }
+
     fn modified_type_name(&self, modifier: &Modifier, name: &str) -> String {
         match modifier {
             Modifier::Const => format!("const {}", name),
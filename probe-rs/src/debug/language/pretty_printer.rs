@@ -0,0 +1,1090 @@
+//! Data-driven pretty printers for container and smart pointer types.
+//!
+//! The DWARF description of types like `Vec<T>` or `Rc<T>` only tells us about their internal
+//! fields, which is rarely what a user wants to see. Similar to the Rust printers shipped for gdb,
+//! a [`PrettyPrinter`] describes where the logical contents of such a type live, and the engine in
+//! this module uses that description to replace the raw fields with the logical children (e.g. the
+//! elements of a vector) and a one line summary value.
+//!
+//! Field layouts of the standard library change between compiler versions, so every member
+//! reference is a list of alternative paths, and the first path that exists in the DWARF is used.
+//! If no alternative matches, the type is displayed with its raw fields as before.
+
+use gimli::{AttributeValue, UnitOffset};
+
+use crate::{
+    debug::{
+        debug_info::DebugInfo, extract_byte_size, language, stack_frame::StackFrameInfo,
+        unit_info::UnitInfo, DebugError, Variable, VariableCache, VariableLocation, VariableName,
+        VariableNodeType, VariableType, VariableValue,
+    },
+    MemoryInterface,
+};
+
+/// Alternative paths of member names, leading from a type to one of its (nested) members.
+pub(crate) type MemberPaths = &'static [&'static [&'static str]];
+
+/// The maximum number of elements that are added as children of a container.
+const MAX_ELEMENTS: u64 = 100;
+
+/// The maximum number of elements that are shown in the summary value of a container.
+const MAX_SUMMARY_ELEMENTS: usize = 10;
+
+/// The maximum number of bytes that are read for a string.
+const MAX_TEXT_BYTES: u64 = 200;
+
+/// Pretty printed values are resolved eagerly, so this limits how deep a nested (or cyclic, in the
+/// case of `Rc`) structure is expanded.
+const MAX_DEPTH: usize = 12;
+
+/// Describes how to render a type in terms of its logical contents.
+#[derive(Debug)]
+pub(crate) struct PrettyPrinter {
+    /// The type names this printer applies to. Generic types match on their name without the
+    /// parameters, e.g. `Vec` matches `Vec<u8, alloc::alloc::Global>`. A name ending in `[`
+    /// matches any type name with that prefix, which is used for slices.
+    pub names: &'static [&'static str],
+    /// Where the contents of the type are stored.
+    pub layout: Layout,
+}
+
+impl PrettyPrinter {
+    /// Returns `true` if this printer applies to the given type name.
+    pub fn matches(&self, type_name: &str) -> bool {
+        self.names.iter().any(|name| {
+            if name.ends_with('[') {
+                type_name.starts_with(name)
+            } else {
+                type_name
+                    .strip_prefix(name)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('<'))
+            }
+        })
+    }
+}
+
+/// Where the elements of a container are stored.
+#[derive(Debug)]
+pub(crate) enum Elements {
+    /// The member is a pointer to the first element.
+    Pointer(MemberPaths),
+    /// The member is an array which holds the elements inline.
+    Inline(MemberPaths),
+}
+
+/// The number of elements in a ring buffer.
+#[derive(Debug)]
+pub(crate) enum RingLength {
+    /// The number of elements is stored in a member.
+    Length(MemberPaths),
+    /// The number of elements is the distance between the head and the `back` index, or the
+    /// capacity if the `full` flag is set.
+    Back {
+        back: MemberPaths,
+        full: MemberPaths,
+    },
+}
+
+/// The layout of a pretty printed type.
+#[derive(Debug)]
+pub(crate) enum Layout {
+    /// A contiguous sequence of elements, like `Vec<T>` or `&[T]`.
+    Sequence {
+        elements: Elements,
+        len: MemberPaths,
+    },
+    /// A contiguous sequence of UTF-8 bytes, like `String`.
+    Text { bytes: Elements, len: MemberPaths },
+    /// A ring buffer of elements, like `VecDeque<T>`.
+    Ring {
+        elements: Elements,
+        /// The capacity of the buffer. If this is `None`, the length of the inline array is used.
+        capacity: Option<MemberPaths>,
+        head: MemberPaths,
+        len: RingLength,
+    },
+    /// A pointer to a heap allocation holding the value, like `Box<T>` or `Rc<T>`.
+    Pointer {
+        pointer: MemberPaths,
+        /// The path to the value inside the allocation. An empty path refers to the allocation itself.
+        value: MemberPaths,
+        /// The name of the child holding the value. If this is `None`, the child is named like a
+        /// dereferenced pointer, `*name`.
+        value_name: Option<&'static str>,
+        /// Additional children, like reference counts, inside the allocation.
+        extra: &'static [(&'static str, MemberPaths)],
+    },
+    /// A type that wraps a single value, like `Cell<T>`.
+    Wrapper {
+        value: MemberPaths,
+        /// Additional state that is shown as a child, like the borrow flag of `RefCell<T>`.
+        state: Option<(&'static str, MemberPaths)>,
+    },
+    /// The B-tree of a `BTreeMap<K, V>`.
+    BTreeMap {
+        node: MemberPaths,
+        height: MemberPaths,
+        len: MemberPaths,
+    },
+    /// An enum whose active variant is shown as `Variant(payload)`, like `Option<T>`.
+    Variants,
+}
+
+/// The location and type of a (nested) member of a type.
+#[derive(Clone, Copy, Debug)]
+struct Member {
+    offset: u64,
+    type_offset: UnitOffset,
+}
+
+/// A value that will be added as a child of the pretty printed variable.
+struct Child {
+    name: String,
+    type_offset: UnitOffset,
+    address: u64,
+}
+
+impl UnitInfo {
+    /// Try to render `variable`, which is located in memory and has the type described by the
+    /// `type_offset` DIE, using the pretty printer for `type_name`.
+    ///
+    /// Returns `Ok(false)` if there is no printer for the type, or the layout of the type does not
+    /// match the printer. In that case the cache has not been modified.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn pretty_print(
+        &self,
+        debug_info: &DebugInfo,
+        type_offset: UnitOffset,
+        type_name: &str,
+        variable: &mut Variable,
+        memory: &mut dyn MemoryInterface,
+        cache: &mut VariableCache,
+        frame_info: StackFrameInfo<'_>,
+    ) -> Result<bool, DebugError> {
+        let Ok(address) = variable.memory_location.memory_address() else {
+            return Ok(false);
+        };
+        if nesting_depth(variable, cache) > MAX_DEPTH {
+            return Ok(false);
+        }
+
+        for printer in self.language.pretty_printers() {
+            if !printer.matches(type_name) {
+                continue;
+            }
+
+            // Reading the target memory may fail, e.g. for uninitialized pointers. We fall back to
+            // the raw fields in that case.
+            let planned = match self.plan(debug_info, type_offset, address, &printer.layout, memory)
+            {
+                Ok(Some(planned)) => planned,
+                Ok(None) => continue,
+                Err(error) => {
+                    tracing::debug!("Failed to pretty print {type_name}: {error:?}");
+                    continue;
+                }
+            };
+
+            match planned {
+                Plan::Children { children, summary } => {
+                    let mut displays = Vec::with_capacity(children.len());
+                    for child in children {
+                        let child = self
+                            .cache_value(debug_info, variable, child, memory, cache, frame_info)?;
+                        displays.push(short_display(&child, cache));
+                    }
+                    let value = match summary {
+                        Summary::List { total } => list_summary(&displays, total, "[", "]"),
+                        Summary::First => displays.into_iter().next().unwrap_or_default(),
+                        Summary::Text(text) => text,
+                    };
+                    variable.set_value(VariableValue::Valid(value));
+                }
+                Plan::Map { entries, total } => {
+                    let mut displays = Vec::with_capacity(entries.len());
+                    for (index, (key, value)) in entries.into_iter().enumerate() {
+                        let mut entry =
+                            cache.create_variable(variable.variable_key(), Some(self))?;
+                        entry.name = VariableName::Named(format!("__{index}"));
+                        entry.type_name = VariableType::Struct("(K, V)".to_string());
+                        entry.memory_location = VariableLocation::Address(key.address);
+                        entry.variable_node_type = VariableNodeType::DoNotRecurse;
+                        let key =
+                            self.cache_value(debug_info, &entry, key, memory, cache, frame_info)?;
+                        let value =
+                            self.cache_value(debug_info, &entry, value, memory, cache, frame_info)?;
+                        let display = format!(
+                            "{}: {}",
+                            short_display(&key, cache),
+                            short_display(&value, cache)
+                        );
+                        entry.set_value(VariableValue::Valid(display.clone()));
+                        cache.update_variable(&entry)?;
+                        displays.push(display);
+                    }
+                    variable.set_value(VariableValue::Valid(list_summary(
+                        &displays, total, "{", "}",
+                    )));
+                }
+                Plan::Variants => {
+                    variable.variable_node_type = VariableNodeType::RecurseToBaseType;
+                    let mut tree = self.unit.entries_tree(Some(type_offset))?;
+                    self.process_tree(
+                        debug_info,
+                        tree.root()?,
+                        variable,
+                        memory,
+                        cache,
+                        frame_info,
+                    )?;
+
+                    let Some(active) = cache.get_children(variable.variable_key()).next().cloned()
+                    else {
+                        return Ok(true);
+                    };
+                    let payload = cache
+                        .get_children(active.variable_key())
+                        .map(|field| short_display(field, cache))
+                        .collect::<Vec<_>>();
+                    let variant = active.type_name.display_name(self.language.as_ref());
+                    variable.set_value(VariableValue::Valid(if payload.is_empty() {
+                        variant
+                    } else {
+                        format!("{variant}({})", payload.join(", "))
+                    }));
+                }
+            }
+
+            variable.variable_node_type = VariableNodeType::DoNotRecurse;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Resolve the layout of the printer against the DWARF type, and read the values needed to
+    /// locate the children. This does not modify the cache.
+    fn plan(
+        &self,
+        debug_info: &DebugInfo,
+        type_offset: UnitOffset,
+        address: u64,
+        layout: &Layout,
+        memory: &mut dyn MemoryInterface,
+    ) -> Result<Option<Plan>, DebugError> {
+        let plan = match layout {
+            Layout::Sequence { elements, len } => {
+                let Some(len) = self.read_member(debug_info, type_offset, address, len, memory)?
+                else {
+                    return Ok(None);
+                };
+                let Some((first, element_type, stride)) =
+                    self.elements(debug_info, type_offset, address, elements, len, memory)?
+                else {
+                    return Ok(None);
+                };
+                Plan::Children {
+                    children: (0..len.min(MAX_ELEMENTS))
+                        .map(|index| Child {
+                            name: format!("__{index}"),
+                            type_offset: element_type,
+                            address: first + index * stride,
+                        })
+                        .collect(),
+                    summary: Summary::List { total: len },
+                }
+            }
+            Layout::Text { bytes, len } => {
+                let Some(len) = self.read_member(debug_info, type_offset, address, len, memory)?
+                else {
+                    return Ok(None);
+                };
+                let Some((first, _, _)) =
+                    self.elements(debug_info, type_offset, address, bytes, len, memory)?
+                else {
+                    return Ok(None);
+                };
+                let mut buffer = vec![0; len.min(MAX_TEXT_BYTES) as usize];
+                if !buffer.is_empty() {
+                    memory.read(first, &mut buffer)?;
+                }
+                let mut text = String::from_utf8_lossy(&buffer).into_owned();
+                if len > MAX_TEXT_BYTES {
+                    text.push_str("...");
+                }
+                Plan::Children {
+                    children: vec![],
+                    summary: Summary::Text(text),
+                }
+            }
+            Layout::Ring {
+                elements,
+                capacity,
+                head,
+                len,
+            } => {
+                let Some(head) =
+                    self.read_member(debug_info, type_offset, address, head, memory)?
+                else {
+                    return Ok(None);
+                };
+                let capacity = match capacity {
+                    Some(capacity) => {
+                        self.read_member(debug_info, type_offset, address, capacity, memory)?
+                    }
+                    None => self.inline_capacity(debug_info, type_offset, elements)?,
+                };
+                let Some(capacity) = capacity else {
+                    return Ok(None);
+                };
+                let len = match len {
+                    RingLength::Length(len) => {
+                        self.read_member(debug_info, type_offset, address, len, memory)?
+                    }
+                    RingLength::Back { back, full } => {
+                        let back =
+                            self.read_member(debug_info, type_offset, address, back, memory)?;
+                        let full =
+                            self.read_member(debug_info, type_offset, address, full, memory)?;
+                        back.zip(full).map(|(back, full)| {
+                            if full != 0 {
+                                capacity
+                            } else {
+                                (back + capacity - head) % capacity.max(1)
+                            }
+                        })
+                    }
+                };
+                let Some(len) = len else {
+                    return Ok(None);
+                };
+                if len > capacity {
+                    return Ok(None);
+                }
+                let Some((first, element_type, stride)) =
+                    self.elements(debug_info, type_offset, address, elements, len, memory)?
+                else {
+                    return Ok(None);
+                };
+                Plan::Children {
+                    children: (0..len.min(MAX_ELEMENTS))
+                        .map(|index| Child {
+                            name: format!("__{index}"),
+                            type_offset: element_type,
+                            address: first + ((head + index) % capacity) * stride,
+                        })
+                        .collect(),
+                    summary: Summary::List { total: len },
+                }
+            }
+            Layout::Pointer {
+                pointer,
+                value,
+                value_name,
+                extra,
+            } => {
+                let Some(member) = self.member_path(debug_info, type_offset, pointer)? else {
+                    return Ok(None);
+                };
+                let Some(pointee) = self.pointee_type(member.type_offset)? else {
+                    return Ok(None);
+                };
+                let allocation = self.read_pointer(address + member.offset, memory)?;
+                if allocation == 0 {
+                    return Ok(None);
+                }
+
+                let Some(value) = self.member_path(debug_info, pointee, value)? else {
+                    return Ok(None);
+                };
+                let mut children = vec![Child {
+                    name: value_name.map_or_else(|| "*".to_string(), str::to_string),
+                    type_offset: value.type_offset,
+                    address: allocation + value.offset,
+                }];
+                for (name, paths) in extra.iter() {
+                    let Some(member) = self.member_path(debug_info, pointee, paths)? else {
+                        return Ok(None);
+                    };
+                    children.push(Child {
+                        name: name.to_string(),
+                        type_offset: member.type_offset,
+                        address: allocation + member.offset,
+                    });
+                }
+                Plan::Children {
+                    children,
+                    summary: Summary::First,
+                }
+            }
+            Layout::Wrapper { value, state } => {
+                let Some(value) = self.member_path(debug_info, type_offset, value)? else {
+                    return Ok(None);
+                };
+                let mut children = vec![Child {
+                    name: "value".to_string(),
+                    type_offset: value.type_offset,
+                    address: address + value.offset,
+                }];
+                if let Some((name, paths)) = state {
+                    let Some(member) = self.member_path(debug_info, type_offset, paths)? else {
+                        return Ok(None);
+                    };
+                    children.push(Child {
+                        name: name.to_string(),
+                        type_offset: member.type_offset,
+                        address: address + member.offset,
+                    });
+                }
+                Plan::Children {
+                    children,
+                    summary: Summary::First,
+                }
+            }
+            Layout::BTreeMap { node, height, len } => {
+                let Some(len) = self.read_member(debug_info, type_offset, address, len, memory)?
+                else {
+                    return Ok(None);
+                };
+                let key_type = self.template_parameter(debug_info, type_offset, "K")?;
+                let value_type = self.template_parameter(debug_info, type_offset, "V")?;
+                let (Some(key_type), Some(value_type)) = (key_type, value_type) else {
+                    return Ok(None);
+                };
+                if len == 0 {
+                    return Ok(Some(Plan::Map {
+                        entries: vec![],
+                        total: 0,
+                    }));
+                }
+
+                let Some(node_member) = self.member_path(debug_info, type_offset, node)? else {
+                    return Ok(None);
+                };
+                let Some(height) =
+                    self.read_member(debug_info, type_offset, address, height, memory)?
+                else {
+                    return Ok(None);
+                };
+                let Some(leaf_type) = self.pointee_type(node_member.type_offset)? else {
+                    return Ok(None);
+                };
+                let Some(tree) = BTree::new(self, debug_info, leaf_type, key_type, value_type)?
+                else {
+                    return Ok(None);
+                };
+
+                let root = self.read_pointer(address + node_member.offset, memory)?;
+                let mut entries = Vec::new();
+                tree.collect(self, root, height, memory, &mut entries)?;
+                Plan::Map {
+                    entries: entries
+                        .into_iter()
+                        .map(|(key, value)| {
+                            (
+                                Child {
+                                    name: "key".to_string(),
+                                    type_offset: key_type,
+                                    address: key,
+                                },
+                                Child {
+                                    name: "value".to_string(),
+                                    type_offset: value_type,
+                                    address: value,
+                                },
+                            )
+                        })
+                        .collect(),
+                    total: len,
+                }
+            }
+            Layout::Variants => Plan::Variants,
+        };
+
+        Ok(Some(plan))
+    }
+
+    /// Create a child of `parent` for the value at `child.address`, and resolve it like any other
+    /// variable.
+    fn cache_value(
+        &self,
+        debug_info: &DebugInfo,
+        parent: &Variable,
+        child: Child,
+        memory: &mut dyn MemoryInterface,
+        cache: &mut VariableCache,
+        frame_info: StackFrameInfo<'_>,
+    ) -> Result<Variable, DebugError> {
+        let mut variable = cache.create_variable(parent.variable_key(), Some(self))?;
+        variable.name = VariableName::Named(if child.name == "*" {
+            format!("*{}", parent.name)
+        } else {
+            child.name
+        });
+        variable.memory_location = VariableLocation::Address(child.address);
+
+        let type_entry = self.unit.entry(child.type_offset)?;
+        self.extract_type(
+            debug_info,
+            &type_entry,
+            parent,
+            &mut variable,
+            memory,
+            cache,
+            frame_info,
+        )?;
+
+        Ok(variable)
+    }
+
+    /// Locate the elements of a container, returning the address of the first element, the
+    /// element type, and the distance between elements.
+    fn elements(
+        &self,
+        debug_info: &DebugInfo,
+        type_offset: UnitOffset,
+        address: u64,
+        elements: &Elements,
+        len: u64,
+        memory: &mut dyn MemoryInterface,
+    ) -> Result<Option<(u64, UnitOffset, u64)>, DebugError> {
+        let (paths, inline) = match elements {
+            Elements::Pointer(paths) => (paths, false),
+            Elements::Inline(paths) => (paths, true),
+        };
+        let Some(member) = self.member_path(debug_info, type_offset, paths)? else {
+            return Ok(None);
+        };
+
+        let declared_type = if inline {
+            self.array_type(member.type_offset)?
+                .map(|(element, _)| element)
+        } else {
+            self.pointee_type(member.type_offset)?
+        };
+        let element_type = match self.template_parameter(debug_info, type_offset, "T")? {
+            Some(element_type) => Some(element_type),
+            None => declared_type,
+        };
+        let Some(element_type) = element_type else {
+            return Ok(None);
+        };
+        let Some(stride) = self.type_size(element_type)? else {
+            return Ok(None);
+        };
+
+        let first = if inline {
+            address + member.offset
+        } else if len == 0 {
+            // Empty containers may use a dangling pointer, which must not be read.
+            0
+        } else {
+            self.read_pointer(address + member.offset, memory)?
+        };
+
+        Ok(Some((first, element_type, stride)))
+    }
+
+    /// The number of elements in the inline array of a container.
+    fn inline_capacity(
+        &self,
+        debug_info: &DebugInfo,
+        type_offset: UnitOffset,
+        elements: &Elements,
+    ) -> Result<Option<u64>, DebugError> {
+        let Elements::Inline(paths) = elements else {
+            return Ok(None);
+        };
+        let Some(member) = self.member_path(debug_info, type_offset, paths)? else {
+            return Ok(None);
+        };
+        Ok(self.array_type(member.type_offset)?.map(|(_, count)| count))
+    }
+
+    /// Read the unsigned integer (or boolean) member at one of the `paths`.
+    fn read_member(
+        &self,
+        debug_info: &DebugInfo,
+        type_offset: UnitOffset,
+        address: u64,
+        paths: MemberPaths,
+        memory: &mut dyn MemoryInterface,
+    ) -> Result<Option<u64>, DebugError> {
+        let Some(member) = self.member_path(debug_info, type_offset, paths)? else {
+            return Ok(None);
+        };
+        let Some(size) = self.type_size(member.type_offset)? else {
+            return Ok(None);
+        };
+        read_unsigned(memory, address + member.offset, size)
+    }
+
+    fn read_pointer(
+        &self,
+        address: u64,
+        memory: &mut dyn MemoryInterface,
+    ) -> Result<u64, DebugError> {
+        let size = self.unit.header.address_size() as u64;
+        read_unsigned(memory, address, size)?
+            .ok_or_else(|| DebugError::Other(format!("Unsupported pointer size {size}")))
+    }
+
+    /// Follow the first of the `paths` that exists, starting at the type `type_offset`.
+    fn member_path(
+        &self,
+        debug_info: &DebugInfo,
+        type_offset: UnitOffset,
+        paths: MemberPaths,
+    ) -> Result<Option<Member>, DebugError> {
+        'paths: for path in paths.iter() {
+            let mut member = Member {
+                offset: 0,
+                type_offset,
+            };
+            for name in path.iter() {
+                let parent_type = self.resolve_type(member.type_offset)?;
+                let mut tree = self.unit.entries_tree(Some(parent_type))?;
+                let Some(next) = self.find_member(debug_info, tree.root()?, name)? else {
+                    continue 'paths;
+                };
+                member = Member {
+                    offset: member.offset + next.offset,
+                    type_offset: next.type_offset,
+                };
+            }
+            return Ok(Some(member));
+        }
+        Ok(None)
+    }
+
+    /// Find a direct member by name. Members of enum variants are found as well, so that paths
+    /// can lead through the `Some` variant of an `Option`.
+    fn find_member(
+        &self,
+        debug_info: &DebugInfo,
+        node: gimli::EntriesTreeNode<crate::debug::GimliReader>,
+        name: &str,
+    ) -> Result<Option<Member>, DebugError> {
+        let mut children = node.children();
+        while let Some(child) = children.next()? {
+            match child.entry().tag() {
+                gimli::DW_TAG_member => {
+                    if debug_info.die_name(self, child.entry()).as_deref() != Some(name) {
+                        continue;
+                    }
+                    let offset = match child
+                        .entry()
+                        .attr_value(gimli::DW_AT_data_member_location)?
+                    {
+                        Some(location) => location.udata_value().unwrap_or(0),
+                        None => 0,
+                    };
+                    let Some(AttributeValue::UnitRef(type_offset)) =
+                        child.entry().attr_value(gimli::DW_AT_type)?
+                    else {
+                        return Ok(None);
+                    };
+                    return Ok(Some(Member {
+                        offset,
+                        type_offset,
+                    }));
+                }
+                gimli::DW_TAG_variant_part | gimli::DW_TAG_variant => {
+                    if let Some(member) = self.find_member(debug_info, child, name)? {
+                        return Ok(Some(member));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the type of a generic type parameter, e.g. `T` of `Vec<T>`.
+    fn template_parameter(
+        &self,
+        debug_info: &DebugInfo,
+        type_offset: UnitOffset,
+        name: &str,
+    ) -> Result<Option<UnitOffset>, DebugError> {
+        let mut tree = self
+            .unit
+            .entries_tree(Some(self.resolve_type(type_offset)?))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            if entry.tag() == gimli::DW_TAG_template_type_parameter
+                && debug_info.die_name(self, entry).as_deref() == Some(name)
+            {
+                if let Some(AttributeValue::UnitRef(parameter)) =
+                    entry.attr_value(gimli::DW_AT_type)?
+                {
+                    return Ok(Some(parameter));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Skip typedefs and type modifiers.
+    fn resolve_type(&self, mut type_offset: UnitOffset) -> Result<UnitOffset, DebugError> {
+        loop {
+            let entry = self.unit.entry(type_offset)?;
+            if !matches!(
+                entry.tag(),
+                gimli::DW_TAG_typedef | gimli::DW_TAG_const_type | gimli::DW_TAG_volatile_type
+            ) {
+                return Ok(type_offset);
+            }
+            match entry.attr_value(gimli::DW_AT_type)? {
+                Some(AttributeValue::UnitRef(next)) => type_offset = next,
+                _ => return Ok(type_offset),
+            }
+        }
+    }
+
+    /// The type a pointer points to. Pointers wrapped in structs, like `NonNull<T>`, are followed
+    /// through their `pointer` member.
+    fn pointee_type(&self, type_offset: UnitOffset) -> Result<Option<UnitOffset>, DebugError> {
+        let type_offset = self.resolve_type(type_offset)?;
+        let entry = self.unit.entry(type_offset)?;
+        match entry.tag() {
+            gimli::DW_TAG_pointer_type | gimli::DW_TAG_reference_type => {
+                match entry.attr_value(gimli::DW_AT_type)? {
+                    Some(AttributeValue::UnitRef(pointee)) => Ok(Some(pointee)),
+                    _ => Ok(None),
+                }
+            }
+            gimli::DW_TAG_structure_type => {
+                let mut tree = self.unit.entries_tree(Some(type_offset))?;
+                let mut children = tree.root()?.children();
+                while let Some(child) = children.next()? {
+                    if child.entry().tag() == gimli::DW_TAG_member {
+                        if let Some(AttributeValue::UnitRef(member_type)) =
+                            child.entry().attr_value(gimli::DW_AT_type)?
+                        {
+                            return self.pointee_type(member_type);
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The element type and element count of an array type.
+    fn array_type(&self, type_offset: UnitOffset) -> Result<Option<(UnitOffset, u64)>, DebugError> {
+        let type_offset = self.resolve_type(type_offset)?;
+        let entry = self.unit.entry(type_offset)?;
+        if entry.tag() != gimli::DW_TAG_array_type {
+            return Ok(None);
+        }
+        let Some(AttributeValue::UnitRef(element_type)) = entry.attr_value(gimli::DW_AT_type)?
+        else {
+            return Ok(None);
+        };
+
+        let mut tree = self.unit.entries_tree(Some(type_offset))?;
+        let mut children = tree.root()?.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            if entry.tag() != gimli::DW_TAG_subrange_type {
+                continue;
+            }
+            if let Some(count) = entry
+                .attr_value(gimli::DW_AT_count)?
+                .and_then(|count| count.udata_value())
+            {
+                return Ok(Some((element_type, count)));
+            }
+            if let Some(upper_bound) = entry
+                .attr_value(gimli::DW_AT_upper_bound)?
+                .and_then(|bound| bound.udata_value())
+            {
+                return Ok(Some((element_type, upper_bound + 1)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The size of a type in bytes.
+    fn type_size(&self, type_offset: UnitOffset) -> Result<Option<u64>, DebugError> {
+        let type_offset = self.resolve_type(type_offset)?;
+        let entry = self.unit.entry(type_offset)?;
+        if let Some(size) = extract_byte_size(&entry) {
+            return Ok(Some(size));
+        }
+        Ok(match entry.tag() {
+            gimli::DW_TAG_pointer_type | gimli::DW_TAG_reference_type => {
+                Some(self.unit.header.address_size() as u64)
+            }
+            gimli::DW_TAG_array_type => match self.array_type(type_offset)? {
+                Some((element, count)) => self.type_size(element)?.map(|size| size * count),
+                None => None,
+            },
+            _ => None,
+        })
+    }
+}
+
+/// The children and summary of a pretty printed variable, resolved before the cache is modified.
+enum Plan {
+    Children {
+        children: Vec<Child>,
+        summary: Summary,
+    },
+    Map {
+        entries: Vec<(Child, Child)>,
+        total: u64,
+    },
+    Variants,
+}
+
+/// How the value of a pretty printed variable is derived from its children.
+enum Summary {
+    /// A list of all children, e.g. `[1, 2, 3]`.
+    List { total: u64 },
+    /// The value of the first child.
+    First,
+    /// A text value.
+    Text(String),
+}
+
+/// The layout of the nodes of a `BTreeMap<K, V>`.
+struct BTree {
+    len_offset: u64,
+    len_size: u64,
+    keys_offset: u64,
+    key_size: u64,
+    values_offset: u64,
+    value_size: u64,
+    edges_offset: u64,
+    pointer_size: u64,
+}
+
+impl BTree {
+    fn new(
+        unit_info: &UnitInfo,
+        debug_info: &DebugInfo,
+        leaf_type: UnitOffset,
+        key_type: UnitOffset,
+        value_type: UnitOffset,
+    ) -> Result<Option<Self>, DebugError> {
+        let len = unit_info.member_path(debug_info, leaf_type, &[&["len"]])?;
+        let keys = unit_info.member_path(debug_info, leaf_type, &[&["keys"]])?;
+        let values = unit_info.member_path(debug_info, leaf_type, &[&["vals"]])?;
+        let (Some(len), Some(keys), Some(values)) = (len, keys, values) else {
+            return Ok(None);
+        };
+        let len_size = unit_info.type_size(len.type_offset)?;
+        let key_size = unit_info.type_size(key_type)?;
+        let value_size = unit_info.type_size(value_type)?;
+        let leaf_size = unit_info.type_size(leaf_type)?;
+        let (Some(len_size), Some(key_size), Some(value_size), Some(leaf_size)) =
+            (len_size, key_size, value_size, leaf_size)
+        else {
+            return Ok(None);
+        };
+
+        // Internal nodes start with a leaf node, followed by the edges to their children.
+        let pointer_size = unit_info.unit.header.address_size() as u64;
+        let edges_offset = leaf_size.div_ceil(pointer_size) * pointer_size;
+
+        Ok(Some(Self {
+            len_offset: len.offset,
+            len_size,
+            keys_offset: keys.offset,
+            key_size,
+            values_offset: values.offset,
+            value_size,
+            edges_offset,
+            pointer_size,
+        }))
+    }
+
+    /// Collect the addresses of the keys and values below `node`, in order.
+    fn collect(
+        &self,
+        unit_info: &UnitInfo,
+        node: u64,
+        height: u64,
+        memory: &mut dyn MemoryInterface,
+        entries: &mut Vec<(u64, u64)>,
+    ) -> Result<(), DebugError> {
+        if node == 0 || entries.len() as u64 >= MAX_ELEMENTS {
+            return Ok(());
+        }
+        let len = read_unsigned(memory, node + self.len_offset, self.len_size)?.unwrap_or(0);
+
+        for index in 0..=len {
+            if height > 0 {
+                let edge = node + self.edges_offset + index * self.pointer_size;
+                let child = unit_info.read_pointer(edge, memory)?;
+                self.collect(unit_info, child, height - 1, memory, entries)?;
+            }
+            if index < len && (entries.len() as u64) < MAX_ELEMENTS {
+                entries.push((
+                    node + self.keys_offset + index * self.key_size,
+                    node + self.values_offset + index * self.value_size,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read an unsigned integer of `size` bytes. Returns `None` for unsupported sizes.
+fn read_unsigned(
+    memory: &mut dyn MemoryInterface,
+    address: u64,
+    size: u64,
+) -> Result<Option<u64>, DebugError> {
+    Ok(Some(match size {
+        1 => memory.read_word_8(address)? as u64,
+        2 => memory.read_word_16(address)? as u64,
+        4 => memory.read_word_32(address)? as u64,
+        8 => memory.read_word_64(address)?,
+        _ => return Ok(None),
+    }))
+}
+
+/// The number of ancestors of `variable` in the cache.
+fn nesting_depth(variable: &Variable, cache: &VariableCache) -> usize {
+    let mut depth = 0;
+    let mut parent = variable.parent_key;
+    while let Some(ancestor) = cache.get_variable_by_key(parent) {
+        depth += 1;
+        if depth > MAX_DEPTH {
+            break;
+        }
+        parent = ancestor.parent_key;
+    }
+    depth
+}
+
+/// A single line representation of a child, used in the summary of its parent.
+fn short_display(variable: &Variable, cache: &VariableCache) -> String {
+    let display = variable.to_string(cache);
+    if display.contains('\n') {
+        variable
+            .type_name
+            .display_name(language::from_dwarf(variable.language).as_ref())
+    } else {
+        display
+    }
+}
+
+/// Format a list of element displays with the length of the collection, e.g.
+/// `len = 10, [1, 2, ... and 8 more]`.
+fn list_summary(displays: &[String], total: u64, open: &str, close: &str) -> String {
+    let shown = displays.len().min(MAX_SUMMARY_ELEMENTS);
+    let mut summary = displays[..shown].join(", ");
+    let hidden = total.saturating_sub(shown as u64);
+    if hidden > 0 {
+        if shown > 0 {
+            summary.push_str(", ");
+        }
+        summary.push_str(&format!("... and {hidden} more"));
+    }
+    format!("len = {total}, {open}{summary}{close}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{debug::exception_handling::exception_handler_for_core, CoreDump};
+    use std::path::PathBuf;
+
+    #[test]
+    fn match_type_names() {
+        let printer = PrettyPrinter {
+            names: &["Vec", "&["],
+            layout: Layout::Variants,
+        };
+
+        assert!(printer.matches("Vec<u8, alloc::alloc::Global>"));
+        assert!(printer.matches("Vec"));
+        assert!(printer.matches("&[i32]"));
+        assert!(!printer.matches("VecDeque<u8, alloc::alloc::Global>"));
+        assert!(!printer.matches("&str"));
+    }
+
+    #[test]
+    fn summaries() {
+        let displays = (1..=12).map(|i| i.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            list_summary(&displays[..3], 3, "[", "]"),
+            "len = 3, [1, 2, 3]"
+        );
+        assert_eq!(list_summary(&[], 0, "{", "}"), "len = 0, {}");
+        assert_eq!(
+            list_summary(&displays, 250, "[", "]"),
+            "len = 250, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, ... and 240 more]"
+        );
+    }
+
+    #[test]
+    fn pretty_print_locals() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("tests/debug-unwind-tests");
+        let debug_info = DebugInfo::from_file(path.join("nRF52833_xxAA_full_unwind.elf")).unwrap();
+        let mut core_dump =
+            CoreDump::load(&path.join("nRF52833_xxAA_full_unwind.coredump")).unwrap();
+
+        let initial_registers = core_dump.debug_registers();
+        let exception_handler = exception_handler_for_core(core_dump.core_type());
+        let instruction_set = core_dump.instruction_set();
+        let mut frame = debug_info
+            .unwind(
+                &mut core_dump,
+                initial_registers,
+                exception_handler.as_ref(),
+                Some(instruction_set),
+            )
+            .unwrap()
+            .into_iter()
+            .find(|frame| frame.function_name == "setup_data_types")
+            .unwrap();
+        let mut cache = frame.local_variables.take().unwrap();
+        cache.recurse_deferred_variables(
+            &debug_info,
+            &mut core_dump,
+            1,
+            StackFrameInfo {
+                registers: &frame.registers,
+                frame_base: frame.frame_base,
+                canonical_frame_address: frame.canonical_frame_address,
+            },
+        );
+
+        let local = |name: &str| {
+            let variable = cache
+                .get_variable_by_name(&VariableName::Named(name.to_string()))
+                .unwrap();
+            let children = cache
+                .get_children(variable.variable_key())
+                .map(|child| (child.name.to_string(), child.to_string(&cache)))
+                .collect::<Vec<_>>();
+            (variable.to_string(&cache), children)
+        };
+
+        let (value, children) = local("heapless_vec");
+        assert_eq!(value, "len = 3, [1, 2, 3]");
+        assert_eq!(
+            children,
+            [("__0", "1"), ("__1", "2"), ("__2", "3")].map(|(n, v)| (n.into(), v.into()))
+        );
+
+        let (value, children) = local("loop_counter");
+        assert_eq!(value, "0");
+        assert_eq!(children, [("value".into(), "0".into())]);
+
+        let (value, _) = local("function_result");
+        assert_eq!(value, "Err(Forcing the return of an Error variant)");
+
+        let (value, _) = local("struct_with_one_variant");
+        assert_eq!(value, "Some(Univariant @ 0x20003BA8)");
+    }
+}
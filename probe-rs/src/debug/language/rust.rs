@@ -1,6 +1,7 @@
 use crate::{
     debug::{
         language::{
+            pretty_printer::{Elements, Layout, PrettyPrinter, RingLength},
             value::{format_float, Value},
             ProgrammingLanguage,
         },
@@ -127,4 +128,172 @@ impl ProgrammingLanguage for Rust {
             || name.starts_with("Ok")
             || name.starts_with("Err")
     }
+
+    fn pretty_printers(&self) -> &'static [PrettyPrinter] {
+        PRETTY_PRINTERS
+    }
 }
+
+/// The pointer to the buffer of a `RawVec`, for the layouts used by different compiler versions.
+macro_rules! raw_vec_pointer {
+    ($($prefix:literal),*) => {
+        &[
+            &[$($prefix,)* "buf", "inner", "ptr", "pointer", "pointer"],
+            &[$($prefix,)* "buf", "ptr", "pointer", "pointer"],
+            &[$($prefix,)* "buf", "ptr", "pointer"],
+        ]
+    };
+}
+
+/// Pretty printers for the containers, smart pointers and cells of the standard library and
+/// `heapless`.
+static PRETTY_PRINTERS: &[PrettyPrinter] = &[
+    // alloc::vec::Vec
+    PrettyPrinter {
+        names: &["Vec"],
+        layout: Layout::Sequence {
+            elements: Elements::Pointer(raw_vec_pointer!()),
+            len: &[&["len"]],
+        },
+    },
+    // heapless::Vec
+    PrettyPrinter {
+        names: &["Vec"],
+        layout: Layout::Sequence {
+            elements: Elements::Inline(&[&["buffer"], &["inner", "buffer"]]),
+            len: &[&["len"], &["inner", "len"]],
+        },
+    },
+    // alloc::string::String
+    PrettyPrinter {
+        names: &["String"],
+        layout: Layout::Text {
+            bytes: Elements::Pointer(raw_vec_pointer!("vec")),
+            len: &[&["vec", "len"]],
+        },
+    },
+    // heapless::String
+    PrettyPrinter {
+        names: &["String"],
+        layout: Layout::Text {
+            bytes: Elements::Inline(&[&["vec", "buffer"], &["vec", "inner", "buffer"]]),
+            len: &[&["vec", "len"], &["vec", "inner", "len"]],
+        },
+    },
+    // Slices
+    PrettyPrinter {
+        names: &["&[", "&mut ["],
+        layout: Layout::Sequence {
+            elements: Elements::Pointer(&[&["data_ptr"]]),
+            len: &[&["length"]],
+        },
+    },
+    // alloc::collections::VecDeque
+    PrettyPrinter {
+        names: &["VecDeque"],
+        layout: Layout::Ring {
+            elements: Elements::Pointer(raw_vec_pointer!()),
+            capacity: Some(&[
+                &["buf", "inner", "cap", "__0"],
+                &["buf", "cap", "__0"],
+                &["buf", "cap"],
+            ]),
+            head: &[&["head"]],
+            len: RingLength::Length(&[&["len"]]),
+        },
+    },
+    // heapless::Deque
+    PrettyPrinter {
+        names: &["Deque"],
+        layout: Layout::Ring {
+            elements: Elements::Inline(&[&["buffer"]]),
+            capacity: None,
+            head: &[&["front"]],
+            len: RingLength::Back {
+                back: &[&["back"]],
+                full: &[&["full"]],
+            },
+        },
+    },
+    // alloc::boxed::Box, when it is not described as a plain pointer.
+    PrettyPrinter {
+        names: &["Box"],
+        layout: Layout::Pointer {
+            pointer: &[&["__0", "pointer", "pointer"], &["__0", "pointer"]],
+            value: &[&[]],
+            value_name: None,
+            extra: &[],
+        },
+    },
+    // alloc::rc::Rc
+    PrettyPrinter {
+        names: &["Rc"],
+        layout: Layout::Pointer {
+            pointer: &[&["ptr", "pointer"]],
+            value: &[&["value"]],
+            value_name: Some("value"),
+            extra: &[
+                ("strong", &[&["strong", "value", "value"]]),
+                ("weak", &[&["weak", "value", "value"]]),
+            ],
+        },
+    },
+    // alloc::sync::Arc
+    PrettyPrinter {
+        names: &["Arc"],
+        layout: Layout::Pointer {
+            pointer: &[&["ptr", "pointer"]],
+            value: &[&["data"]],
+            value_name: Some("value"),
+            extra: &[
+                ("strong", &[&["strong", "v", "value"]]),
+                ("weak", &[&["weak", "v", "value"]]),
+            ],
+        },
+    },
+    // core::cell::RefCell
+    PrettyPrinter {
+        names: &["RefCell"],
+        layout: Layout::Wrapper {
+            value: &[&["value", "value"]],
+            state: Some(("borrow", &[&["borrow", "value", "value"]])),
+        },
+    },
+    // core::cell::Cell
+    PrettyPrinter {
+        names: &["Cell"],
+        layout: Layout::Wrapper {
+            value: &[&["value", "value"]],
+            state: None,
+        },
+    },
+    // core::cell::OnceCell
+    PrettyPrinter {
+        names: &["OnceCell"],
+        layout: Layout::Wrapper {
+            value: &[&["inner", "value"]],
+            state: None,
+        },
+    },
+    // Transparent wrappers from core.
+    PrettyPrinter {
+        names: &["UnsafeCell", "ManuallyDrop", "Wrapping", "Saturating"],
+        layout: Layout::Wrapper {
+            value: &[&["value"], &["__0"]],
+            state: None,
+        },
+    },
+    // alloc::collections::BTreeMap
+    PrettyPrinter {
+        names: &["BTreeMap"],
+        layout: Layout::BTreeMap {
+            node: &[&["root", "Some", "__0", "node", "pointer"]],
+            height: &[&["root", "Some", "__0", "height"]],
+            len: &[&["length"]],
+        },
+    },
+    PrettyPrinter {
+        names: &["Option", "Result"],
+        layout: Layout::Variants,
+    },
+];
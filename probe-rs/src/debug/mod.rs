@@ -7,14 +7,22 @@
 pub mod debug_info;
 /// Stepping through a program during debug, at various granularities.
 pub mod debug_step;
+/// Evaluation of expressions in the context of a stack frame.
+pub mod expression;
 /// References to the DIE (debug information entry) of functions.
 pub mod function_die;
+/// Lookup of functions by name.
+pub(crate) mod function_lookup;
 /// Programming languages
 pub(crate) mod language;
 /// Target Register definitions, expanded from [`crate::core::registers::CoreRegister`] to include unwind specific information.
 pub mod registers;
+/// Lookup of debug information in separate files, for stripped binaries and split DWARF.
+pub(crate) mod separate_debug_info;
 /// The source statement information used while identifying haltpoints for debug stepping and breakpoints.
 pub(crate) mod source_instructions;
+/// Mapping of the source paths in the debug information to paths on this machine.
+pub mod source_path;
 /// The stack frame information used while unwinding the stack from a specific program counter.
 pub mod stack_frame;
 /// Information about a Unit in the debug information.
@@ -27,8 +35,19 @@ pub mod variable_cache;
 pub(crate) mod exception_handling;
 
 pub use self::{
-    debug_info::*, debug_step::SteppingMode, registers::*, source_instructions::SourceLocation,
-    source_instructions::VerifiedBreakpoint, stack_frame::StackFrame, variable::*,
+    debug_info::*,
+    debug_step::SteppingMode,
+    exception_handling::fault_report::{
+        ExcReturnInfo, FaultAddress, FaultReport, FaultStatusBit, FaultStatusRegister,
+        FaultingInstruction, StackedFrame,
+    },
+    expression::EvaluatedExpression,
+    registers::*,
+    source_instructions::SourceLocation,
+    source_instructions::VerifiedBreakpoint,
+    source_path::PathSubstitution,
+    stack_frame::StackFrame,
+    variable::*,
     variable_cache::VariableCache,
 };
 use crate::{core::Core, MemoryInterface};
@@ -36,6 +55,7 @@ use crate::{core::Core, MemoryInterface};
 use gimli::AttributeValue;
 use gimli::DebuggingInformationEntry;
 use gimli::EvaluationResult;
+use gimli::Reader;
 use serde::Serialize;
 use typed_path::TypedPathBuf;
 
@@ -48,8 +68,12 @@ use std::{
     vec,
 };
 
-/// A simplified type alias of the [`gimli::EndianReader`] type.
-pub type EndianReader = gimli::EndianReader<gimli::LittleEndian, std::rc::Rc<[u8]>>;
+/// The [`gimli::Reader`] used to read debug information, which shifts all addresses by the
+/// [`LoadOffset`] of the object file.
+pub type EndianReader = gimli::read::RelocateReader<
+    gimli::EndianReader<gimli::LittleEndian, std::rc::Rc<[u8]>>,
+    debug_info::LoadOffset,
+>;
 
 /// An error occurred while debugging the target.
 #[derive(Debug, thiserror::Error)]
@@ -256,7 +280,7 @@ pub(crate) fn _print_all_attributes(
             AttributeValue::Addr(a) => println!("{a:#010x}"),
             AttributeValue::DebugStrRef(str_ref) => {
                 let val = dwarf.string(str_ref).unwrap();
-                println!("{}", std::str::from_utf8(&val).unwrap());
+                println!("{}", val.to_string().unwrap());
             }
             AttributeValue::Exprloc(e) => {
                 let mut evaluation = e.evaluation(unit.encoding());
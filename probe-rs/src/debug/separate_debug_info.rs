@@ -0,0 +1,331 @@
+//! Lookup of debug information which is not embedded in the binary itself.
+//!
+//! - Stripped binaries refer to a companion file with their debug information, either by the
+//!   name and checksum in the `.gnu_debuglink` section, or by their build ID.
+//! - With split DWARF, the binary only contains skeleton units, and the debug information of each
+//!   compilation unit is stored in a `.dwo` file, or in a `.dwp` package of all `.dwo` files.
+
+use super::debug_info::{load_section, DwarfReader};
+use object::read::Object;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// The default directory for separate debug information files.
+const DEFAULT_DEBUG_FILE_DIRECTORY: &str = "/usr/lib/debug";
+
+/// Find the file with the debug information of a stripped binary.
+///
+/// The file is looked up by the build ID of the binary in the `.build-id` subdirectory of the
+/// `debug_file_directories`, and by the name in the `.gnu_debuglink` section in the directory of
+/// the binary, its `.debug` subdirectory and the `debug_file_directories`. The default directory
+/// `/usr/lib/debug` is searched after the `debug_file_directories`.
+pub(crate) fn find_debug_file(
+    object: &object::File,
+    path: &Path,
+    debug_file_directories: &[PathBuf],
+) -> Option<PathBuf> {
+    let directories = debug_file_directories
+        .iter()
+        .cloned()
+        .chain(std::iter::once(PathBuf::from(DEFAULT_DEBUG_FILE_DIRECTORY)))
+        .collect::<Vec<_>>();
+
+    if let Some(build_id) = object.build_id().ok().flatten().filter(|id| id.len() > 1) {
+        let hex = build_id.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        });
+
+        for directory in &directories {
+            let candidate = directory
+                .join(".build-id")
+                .join(&hex[..2])
+                .join(format!("{}.debug", &hex[2..]));
+            if has_build_id(&candidate, build_id) {
+                return Some(candidate);
+            }
+        }
+    }
+
+    if let Some((name, crc)) = object.gnu_debuglink().ok().flatten() {
+        let name = Path::new(std::str::from_utf8(name).ok()?);
+        let binary_directory = path.parent().unwrap_or(Path::new(""));
+
+        let mut candidates = vec![
+            binary_directory.join(name),
+            binary_directory.join(".debug").join(name),
+        ];
+        for directory in &directories {
+            // The directory of the binary is mirrored in the debug file directory.
+            if let Ok(binary_directory) = binary_directory.canonicalize() {
+                let relative = binary_directory
+                    .strip_prefix("/")
+                    .unwrap_or(&binary_directory);
+                candidates.push(directory.join(relative).join(name));
+            }
+            candidates.push(directory.join(name));
+        }
+
+        for candidate in candidates {
+            if candidate.as_path() != path && has_crc(&candidate, crc) {
+                return Some(candidate);
+            }
+        }
+    }
+
+    None
+}
+
+/// Check if the file at `path` is an object file with the given build ID.
+fn has_build_id(path: &Path, build_id: &[u8]) -> bool {
+    let Ok(data) = std::fs::read(path) else {
+        return false;
+    };
+
+    object::File::parse(&*data)
+        .ok()
+        .and_then(|object| object.build_id().ok().flatten().map(|id| id == build_id))
+        .unwrap_or(false)
+}
+
+/// Check if the CRC32 checksum of the file at `path` matches the one in a `.gnu_debuglink` section.
+fn has_crc(path: &Path, crc: u32) -> bool {
+    let Ok(data) = std::fs::read(path) else {
+        return false;
+    };
+
+    let mut checksum = flate2::Crc::new();
+    checksum.update(&data);
+    checksum.sum() == crc
+}
+
+/// Loads the split DWARF units which belong to the skeleton units of a binary.
+pub(crate) struct SplitDwarfLoader<'a> {
+    /// The path of the binary, used to find the `.dwp` package and `.dwo` files next to it.
+    binary_path: Option<&'a Path>,
+    load_offset: u64,
+    /// The `.dwp` package, which is loaded when the first split unit is requested.
+    package: Option<Option<gimli::DwarfPackage<DwarfReader>>>,
+}
+
+impl<'a> SplitDwarfLoader<'a> {
+    pub(crate) fn new(binary_path: Option<&'a Path>, load_offset: u64) -> Self {
+        Self {
+            binary_path,
+            load_offset,
+            package: None,
+        }
+    }
+
+    /// Load the split unit of the `skeleton` unit, from the `.dwp` package of the binary or from
+    /// the `.dwo` file the skeleton refers to.
+    ///
+    /// Returns the DWARF sections of the split unit, and the unit itself, with the attributes
+    /// which are only stored in the skeleton unit (e.g. the base address and the line program),
+    /// and the address ranges of the skeleton unit.
+    pub(crate) fn load(
+        &mut self,
+        dwarf: &gimli::Dwarf<DwarfReader>,
+        skeleton: &gimli::Unit<DwarfReader>,
+    ) -> Option<(
+        gimli::Dwarf<DwarfReader>,
+        gimli::Unit<DwarfReader>,
+        Vec<gimli::Range>,
+    )> {
+        let dwo_id = skeleton.dwo_id?;
+
+        let split_dwarf = match self.package() {
+            Some(package) => package.find_cu(dwo_id, dwarf).ok().flatten(),
+            None => None,
+        };
+        let mut split_dwarf = match split_dwarf {
+            Some(split_dwarf) => split_dwarf,
+            None => self.load_dwo(dwarf, skeleton)?,
+        };
+        // The file names of the line program in the skeleton unit are stored in the binary.
+        split_dwarf.debug_line_str = dwarf.debug_line_str.clone();
+
+        let header = split_dwarf.units().next().ok()??;
+        let mut unit = split_dwarf.unit(header).ok()?;
+        if unit.dwo_id != Some(dwo_id) {
+            tracing::warn!(
+                "The split DWARF unit for {:?} does not match the skeleton unit.",
+                skeleton.name
+            );
+            return None;
+        }
+
+        unit.copy_relocated_attributes(skeleton);
+        unit.comp_dir.clone_from(&skeleton.comp_dir);
+        unit.line_program.clone_from(&skeleton.line_program);
+
+        let mut ranges = Vec::new();
+        let mut iter = dwarf.unit_ranges(skeleton).ok()?;
+        while let Ok(Some(range)) = iter.next() {
+            ranges.push(range);
+        }
+
+        Some((split_dwarf, unit, ranges))
+    }
+
+    /// The `.dwp` package next to the binary, if there is one.
+    fn package(&mut self) -> Option<&gimli::DwarfPackage<DwarfReader>> {
+        let load_offset = self.load_offset;
+        self.package
+            .get_or_insert_with(|| {
+                let binary_path = self.binary_path?;
+                let mut package_path = binary_path.as_os_str().to_owned();
+                package_path.push(".dwp");
+
+                [
+                    PathBuf::from(package_path),
+                    binary_path.with_extension("dwp"),
+                ]
+                .into_iter()
+                .find_map(|path| {
+                    let data = std::fs::read(&path).ok()?;
+                    let object = object::File::parse(&*data).ok()?;
+                    let package = gimli::DwarfPackage::load(
+                        |id| -> Result<_, gimli::Error> {
+                            Ok(load_section(&object, id.dwo_name(), load_offset))
+                        },
+                        load_section(&object, None, load_offset),
+                    );
+                    match package {
+                        Ok(package) => {
+                            tracing::debug!("Loaded split DWARF package {}", path.display());
+                            Some(package)
+                        }
+                        Err(error) => {
+                            tracing::warn!(
+                                "Failed to load split DWARF package {}: {error}",
+                                path.display()
+                            );
+                            None
+                        }
+                    }
+                })
+            })
+            .as_ref()
+    }
+
+    /// Load the `.dwo` file of the `skeleton` unit.
+    ///
+    /// The path in the skeleton unit is relative to its compilation directory. If the file does not
+    /// exist there, e.g. because the binary was built on a different machine, the file is looked up
+    /// in the directory of the binary.
+    fn load_dwo(
+        &self,
+        dwarf: &gimli::Dwarf<DwarfReader>,
+        skeleton: &gimli::Unit<DwarfReader>,
+    ) -> Option<gimli::Dwarf<DwarfReader>> {
+        use gimli::Reader;
+
+        let dwo_name = dwarf
+            .attr_string(skeleton, skeleton.dwo_name().ok()??)
+            .ok()?;
+        let dwo_name = PathBuf::from(dwo_name.to_string_lossy().ok()?.into_owned());
+
+        let mut candidates = Vec::new();
+        match &skeleton.comp_dir {
+            Some(comp_dir) if dwo_name.is_relative() => {
+                let comp_dir = comp_dir.to_string_lossy().ok()?;
+                candidates.push(Path::new(&*comp_dir).join(&dwo_name));
+            }
+            _ => candidates.push(dwo_name.clone()),
+        }
+        if let (Some(directory), Some(file_name)) = (
+            self.binary_path.and_then(Path::parent),
+            dwo_name.file_name(),
+        ) {
+            candidates.push(directory.join(file_name));
+        }
+
+        let Some(data) = candidates
+            .iter()
+            .find_map(|candidate| std::fs::read(candidate).ok())
+        else {
+            tracing::warn!(
+                "Split DWARF file {} not found, debug information for this unit is incomplete.",
+                dwo_name.display()
+            );
+            return None;
+        };
+
+        let object = object::File::parse(&*data).ok()?;
+        let mut split_dwarf = gimli::Dwarf::load(|id| -> Result<_, gimli::Error> {
+            Ok(load_section(&object, id.dwo_name(), self.load_offset))
+        })
+        .ok()?;
+        split_dwarf.make_dwo(dwarf);
+
+        Some(split_dwarf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debug::{DebugInfo, DebugInfoOptions, VerifiedBreakpoint};
+    use std::path::PathBuf;
+    use test_case::test_case;
+
+    fn test_file(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/separate-debug-info")
+            .join(name)
+    }
+
+    fn function_breakpoint(debug_info: &DebugInfo, name: &str) -> VerifiedBreakpoint {
+        debug_info
+            .get_function_breakpoint_locations(name)
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test_case("stripped"; "gnu_debuglink")]
+    #[test_case("build-id-only"; "build id")]
+    #[test_case("compressed"; "compressed sections")]
+    #[test_case("split"; "dwo file")]
+    #[test_case("dwp/packaged"; "dwp package")]
+    fn separate_debug_info(binary: &str) {
+        let expected = DebugInfo::from_file(test_file("full")).unwrap();
+
+        let options = DebugInfoOptions {
+            debug_file_directories: vec![test_file("symbols")],
+            ..Default::default()
+        };
+        let debug_info = DebugInfo::from_file_with_options(test_file(binary), &options).unwrap();
+
+        for function in ["main", "add"] {
+            let breakpoint = function_breakpoint(&debug_info, function);
+            let expected_breakpoint = function_breakpoint(&expected, function);
+
+            assert_eq!(breakpoint.address, expected_breakpoint.address);
+            assert_eq!(
+                breakpoint.source_location.file.as_deref(),
+                Some("main.c"),
+                "{function}"
+            );
+            assert_eq!(
+                breakpoint.source_location.line,
+                expected_breakpoint.source_location.line
+            );
+            assert_eq!(
+                debug_info.get_source_location(breakpoint.address),
+                expected.get_source_location(breakpoint.address)
+            );
+        }
+    }
+
+    #[test]
+    fn missing_debug_file() {
+        // Without the build ID directory, the stripped binary has no debug information.
+        let debug_info = DebugInfo::from_file(test_file("build-id-only")).unwrap();
+
+        assert!(debug_info
+            .get_function_breakpoint_locations("main")
+            .is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use typed_path::{TypedPath, TypedPathBuf};
+
+/// A rule to replace the prefix of source file paths in the debug information, similar to the
+/// `substitute-path` command of GDB.
+///
+/// This is useful when a binary was built in a different location (e.g. in a container on a CI
+/// server) than where the sources are available to the debugger.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathSubstitution {
+    /// The path prefix, as it appears in the debug information.
+    pub from: String,
+    /// The path that replaces the prefix.
+    pub to: String,
+}
+
+impl PathSubstitution {
+    /// Create a new rule which replaces the prefix `from` with `to`.
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    /// Apply the rule to `path`. Returns `None` if the path does not start with the prefix.
+    ///
+    /// Paths are compared component-wise, so the prefix `/build` does not match `/build-2/main.rs`.
+    pub fn apply(&self, path: &TypedPathBuf) -> Option<TypedPathBuf> {
+        let path = path.normalize();
+        let from = TypedPath::derive(self.from.as_str()).normalize();
+        let remainder = path.strip_prefix(from.as_bytes()).ok()?;
+
+        let to = TypedPath::derive(self.to.as_str());
+        if remainder.as_bytes().is_empty() {
+            Some(to.to_path_buf())
+        } else {
+            Some(to.join(remainder.as_bytes()))
+        }
+    }
+}
+
+impl std::str::FromStr for PathSubstitution {
+    type Err = String;
+
+    /// Parse a rule in the form `FROM=TO`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((from, to)) if !from.is_empty() => Ok(Self::new(from, to)),
+            _ => Err(format!(
+                "'{s}' is not a valid path substitution. Use the format `FROM=TO`."
+            )),
+        }
+    }
+}
+
+/// Apply the first matching rule of `substitutions` to `path`.
+pub(crate) fn substitute_path(
+    substitutions: &[PathSubstitution],
+    path: TypedPathBuf,
+) -> TypedPathBuf {
+    substitutions
+        .iter()
+        .find_map(|substitution| substitution.apply(&path))
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn substitute(substitutions: &[PathSubstitution], path: &str) -> String {
+        substitute_path(substitutions, TypedPath::derive(path).to_path_buf())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn substitute_prefixes() {
+        let substitutions = [
+            PathSubstitution::new("/build/firmware", "/home/user/firmware"),
+            PathSubstitution::new("/build", "/home/user/other"),
+            PathSubstitution::new(
+                "/usr/local/cargo/registry",
+                "C:\\Users\\user\\.cargo\\registry",
+            ),
+        ];
+
+        assert_eq!(
+            substitute(&substitutions, "/build/firmware/src/main.rs"),
+            "/home/user/firmware/src/main.rs"
+        );
+        assert_eq!(
+            substitute(&substitutions, "/build/lib/../lib.rs"),
+            "/home/user/other/lib.rs"
+        );
+        assert_eq!(
+            substitute(&substitutions, "/build-2/main.rs"),
+            "/build-2/main.rs"
+        );
+        assert_eq!(
+            substitute(&substitutions, "/usr/local/cargo/registry/src/lib.rs"),
+            "C:\\Users\\user\\.cargo\\registry\\src\\lib.rs"
+        );
+        assert_eq!(substitute(&[], "/build/main.rs"), "/build/main.rs");
+    }
+
+    #[test]
+    fn parse_substitution() {
+        assert_eq!(
+            "/build=/home/user/src".parse::<PathSubstitution>(),
+            Ok(PathSubstitution::new("/build", "/home/user/src"))
+        );
+        assert!("/build".parse::<PathSubstitution>().is_err());
+        assert!("=/home".parse::<PathSubstitution>().is_err());
+    }
+}
@@ -9,7 +9,7 @@ use crate::{
     MemoryInterface,
 };
 use gimli::{
-    AttributeValue, DebugInfoOffset, DebuggingInformationEntry, EvaluationResult, Location,
+    AttributeValue, DebugInfoOffset, DebuggingInformationEntry, EvaluationResult, Location, Reader,
     UnitOffset,
 };
 
@@ -24,7 +24,11 @@ pub(crate) enum ExpressionResult {
 pub struct UnitInfo {
     pub(crate) unit: gimli::Unit<GimliReader, usize>,
     dwarf_language: gimli::DwLang,
-    language: Box<dyn language::ProgrammingLanguage>,
+    pub(crate) language: Box<dyn language::ProgrammingLanguage>,
+    /// The index of the object file which contains this unit, see [`DebugInfo::add_object`].
+    pub(crate) object_index: usize,
+    /// The address ranges of a split DWARF unit, which are stored in its skeleton unit.
+    pub(crate) skeleton_ranges: Option<Vec<gimli::Range>>,
 }
 
 impl UnitInfo {
@@ -44,6 +48,8 @@ impl UnitInfo {
             unit,
             dwarf_language,
             language: language::from_dwarf(dwarf_language),
+            object_index: 0,
+            skeleton_ranges: None,
         }
     }
 
@@ -374,7 +380,8 @@ impl UnitInfo {
                         let value = attr.value();
                         let raw_str = debug_info.dwarf.attr_string(&self.unit, value).ok();
 
-                        let linkage_name = raw_str.and_then(|r| String::from_utf8(r.to_vec()).ok());
+                        let linkage_name =
+                            raw_str.and_then(|r| r.to_string().ok().map(String::from));
 
                         child_variable.linkage_name = linkage_name;
                     }
@@ -977,7 +984,7 @@ impl UnitInfo {
     /// [e]: Self::extract_type()
     /// [p]: Self::process_tree()
     #[allow(clippy::too_many_arguments)]
-    fn extract_type(
+    pub(crate) fn extract_type(
         &self,
         debug_info: &DebugInfo,
         node: &gimli::DebuggingInformationEntry<GimliReader>,
@@ -1002,6 +1009,11 @@ impl UnitInfo {
             return Ok(());
         }
 
+        // Typedefs and modifiers recurse into the type they refer to, keep the outermost type.
+        if child_variable.type_node.is_none() {
+            child_variable.type_node = Some((self.debug_info_offset()?, node.offset()));
+        }
+
         match node.tag() {
             gimli::DW_TAG_base_type => {
                 child_variable.type_name = VariableType::Base(
@@ -1103,9 +1115,19 @@ impl UnitInfo {
                     // The default behaviour is to defer the processing of child types.
                     child_variable.variable_node_type =
                         VariableNodeType::TypeOffset(self.debug_info_offset()?, node.offset());
+                    // Containers and smart pointers are shown with their logical contents.
+                    let pretty_printed = self.pretty_print(
+                        debug_info,
+                        node.offset(),
+                        &type_name,
+                        child_variable,
+                        memory,
+                        cache,
+                        frame_info,
+                    )?;
                     // In some cases, it really simplifies the UX if we can auto resolve the
                     // children and derive a value that is visible at first glance to the user.
-                    if self.language.auto_resolve_children(&type_name) {
+                    if !pretty_printed && self.language.auto_resolve_children(&type_name) {
                         let temp_node_type = std::mem::replace(
                             &mut child_variable.variable_node_type,
                             VariableNodeType::RecurseToBaseType,
@@ -1784,13 +1806,7 @@ impl UnitInfo {
         frame_info: StackFrameInfo<'_>,
         memory: &mut dyn MemoryInterface,
     ) -> Result<ExpressionResult, DebugError> {
-        let mut locations = match debug_info.locations_section.locations(
-            location_list_offset,
-            self.unit.header.encoding(),
-            self.unit.low_pc,
-            &debug_info.address_section,
-            self.unit.addr_base,
-        ) {
+        let mut locations = match debug_info.dwarf.locations(&self.unit, location_list_offset) {
             Ok(locations) => locations,
             Err(error) => {
                 return Ok(ExpressionResult::Location(VariableLocation::Error(
@@ -2083,14 +2099,17 @@ impl UnitInfo {
                 let name = match attr.value() {
                     gimli::AttributeValue::DebugStrRef(name_ref) => {
                         if let Ok(name_raw) = debug_info.dwarf.string(name_ref) {
-                            String::from_utf8_lossy(&name_raw).to_string()
+                            name_raw.to_string_lossy().map_or_else(
+                                |_| "Invalid DW_AT_name value".to_string(),
+                                String::from,
+                            )
                         } else {
                             "Invalid DW_AT_name value".to_string()
                         }
                     }
-                    gimli::AttributeValue::String(name) => {
-                        String::from_utf8_lossy(&name).to_string()
-                    }
+                    gimli::AttributeValue::String(name) => name
+                        .to_string_lossy()
+                        .map_or_else(|_| "Invalid DW_AT_name value".to_string(), String::from),
                     other => format!("Unimplemented: Evaluate name from {other:?}"),
                 };
 
@@ -2226,12 +2245,16 @@ fn extract_name(
     let name = match attr {
         gimli::AttributeValue::DebugStrRef(name_ref) => {
             if let Ok(name_raw) = debug_info.dwarf.string(name_ref) {
-                String::from_utf8_lossy(&name_raw).to_string()
+                name_raw
+                    .to_string_lossy()
+                    .map_or_else(|_| "Invalid DW_AT_name value".to_string(), String::from)
             } else {
                 "Invalid DW_AT_name value".to_string()
             }
         }
-        gimli::AttributeValue::String(name) => String::from_utf8_lossy(&name).to_string(),
+        gimli::AttributeValue::String(name) => name
+            .to_string_lossy()
+            .map_or_else(|_| "Invalid DW_AT_name value".to_string(), String::from),
         other => format!("Unimplemented: Evaluate name from {other:?}"),
     };
 
@@ -494,6 +494,12 @@ pub struct Variable {
     pub member_index: Option<i64>,
     /// The role of this variable.
     pub role: VariantRole,
+    /// The DWARF type of this variable, used to reinterpret the memory of the variable when
+    /// evaluating expressions.
+    pub(crate) type_node: Option<(DebugInfoOffset, UnitOffset)>,
+    /// The index of the object file which contains the debug information of this variable, see
+    /// [`DebugInfo::add_object`](super::DebugInfo::add_object).
+    pub(crate) object_index: usize,
 }
 
 impl Variable {
@@ -504,6 +510,7 @@ impl Variable {
             language: unit_info
                 .map(|info| info.get_language())
                 .unwrap_or(gimli::DW_LANG_Rust),
+            object_index: unit_info.map_or(0, |info| info.object_index),
             variable_key: Default::default(),
             parent_key: Default::default(),
             name: Default::default(),
@@ -516,6 +523,7 @@ impl Variable {
             byte_size: None,
             member_index: None,
             role: Default::default(),
+            type_node: None,
         }
     }
 
@@ -106,7 +106,7 @@ impl Serialize for VariableCache {
 }
 
 impl VariableCache {
-    fn new(mut variable: Variable) -> Self {
+    pub(crate) fn new(mut variable: Variable) -> Self {
         let key = get_object_reference();
 
         variable.variable_key = key;
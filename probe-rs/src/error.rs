@@ -44,6 +44,11 @@ pub enum Error {
     /// implement new functionality on selected architectures first, and then add support for
     /// the other architectures later.
     NotImplemented(&'static str),
+    /// {0} is not possible when debugging a core dump.
+    ///
+    /// A core dump is a read-only snapshot of the state of a core, it can not be modified or run.
+    #[ignore_extra_doc_attributes]
+    CoreDumpReadOnly(&'static str),
     /// Some uncategorized error occurred.
     #[display("{0}")]
     Other(String),
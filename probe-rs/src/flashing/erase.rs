@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::ops::Range;
 
 use probe_rs_target::{MemoryRange, MemoryRegion, NvmRegion};
 
@@ -155,6 +156,37 @@ pub fn erase_sectors(
         start_sector + sectors
     );
 
+    let sector_indices = start_sector..start_sector + sectors;
+    erase_selected_sectors(session, progress, |index, _| {
+        sector_indices.contains(&index)
+    })
+}
+
+/// Erases all flash sectors which overlap the given address `range`.
+///
+/// Since flash can only be erased in whole sectors, this may erase memory
+/// outside of `range` if it is not aligned to sector boundaries.
+// TODO: currently no progress is reported by anything in this function.
+pub fn erase(
+    session: &mut Session,
+    progress: FlashProgress,
+    range: Range<u64>,
+) -> Result<(), FlashError> {
+    tracing::debug!("Erasing range {:#010x?}", range);
+
+    erase_selected_sectors(session, progress, |_, sector| {
+        sector.start < range.end && range.start < sector.end
+    })
+}
+
+/// Erases the sectors for which `select` returns `true`.
+///
+/// `select` is called with the index of the sector in its flash algorithm and the address range it covers.
+fn erase_selected_sectors(
+    session: &mut Session,
+    progress: FlashProgress,
+    select: impl Fn(usize, &Range<u64>) -> bool,
+) -> Result<(), FlashError> {
     let mut algos: HashMap<(String, String), Vec<NvmRegion>> = HashMap::new();
     tracing::debug!("Regions:");
     for region in session
@@ -202,12 +234,12 @@ pub fn erase_sectors(
         let sectors = flasher
             .flash_algorithm()
             .iter_sectors()
-            .skip(start_sector)
-            .take(sectors)
-            .filter(|info| {
+            .enumerate()
+            .filter(|(index, info)| {
                 let range = info.base_address..info.base_address + info.size;
-                regions.iter().any(|r| r.range.contains_range(&range))
+                select(*index, &range) && regions.iter().any(|r| r.range.contains_range(&range))
             })
+            .map(|(_, info)| info)
             .collect::<Vec<_>>();
 
         flasher.run_erase(|active| {
@@ -18,6 +18,19 @@ pub struct GdbInstanceConfiguration {
     pub cores: Vec<usize>,
     /// The list of [SocketAddr] addresses to bind to
     pub socket_addrs: Vec<SocketAddr>,
+    /// Memory mapped peripheral registers which can be accessed with the `reg` monitor command
+    pub peripheral_registers: Vec<PeripheralRegister>,
+}
+
+/// A memory mapped peripheral register, e.g. loaded from a CMSIS-SVD file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeripheralRegister {
+    /// The name of the register, e.g. `GPIOA.ODR`
+    pub name: String,
+    /// The address of the register
+    pub address: u64,
+    /// The size of the register in bits
+    pub size: u32,
 }
 
 impl GdbInstanceConfiguration {
@@ -65,6 +78,7 @@ impl GdbInstanceConfiguration {
                 core_type,
                 cores,
                 socket_addrs: adjust_addrs(&addrs, i),
+                peripheral_registers: Vec::new(),
             })
             .collect()
     }
@@ -87,7 +101,12 @@ pub fn run<'a>(
     // Turn our group list into GDB targets
     let mut targets = instances
         .map(|instance| {
-            target::RuntimeTarget::new(session, instance.cores.to_vec(), &instance.socket_addrs[..])
+            target::RuntimeTarget::new(
+                session,
+                instance.cores.to_vec(),
+                &instance.socket_addrs[..],
+                instance.peripheral_registers.clone(),
+            )
         })
         .collect::<Result<Vec<_>, _>>()?;
 
@@ -0,0 +1,270 @@
+use gdbstub::common::{Signal, Tid};
+use gdbstub::target::ext::base::multithread::{
+    MultiThreadBase, MultiThreadResume, MultiThreadResumeOps, MultiThreadSingleStep,
+    MultiThreadSingleStepOps,
+};
+use gdbstub::target::ext::base::single_register_access::{
+    SingleRegisterAccess, SingleRegisterAccessOps,
+};
+use gdbstub::target::{TargetError, TargetResult};
+
+use super::desc::{GdbRegister, GdbRegisterSource};
+use super::{tid_to_core_id, ProbeRsErrorExt, ResumeAction, RuntimeTarget};
+use crate::gdb_server::arch::{RuntimeRegId, RuntimeRegisters};
+use crate::{Core, Error, MemoryInterface, RegisterValue};
+
+impl MultiThreadBase for RuntimeTarget<'_> {
+    fn read_registers(&mut self, regs: &mut RuntimeRegisters, tid: Tid) -> TargetResult<(), Self> {
+        self.current_core = tid_to_core_id(tid);
+
+        let mut session = self.session.lock();
+        let mut core = session.core(tid_to_core_id(tid)).into_target_result()?;
+
+        regs.pc = core
+            .read_core_reg(core.program_counter())
+            .into_target_result()?;
+
+        regs.regs.clear();
+        for register in self.target_desc.registers() {
+            let mut value = vec![0; register.size_in_bytes()];
+            read_register(&mut core, register, &mut value).into_target_result()?;
+            regs.regs.extend(value);
+        }
+
+        Ok(())
+    }
+
+    fn write_registers(&mut self, regs: &RuntimeRegisters, tid: Tid) -> TargetResult<(), Self> {
+        self.current_core = tid_to_core_id(tid);
+        self.check_writable()?;
+
+        let mut session = self.session.lock();
+        let mut core = session.core(tid_to_core_id(tid)).into_target_result()?;
+
+        let mut values = regs.regs.as_slice();
+        for register in self.target_desc.registers() {
+            let size = register.size_in_bytes();
+            if values.len() < size {
+                // GDB may send fewer registers than described
+                break;
+            }
+
+            let (value, rest) = values.split_at(size);
+            write_register(&mut core, register, value).into_target_result()?;
+            values = rest;
+        }
+
+        Ok(())
+    }
+
+    fn support_single_register_access(&mut self) -> Option<SingleRegisterAccessOps<'_, Tid, Self>> {
+        Some(self)
+    }
+
+    fn read_addrs(
+        &mut self,
+        start_addr: u64,
+        data: &mut [u8],
+        tid: Tid,
+    ) -> TargetResult<usize, Self> {
+        self.current_core = tid_to_core_id(tid);
+
+        let mut session = self.session.lock();
+        let mut core = session.core(tid_to_core_id(tid)).into_target_result()?;
+
+        core.read(start_addr, data).into_target_result()?;
+
+        Ok(data.len())
+    }
+
+    fn write_addrs(&mut self, start_addr: u64, data: &[u8], tid: Tid) -> TargetResult<(), Self> {
+        self.current_core = tid_to_core_id(tid);
+        self.check_writable()?;
+
+        let mut session = self.session.lock();
+        let mut core = session.core(tid_to_core_id(tid)).into_target_result()?;
+
+        core.write_8(start_addr, data).into_target_result()
+    }
+
+    #[inline(always)]
+    fn list_active_threads(
+        &mut self,
+        thread_is_active: &mut dyn FnMut(Tid),
+    ) -> Result<(), Self::Error> {
+        for core_id in &self.cores {
+            thread_is_active(super::core_id_to_tid(*core_id));
+        }
+
+        Ok(())
+    }
+
+    fn support_resume(&mut self) -> Option<MultiThreadResumeOps<'_, Self>> {
+        // A core dump can't be resumed or stepped
+        if self.core_dump {
+            return None;
+        }
+
+        Some(self)
+    }
+}
+
+impl MultiThreadResume for RuntimeTarget<'_> {
+    fn resume(&mut self) -> Result<(), Self::Error> {
+        let mut session = self.session.lock();
+
+        match self.resume_action {
+            (_, ResumeAction::Unchanged) => Ok(()),
+            (_, ResumeAction::Resume) => {
+                for core_id in &self.cores {
+                    session.core(*core_id)?.run()?;
+                }
+
+                Ok(())
+            }
+            (core_id, ResumeAction::Step) => {
+                session.core(core_id)?.step()?;
+
+                Ok(())
+            }
+        }
+    }
+
+    fn clear_resume_actions(&mut self) -> Result<(), Self::Error> {
+        self.resume_action = (0, ResumeAction::Unchanged);
+
+        Ok(())
+    }
+
+    fn set_resume_action_continue(
+        &mut self,
+        tid: Tid,
+        _signal: Option<Signal>,
+    ) -> Result<(), Self::Error> {
+        // A step of one core takes precedence over continuing the others
+        if self.resume_action.1 != ResumeAction::Step {
+            self.resume_action = (tid_to_core_id(tid), ResumeAction::Resume);
+        }
+
+        Ok(())
+    }
+
+    fn support_single_step(&mut self) -> Option<MultiThreadSingleStepOps<'_, Self>> {
+        Some(self)
+    }
+}
+
+impl MultiThreadSingleStep for RuntimeTarget<'_> {
+    fn set_resume_action_step(
+        &mut self,
+        tid: Tid,
+        _signal: Option<Signal>,
+    ) -> Result<(), Self::Error> {
+        self.resume_action = (tid_to_core_id(tid), ResumeAction::Step);
+
+        Ok(())
+    }
+}
+
+impl SingleRegisterAccess<Tid> for RuntimeTarget<'_> {
+    fn read_register(
+        &mut self,
+        tid: Tid,
+        reg_id: RuntimeRegId,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        let Some(register) = self.target_desc.register(reg_id.into()) else {
+            return Err(TargetError::NonFatal);
+        };
+
+        let size = register.size_in_bytes();
+        if buf.len() < size {
+            return Err(TargetError::NonFatal);
+        }
+
+        let mut session = self.session.lock();
+        let mut core = session.core(tid_to_core_id(tid)).into_target_result()?;
+
+        read_register(&mut core, register, &mut buf[..size]).into_target_result()?;
+
+        Ok(size)
+    }
+
+    fn write_register(
+        &mut self,
+        tid: Tid,
+        reg_id: RuntimeRegId,
+        val: &[u8],
+    ) -> TargetResult<(), Self> {
+        let Some(register) = self.target_desc.register(reg_id.into()) else {
+            return Err(TargetError::NonFatal);
+        };
+
+        if val.len() < register.size_in_bytes() {
+            return Err(TargetError::NonFatal);
+        }
+
+        self.check_writable()?;
+
+        let mut session = self.session.lock();
+        let mut core = session.core(tid_to_core_id(tid)).into_target_result()?;
+
+        write_register(&mut core, register, val).into_target_result()
+    }
+}
+
+/// Read the value of the GDB `register` into `buf`, in little endian byte order.
+fn read_register(core: &mut Core, register: &GdbRegister, buf: &mut [u8]) -> Result<(), Error> {
+    match register.source() {
+        GdbRegisterSource::SingleRegister(id) => {
+            let value: u128 = core.read_core_reg(id)?;
+            buf.copy_from_slice(&value.to_le_bytes()[..buf.len()]);
+        }
+        GdbRegisterSource::TwoWordRegister {
+            low,
+            high,
+            word_size,
+        } => {
+            let low: u128 = core.read_core_reg(low)?;
+            let high: u128 = core.read_core_reg(high)?;
+
+            let (low_buf, high_buf) = buf.split_at_mut(word_size);
+            low_buf.copy_from_slice(&low.to_le_bytes()[..word_size]);
+            high_buf.copy_from_slice(&high.to_le_bytes()[..high_buf.len()]);
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the value of the GDB `register` from `value`, in little endian byte order.
+fn write_register(core: &mut Core, register: &GdbRegister, value: &[u8]) -> Result<(), Error> {
+    match register.source() {
+        GdbRegisterSource::SingleRegister(id) => {
+            core.write_core_reg(id, register_value(&value[..register.size_in_bytes()]))
+        }
+        GdbRegisterSource::TwoWordRegister {
+            low,
+            high,
+            word_size,
+        } => {
+            let (low_value, high_value) = value[..register.size_in_bytes()].split_at(word_size);
+
+            core.write_core_reg(low, register_value(low_value))?;
+            core.write_core_reg(high, register_value(high_value))
+        }
+    }
+}
+
+/// Convert little endian bytes into a [RegisterValue] of the same size.
+fn register_value(bytes: &[u8]) -> RegisterValue {
+    let mut le_bytes = [0u8; 16];
+    le_bytes[..bytes.len()].copy_from_slice(bytes);
+    let value = u128::from_le_bytes(le_bytes);
+
+    match bytes.len() {
+        0..=4 => RegisterValue::U32(value as u32),
+        5..=8 => RegisterValue::U64(value as u64),
+        _ => RegisterValue::U128(value),
+    }
+}
@@ -0,0 +1,212 @@
+use gdbstub::target::ext::breakpoints::{
+    Breakpoints, HwBreakpoint, HwBreakpointOps, HwWatchpoint, HwWatchpointOps, WatchKind,
+};
+use gdbstub::target::TargetResult;
+
+use super::{ProbeRsErrorExt, RuntimeTarget};
+use crate::{Core, Error, Watchpoint, WatchpointCause, WatchpointKind};
+
+impl Breakpoints for RuntimeTarget<'_> {
+    fn support_hw_breakpoint(&mut self) -> Option<HwBreakpointOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_hw_watchpoint(&mut self) -> Option<HwWatchpointOps<'_, Self>> {
+        Some(self)
+    }
+}
+
+impl HwBreakpoint for RuntimeTarget<'_> {
+    fn add_hw_breakpoint(&mut self, addr: u64, _kind: usize) -> TargetResult<bool, Self> {
+        let mut session = self.session.lock();
+
+        for core_id in &self.cores {
+            let mut core = session.core(*core_id).into_target_result()?;
+
+            core.set_hw_breakpoint(addr).into_target_result()?;
+        }
+
+        Ok(true)
+    }
+
+    fn remove_hw_breakpoint(&mut self, addr: u64, _kind: usize) -> TargetResult<bool, Self> {
+        let mut session = self.session.lock();
+
+        for core_id in &self.cores {
+            let mut core = session.core(*core_id).into_target_result()?;
+
+            core.clear_hw_breakpoint(addr).into_target_result()?;
+        }
+
+        Ok(true)
+    }
+}
+
+impl HwWatchpoint for RuntimeTarget<'_> {
+    fn add_hw_watchpoint(
+        &mut self,
+        addr: u64,
+        len: u64,
+        kind: WatchKind,
+    ) -> TargetResult<bool, Self> {
+        let watchpoint = watchpoint(addr, len, kind);
+        let mut session = self.session.lock();
+
+        // The watchpoint is set on all cores, or on none of them.
+        for (index, core_id) in self.cores.iter().enumerate() {
+            let result = set_watchpoint(
+                &mut session.core(*core_id).into_target_result()?,
+                watchpoint,
+            );
+
+            if let Err(e) = result {
+                tracing::warn!(
+                    "Failed to set watchpoint on {len} bytes at {addr:#010x} on core {core_id}: {e}"
+                );
+
+                for core_id in &self.cores[..index] {
+                    let mut core = session.core(*core_id).into_target_result()?;
+                    clear_watchpoint(&mut core, watchpoint);
+                }
+
+                // GDB reports that the watchpoint could not be inserted
+                return Ok(false);
+            }
+        }
+
+        self.watchpoints.push(watchpoint);
+
+        Ok(true)
+    }
+
+    fn remove_hw_watchpoint(
+        &mut self,
+        addr: u64,
+        len: u64,
+        kind: WatchKind,
+    ) -> TargetResult<bool, Self> {
+        let watchpoint = watchpoint(addr, len, kind);
+
+        let Some(index) = self.watchpoints.iter().position(|wp| *wp == watchpoint) else {
+            return Ok(false);
+        };
+        self.watchpoints.remove(index);
+
+        let mut session = self.session.lock();
+        for core_id in &self.cores {
+            let mut core = session.core(*core_id).into_target_result()?;
+            clear_watchpoint(&mut core, watchpoint);
+        }
+
+        Ok(true)
+    }
+}
+
+fn watchpoint(address: u64, length: u64, kind: WatchKind) -> Watchpoint {
+    Watchpoint {
+        address,
+        length,
+        kind: match kind {
+            WatchKind::Write => WatchpointKind::Write,
+            WatchKind::Read => WatchpointKind::Read,
+            WatchKind::ReadWrite => WatchpointKind::Access,
+        },
+    }
+}
+
+/// The GDB watch kind of a probe-rs watchpoint kind.
+pub(crate) fn watch_kind(kind: WatchpointKind) -> WatchKind {
+    match kind {
+        WatchpointKind::Write => WatchKind::Write,
+        WatchpointKind::Read => WatchKind::Read,
+        WatchpointKind::Access => WatchKind::ReadWrite,
+    }
+}
+
+/// Find the GDB watchpoint which caused a halt.
+///
+/// The core reports the hardware watchpoint it hit, which can be one of several parts of a GDB watchpoint.
+/// If the core can't tell which watchpoint was hit, it is only known if GDB set a single one.
+pub(crate) fn watchpoint_hit(
+    watchpoints: &[Watchpoint],
+    cause: WatchpointCause,
+) -> Option<Watchpoint> {
+    match cause {
+        WatchpointCause::Hit(hit) => watchpoints
+            .iter()
+            .find(|watchpoint| {
+                watchpoint.kind == hit.kind
+                    && watchpoint.address <= hit.address
+                    && hit.address < watchpoint.address + watchpoint.length
+            })
+            .copied()
+            .or(Some(hit)),
+        WatchpointCause::Unknown => match watchpoints {
+            [watchpoint] => Some(*watchpoint),
+            _ => None,
+        },
+    }
+}
+
+/// Set `watchpoint` on `core`, using several hardware watchpoints if the range is not naturally aligned.
+///
+/// If not all of them can be set, none of them are.
+fn set_watchpoint(core: &mut Core, watchpoint: Watchpoint) -> Result<(), Error> {
+    let parts = watchpoint.naturally_aligned_parts();
+
+    for (index, part) in parts.iter().enumerate() {
+        if let Err(e) = core.set_hw_watchpoint(*part) {
+            for part in &parts[..index] {
+                if let Err(e) = core.clear_hw_watchpoint(*part) {
+                    tracing::warn!("Failed to clear watchpoint {part:?}: {e}");
+                }
+            }
+
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+fn clear_watchpoint(core: &mut Core, watchpoint: Watchpoint) {
+    for part in watchpoint.naturally_aligned_parts() {
+        if let Err(e) = core.clear_hw_watchpoint(part) {
+            tracing::warn!("Failed to clear watchpoint {part:?}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::watchpoint_hit;
+    use crate::{Watchpoint, WatchpointCause, WatchpointKind};
+
+    #[test]
+    fn watchpoint_hit_is_mapped_to_gdb_watchpoint() {
+        let watchpoint = Watchpoint {
+            address: 0x2000_0002,
+            length: 4,
+            kind: WatchpointKind::Write,
+        };
+        let mut watchpoints = vec![watchpoint];
+
+        // The second naturally aligned part of the watchpoint was hit.
+        let part = Watchpoint {
+            address: 0x2000_0004,
+            length: 2,
+            kind: WatchpointKind::Write,
+        };
+        assert_eq!(
+            watchpoint_hit(&watchpoints, WatchpointCause::Hit(part)),
+            Some(watchpoint)
+        );
+        assert_eq!(
+            watchpoint_hit(&watchpoints, WatchpointCause::Unknown),
+            Some(watchpoint)
+        );
+
+        watchpoints.push(part);
+        assert_eq!(watchpoint_hit(&watchpoints, WatchpointCause::Unknown), None);
+    }
+}
@@ -0,0 +1,297 @@
+use std::fmt::Write;
+
+use crate::{Core, CoreRegister, CoreType, RegisterId};
+
+/// Where the value of a GDB register is read from, and written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GdbRegisterSource {
+    /// The register corresponds to a single probe-rs register.
+    SingleRegister(RegisterId),
+    /// The register is made up of two probe-rs registers, for example an ARM VFP `d` register
+    /// which is made up of two `s` registers.
+    TwoWordRegister {
+        /// The register containing the least significant word.
+        low: RegisterId,
+        /// The register containing the most significant word.
+        high: RegisterId,
+        /// The size of each of the two registers, in bytes.
+        word_size: usize,
+    },
+}
+
+/// A register as it is presented to GDB.
+#[derive(Debug, Clone)]
+pub(crate) struct GdbRegister {
+    name: String,
+    size_in_bits: usize,
+    gdb_type: &'static str,
+    group: Option<&'static str>,
+    source: GdbRegisterSource,
+}
+
+impl GdbRegister {
+    /// The size of the register, in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.size_in_bits.div_ceil(8)
+    }
+
+    /// Where the value of the register is stored on the target.
+    pub fn source(&self) -> GdbRegisterSource {
+        self.source
+    }
+}
+
+/// A group of registers which GDB recognizes by its name, e.g. `org.gnu.gdb.arm.m-profile`.
+#[derive(Debug)]
+struct GdbFeature {
+    name: &'static str,
+    registers: Vec<GdbRegister>,
+}
+
+/// Description of a target's architecture and registers, which is sent to GDB as the target description XML.
+///
+/// The order of the registers in the description defines the GDB register numbers.
+#[derive(Debug)]
+pub(crate) struct TargetDescription {
+    architecture: &'static str,
+    features: Vec<GdbFeature>,
+}
+
+impl TargetDescription {
+    /// Build the target description for the architecture and registers of `core`.
+    pub fn new(core: &Core) -> Self {
+        let mut desc = Self {
+            architecture: "",
+            features: Vec::new(),
+        };
+
+        match core.core_type() {
+            CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => {
+                desc.architecture = "arm";
+
+                desc.add_feature("org.gnu.gdb.arm.m-profile", |feature| {
+                    feature.add_arm_core_registers(core);
+                    feature.add(core, "xpsr", "XPSR", "int");
+                });
+
+                desc.add_feature("org.gnu.gdb.arm.m-system", |feature| {
+                    feature.add(core, "msp", "MSP", "data_ptr");
+                    feature.add(core, "psp", "PSP", "data_ptr");
+                });
+
+                if core.registers().fpu_registers().is_some() {
+                    desc.add_feature("org.gnu.gdb.arm.vfp", |feature| {
+                        for n in 0..16 {
+                            feature.add_two_word(
+                                core,
+                                &format!("d{n}"),
+                                &format!("S{}", 2 * n),
+                                &format!("S{}", 2 * n + 1),
+                            );
+                        }
+                        feature.add(core, "fpscr", "FPSCR", "int");
+                    });
+                }
+            }
+            CoreType::Armv8a if core.is_64_bit() => {
+                desc.architecture = "aarch64";
+
+                desc.add_feature("org.gnu.gdb.aarch64.core", |feature| {
+                    for n in 0..31 {
+                        feature.add(core, &format!("x{n}"), &format!("X{n}"), "int");
+                    }
+                    feature.add(core, "sp", "SP", "data_ptr");
+                    feature.add(core, "pc", "PC", "code_ptr");
+                    feature.add(core, "cpsr", "PSTATE", "int");
+                });
+
+                if core.registers().fpu_registers().is_some() {
+                    desc.add_feature("org.gnu.gdb.aarch64.fpu", |feature| {
+                        for n in 0..32 {
+                            feature.add(core, &format!("v{n}"), &format!("v{n}"), "uint128");
+                        }
+                        feature.add(core, "fpsr", "FPSR", "int");
+                        feature.add(core, "fpcr", "FPCR", "int");
+                    });
+                }
+            }
+            CoreType::Armv7a | CoreType::Armv8a => {
+                desc.architecture = "arm";
+
+                desc.add_feature("org.gnu.gdb.arm.core", |feature| {
+                    feature.add_arm_core_registers(core);
+                    feature.add(core, "cpsr", "CPSR", "int");
+                });
+
+                if let Some(fpu_registers) = core.registers().fpu_registers() {
+                    let fpu_registers = fpu_registers.collect::<Vec<_>>();
+                    desc.add_feature("org.gnu.gdb.arm.vfp", |feature| {
+                        for register in fpu_registers {
+                            feature.add_register(
+                                register.name().to_lowercase(),
+                                register,
+                                "ieee_double",
+                            );
+                        }
+                        feature.add(core, "fpscr", "FPSCR", "int");
+                    });
+                }
+            }
+            CoreType::Riscv => {
+                desc.architecture = "riscv:rv32";
+
+                desc.add_feature("org.gnu.gdb.riscv.cpu", |feature| {
+                    for n in 0..32 {
+                        let gdb_type = match n {
+                            2 => "data_ptr",
+                            _ => "int",
+                        };
+                        feature.add(core, &format!("x{n}"), &format!("x{n}"), gdb_type);
+                    }
+                    feature.add(core, "pc", "pc", "code_ptr");
+                });
+            }
+            CoreType::Xtensa => {
+                desc.architecture = "xtensa";
+
+                desc.add_feature("org.gnu.gdb.xtensa.core", |feature| {
+                    feature.add(core, "pc", "pc", "code_ptr");
+                    for register in core.registers().core_registers() {
+                        if register.id() != core.program_counter().id() {
+                            feature.add_register(register.name().to_string(), register, "int");
+                        }
+                    }
+                });
+            }
+        }
+
+        desc
+    }
+
+    fn add_feature(&mut self, name: &'static str, build: impl FnOnce(&mut GdbFeature)) {
+        let mut feature = GdbFeature {
+            name,
+            registers: Vec::new(),
+        };
+
+        build(&mut feature);
+
+        self.features.push(feature);
+    }
+
+    /// All registers, ordered by their GDB register number.
+    pub fn registers(&self) -> impl Iterator<Item = &GdbRegister> {
+        self.features
+            .iter()
+            .flat_map(|feature| feature.registers.iter())
+    }
+
+    /// Get the register with the GDB register number `regnum`.
+    pub fn register(&self, regnum: usize) -> Option<&GdbRegister> {
+        self.registers().nth(regnum)
+    }
+
+    /// Generate the target description XML.
+    pub fn get_target_xml(&self) -> String {
+        let mut xml = format!(
+            r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+<architecture>{}</architecture>
+"#,
+            self.architecture
+        );
+
+        let mut regnum = 0;
+        for feature in &self.features {
+            let _ = writeln!(xml, r#"<feature name="{}">"#, feature.name);
+
+            for register in &feature.registers {
+                let _ = write!(
+                    xml,
+                    r#"<reg name="{}" bitsize="{}" regnum="{}" type="{}""#,
+                    register.name, register.size_in_bits, regnum, register.gdb_type
+                );
+                if let Some(group) = register.group {
+                    let _ = write!(xml, r#" group="{group}""#);
+                }
+                xml.push_str("/>\n");
+
+                regnum += 1;
+            }
+
+            xml.push_str("</feature>\n");
+        }
+
+        xml.push_str("</target>\n");
+
+        xml
+    }
+}
+
+impl GdbFeature {
+    /// Add the registers `r0` to `r12`, `sp`, `lr` and `pc`, which are common to all ARM 32-bit cores.
+    fn add_arm_core_registers(&mut self, core: &Core) {
+        for n in 0..13 {
+            self.add(core, &format!("r{n}"), &format!("R{n}"), "int");
+        }
+        self.add(core, "sp", "R13", "data_ptr");
+        self.add(core, "lr", "R14", "int");
+        self.add(core, "pc", "R15", "code_ptr");
+    }
+
+    /// Add the probe-rs register called `probe_rs_name` as `gdb_name`.
+    fn add(&mut self, core: &Core, gdb_name: &str, probe_rs_name: &str, gdb_type: &'static str) {
+        match find_register(core, probe_rs_name) {
+            Some(register) => self.add_register(gdb_name.to_string(), register, gdb_type),
+            None => tracing::debug!(
+                "Register {probe_rs_name} is not available on this core, not adding it to the GDB target description"
+            ),
+        }
+    }
+
+    fn add_register(&mut self, name: String, register: &CoreRegister, gdb_type: &'static str) {
+        let is_float = register.register_has_role(crate::RegisterRole::FloatingPoint)
+            || register.register_has_role(crate::RegisterRole::FloatingPointStatus);
+
+        self.registers.push(GdbRegister {
+            name,
+            size_in_bits: register.size_in_bits(),
+            gdb_type,
+            group: is_float.then_some("float"),
+            source: GdbRegisterSource::SingleRegister(register.id()),
+        });
+    }
+
+    /// Add a 64-bit floating point register made up of the two 32-bit registers `low_name` and `high_name`.
+    fn add_two_word(&mut self, core: &Core, gdb_name: &str, low_name: &str, high_name: &str) {
+        let (Some(low), Some(high)) = (
+            find_register(core, low_name),
+            find_register(core, high_name),
+        ) else {
+            tracing::debug!(
+                "Registers {low_name} and {high_name} are not available on this core, not adding {gdb_name} to the GDB target description"
+            );
+            return;
+        };
+
+        self.registers.push(GdbRegister {
+            name: gdb_name.to_string(),
+            size_in_bits: low.size_in_bits() + high.size_in_bits(),
+            gdb_type: "ieee_double",
+            group: Some("float"),
+            source: GdbRegisterSource::TwoWordRegister {
+                low: low.id(),
+                high: high.id(),
+                word_size: low.size_in_bytes(),
+            },
+        });
+    }
+}
+
+/// Find a register of `core` by its name, ignoring the case.
+fn find_register(core: &Core, name: &str) -> Option<&'static CoreRegister> {
+    core.registers()
+        .all_registers()
+        .find(|register| register.name().eq_ignore_ascii_case(name))
+}
@@ -0,0 +1,100 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use gdbstub::target::ext::flash::Flash;
+use gdbstub::target::{TargetError, TargetResult};
+
+use super::{ProbeRsErrorExt, RuntimeTarget};
+use crate::flashing::{self, DownloadOptions, FlashError, FlashProgress};
+
+impl Flash for RuntimeTarget<'_> {
+    fn flash_erase(&mut self, start_addr: u64, length: u64) -> TargetResult<(), Self> {
+        tracing::debug!("GDB requested to erase {length} bytes of flash at {start_addr:#010x}");
+
+        self.pending_flash
+            .erase
+            .push(start_addr..start_addr.saturating_add(length));
+
+        Ok(())
+    }
+
+    fn flash_write(&mut self, start_addr: u64, data: &[u8]) -> TargetResult<(), Self> {
+        tracing::debug!(
+            "GDB requested to write {} bytes of flash at {start_addr:#010x}",
+            data.len()
+        );
+
+        let session = self.session.lock();
+        let loader = self
+            .pending_flash
+            .loader
+            .get_or_insert_with(|| session.target().flash_loader());
+
+        loader.add_data(start_addr, data).map_err(flash_error)
+    }
+
+    fn flash_done(&mut self) -> TargetResult<(), Self> {
+        let erase = merge_ranges(std::mem::take(&mut self.pending_flash.erase));
+        let loader = self.pending_flash.loader.take();
+
+        let mut session = self.session.lock();
+
+        // GDB erases all blocks before writing to them, so the flash loader does not need to erase again.
+        for range in erase {
+            flashing::erase(&mut session, FlashProgress::empty(), range).map_err(flash_error)?;
+        }
+
+        if let Some(loader) = loader {
+            let options = DownloadOptions {
+                skip_erase: true,
+                ..Default::default()
+            };
+
+            loader.commit(&mut session, options).map_err(flash_error)?;
+        }
+
+        // Flashing runs the flash algorithm on the target, so the cores have to be reset.
+        for core_id in &self.cores {
+            session
+                .core(*core_id)
+                .into_target_result()?
+                .reset_and_halt(Duration::from_millis(100))
+                .into_target_result()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn flash_error(e: FlashError) -> TargetError<crate::Error> {
+    tracing::warn!("Error during GDB flash request: {e}");
+
+    TargetError::NonFatal
+}
+
+/// Merge overlapping and adjacent ranges, so each flash sector is erased only once.
+fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::merge_ranges;
+
+    #[test]
+    fn erase_ranges_are_merged() {
+        let ranges = vec![0x1000..0x2000, 0x0..0x1000, 0x4000..0x5000, 0x1800..0x3000];
+
+        assert_eq!(merge_ranges(ranges), vec![0x0..0x3000, 0x4000..0x5000]);
+    }
+}
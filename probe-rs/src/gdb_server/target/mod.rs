@@ -0,0 +1,506 @@
+mod base;
+mod breakpoints;
+mod desc;
+mod flash;
+mod monitor;
+mod semihosting;
+mod traits;
+mod utils;
+
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::time::Duration;
+
+use gdbstub::common::Signal;
+use gdbstub::conn::ConnectionExt;
+use gdbstub::stub::state_machine::GdbStubStateMachine;
+use gdbstub::stub::{GdbStub, MultiThreadStopReason};
+use gdbstub::target::ext::base::BaseOps;
+use gdbstub::target::ext::breakpoints::BreakpointsOps;
+use gdbstub::target::ext::flash::FlashOps;
+use gdbstub::target::ext::memory_map::{MemoryMap, MemoryMapOps};
+use gdbstub::target::ext::monitor_cmd::MonitorCmdOps;
+use gdbstub::target::ext::target_description_xml_override::{
+    TargetDescriptionXmlOverride, TargetDescriptionXmlOverrideOps,
+};
+use gdbstub::target::{Target, TargetResult};
+use parking_lot::FairMutex;
+
+use super::arch::RuntimeArch;
+use super::PeripheralRegister;
+use crate::flashing::FlashLoader;
+use crate::rtt::Rtt;
+use crate::{BreakpointCause, CoreStatus, Error, HaltReason, Session, Watchpoint};
+
+use desc::TargetDescription;
+pub(crate) use traits::ProbeRsErrorExt;
+
+/// Actions for resuming a core
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ResumeAction {
+    /// Don't change the state
+    Unchanged,
+    /// Resume core
+    Resume,
+    /// Single step core
+    Step,
+}
+
+/// Flash programming requested by GDB with `vFlashErase` and `vFlashWrite` packets,
+/// which is performed when GDB sends `vFlashDone`.
+#[derive(Default)]
+pub(crate) struct PendingFlash {
+    /// The address ranges GDB asked to erase.
+    erase: Vec<Range<u64>>,
+    /// The data GDB asked to write.
+    loader: Option<FlashLoader>,
+}
+
+/// The top level gdbstub target for a probe-rs debug session
+pub(crate) struct RuntimeTarget<'a> {
+    /// The probe-rs session object
+    session: &'a FairMutex<Session>,
+    /// A list of core IDs for this stub
+    cores: Vec<usize>,
+    /// Whether the session debugs core dumps, which can't be changed or resumed
+    core_dump: bool,
+    /// The core of the GDB thread which was accessed last, used by monitor commands
+    current_core: usize,
+
+    /// TCP listener accepting incoming connections
+    listener: TcpListener,
+    /// The current GDB stub state machine
+    gdb: Option<GdbStubStateMachine<'a, RuntimeTarget<'a>, TcpStream>>,
+    /// Resume action to be used upon a continue request
+    resume_action: (usize, ResumeAction),
+
+    /// Description of target's architecture and registers
+    target_desc: TargetDescription,
+    /// GDB memory map XML of the memory accessible by the cores of this stub
+    memory_map: String,
+    /// Flash programming which has not been committed yet
+    pending_flash: PendingFlash,
+    /// Watchpoints set by GDB
+    watchpoints: Vec<Watchpoint>,
+
+    /// Memory mapped peripheral registers for the `reg` monitor command
+    peripheral_registers: Vec<PeripheralRegister>,
+    /// Whether semihosting requests of the target are served, or reported to GDB as a stop
+    semihosting: bool,
+    /// The RTT control block, once the `rtt` monitor command attached to it
+    rtt: Option<Rtt>,
+}
+
+impl<'a> RuntimeTarget<'a> {
+    /// Create a new RuntimeTarget and get ready to start processing GDB input
+    pub fn new(
+        session: &'a FairMutex<Session>,
+        cores: Vec<usize>,
+        addrs: &[SocketAddr],
+        peripheral_registers: Vec<PeripheralRegister>,
+    ) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addrs)?;
+        listener.set_nonblocking(true)?;
+
+        let core_dump = session.lock().is_core_dump();
+
+        let (target_desc, memory_map) = {
+            let mut session = session.lock();
+            let core_name = session.target().cores[cores[0]].name.clone();
+            let memory_map = utils::gdb_memory_map(session.target(), &core_name);
+
+            let core = session.core(cores[0])?;
+            (TargetDescription::new(&core), memory_map)
+        };
+
+        Ok(Self {
+            session,
+            current_core: cores[0],
+            cores,
+            core_dump,
+            listener,
+            gdb: None,
+            resume_action: (0, ResumeAction::Unchanged),
+            target_desc,
+            memory_map,
+            pending_flash: PendingFlash::default(),
+            watchpoints: Vec::new(),
+            peripheral_registers,
+            semihosting: false,
+            rtt: None,
+        })
+    }
+
+    /// Process any pending work for this target
+    ///
+    /// Returns: Duration to wait before processing this target again
+    pub fn process(&mut self) -> anyhow::Result<Duration> {
+        // State 1 - unconnected
+        let Some(gdb) = self.gdb.take() else {
+            // See if we have a connection
+            match self.listener.accept() {
+                Ok((stream, addr)) => {
+                    tracing::info!("New connection from {:#?}", addr);
+
+                    // When we first attach to the core, GDB expects us to halt the core, so we do this here when a new client connects.
+                    // If the core is already halted, nothing happens if we issue a halt command again, so we always do this no matter of core state.
+                    {
+                        let mut session = self.session.lock();
+                        for core_id in &self.cores {
+                            session.core(*core_id)?.halt(Duration::from_millis(100))?;
+                        }
+                    }
+
+                    // Start the GDB Stub state machine
+                    let stub = GdbStub::<RuntimeTarget, _>::new(stream);
+                    self.gdb = Some(stub.run_state_machine(self)?);
+
+                    return Ok(Duration::ZERO);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    // No connection yet
+                    return Ok(Duration::from_millis(10));
+                }
+                Err(e) => {
+                    // Fatal error
+                    return Err(e.into());
+                }
+            }
+        };
+
+        // Stage 2 - connected
+        let mut wait_time = Duration::ZERO;
+
+        self.gdb =
+            match gdb {
+                GdbStubStateMachine::Idle(mut state) => {
+                    // Read data if available
+                    match read_if_available(state.borrow_conn()) {
+                        Ok(Some(byte)) => Some(state.incoming_data(self, byte)?),
+                        Ok(None) => {
+                            wait_time = Duration::from_millis(10);
+                            Some(state.into())
+                        }
+                        Err(e) => {
+                            tracing::info!("GDB connection closed: {e}");
+                            None
+                        }
+                    }
+                }
+                GdbStubStateMachine::Running(mut state) => {
+                    // Read data if available
+                    match read_if_available(state.borrow_conn()) {
+                        Ok(Some(byte)) => Some(state.incoming_data(self, byte)?),
+                        Ok(None) => {
+                            // Check for break
+                            if let Some(reason) = self.check_for_halt()? {
+                                Some(state.report_stop(self, reason)?)
+                            } else {
+                                wait_time = Duration::from_millis(10);
+                                Some(state.into())
+                            }
+                        }
+                        Err(e) => {
+                            tracing::info!("GDB connection closed: {e}");
+                            None
+                        }
+                    }
+                }
+                GdbStubStateMachine::CtrlCInterrupt(state) => {
+                    // Break core, handle interrupt
+                    {
+                        let mut session = self.session.lock();
+                        for core_id in &self.cores {
+                            session.core(*core_id)?.halt(Duration::from_millis(100))?;
+                        }
+                    }
+
+                    Some(state.interrupt_handled(
+                        self,
+                        Some(MultiThreadStopReason::Signal(Signal::SIGINT)),
+                    )?)
+                }
+                GdbStubStateMachine::Disconnected(state) => {
+                    tracing::info!("GDB client disconnected: {:?}", state.get_reason());
+
+                    None
+                }
+            };
+
+        Ok(wait_time)
+    }
+
+    /// Check if any core of this stub has halted, and determine the stop reason to report to GDB.
+    ///
+    /// GDB expects all or nothing stops, so the remaining cores are halted as well.
+    fn check_for_halt(&mut self) -> Result<Option<MultiThreadStopReason<u64>>, Error> {
+        let mut session = self.session.lock();
+
+        let mut stop_reason = None;
+        for core_id in &self.cores {
+            let mut core = session.core(*core_id)?;
+
+            if let CoreStatus::Halted(reason) = core.status()? {
+                let tid = core_id_to_tid(*core_id);
+
+                stop_reason = Some(match reason {
+                    HaltReason::Breakpoint(BreakpointCause::Semihosting(command))
+                        if self.semihosting =>
+                    {
+                        match semihosting::handle(&mut core, command, tid)? {
+                            Some(stop_reason) => stop_reason,
+                            None => {
+                                core.run()?;
+                                continue;
+                            }
+                        }
+                    }
+                    HaltReason::Breakpoint(BreakpointCause::Hardware) => {
+                        MultiThreadStopReason::HwBreak(tid)
+                    }
+                    HaltReason::Breakpoint(BreakpointCause::Semihosting(_)) => {
+                        MultiThreadStopReason::SignalWithThread {
+                            tid,
+                            signal: Signal::SIGTRAP,
+                        }
+                    }
+                    HaltReason::Breakpoint(_) => MultiThreadStopReason::SwBreak(tid),
+                    HaltReason::Step => MultiThreadStopReason::DoneStep,
+                    HaltReason::Watchpoint(cause) => {
+                        match breakpoints::watchpoint_hit(&self.watchpoints, cause) {
+                            Some(watchpoint) => MultiThreadStopReason::Watch {
+                                tid,
+                                kind: breakpoints::watch_kind(watchpoint.kind),
+                                addr: watchpoint.address,
+                            },
+                            None => MultiThreadStopReason::SignalWithThread {
+                                tid,
+                                signal: Signal::SIGTRAP,
+                            },
+                        }
+                    }
+                    HaltReason::Request | HaltReason::External => {
+                        MultiThreadStopReason::SignalWithThread {
+                            tid,
+                            signal: Signal::SIGINT,
+                        }
+                    }
+                    _ => MultiThreadStopReason::SignalWithThread {
+                        tid,
+                        signal: Signal::SIGTRAP,
+                    },
+                });
+                self.current_core = *core_id;
+                break;
+            }
+        }
+
+        if stop_reason.is_some() {
+            for core_id in &self.cores {
+                let mut core = session.core(*core_id)?;
+                if !core.core_halted()? {
+                    core.halt(Duration::from_millis(100))?;
+                }
+            }
+        }
+
+        Ok(stop_reason)
+    }
+
+    /// Reject GDB requests to change registers or memory if the session debugs core dumps.
+    fn check_writable(&self) -> TargetResult<(), Self> {
+        if self.core_dump {
+            return Err(Error::CoreDumpReadOnly("Changing registers or memory"))
+                .into_target_result();
+        }
+
+        Ok(())
+    }
+}
+
+impl Target for RuntimeTarget<'_> {
+    type Arch = RuntimeArch;
+    type Error = Error;
+
+    fn base_ops(&mut self) -> BaseOps<'_, Self::Arch, Self::Error> {
+        BaseOps::MultiThread(self)
+    }
+
+    fn support_target_description_xml_override(
+        &mut self,
+    ) -> Option<TargetDescriptionXmlOverrideOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_breakpoints(&mut self) -> Option<BreakpointsOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_memory_map(&mut self) -> Option<MemoryMapOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_flash_operations(&mut self) -> Option<FlashOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_monitor_cmd(&mut self) -> Option<MonitorCmdOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn guard_rail_implicit_sw_breakpoints(&self) -> bool {
+        true
+    }
+}
+
+impl TargetDescriptionXmlOverride for RuntimeTarget<'_> {
+    fn target_description_xml(
+        &self,
+        annex: &[u8],
+        offset: u64,
+        length: usize,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        let xml = match annex {
+            b"target.xml" => self.target_desc.get_target_xml(),
+            _ => return Err(gdbstub::target::TargetError::NonFatal),
+        };
+
+        Ok(utils::copy_range_to_buf(
+            xml.as_bytes(),
+            offset,
+            length,
+            buf,
+        ))
+    }
+}
+
+impl MemoryMap for RuntimeTarget<'_> {
+    fn memory_map_xml(
+        &self,
+        offset: u64,
+        length: usize,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        Ok(utils::copy_range_to_buf(
+            self.memory_map.as_bytes(),
+            offset,
+            length,
+            buf,
+        ))
+    }
+}
+
+/// GDB thread IDs start at 1, so the thread of a core is its ID plus one.
+pub(crate) fn core_id_to_tid(core_id: usize) -> NonZeroUsize {
+    NonZeroUsize::new(core_id + 1).unwrap()
+}
+
+/// The core ID of a GDB thread, see [core_id_to_tid].
+pub(crate) fn tid_to_core_id(tid: NonZeroUsize) -> usize {
+    tid.get() - 1
+}
+
+/// Read a byte from a stream if available, otherwise return None
+fn read_if_available(conn: &mut TcpStream) -> std::io::Result<Option<u8>> {
+    match conn.peek()? {
+        // Read the byte which peek already showed is in the buffer.
+        Some(_) => conn.read().map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use gdbstub::target::ext::base::multithread::MultiThreadBase;
+    use parking_lot::FairMutex;
+
+    use super::{core_id_to_tid, utils, RuntimeTarget};
+    use crate::config::get_target_by_name;
+    use crate::gdb_server::arch::RuntimeRegisters;
+    use crate::{CoreDump, RegisterValue, Session};
+
+    fn core_dump_session(name: &str) -> FairMutex<Session> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/debug-unwind-tests")
+            .join(format!("{name}.coredump"));
+        let dump = CoreDump::load(&path).unwrap();
+
+        FairMutex::new(Session::from_core_dumps(vec![dump], None).unwrap())
+    }
+
+    fn runtime_target(session: &FairMutex<Session>) -> RuntimeTarget<'_> {
+        RuntimeTarget::new(
+            session,
+            vec![0],
+            &["127.0.0.1:0".parse().unwrap()],
+            Vec::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn cortex_m_target_description() {
+        let session = core_dump_session("RP2040_full_unwind");
+        let target = runtime_target(&session);
+
+        let xml = target.target_desc.get_target_xml();
+        assert!(xml.contains("<architecture>arm</architecture>"));
+        assert!(xml.contains(r#"<feature name="org.gnu.gdb.arm.m-profile">"#));
+        assert!(xml.contains(r#"<reg name="r0" bitsize="32" regnum="0" type="int"/>"#));
+        assert!(xml.contains(r#"<reg name="pc" bitsize="32" regnum="15" type="code_ptr"/>"#));
+        assert!(xml.contains(r#"<reg name="xpsr" bitsize="32" regnum="16" type="int"/>"#));
+    }
+
+    #[test]
+    fn read_registers_in_target_description_order() {
+        let session = core_dump_session("RP2040_full_unwind");
+        let mut target = runtime_target(&session);
+
+        let mut regs = RuntimeRegisters::default();
+        assert!(target.read_registers(&mut regs, core_id_to_tid(0)).is_ok());
+
+        let (pc, expected_pc) = {
+            let mut session = session.lock();
+            let mut core = session.core(0).unwrap();
+            let pc_id = core.program_counter().id();
+            let pc: u32 = core.read_core_reg(pc_id).unwrap();
+            (pc, core.read_core_reg::<RegisterValue>(pc_id).unwrap())
+        };
+
+        assert_eq!(RegisterValue::from(regs.pc as u32), expected_pc);
+        // 17 core registers and MSP, PSP, 4 bytes each.
+        assert_eq!(regs.regs.len(), 19 * 4);
+        assert_eq!(regs.regs[15 * 4..16 * 4], pc.to_le_bytes());
+    }
+
+    #[test]
+    fn core_dump_is_read_only() {
+        let session = core_dump_session("RP2040_full_unwind");
+        let mut target = runtime_target(&session);
+        let tid = core_id_to_tid(0);
+
+        assert!(target.write_addrs(0x2000_0000, &[0; 4], tid).is_err());
+
+        let mut regs = RuntimeRegisters::default();
+        assert!(target.read_registers(&mut regs, tid).is_ok());
+        assert!(target.write_registers(&regs, tid).is_err());
+
+        assert!(target.support_resume().is_none());
+    }
+
+    #[test]
+    fn memory_map_contains_flash_sectors() {
+        let target = get_target_by_name("nRF52833_xxAA").unwrap();
+
+        let xml = utils::gdb_memory_map(&target, &target.cores[0].name);
+
+        assert!(xml.contains(
+            r#"<memory type="flash" start="0x0" length="0x80000"><property name="blocksize">0x1000</property></memory>"#
+        ));
+        assert!(xml.contains(r#"<memory type="ram" start="0x20000000" length="0x20000"/>"#));
+    }
+}
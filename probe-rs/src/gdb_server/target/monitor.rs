@@ -0,0 +1,367 @@
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use gdbstub::target::ext::monitor_cmd::{outputln, ConsoleOutput, MonitorCmd};
+
+use super::RuntimeTarget;
+use crate::flashing::{self, DownloadOptions, FlashProgress, FormatKind};
+use crate::rtt::Rtt;
+use crate::{MemoryInterface, VectorCatchCondition};
+
+const HELP: &str = "Supported Commands:
+
+\tinfo - print session information
+\treset - reset target
+\treset halt - reset target and halt afterwards
+\terase - erase all flash memory
+\terase <address> <length> - erase the flash sectors overlapping a memory range
+\tflash <path> [bin|hex|elf|uf2|idf] - program a file into flash, the format defaults to ELF
+\tspeed <kHz> - set the probe speed
+\treg <name> - read a peripheral register
+\treg <name> <value> - write a peripheral register
+\tsemihosting enable|disable - serve semihosting console output
+\tvector_catch <condition> enable|disable - halt on reset, faults or exceptions
+\t\tconditions: hardfault, reset, securefault, busfault, memmanage, usagefault, faults, all
+\trtt - print the data available in the RTT up channels
+
+Commands which access a core use the current GDB thread.";
+
+/// A monitor command sent by GDB.
+#[derive(Debug, PartialEq, Eq)]
+enum Command<'a> {
+    Info,
+    Reset { halt: bool },
+    EraseAll,
+    Erase(Range<u64>),
+    Flash { path: &'a str, format: FormatKind },
+    Speed(u32),
+    ReadRegister(&'a str),
+    WriteRegister(&'a str, u64),
+    Semihosting(bool),
+    VectorCatch(VectorCatchCondition, bool),
+    Rtt,
+    Help,
+}
+
+impl<'a> Command<'a> {
+    fn parse(cmd: &'a str) -> Result<Self, String> {
+        let args = cmd.split_whitespace().collect::<Vec<_>>();
+
+        let command = match args.as_slice() {
+            ["info"] => Self::Info,
+            ["reset"] => Self::Reset { halt: false },
+            ["reset", "halt"] => Self::Reset { halt: true },
+            ["erase"] => Self::EraseAll,
+            ["erase", address, length] => {
+                let address = parse_number(address)?;
+                let length = parse_number(length)?;
+                let end = address
+                    .checked_add(length)
+                    .ok_or_else(|| format!("The range {address:#x} + {length:#x} overflows"))?;
+
+                Self::Erase(address..end)
+            }
+            ["flash", path] => Self::Flash {
+                path,
+                format: FormatKind::default(),
+            },
+            ["flash", path, format] => Self::Flash {
+                path,
+                format: FormatKind::from_str(format)?,
+            },
+            ["speed", speed] => Self::Speed(
+                speed
+                    .parse()
+                    .map_err(|_| format!("Invalid speed '{speed}'"))?,
+            ),
+            ["reg", name] => Self::ReadRegister(name),
+            ["reg", name, value] => Self::WriteRegister(name, parse_number(value)?),
+            ["semihosting", enable] => Self::Semihosting(parse_enable(enable)?),
+            ["vector_catch", condition, enable] => {
+                Self::VectorCatch(parse_vector_catch(condition)?, parse_enable(enable)?)
+            }
+            ["rtt"] => Self::Rtt,
+            _ => Self::Help,
+        };
+
+        Ok(command)
+    }
+}
+
+impl MonitorCmd for RuntimeTarget<'_> {
+    fn handle_monitor_cmd(
+        &mut self,
+        cmd: &[u8],
+        mut out: ConsoleOutput<'_>,
+    ) -> Result<(), Self::Error> {
+        let cmd = String::from_utf8_lossy(cmd);
+
+        let result = match Command::parse(&cmd) {
+            Ok(command) => self.run_command(command, &mut out),
+            Err(e) => Err(anyhow!(e)),
+        };
+
+        if let Err(e) = result {
+            outputln!(out, "Error: {e:#}");
+        }
+
+        Ok(())
+    }
+}
+
+impl RuntimeTarget<'_> {
+    fn run_command(&mut self, command: Command, out: &mut ConsoleOutput) -> anyhow::Result<()> {
+        let mut session = self.session.lock();
+
+        match command {
+            Command::Info => {
+                outputln!(out, "Target info:\n\n{:#?}", session.target());
+            }
+            Command::Reset { halt: false } => {
+                outputln!(out, "Resetting target");
+                session.core(self.current_core)?.reset()?;
+                outputln!(out, "Done");
+            }
+            Command::Reset { halt: true } => {
+                outputln!(out, "Resetting and halting target");
+                session
+                    .core(self.current_core)?
+                    .reset_and_halt(Duration::from_millis(1000))?;
+                outputln!(out, "Target halted");
+            }
+            Command::EraseAll => {
+                outputln!(out, "Erasing all flash memory");
+                flashing::erase_all(&mut session, FlashProgress::empty())?;
+                self.reset_and_halt_cores(&mut session)?;
+                outputln!(out, "Done");
+            }
+            Command::Erase(range) => {
+                outputln!(out, "Erasing flash sectors in {range:#010x?}");
+                flashing::erase(&mut session, FlashProgress::empty(), range)?;
+                self.reset_and_halt_cores(&mut session)?;
+                outputln!(out, "Done");
+            }
+            Command::Flash { path, format } => {
+                outputln!(out, "Programming {path}");
+                flashing::download_file_with_options(
+                    &mut session,
+                    Path::new(path),
+                    format,
+                    DownloadOptions::default(),
+                )?;
+                self.reset_and_halt_cores(&mut session)?;
+                outputln!(out, "Done");
+            }
+            Command::Speed(speed_khz) => {
+                let speed_khz = session.set_probe_speed(speed_khz)?;
+                outputln!(out, "Probe speed set to {speed_khz} kHz");
+            }
+            Command::ReadRegister(name) => {
+                let register = self.peripheral_register(name)?;
+                let mut core = session.core(self.current_core)?;
+
+                let value = match register.size {
+                    8 => core.read_word_8(register.address)? as u64,
+                    16 => core.read_word_16(register.address)? as u64,
+                    32 => core.read_word_32(register.address)? as u64,
+                    64 => core.read_word_64(register.address)?,
+                    size => return Err(anyhow!("Unsupported register size of {size} bits")),
+                };
+
+                outputln!(
+                    out,
+                    "{} @ {:#010x} = {value:#x}",
+                    register.name,
+                    register.address
+                );
+            }
+            Command::WriteRegister(name, value) => {
+                let register = self.peripheral_register(name)?;
+                let mut core = session.core(self.current_core)?;
+
+                match register.size {
+                    8 => core.write_word_8(register.address, value.try_into()?)?,
+                    16 => core.write_word_16(register.address, value.try_into()?)?,
+                    32 => core.write_word_32(register.address, value.try_into()?)?,
+                    64 => core.write_word_64(register.address, value)?,
+                    size => return Err(anyhow!("Unsupported register size of {size} bits")),
+                }
+
+                outputln!(
+                    out,
+                    "{} @ {:#010x} = {value:#x}",
+                    register.name,
+                    register.address
+                );
+            }
+            Command::Semihosting(enable) => {
+                self.semihosting = enable;
+                outputln!(
+                    out,
+                    "Semihosting {}",
+                    if enable { "enabled" } else { "disabled" }
+                );
+            }
+            Command::VectorCatch(condition, enable) => {
+                let mut core = session.core(self.current_core)?;
+
+                if enable {
+                    core.enable_vector_catch(condition)?;
+                } else {
+                    core.disable_vector_catch(condition)?;
+                }
+
+                outputln!(
+                    out,
+                    "Vector catch for {condition:?} {}",
+                    if enable { "enabled" } else { "disabled" }
+                );
+            }
+            Command::Rtt => {
+                let mut core = session.core(self.current_core)?;
+
+                let rtt = match &mut self.rtt {
+                    Some(rtt) => rtt,
+                    rtt => rtt.insert(Rtt::attach(&mut core).context("RTT is not available")?),
+                };
+
+                let mut buf = [0; 1024];
+                for channel in rtt.up_channels() {
+                    let count = channel.read(&mut core, &mut buf)?;
+                    if count > 0 {
+                        let name = channel.name().unwrap_or("unnamed");
+                        outputln!(out, "{}: {}", name, String::from_utf8_lossy(&buf[..count]));
+                    }
+                }
+            }
+            Command::Help => {
+                outputln!(out, "{HELP}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flash algorithms run on the target, so its cores have to be reset afterwards.
+    fn reset_and_halt_cores(&self, session: &mut crate::Session) -> Result<(), crate::Error> {
+        for core_id in &self.cores {
+            session
+                .core(*core_id)?
+                .reset_and_halt(Duration::from_millis(100))?;
+        }
+
+        Ok(())
+    }
+
+    fn peripheral_register(&self, name: &str) -> anyhow::Result<&super::PeripheralRegister> {
+        self.peripheral_registers
+            .iter()
+            .find(|register| register.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| anyhow!("Unknown register '{name}', load the registers with --svd"))
+    }
+}
+
+/// Parse a hexadecimal number prefixed with `0x`, or a decimal number.
+fn parse_number(value: &str) -> Result<u64, String> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    parsed.map_err(|_| format!("Invalid number '{value}'"))
+}
+
+fn parse_enable(value: &str) -> Result<bool, String> {
+    match value {
+        "enable" | "on" => Ok(true),
+        "disable" | "off" => Ok(false),
+        _ => Err(format!("Expected 'enable' or 'disable', got '{value}'")),
+    }
+}
+
+fn parse_vector_catch(value: &str) -> Result<VectorCatchCondition, String> {
+    let condition = match value {
+        "hardfault" => VectorCatchCondition::HardFault,
+        "reset" => VectorCatchCondition::CoreReset,
+        "securefault" => VectorCatchCondition::SecureFault,
+        "busfault" => VectorCatchCondition::BusFault,
+        "memmanage" => VectorCatchCondition::MemoryManagementFault,
+        "usagefault" => VectorCatchCondition::UsageFault,
+        "faults" => VectorCatchCondition::Exception,
+        "all" => VectorCatchCondition::All,
+        _ => return Err(format!("Unknown vector catch condition '{value}'")),
+    };
+
+    Ok(condition)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Command;
+    use crate::flashing::FormatKind;
+    use crate::VectorCatchCondition;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(Command::parse("info"), Ok(Command::Info));
+        assert_eq!(
+            Command::parse(" reset  halt "),
+            Ok(Command::Reset { halt: true })
+        );
+        assert_eq!(Command::parse("erase"), Ok(Command::EraseAll));
+        assert_eq!(
+            Command::parse("erase 0x1000 4096"),
+            Ok(Command::Erase(0x1000..0x2000))
+        );
+        assert_eq!(
+            Command::parse("flash firmware.hex hex"),
+            Ok(Command::Flash {
+                path: "firmware.hex",
+                format: FormatKind::Hex
+            })
+        );
+        assert_eq!(
+            Command::parse("flash firmware.elf"),
+            Ok(Command::Flash {
+                path: "firmware.elf",
+                format: FormatKind::Elf
+            })
+        );
+        assert_eq!(Command::parse("speed 4000"), Ok(Command::Speed(4000)));
+        assert_eq!(
+            Command::parse("reg GPIOA.ODR"),
+            Ok(Command::ReadRegister("GPIOA.ODR"))
+        );
+        assert_eq!(
+            Command::parse("reg GPIOA.ODR 0xff"),
+            Ok(Command::WriteRegister("GPIOA.ODR", 0xff))
+        );
+        assert_eq!(
+            Command::parse("semihosting enable"),
+            Ok(Command::Semihosting(true))
+        );
+        assert_eq!(
+            Command::parse("vector_catch faults disable"),
+            Ok(Command::VectorCatch(VectorCatchCondition::Exception, false))
+        );
+        assert_eq!(Command::parse("rtt"), Ok(Command::Rtt));
+        assert_eq!(Command::parse("unknown"), Ok(Command::Help));
+    }
+
+    #[test]
+    fn parse_invalid_arguments() {
+        assert!(Command::parse("erase 0x1000").is_ok_and(|c| c == Command::Help));
+        assert!(Command::parse("erase 0xffffffffffffffff 2").is_err());
+        assert!(Command::parse("flash firmware.bin zip").is_err());
+        assert!(Command::parse("speed fast").is_err());
+        assert!(Command::parse("reg GPIOA.ODR 0xfg").is_err());
+        assert!(Command::parse("semihosting maybe").is_err());
+        assert!(Command::parse("vector_catch nmi enable").is_err());
+    }
+}
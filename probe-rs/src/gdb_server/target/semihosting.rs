@@ -0,0 +1,71 @@
+use std::io::Write;
+use std::num::NonZeroU32;
+
+use gdbstub::common::{Signal, Tid};
+use gdbstub::stub::MultiThreadStopReason;
+
+use crate::semihosting::SemihostingCommand;
+use crate::{Core, Error};
+
+/// Handles of the console streams, as returned for `SYS_OPEN` of `:tt`.
+const STDOUT: u32 = 1;
+const STDERR: u32 = 2;
+const STDIN: u32 = 3;
+
+/// Serve a semihosting request of the target on the console of the GDB server.
+///
+/// Returns the stop reason to report to GDB if the request can't be served,
+/// or `None` if the core can continue running.
+pub(super) fn handle(
+    core: &mut Core,
+    command: SemihostingCommand,
+    tid: Tid,
+) -> Result<Option<MultiThreadStopReason<u64>>, Error> {
+    match command {
+        SemihostingCommand::ExitSuccess => return Ok(Some(MultiThreadStopReason::Exited(0))),
+        SemihostingCommand::ExitError(details) => {
+            let status = details.exit_status.unwrap_or(1);
+            return Ok(Some(MultiThreadStopReason::Exited(status as u8)));
+        }
+        SemihostingCommand::WriteConsole(request) => {
+            print!("{}", request.read(core)?);
+        }
+        SemihostingCommand::Open(request) if request.path(core)? == ":tt" => {
+            let handle = match request.mode().as_bytes()[0] {
+                b'r' => STDIN,
+                b'w' => STDOUT,
+                _ => STDERR,
+            };
+            request.respond_with_handle(core, NonZeroU32::new(handle).unwrap())?;
+        }
+        SemihostingCommand::Write(request) if matches!(request.file_handle(), STDOUT | STDERR) => {
+            let data = request.read(core)?;
+            let result = if request.file_handle() == STDOUT {
+                std::io::stdout().write_all(&data)
+            } else {
+                std::io::stderr().write_all(&data)
+            };
+
+            // The target expects the number of bytes which were not written
+            let status = if result.is_ok() { 0 } else { data.len() as i32 };
+            request.write_status(core, status)?;
+        }
+        SemihostingCommand::Close(request)
+            if matches!(request.file_handle(core)?, STDOUT | STDERR | STDIN) =>
+        {
+            request.success(core)?;
+        }
+        other => {
+            tracing::warn!(
+                "Semihosting request {other:?} is not supported by the GDB server, halting the core"
+            );
+
+            return Ok(Some(MultiThreadStopReason::SignalWithThread {
+                tid,
+                signal: Signal::SIGTRAP,
+            }));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,23 @@
+use gdbstub::target::{TargetError, TargetResult};
+
+use crate::Error;
+
+use super::RuntimeTarget;
+
+/// Extension trait to convert a probe-rs [Result] into a gdbstub [TargetResult].
+pub(crate) trait ProbeRsErrorExt<T> {
+    /// Convert the error into a non-fatal gdbstub error.
+    ///
+    /// The error is logged, since GDB only receives a generic error code.
+    fn into_target_result(self) -> TargetResult<T, RuntimeTarget<'static>>;
+}
+
+impl<T> ProbeRsErrorExt<T> for Result<T, Error> {
+    fn into_target_result(self) -> TargetResult<T, RuntimeTarget<'static>> {
+        self.map_err(|e| {
+            tracing::warn!("Error during GDB request: {e}");
+
+            TargetError::NonFatal
+        })
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use probe_rs_target::{MemoryRange, MemoryRegion};
+
+use crate::Target;
+
+/// Copy the part of `data` described by `offset` and `length` into `buf`.
+///
+/// This is used to answer GDB's `qXfer` requests, which read documents in chunks.
+///
+/// # Returns
+/// The number of bytes copied into `buf`
+pub(crate) fn copy_range_to_buf(data: &[u8], offset: u64, length: usize, buf: &mut [u8]) -> usize {
+    let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+    if offset >= data.len() {
+        return 0;
+    }
+
+    let end = data.len().min(offset.saturating_add(length));
+    let chunk = &data[offset..end];
+    let len = chunk.len().min(buf.len());
+    buf[..len].copy_from_slice(&chunk[..len]);
+
+    len
+}
+
+/// Build the GDB memory map XML for the memory regions of `target` which are accessible by `core_name`.
+///
+/// Flash regions are split into parts with a uniform sector size, since GDB requires
+/// the erase block size for each flash region to program it with `load`.
+pub(crate) fn gdb_memory_map(target: &Target, core_name: &str) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0"?>
+<!DOCTYPE memory-map PUBLIC "+//IDN gnu.org//DTD GDB Memory Map V1.0//EN" "http://sourceware.org/gdb/gdb-memory-map.dtd">
+<memory-map>
+"#,
+    );
+
+    for region in &target.memory_map {
+        match region {
+            MemoryRegion::Ram(ram) if ram.accessible_by(core_name) => {
+                write_memory(&mut xml, "ram", &ram.range);
+            }
+            MemoryRegion::Generic(generic) if generic.accessible_by(core_name) => {
+                // Peripheral and other memory is accessed like RAM.
+                write_memory(&mut xml, "ram", &generic.range);
+            }
+            MemoryRegion::Nvm(nvm) if nvm.accessible_by(core_name) && !nvm.is_alias => {
+                let sectors = flash_sectors(target, &nvm.range);
+
+                if sectors.is_empty() {
+                    // Without a flash algorithm the region can still be read.
+                    write_memory(&mut xml, "rom", &nvm.range);
+                }
+
+                for (range, sector_size) in sectors {
+                    let _ = writeln!(
+                        xml,
+                        r#"<memory type="flash" start="{:#x}" length="{:#x}"><property name="blocksize">{:#x}</property></memory>"#,
+                        range.start,
+                        range.end - range.start,
+                        sector_size
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    xml.push_str("</memory-map>\n");
+
+    xml
+}
+
+fn write_memory(xml: &mut String, memory_type: &str, range: &Range<u64>) {
+    let _ = writeln!(
+        xml,
+        r#"<memory type="{memory_type}" start="{:#x}" length="{:#x}"/>"#,
+        range.start,
+        range.end - range.start
+    );
+}
+
+/// Split the flash `range` into parts with a uniform sector size, using the sector
+/// descriptions of the flash algorithm responsible for it.
+fn flash_sectors(target: &Target, range: &Range<u64>) -> Vec<(Range<u64>, u64)> {
+    let Some(algorithm) = target.flash_algorithms.iter().find(|algorithm| {
+        algorithm
+            .flash_properties
+            .address_range
+            .contains_range(range)
+    }) else {
+        return vec![];
+    };
+
+    let properties = &algorithm.flash_properties;
+    let flash_start = properties.address_range.start;
+
+    let mut sectors = vec![];
+    for (index, description) in properties.sectors.iter().enumerate() {
+        let start = flash_start + description.address;
+        let end = properties
+            .sectors
+            .get(index + 1)
+            .map(|next| flash_start + next.address)
+            .unwrap_or(properties.address_range.end);
+
+        let start = start.max(range.start);
+        let end = end.min(range.end);
+        if start < end {
+            sectors.push((start..end, description.size));
+        }
+    }
+
+    sectors
+}
+
+#[cfg(test)]
+mod test {
+    use super::copy_range_to_buf;
+
+    #[test]
+    fn copy_range_in_chunks() {
+        let data = b"0123456789";
+        let mut buf = [0u8; 4];
+
+        assert_eq!(copy_range_to_buf(data, 0, 4, &mut buf), 4);
+        assert_eq!(&buf, b"0123");
+
+        assert_eq!(copy_range_to_buf(data, 8, 4, &mut buf), 2);
+        assert_eq!(&buf[..2], b"89");
+
+        assert_eq!(copy_range_to_buf(data, 10, 4, &mut buf), 0);
+    }
+}
@@ -94,7 +94,8 @@ pub use crate::config::{CoreType, InstructionSet, Target};
 pub use crate::core::{
     Architecture, BreakpointCause, Core, CoreInformation, CoreInterface, CoreRegister,
     CoreRegisters, CoreState, CoreStatus, HaltReason, MemoryMappedRegister, RegisterId,
-    RegisterRole, RegisterValue, SpecificCoreState, VectorCatchCondition,
+    RegisterRole, RegisterValue, SpecificCoreState, VectorCatchCondition, Watchpoint,
+    WatchpointCause, WatchpointKind,
 };
 pub use crate::error::Error;
 pub use crate::memory::MemoryInterface;
@@ -12,7 +12,7 @@ use crate::architecture::arm::{
     FullyQualifiedApAddress, RawDapAccess, SwoAccess,
 };
 use crate::probe::blackmagic::{Align, BlackMagicProbe, ProtocolVersion, RemoteCommand};
-use crate::probe::{DebugProbeError, Probe};
+use crate::probe::{DebugProbe, DebugProbeError, Probe};
 use crate::{Error as ProbeRsError, MemoryInterface};
 use std::collections::BTreeSet;
 use std::sync::Arc;
@@ -200,6 +200,10 @@ impl ArmProbeInterface for BlackMagicProbeArmDebug {
         DpAddress::Default
     }
 
+    fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
+        self.probe.set_speed(speed_khz)
+    }
+
     fn memory_interface(
         &mut self,
         access_port: &FullyQualifiedApAddress,
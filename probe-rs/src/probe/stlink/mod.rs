@@ -1462,6 +1462,10 @@ impl ArmProbeInterface for StlinkArmDebug {
         Ok(None)
     }
 
+    fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
+        self.probe.set_speed(speed_khz)
+    }
+
     fn access_ports(
         &mut self,
         dp: DpAddress,
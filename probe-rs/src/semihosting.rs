@@ -32,6 +32,45 @@ pub enum SemihostingCommand {
     /// The target indicated that it would like to write to the console.
     Write(WriteRequest),
 
+    /// The target indicated that it would like to read from a file on the host.
+    Read(ReadRequest),
+
+    /// The target indicated that it would like to read a character from the console.
+    ReadConsole(ReadConsoleRequest),
+
+    /// The target wants to know whether a file handle refers to an interactive device.
+    IsTty(FileHandleRequest),
+
+    /// The target requests to move the position in a file on the host.
+    Seek(SeekRequest),
+
+    /// The target requests the length of a file on the host.
+    FileLength(FileHandleRequest),
+
+    /// The target requests to delete a file on the host.
+    Remove(RemoveRequest),
+
+    /// The target requests to rename a file on the host.
+    Rename(RenameRequest),
+
+    /// The target requests the number of centiseconds since execution started.
+    Clock(ValueRequest),
+
+    /// The target requests the number of seconds since 00:00 January 1, 1970.
+    Time(ValueRequest),
+
+    /// The target requests the number of elapsed target ticks since execution started.
+    Elapsed(ElapsedRequest),
+
+    /// The target requests the tick frequency used by [`SemihostingCommand::Elapsed`].
+    TickFreq(ValueRequest),
+
+    /// The target requests the error number of the last failed semihosting operation.
+    Errno(ValueRequest),
+
+    /// The target requests the location of its heap and stack.
+    HeapInfo(HeapInfoRequest),
+
     /// The target indicated that it would like to run a semihosting operation which we don't support yet.
     Unknown(UnknownCommandDetails),
 }
@@ -109,8 +148,6 @@ impl GetCommandLineRequest {
 }
 
 /// A request to open a file on the host.
-///
-/// Note that this is not implemented by probe-rs yet.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct OpenRequest {
     path: ZeroTerminatedString,
@@ -139,8 +176,6 @@ impl OpenRequest {
 }
 
 /// A request to open a file on the host.
-///
-/// Note that this is not implemented by probe-rs yet.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct CloseRequest {
     pointer: u32,
@@ -194,6 +229,205 @@ impl WriteRequest {
     }
 }
 
+/// A request to read from a file
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ReadRequest {
+    handle: u32,
+    bytes: u32,
+    len: u32,
+}
+impl ReadRequest {
+    /// Returns the handle of the file to read from
+    pub fn file_handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Returns the number of bytes the target wants to read.
+    pub fn buffer_length(&self) -> u32 {
+        self.len
+    }
+
+    /// Writes the data read from the file to the target.
+    ///
+    /// At most [`buffer_length`](Self::buffer_length) bytes are written,
+    /// a shorter buffer signals the end of the file to the target.
+    pub fn respond_with_data(
+        &self,
+        core: &mut dyn CoreInterface,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        let data = &data[..data.len().min(self.len as usize)];
+        core.write_8(self.bytes as u64, data)?;
+
+        // The target expects the number of bytes which were not read
+        write_status(core, (self.len as usize - data.len()) as i32)
+    }
+
+    /// Writes the status of the semihosting operation to the return register of the target
+    pub fn write_status(&self, core: &mut dyn CoreInterface, status: i32) -> Result<(), Error> {
+        write_status(core, status)
+    }
+}
+
+/// A request to read a character from the console
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ReadConsoleRequest;
+impl ReadConsoleRequest {
+    /// Responds with the character read from the console.
+    pub fn respond_with_char(&self, core: &mut dyn CoreInterface, c: u8) -> Result<(), Error> {
+        write_status(core, c as i32)
+    }
+}
+
+/// A request which only refers to a file handle
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct FileHandleRequest {
+    handle: u32,
+}
+impl FileHandleRequest {
+    /// Returns the handle of the file
+    pub fn file_handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Writes the status of the semihosting operation to the return register of the target
+    pub fn write_status(&self, core: &mut dyn CoreInterface, status: i32) -> Result<(), Error> {
+        write_status(core, status)
+    }
+}
+
+/// A request to seek to a position in a file
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SeekRequest {
+    handle: u32,
+    position: u32,
+}
+impl SeekRequest {
+    /// Returns the handle of the file
+    pub fn file_handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Returns the absolute position to seek to, in bytes from the start of the file
+    pub fn position(&self) -> u32 {
+        self.position
+    }
+
+    /// Responds with success to the target.
+    pub fn success(&self, core: &mut dyn CoreInterface) -> Result<(), Error> {
+        write_status(core, 0)
+    }
+}
+
+/// A request to delete a file
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct RemoveRequest {
+    path: ZeroTerminatedString,
+}
+impl RemoveRequest {
+    /// Reads the path from the target.
+    pub fn path(&self, core: &mut dyn CoreInterface) -> Result<String, Error> {
+        self.path.read(core)
+    }
+
+    /// Responds with success to the target.
+    pub fn success(&self, core: &mut dyn CoreInterface) -> Result<(), Error> {
+        write_status(core, 0)
+    }
+}
+
+/// A request to rename a file
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct RenameRequest {
+    from: ZeroTerminatedString,
+    to: ZeroTerminatedString,
+}
+impl RenameRequest {
+    /// Reads the current path of the file from the target.
+    pub fn from_path(&self, core: &mut dyn CoreInterface) -> Result<String, Error> {
+        self.from.read(core)
+    }
+
+    /// Reads the new path of the file from the target.
+    pub fn to_path(&self, core: &mut dyn CoreInterface) -> Result<String, Error> {
+        self.to.read(core)
+    }
+
+    /// Responds with success to the target.
+    pub fn success(&self, core: &mut dyn CoreInterface) -> Result<(), Error> {
+        write_status(core, 0)
+    }
+}
+
+/// A request which is answered with a single value
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ValueRequest;
+impl ValueRequest {
+    /// Responds with the requested value to the target.
+    pub fn respond_with_value(
+        &self,
+        core: &mut dyn CoreInterface,
+        value: i32,
+    ) -> Result<(), Error> {
+        write_status(core, value)
+    }
+}
+
+/// A request for the number of elapsed ticks
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ElapsedRequest {
+    pointer: u32,
+}
+impl ElapsedRequest {
+    /// Responds with the number of elapsed ticks to the target.
+    pub fn respond_with_ticks(
+        &self,
+        core: &mut dyn CoreInterface,
+        ticks: u64,
+    ) -> Result<(), Error> {
+        core.write_32(self.pointer as u64, &[ticks as u32, (ticks >> 32) as u32])?;
+        write_status(core, 0)
+    }
+}
+
+/// The location of the heap and stack of the target, as returned by SYS_HEAPINFO.
+///
+/// A value of zero means that the location is not known.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct HeapInfo {
+    /// The lowest address of the heap
+    pub heap_base: u32,
+    /// The highest address of the heap
+    pub heap_limit: u32,
+    /// The initial stack pointer
+    pub stack_base: u32,
+    /// The lowest address of the stack
+    pub stack_limit: u32,
+}
+
+/// A request for the location of the heap and stack
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct HeapInfoRequest {
+    block_address: u32,
+}
+impl HeapInfoRequest {
+    /// Writes the heap and stack location to the target.
+    pub fn respond_with_info(
+        &self,
+        core: &mut dyn CoreInterface,
+        info: HeapInfo,
+    ) -> Result<(), Error> {
+        let block = [
+            info.heap_base,
+            info.heap_limit,
+            info.stack_base,
+            info.stack_limit,
+        ];
+        core.write_32(self.block_address as u64, &block)?;
+        Ok(())
+    }
+}
+
 fn write_status(core: &mut dyn CoreInterface, value: i32) -> Result<(), crate::Error> {
     let reg = core.registers().get_argument_register(0).unwrap();
     core.write_core_reg(reg.into(), RegisterValue::U32(value as u32))?;
@@ -284,7 +518,6 @@ impl ZeroTerminatedString {
 }
 
 /// Decodes a semihosting syscall without running the requested action.
-/// Only supports SYS_EXIT, SYS_EXIT_EXTENDED and SYS_GET_CMDLINE at the moment
 pub fn decode_semihosting_syscall(
     core: &mut dyn CoreInterface,
 ) -> Result<SemihostingCommand, Error> {
@@ -309,6 +542,19 @@ pub fn decode_semihosting_syscall(
     const SYS_WRITEC: u32 = 0x03;
     const SYS_WRITE0: u32 = 0x04;
     const SYS_WRITE: u32 = 0x05;
+    const SYS_READ: u32 = 0x06;
+    const SYS_READC: u32 = 0x07;
+    const SYS_ISTTY: u32 = 0x09;
+    const SYS_SEEK: u32 = 0x0A;
+    const SYS_FLEN: u32 = 0x0C;
+    const SYS_REMOVE: u32 = 0x0E;
+    const SYS_RENAME: u32 = 0x0F;
+    const SYS_CLOCK: u32 = 0x10;
+    const SYS_TIME: u32 = 0x11;
+    const SYS_ERRNO: u32 = 0x13;
+    const SYS_HEAPINFO: u32 = 0x16;
+    const SYS_ELAPSED: u32 = 0x30;
+    const SYS_TICKFREQ: u32 = 0x31;
 
     Ok(match (operation, parameter) {
         (SYS_EXIT, SYS_EXIT_ADP_STOPPED_APPLICATIONEXIT) => SemihostingCommand::ExitSuccess,
@@ -410,6 +656,113 @@ pub fn decode_semihosting_syscall(
             SemihostingCommand::Write(WriteRequest { handle, bytes, len })
         }
 
+        (SYS_READ, pointer) => {
+            let [handle, bytes, len] = param3(core, pointer)?;
+            // signal to target: status = failure, in case the application does not answer this request
+            // The number of bytes not read equal to the buffer length indicates that nothing was read.
+            write_status(core, len as i32)?;
+            SemihostingCommand::Read(ReadRequest { handle, bytes, len })
+        }
+
+        (SYS_READC, _) => {
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::ReadConsole(ReadConsoleRequest)
+        }
+
+        (SYS_ISTTY, pointer) => {
+            let handle = core.read_word_32(pointer as u64)?;
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::IsTty(FileHandleRequest { handle })
+        }
+
+        (SYS_SEEK, pointer) => {
+            let mut buf = [0; 2];
+            core.read_32(pointer as u64, &mut buf)?;
+            let [handle, position] = buf;
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::Seek(SeekRequest { handle, position })
+        }
+
+        (SYS_FLEN, pointer) => {
+            let handle = core.read_word_32(pointer as u64)?;
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::FileLength(FileHandleRequest { handle })
+        }
+
+        (SYS_REMOVE, pointer) => {
+            let mut buf = [0; 2];
+            core.read_32(pointer as u64, &mut buf)?;
+            let [address, length] = buf;
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::Remove(RemoveRequest {
+                path: ZeroTerminatedString {
+                    address,
+                    length: Some(length),
+                },
+            })
+        }
+
+        (SYS_RENAME, pointer) => {
+            let mut buf = [0; 4];
+            core.read_32(pointer as u64, &mut buf)?;
+            let [from, from_length, to, to_length] = buf;
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::Rename(RenameRequest {
+                from: ZeroTerminatedString {
+                    address: from,
+                    length: Some(from_length),
+                },
+                to: ZeroTerminatedString {
+                    address: to,
+                    length: Some(to_length),
+                },
+            })
+        }
+
+        (SYS_CLOCK, _) => {
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::Clock(ValueRequest)
+        }
+
+        (SYS_TIME, _) => {
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::Time(ValueRequest)
+        }
+
+        (SYS_ELAPSED, pointer) => {
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::Elapsed(ElapsedRequest { pointer })
+        }
+
+        (SYS_TICKFREQ, _) => {
+            // signal to target: status = failure, in case the application does not answer this request
+            write_status(core, -1)?;
+            SemihostingCommand::TickFreq(ValueRequest)
+        }
+
+        (SYS_ERRNO, _) => {
+            // No error is known, in case the application does not answer this request
+            write_status(core, 0)?;
+            SemihostingCommand::Errno(ValueRequest)
+        }
+
+        (SYS_HEAPINFO, pointer) => {
+            // The parameter points to the address of the block to fill in.
+            let block_address = core.read_word_32(pointer as u64)?;
+            // Zero marks all locations as unknown, in case the application does not answer this request
+            core.write_32(block_address as u64, &[0; 4])?;
+            SemihostingCommand::HeapInfo(HeapInfoRequest { block_address })
+        }
+
         _ => {
             // signal to target: status = failure, in case the application does not answer this request
             // It is not guaranteed that a value of -1 will be treated as an error by the target, but it is a common value to indicate an error.
@@ -2,7 +2,8 @@ use crate::{
     architecture::{
         arm::{
             communication_interface::ArmProbeInterface,
-            component::{get_arm_components, TraceSink},
+            component::{get_arm_components, MtbBuffer, TraceSink},
+            instruction_trace::InstructionTrace,
             memory::CoresightComponent,
             sequences::{ArmDebugSequence, DefaultArmSequence},
             ArmError, DpAddress, SwoReader,
@@ -22,6 +23,8 @@ use crate::{
     },
     Core, CoreType, Error,
 };
+#[cfg(feature = "debug")]
+use crate::{core::dump::DumpedCore, CoreDump};
 use std::ops::DerefMut;
 use std::{fmt, sync::Arc, time::Duration};
 
@@ -34,6 +37,9 @@ use std::{fmt, sync::Arc, time::Duration};
 /// For more control, the [Probe::attach()] and [Probe::attach_under_reset()]
 /// methods can be used to open a `Session` from a specific [Probe].
 ///
+/// A session for the state of a target captured in [CoreDump]s, which can be debugged without a
+/// probe, is opened with [Session::from_core_dumps()].
+///
 /// # Usage
 /// The Session is the common handle that gives a user exclusive access to an active probe.
 /// You can create and share a session between threads to enable multiple stakeholders (e.g. GDB and RTT) to access the target taking turns, by using `Arc<FairMutex<Session>>`.
@@ -83,6 +89,9 @@ impl fmt::Debug for JtagInterface {
 enum ArchitectureInterface {
     Arm(Box<dyn ArmProbeInterface + 'static>),
     Jtag(Probe, Vec<JtagInterface>),
+    /// The dumped state of each core, instead of a connection to the target.
+    #[cfg(feature = "debug")]
+    CoreDump(Vec<CoreDump>),
 }
 
 impl fmt::Debug for ArchitectureInterface {
@@ -93,6 +102,10 @@ impl fmt::Debug for ArchitectureInterface {
                 .debug_tuple("ArchitectureInterface::Other(..)")
                 .field(ifaces)
                 .finish(),
+            #[cfg(feature = "debug")]
+            ArchitectureInterface::CoreDump(_) => {
+                f.write_str("ArchitectureInterface::CoreDump(..)")
+            }
         }
     }
 }
@@ -125,6 +138,16 @@ impl ArchitectureInterface {
                     }
                 }
             }
+            #[cfg(feature = "debug")]
+            ArchitectureInterface::CoreDump(dumps) => {
+                let id = combined_state.id();
+                Ok(Core::new(
+                    id,
+                    &target.cores[id].name,
+                    target,
+                    DumpedCore::new(&mut dumps[id]),
+                ))
+            }
         }
     }
 }
@@ -160,6 +183,7 @@ impl Session {
         };
 
         session.clear_all_hw_breakpoints()?;
+        session.clear_all_hw_watchpoints()?;
 
         Ok(session)
     }
@@ -397,6 +421,77 @@ impl Session {
         Ok(session)
     }
 
+    /// Open a session for the state of a target captured in core dumps, to debug it offline.
+    ///
+    /// The dump at each index is the state of the core with the same index. The session is
+    /// read-only: memory and registers can be read, but operations which change the state of the
+    /// cores return [Error::CoreDumpReadOnly].
+    ///
+    /// If no `target` is given, a generic target with a core for each dump is used.
+    #[cfg(feature = "debug")]
+    pub fn from_core_dumps(dumps: Vec<CoreDump>, target: Option<Target>) -> Result<Self, Error> {
+        let Some(first_dump) = dumps.first() else {
+            return Err(Error::Other("No core dumps were given.".to_string()));
+        };
+        if dumps
+            .iter()
+            .any(|dump| dump.core_type.architecture() != first_dump.core_type.architecture())
+        {
+            return Err(Error::Other(
+                "The core dumps are for cores with different architectures.".to_string(),
+            ));
+        }
+
+        let target = target.unwrap_or_else(|| Target::from_core_dumps(&dumps));
+        if dumps.len() > target.cores.len() {
+            return Err(Error::Other(format!(
+                "{} core dumps were given, but the target {} only has {} cores.",
+                dumps.len(),
+                target.name,
+                target.cores.len()
+            )));
+        }
+        for (index, (dump, core)) in dumps.iter().zip(&target.cores).enumerate() {
+            if dump.core_type != core.core_type {
+                return Err(Error::Other(format!(
+                    "The core dump {index} is for a {:?} core, but core {index} of the target {} is a {:?} core.",
+                    dump.core_type, target.name, core.core_type
+                )));
+            }
+        }
+
+        let cores = target
+            .cores
+            .iter()
+            .take(dumps.len())
+            .enumerate()
+            .map(|(id, core)| {
+                Core::create_state(
+                    id,
+                    core.core_access_options.clone(),
+                    &target,
+                    core.core_type,
+                )
+            })
+            .collect();
+
+        Ok(Session {
+            target,
+            interfaces: ArchitectureInterface::CoreDump(dumps),
+            cores,
+            configured_trace_sink: None,
+        })
+    }
+
+    /// Returns `true` if the session debugs core dumps, see [Session::from_core_dumps()].
+    pub fn is_core_dump(&self) -> bool {
+        match self.interfaces {
+            #[cfg(feature = "debug")]
+            ArchitectureInterface::CoreDump(_) => true,
+            _ => false,
+        }
+    }
+
     /// Automatically creates a session with the first connected probe found.
     #[tracing::instrument(skip(target))]
     pub fn auto_attach(
@@ -541,6 +636,23 @@ impl Session {
         Ok(SwoReader::new(interface))
     }
 
+    /// Changes the speed of the debug probe while the session is active.
+    ///
+    /// Returns the speed in kHz which was actually configured, which may differ
+    /// from the requested speed if the probe does not support it exactly.
+    pub fn set_probe_speed(&mut self, speed_khz: u32) -> Result<u32, Error> {
+        let speed = match &mut self.interfaces {
+            ArchitectureInterface::Arm(interface) => interface.set_speed(speed_khz)?,
+            ArchitectureInterface::Jtag(probe, _) => probe.set_speed(speed_khz)?,
+            #[cfg(feature = "debug")]
+            ArchitectureInterface::CoreDump(_) => {
+                return Err(Error::CoreDumpReadOnly("Changing the probe speed"))
+            }
+        };
+
+        Ok(speed)
+    }
+
     /// Get the Arm probe interface.
     pub fn get_arm_interface(&mut self) -> Result<&mut dyn ArmProbeInterface, ArmError> {
         let interface = match &mut self.interfaces {
@@ -728,6 +840,45 @@ impl Session {
         Ok(())
     }
 
+    /// Configure the target for instruction trace of the core `core_index`.
+    ///
+    /// The ETM traces into the on-chip trace memory. Cores without ETM, like the Cortex-M0+,
+    /// can record their program flow with the MTB into `mtb_buffer`, a region of RAM which the
+    /// firmware must not use.
+    pub fn setup_instruction_trace(
+        &mut self,
+        core_index: usize,
+        mtb_buffer: Option<MtbBuffer>,
+    ) -> Result<(), Error> {
+        {
+            let mut core = self.core(core_index)?;
+            crate::architecture::arm::component::enable_tracing(&mut core)?;
+        }
+
+        let sequence_handle = match &self.target.debug_sequence {
+            DebugSequence::Arm(sequence) => sequence.clone(),
+            _ => unreachable!("Mismatch between architecture and sequence type!"),
+        };
+
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+
+        sequence_handle.trace_start(interface, &components, &TraceSink::TraceMemory)?;
+        crate::architecture::arm::component::setup_instruction_trace(
+            interface,
+            &components,
+            mtb_buffer,
+        )
+    }
+
+    /// Stop the instruction trace configured with [`Session::setup_instruction_trace`], and
+    /// read the recorded trace.
+    pub fn read_instruction_trace(&mut self) -> Result<InstructionTrace, Error> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::read_instruction_trace(interface, &components)
+    }
+
     /// Configure the target to stop emitting SWV trace data.
     #[tracing::instrument(skip(self))]
     pub fn disable_swv(&mut self, core_index: usize) -> Result<(), Error> {
@@ -764,6 +915,8 @@ impl Session {
                     Architecture::Xtensa
                 }
             }
+            #[cfg(feature = "debug")]
+            ArchitectureInterface::CoreDump(_) => self.target.architecture(),
         }
     }
 
@@ -778,6 +931,17 @@ impl Session {
         })
     }
 
+    /// Clears all hardware watchpoints on all cores
+    pub fn clear_all_hw_watchpoints(&mut self) -> Result<(), Error> {
+        self.halted_access(|session| {
+            { 0..session.cores.len() }.try_for_each(|core| match session.core(core) {
+                Ok(mut core) => core.clear_all_hw_watchpoints(),
+                Err(Error::CoreDisabled(_)) => Ok(()),
+                Err(err) => Err(err),
+            })
+        })
+    }
+
     /// Resume all cores
     pub fn resume_all_cores(&mut self) -> Result<(), Error> {
         // Resume cores
@@ -814,6 +978,13 @@ impl Drop for Session {
             );
         }
 
+        if let Err(err) = self.clear_all_hw_watchpoints() {
+            tracing::warn!(
+                "Could not clear all hardware watchpoints: {:?}",
+                anyhow::anyhow!(err)
+            );
+        }
+
         // Call any necessary deconfiguration/shutdown hooks.
         if let Err(err) = { 0..self.cores.len() }.try_for_each(|core| match self.core(core) {
             Ok(mut core) => core.debug_core_stop(),
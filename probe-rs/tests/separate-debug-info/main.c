@@ -0,0 +1,12 @@
+static volatile int counter;
+
+static int add(int a, int b) {
+    return a + b;
+}
+
+int main(void) {
+    for (int i = 0; i < 10; i++) {
+        counter = add(counter, i);
+    }
+    return counter;
+}
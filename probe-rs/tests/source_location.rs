@@ -126,3 +126,34 @@ fn regression_pr2324() {
 
     assert_eq!(addr.address, 0x2e4);
 }
+
+#[test]
+fn function_breakpoint_location() {
+    let di = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+
+    let locations = di
+        .get_function_breakpoint_locations("__cortex_m_rt_main")
+        .unwrap();
+
+    assert_eq!(locations.len(), 1);
+    assert_eq!(locations[0].address, 0x80006EA);
+    assert_eq!(locations[0].source_location.line, Some(240));
+
+    assert!(di
+        .get_function_breakpoint_locations("non_existent")
+        .is_err());
+}
+
+#[test]
+fn function_breakpoint_locations_of_inlined_copies() {
+    let di = DebugInfo::from_file("tests/inlined-functions").unwrap();
+
+    let addresses = di
+        .get_function_breakpoint_locations("cortex_m::interrupt::disable")
+        .unwrap()
+        .into_iter()
+        .map(|location| location.address)
+        .collect::<Vec<_>>();
+
+    assert_eq!(addresses, [0x16C, 0x196]);
+}